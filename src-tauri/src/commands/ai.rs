@@ -0,0 +1,62 @@
+use crate::db::models::*;
+use crate::state::AppState;
+use tauri::State;
+
+#[tauri::command]
+pub async fn breakdown_task(
+    state: State<'_, AppState>,
+    task_id: i64,
+) -> Result<Vec<SubtaskSuggestion>, String> {
+    state
+        .breakdown_service
+        .breakdown_task(task_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn create_subtasks(
+    state: State<AppState>,
+    parent_task_id: i64,
+    subtasks: Vec<SubtaskSuggestion>,
+) -> Result<Vec<Task>, String> {
+    state
+        .breakdown_service
+        .create_subtasks(parent_task_id, subtasks)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_ai_settings(state: State<AppState>) -> Result<AiSettings, String> {
+    state
+        .preferences_service
+        .get_ai_settings()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn update_ai_settings(
+    state: State<AppState>,
+    settings: AiSettingsInput,
+) -> Result<AiSettings, String> {
+    state
+        .preferences_service
+        .update_ai_settings(settings)
+        .map_err(|e| e.to_string())
+}
+
+/// Usage between two days (inclusive), by default of the last 30 days
+#[tauri::command]
+pub fn get_ai_usage(
+    state: State<AppState>,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<AiUsage>, String> {
+    let today = chrono::Local::now().date_naive();
+    let from = from.unwrap_or_else(|| (today - chrono::Duration::days(29)).to_string());
+    let to = to.unwrap_or_else(|| today.to_string());
+    state
+        .ai_queue_service
+        .get_usage(&from, &to)
+        .map_err(|e| e.to_string())
+}
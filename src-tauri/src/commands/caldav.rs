@@ -0,0 +1,39 @@
+use crate::db::models::*;
+use crate::state::AppState;
+use tauri::State;
+
+#[tauri::command]
+pub fn get_caldav_config(state: State<AppState>) -> Result<Option<CaldavConfig>, String> {
+    state
+        .caldav_sync_service
+        .get_config()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn configure_caldav(
+    state: State<AppState>,
+    input: CaldavConfigInput,
+) -> Result<CaldavConfig, String> {
+    state
+        .caldav_sync_service
+        .configure(input)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn disconnect_caldav(state: State<AppState>) -> Result<(), String> {
+    state
+        .caldav_sync_service
+        .disconnect()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn sync_caldav(state: State<'_, AppState>) -> Result<CaldavSyncReport, String> {
+    state
+        .caldav_sync_service
+        .sync()
+        .await
+        .map_err(|e| e.to_string())
+}
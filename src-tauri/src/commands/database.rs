@@ -0,0 +1,60 @@
+use crate::db::{encryption::Passphrase, models::DatabaseStatus};
+use crate::state::AppState;
+use std::path::PathBuf;
+use tauri::{AppHandle, State};
+
+#[tauri::command]
+pub fn get_database_status(state: State<AppState>) -> DatabaseStatus {
+    state.db_pool.status()
+}
+
+/// Open the encrypted database at startup and start the work that needs it
+#[tauri::command]
+pub fn unlock_database(
+    app: AppHandle,
+    state: State<AppState>,
+    passphrase: String,
+) -> Result<(), String> {
+    let passphrase = Passphrase::new(passphrase).map_err(|e| e.to_string())?;
+    state.db_pool.unlock(passphrase).map_err(|e| e.to_string())?;
+    crate::start_background_work(&app);
+    Ok(())
+}
+
+/// Encrypt the plaintext database in place; it has to be unlocked with the
+/// passphrase from the next start on
+#[tauri::command]
+pub fn encrypt_database(
+    state: State<AppState>,
+    passphrase: String,
+) -> Result<DatabaseStatus, String> {
+    let passphrase = Passphrase::new(passphrase).map_err(|e| e.to_string())?;
+    state.db_pool.encrypt(passphrase).map_err(|e| e.to_string())?;
+    Ok(state.db_pool.status())
+}
+
+#[tauri::command]
+pub fn change_database_passphrase(
+    state: State<AppState>,
+    current: String,
+    new: String,
+) -> Result<(), String> {
+    let current = Passphrase::new(current).map_err(|e| e.to_string())?;
+    let new = Passphrase::new(new).map_err(|e| e.to_string())?;
+    state
+        .db_pool
+        .change_passphrase(&current, new)
+        .map_err(|e| e.to_string())
+}
+
+/// Copy the database to a new file, encrypted like the database itself
+#[tauri::command]
+pub fn backup_database(
+    state: State<AppState>,
+    path: String,
+) -> Result<(), String> {
+    state
+        .db_pool
+        .backup_to(&PathBuf::from(path))
+        .map_err(|e| e.to_string())
+}
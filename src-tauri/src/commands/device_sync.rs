@@ -0,0 +1,45 @@
+use crate::db::models::*;
+use crate::state::AppState;
+use std::path::PathBuf;
+use tauri::State;
+
+#[tauri::command]
+pub fn get_device_sync_config(state: State<AppState>) -> Result<Option<DeviceSyncConfig>, String> {
+    state
+        .device_sync_service
+        .get_config()
+        .map_err(|e| e.to_string())
+}
+
+/// Set up sync through `folder` and start watching it
+#[tauri::command]
+pub fn configure_device_sync(
+    state: State<AppState>,
+    folder: String,
+) -> Result<DeviceSyncConfig, String> {
+    let config = state
+        .device_sync_service
+        .configure(&PathBuf::from(folder))
+        .map_err(|e| e.to_string())?;
+    state
+        .device_sync_service
+        .watch()
+        .map_err(|e| e.to_string())?;
+    Ok(config)
+}
+
+#[tauri::command]
+pub fn disconnect_device_sync(state: State<AppState>) -> Result<(), String> {
+    state
+        .device_sync_service
+        .disconnect()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn sync_devices(state: State<AppState>) -> Result<DeviceSyncReport, String> {
+    state
+        .device_sync_service
+        .sync()
+        .map_err(|e| e.to_string())
+}
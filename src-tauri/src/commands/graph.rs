@@ -0,0 +1,47 @@
+use crate::db::models::*;
+use crate::state::AppState;
+use tauri::State;
+
+#[tauri::command]
+pub fn get_graph(
+    state: State<AppState>,
+    start: Option<String>,
+    depth: Option<u32>,
+    max_nodes: Option<usize>,
+) -> Result<GraphData, String> {
+    state
+        .graph_service
+        .get_graph(start.as_deref(), depth, max_nodes)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_orphan_notes(state: State<AppState>) -> Result<Vec<GraphNode>, String> {
+    state
+        .graph_service
+        .get_orphan_notes()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_graph_hubs(
+    state: State<AppState>,
+    limit: Option<usize>,
+) -> Result<Vec<GraphNode>, String> {
+    state
+        .graph_service
+        .get_hubs(limit)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_shortest_path(
+    state: State<AppState>,
+    from: String,
+    to: String,
+) -> Result<Option<GraphData>, String> {
+    state
+        .graph_service
+        .get_shortest_path(&from, &to)
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,27 @@
+use crate::db::models::*;
+use crate::state::AppState;
+use std::path::PathBuf;
+use tauri::State;
+
+#[tauri::command]
+pub fn export_ics(
+    state: State<AppState>,
+    path: String,
+    include_events: Option<bool>,
+) -> Result<usize, String> {
+    state
+        .ics_service
+        .export_to_file(&PathBuf::from(path), include_events.unwrap_or(false))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn import_ics(
+    state: State<AppState>,
+    path: String,
+) -> Result<IcsImportReport, String> {
+    state
+        .ics_service
+        .import_file(&PathBuf::from(path))
+        .map_err(|e| e.to_string())
+}
@@ -1,3 +1,4 @@
+use crate::commands::tasks::EditError;
 use crate::db::models::*;
 use crate::state::AppState;
 use tauri::State;
@@ -8,22 +9,12 @@ pub fn move_task_to_column(
     task_id: i64,
     new_status: String,
     position: i32,
-) -> Result<Task, String> {
-    let pool = &state.db_pool;
-    let conn = pool.get().map_err(|e| e.to_string())?;
-
-    // Update task status and position
-    conn.execute(
-        "UPDATE tasks SET status = ?1, column_position = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = ?3",
-        [&new_status, &position.to_string(), &task_id.to_string()],
-    )
-    .map_err(|e| e.to_string())?;
-
-    // Get updated task
+    expected_version: Option<i64>,
+) -> Result<Task, EditError> {
     state
         .task_service
-        .get_task(task_id)
-        .map_err(|e| e.to_string())
+        .move_task(task_id, &new_status, position, expected_version)
+        .map_err(EditError::from)
 }
 
 #[tauri::command]
@@ -1,2 +1,16 @@
+pub mod ai;
+pub mod caldav;
+pub mod database;
+pub mod device_sync;
+pub mod graph;
+pub mod ics;
 pub mod kanban;
+pub mod notes;
+pub mod obsidian;
+pub mod planner;
+pub mod preferences;
+pub mod reminders;
 pub mod tasks;
+pub mod todotxt;
+pub mod users;
+pub mod workspaces;
@@ -0,0 +1,91 @@
+use crate::db::models::*;
+use crate::state::AppState;
+use tauri::State;
+
+#[tauri::command]
+pub fn create_note(
+    state: State<AppState>,
+    input: CreateNoteInput,
+) -> Result<Note, String> {
+    state
+        .note_service
+        .create_note(input)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_notes(state: State<AppState>) -> Result<Vec<Note>, String> {
+    state
+        .note_service
+        .get_all_notes()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_note(state: State<AppState>, id: i64) -> Result<Note, String> {
+    state
+        .note_service
+        .get_note(id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn update_note(
+    state: State<AppState>,
+    id: i64,
+    input: UpdateNoteInput,
+) -> Result<Note, String> {
+    state
+        .note_service
+        .update_note(id, input)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn delete_note(state: State<AppState>, id: i64) -> Result<(), String> {
+    state
+        .note_service
+        .delete_note(id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn search_notes(
+    state: State<AppState>,
+    query: String,
+) -> Result<Vec<Note>, String> {
+    state
+        .note_service
+        .search_notes(&query)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_note_links(state: State<AppState>, id: i64) -> Result<Vec<Link>, String> {
+    state
+        .note_service
+        .get_note_links(id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_note_backlinks(
+    state: State<AppState>,
+    id: i64,
+) -> Result<Vec<Backlink>, String> {
+    state
+        .note_service
+        .get_note_backlinks(id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_task_backlinks(
+    state: State<AppState>,
+    task_id: i64,
+) -> Result<Vec<Backlink>, String> {
+    state
+        .note_service
+        .get_task_backlinks(task_id)
+        .map_err(|e| e.to_string())
+}
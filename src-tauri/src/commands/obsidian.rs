@@ -0,0 +1,32 @@
+use crate::db::models::*;
+use crate::state::AppState;
+use std::path::PathBuf;
+use tauri::State;
+
+#[tauri::command]
+pub fn sync_obsidian_vault(
+    state: State<AppState>,
+    path: String,
+) -> Result<ObsidianSyncReport, String> {
+    state
+        .obsidian_sync_service
+        .sync_vault(&PathBuf::from(path))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn watch_obsidian_vault(
+    state: State<AppState>,
+    path: String,
+) -> Result<(), String> {
+    state
+        .obsidian_sync_service
+        .watch(PathBuf::from(path))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn unwatch_obsidian_vault(state: State<AppState>) -> Result<(), String> {
+    state.obsidian_sync_service.unwatch();
+    Ok(())
+}
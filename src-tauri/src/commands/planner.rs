@@ -0,0 +1,64 @@
+use crate::db::models::*;
+use crate::state::AppState;
+use tauri::State;
+
+#[tauri::command]
+pub fn plan_day(
+    state: State<AppState>,
+    date: String,
+    settings: Option<PlanSettings>,
+) -> Result<DayPlan, String> {
+    state
+        .planner_service
+        .plan_day(&date, settings)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn reflow_day(
+    state: State<AppState>,
+    date: String,
+    from_time: Option<String>,
+    settings: Option<PlanSettings>,
+) -> Result<DayPlan, String> {
+    state
+        .planner_service
+        .reflow_day(&date, from_time.as_deref(), settings)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_day_plan(
+    state: State<AppState>,
+    date: String,
+) -> Result<DayPlan, String> {
+    state
+        .planner_service
+        .get_day_plan(&date)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn add_fixed_block(
+    state: State<AppState>,
+    date: String,
+    start_time: String,
+    end_time: String,
+    title: String,
+) -> Result<TimeBlock, String> {
+    state
+        .planner_service
+        .add_fixed_block(&date, &start_time, &end_time, &title)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn delete_time_block(
+    state: State<AppState>,
+    id: i64,
+) -> Result<(), String> {
+    state
+        .planner_service
+        .delete_time_block(id)
+        .map_err(|e| e.to_string())
+}
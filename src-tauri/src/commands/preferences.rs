@@ -0,0 +1,33 @@
+use crate::db::models::*;
+use crate::state::AppState;
+use tauri::State;
+
+#[tauri::command]
+pub fn get_preferences(state: State<AppState>) -> Result<Preferences, String> {
+    state
+        .preferences_service
+        .get_preferences()
+        .map_err(|e| e.to_string())
+}
+
+/// Replace all preferences; emits `preferences:changed` once saved
+#[tauri::command]
+pub fn update_preferences(
+    state: State<AppState>,
+    preferences: Preferences,
+) -> Result<Preferences, String> {
+    state
+        .preferences_service
+        .update_preferences(preferences)
+        .map_err(|e| e.to_string())
+}
+
+/// A new token for the local API server, replacing the old one. It cannot
+/// be read again later.
+#[tauri::command]
+pub fn generate_api_token(state: State<AppState>) -> Result<String, String> {
+    state
+        .preferences_service
+        .generate_api_token()
+        .map_err(|e| e.to_string())
+}
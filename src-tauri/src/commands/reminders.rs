@@ -0,0 +1,68 @@
+use crate::db::models::*;
+use crate::state::AppState;
+use tauri::State;
+
+#[tauri::command]
+pub fn add_reminder(
+    state: State<AppState>,
+    task_id: i64,
+    input: CreateReminderInput,
+) -> Result<Reminder, String> {
+    state
+        .reminder_service
+        .add_reminder(task_id, input)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_reminders(
+    state: State<AppState>,
+    task_id: i64,
+) -> Result<Vec<Reminder>, String> {
+    state
+        .reminder_service
+        .get_reminders(task_id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn delete_reminder(
+    state: State<AppState>,
+    id: i64,
+) -> Result<(), String> {
+    state
+        .reminder_service
+        .delete_reminder(id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn snooze_reminder(
+    state: State<AppState>,
+    id: i64,
+    minutes: u32,
+) -> Result<Reminder, String> {
+    state
+        .reminder_service
+        .snooze_reminder(id, minutes)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_quiet_hours(state: State<AppState>) -> Result<Option<QuietHours>, String> {
+    state
+        .preferences_service
+        .get_quiet_hours()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn set_quiet_hours(
+    state: State<AppState>,
+    hours: Option<QuietHours>,
+) -> Result<Option<QuietHours>, String> {
+    state
+        .preferences_service
+        .set_quiet_hours(hours)
+        .map_err(|e| e.to_string())
+}
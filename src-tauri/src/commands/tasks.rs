@@ -1,7 +1,27 @@
-use crate::db::models::*;
+use crate::db::{models::*, repositories::TaskError};
 use crate::state::AppState;
+use serde::Serialize;
 use tauri::State;
 
+/// Error of an edit with an `expected_version`. A conflict carries the
+/// current task, so the UI can ask how to merge.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EditError {
+    Conflict { message: String, current: Box<Task> },
+    Failed { message: String },
+}
+
+impl From<anyhow::Error> for EditError {
+    fn from(e: anyhow::Error) -> Self {
+        let message = e.to_string();
+        match e.downcast::<TaskError>() {
+            Ok(TaskError::Conflict(current)) => EditError::Conflict { message, current },
+            Err(_) => EditError::Failed { message },
+        }
+    }
+}
+
 #[tauri::command]
 pub fn create_task(
     state: State<AppState>,
@@ -13,6 +33,17 @@ pub fn create_task(
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub fn quick_add(
+    state: State<AppState>,
+    text: String,
+) -> Result<QuickAddPreview, String> {
+    state
+        .task_service
+        .parse_quick_add(&text)
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn get_tasks(
     state: State<AppState>,
@@ -40,11 +71,11 @@ pub fn update_task(
     state: State<AppState>,
     id: i64,
     input: UpdateTaskInput,
-) -> Result<Task, String> {
+) -> Result<Task, EditError> {
     state
         .task_service
         .update_task(id, input)
-        .map_err(|e| e.to_string())
+        .map_err(EditError::from)
 }
 
 #[tauri::command]
@@ -92,6 +123,45 @@ pub fn get_subtasks(
         .map_err(|e| e.to_string())
 }
 
+/// Tasks of the active workspace assigned to the signed-in user
+#[tauri::command]
+pub fn get_my_tasks(
+    state: State<AppState>,
+    status: Option<String>,
+) -> Result<Vec<Task>, String> {
+    state
+        .task_service
+        .get_my_tasks(status.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+/// Assign a task to a workspace member, or unassign it with `None`
+#[tauri::command]
+pub fn assign_task(
+    state: State<AppState>,
+    id: i64,
+    assignee_id: Option<i64>,
+) -> Result<Task, String> {
+    state
+        .task_service
+        .assign_task(id, assignee_id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn suggest_next_tasks(
+    state: State<AppState>,
+    current_energy: String,
+    available_minutes: u32,
+    limit: Option<usize>,
+    weights: Option<ScoringWeights>,
+) -> Result<Vec<TaskSuggestion>, String> {
+    state
+        .recommendation_service
+        .suggest_next_tasks(&current_energy, available_minutes, limit, weights)
+        .map_err(|e| e.to_string())
+}
+
 // Temporary debug command for database verification
 #[tauri::command]
 pub fn debug_database(state: State<AppState>) -> Result<serde_json::Value, String> {
@@ -99,39 +169,13 @@ pub fn debug_database(state: State<AppState>) -> Result<serde_json::Value, Strin
     use serde_json::json;
 
     let pool = &state.db_pool;
-    let conn = pool.get().map_err(|e: r2d2::Error| e.to_string())?;
+    let conn = pool.get().map_err(|e| e.to_string())?;
 
     // Query all tables
-    let tasks: Vec<Task> = conn
-        .prepare("SELECT * FROM tasks ORDER BY id")
-        .map_err(|e: RusqliteError| e.to_string())?
-        .query_map([], |row| {
-            Ok(Task {
-                id: row.get(0)?,
-                user_id: row.get(1)?,
-                workspace_id: row.get(2)?,
-                title: row.get(3)?,
-                description: row.get(4)?,
-                project_id: row.get(5)?,
-                status: row.get(6)?,
-                priority: row.get(7)?,
-                estimated_minutes: row.get(8)?,
-                difficulty_level: row.get(9)?,
-                energy_level: row.get(10)?,
-                scheduled_date: row.get(11)?,
-                due_date: row.get(12)?,
-                completed_at: row.get(13)?,
-                parent_task_id: row.get(14)?,
-                order_index: row.get(15)?,
-                column_position: row.get(16)?,
-                tags: row.get(17)?,
-                created_at: row.get(18)?,
-                updated_at: row.get(19)?,
-            })
-        })
-        .map_err(|e: RusqliteError| e.to_string())?
-        .collect::<Result<Vec<_>, RusqliteError>>()
-        .map_err(|e: RusqliteError| e.to_string())?;
+    let tasks = state
+        .task_service
+        .get_all_tasks(None)
+        .map_err(|e| e.to_string())?;
 
     let users: Vec<serde_json::Value> = conn
         .prepare("SELECT id, display_name, email, created_at FROM users")
@@ -0,0 +1,32 @@
+use crate::db::models::*;
+use crate::state::AppState;
+use std::path::PathBuf;
+use tauri::State;
+
+#[tauri::command]
+pub fn sync_todotxt(
+    state: State<AppState>,
+    path: String,
+) -> Result<TodoTxtSyncReport, String> {
+    state
+        .todotxt_sync_service
+        .sync_file(&PathBuf::from(path))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn watch_todotxt(
+    state: State<AppState>,
+    path: String,
+) -> Result<(), String> {
+    state
+        .todotxt_sync_service
+        .watch(PathBuf::from(path))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn unwatch_todotxt(state: State<AppState>) -> Result<(), String> {
+    state.todotxt_sync_service.unwatch();
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use crate::db::models::*;
+use crate::state::AppState;
+use tauri::State;
+
+#[tauri::command]
+pub fn get_users(state: State<AppState>) -> Result<Vec<User>, String> {
+    state
+        .user_service
+        .get_users()
+        .map_err(|e| e.to_string())
+}
+
+/// The signed-in user, `null` while the app is locked
+#[tauri::command]
+pub fn get_current_user(state: State<AppState>) -> Result<Option<User>, String> {
+    state
+        .user_service
+        .current_user()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn create_user(
+    state: State<AppState>,
+    input: CreateUserInput,
+) -> Result<User, String> {
+    state
+        .user_service
+        .create_user(input)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn update_user(
+    state: State<AppState>,
+    id: i64,
+    input: UpdateUserInput,
+) -> Result<User, String> {
+    state
+        .user_service
+        .update_user(id, input)
+        .map_err(|e| e.to_string())
+}
+
+/// Set, change or remove (with `new_pin` null) the PIN of the signed-in user
+#[tauri::command]
+pub fn set_pin(
+    state: State<AppState>,
+    id: i64,
+    current_pin: Option<String>,
+    new_pin: Option<String>,
+) -> Result<User, String> {
+    state
+        .user_service
+        .set_pin(id, current_pin.as_deref(), new_pin.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn sign_in(
+    state: State<AppState>,
+    id: i64,
+    pin: Option<String>,
+) -> Result<User, String> {
+    stop_file_watchers(&state);
+    state
+        .user_service
+        .sign_in(id, pin.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn sign_out(state: State<AppState>) -> Result<(), String> {
+    stop_file_watchers(&state);
+    state
+        .user_service
+        .sign_out()
+        .map_err(|e| e.to_string())
+}
+
+/// Watchers sync on behalf of the signed-in user
+fn stop_file_watchers(state: &AppState) {
+    state.todotxt_sync_service.unwatch();
+    state.obsidian_sync_service.unwatch();
+    state.device_sync_service.unwatch();
+}
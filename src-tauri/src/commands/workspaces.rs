@@ -0,0 +1,119 @@
+use crate::db::models::*;
+use crate::state::AppState;
+use tauri::State;
+
+#[tauri::command]
+pub fn get_workspaces(state: State<AppState>) -> Result<Vec<Workspace>, String> {
+    state
+        .workspace_service
+        .get_workspaces()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn create_workspace(
+    state: State<AppState>,
+    input: CreateWorkspaceInput,
+) -> Result<Workspace, String> {
+    state
+        .workspace_service
+        .create_workspace(input)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn rename_workspace(
+    state: State<AppState>,
+    id: i64,
+    name: String,
+) -> Result<Workspace, String> {
+    state
+        .workspace_service
+        .rename_workspace(id, &name)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn delete_workspace(state: State<AppState>, id: i64) -> Result<(), String> {
+    stop_file_watchers(&state);
+    state
+        .workspace_service
+        .delete_workspace(id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_active_workspace(state: State<AppState>) -> Result<Workspace, String> {
+    state
+        .workspace_service
+        .active_workspace()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn switch_workspace(state: State<AppState>, id: i64) -> Result<Workspace, String> {
+    stop_file_watchers(&state);
+    state
+        .workspace_service
+        .switch_workspace(id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_workspace_members(
+    state: State<AppState>,
+    workspace_id: i64,
+) -> Result<Vec<WorkspaceMember>, String> {
+    state
+        .workspace_service
+        .get_members(workspace_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Add a user to a workspace or change their role ("owner", "editor" or "viewer")
+#[tauri::command]
+pub fn set_workspace_member(
+    state: State<AppState>,
+    workspace_id: i64,
+    user_id: i64,
+    role: String,
+) -> Result<WorkspaceMember, String> {
+    state
+        .workspace_service
+        .set_member_role(workspace_id, user_id, &role)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn remove_workspace_member(
+    state: State<AppState>,
+    workspace_id: i64,
+    user_id: i64,
+) -> Result<(), String> {
+    state
+        .workspace_service
+        .remove_member(workspace_id, user_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Move (or with `copy`, copy) tasks of the active workspace and their
+/// subtasks into another workspace
+#[tauri::command]
+pub fn transfer_tasks(
+    state: State<AppState>,
+    task_ids: Vec<i64>,
+    workspace_id: i64,
+    copy: bool,
+) -> Result<usize, String> {
+    state
+        .workspace_service
+        .transfer_tasks(&task_ids, workspace_id, copy)
+        .map_err(|e| e.to_string())
+}
+
+/// Watched files and vaults sync with the active workspace, so watching ends
+/// when it changes
+fn stop_file_watchers(state: &AppState) {
+    state.todotxt_sync_service.unwatch();
+    state.obsidian_sync_service.unwatch();
+}
@@ -1,3 +0,0 @@
-pub mod task_repository;
-
-pub use task_repository::TaskRepository;
@@ -0,0 +1,60 @@
+//! Delivery of backend events to the frontend
+
+use crate::db::models::ReminderNotification;
+use crate::services::{event_bus::DomainEvent, reminder_service::ReminderSink, EventBus};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_notification::NotificationExt;
+
+pub const REMINDER_EVENT: &str = "reminder";
+
+/// Forward committed changes to all windows: `task:*` events carrying the
+/// task and `preferences:changed` carrying the new preferences
+pub fn forward_domain_events(bus: &EventBus, app: AppHandle) {
+    bus.subscribe_async("frontend", move |event| {
+        let app = app.clone();
+        async move {
+            let Some(name) = event.frontend_name() else {
+                return Ok(());
+            };
+            match &event {
+                DomainEvent::PreferencesChanged(preferences) => app.emit(name, preferences)?,
+                event => app.emit(name, event.task())?,
+            }
+            Ok(())
+        }
+    });
+}
+
+/// Sends fired reminders to all windows and as native notifications
+pub struct TauriReminderSink {
+    app: AppHandle,
+}
+
+impl TauriReminderSink {
+    pub fn new(app: AppHandle) -> Self {
+        Self { app }
+    }
+}
+
+impl ReminderSink for TauriReminderSink {
+    fn fire(&self, notification: &ReminderNotification) {
+        if let Err(e) = self.app.emit(REMINDER_EVENT, notification.clone()) {
+            tracing::error!("Failed to emit reminder event: {e}");
+        }
+
+        let body = match &notification.due_date {
+            Some(due) => format!("Due {}", due.replace('T', " ")),
+            None => "Reminder".to_string(),
+        };
+        if let Err(e) = self
+            .app
+            .notification()
+            .builder()
+            .title(&notification.title)
+            .body(body)
+            .show()
+        {
+            tracing::error!("Failed to show reminder notification: {e}");
+        }
+    }
+}
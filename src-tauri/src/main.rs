@@ -2,12 +2,15 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod commands;
-mod db;
-mod services;
+mod events;
 mod state;
 
+use zg_core::{db, server, services};
+
+use events::TauriReminderSink;
 use state::AppState;
-use tracing_subscriber;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
 
 fn main() {
     // Initialize logging
@@ -23,9 +26,25 @@ fn main() {
 
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_notification::init())
         .manage(app_state)
+        .setup(|app| {
+            events::forward_domain_events(&app.state::<AppState>().event_bus, app.handle().clone());
+
+            // An encrypted database waits for `unlock_database`
+            if !app.state::<AppState>().db_pool.status().locked {
+                start_background_work(app.handle());
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
+            commands::database::get_database_status,
+            commands::database::unlock_database,
+            commands::database::encrypt_database,
+            commands::database::change_database_passphrase,
+            commands::database::backup_database,
             commands::tasks::create_task,
+            commands::tasks::quick_add,
             commands::tasks::get_tasks,
             commands::tasks::get_task_by_id,
             commands::tasks::update_task,
@@ -33,10 +52,108 @@ fn main() {
             commands::tasks::complete_task,
             commands::tasks::get_task_with_subtasks,
             commands::tasks::get_subtasks,
+            commands::tasks::get_my_tasks,
+            commands::tasks::assign_task,
+            commands::tasks::suggest_next_tasks,
+            commands::ai::breakdown_task,
+            commands::ai::create_subtasks,
+            commands::ai::get_ai_settings,
+            commands::ai::update_ai_settings,
+            commands::ai::get_ai_usage,
+            commands::preferences::get_preferences,
+            commands::preferences::update_preferences,
+            commands::preferences::generate_api_token,
+            commands::planner::plan_day,
+            commands::planner::reflow_day,
+            commands::planner::get_day_plan,
+            commands::planner::add_fixed_block,
+            commands::planner::delete_time_block,
+            commands::reminders::add_reminder,
+            commands::reminders::get_reminders,
+            commands::reminders::delete_reminder,
+            commands::reminders::snooze_reminder,
+            commands::reminders::get_quiet_hours,
+            commands::reminders::set_quiet_hours,
             commands::kanban::move_task_to_column,
             commands::kanban::get_tasks_by_status,
+            commands::todotxt::sync_todotxt,
+            commands::todotxt::watch_todotxt,
+            commands::todotxt::unwatch_todotxt,
+            commands::obsidian::sync_obsidian_vault,
+            commands::obsidian::watch_obsidian_vault,
+            commands::obsidian::unwatch_obsidian_vault,
+            commands::caldav::get_caldav_config,
+            commands::caldav::configure_caldav,
+            commands::caldav::disconnect_caldav,
+            commands::caldav::sync_caldav,
+            commands::device_sync::get_device_sync_config,
+            commands::device_sync::configure_device_sync,
+            commands::device_sync::disconnect_device_sync,
+            commands::device_sync::sync_devices,
+            commands::ics::export_ics,
+            commands::ics::import_ics,
+            commands::notes::create_note,
+            commands::notes::get_notes,
+            commands::notes::get_note,
+            commands::notes::update_note,
+            commands::notes::delete_note,
+            commands::notes::search_notes,
+            commands::notes::get_note_links,
+            commands::notes::get_note_backlinks,
+            commands::notes::get_task_backlinks,
+            commands::graph::get_graph,
+            commands::graph::get_orphan_notes,
+            commands::graph::get_graph_hubs,
+            commands::graph::get_shortest_path,
+            commands::workspaces::get_workspaces,
+            commands::workspaces::create_workspace,
+            commands::workspaces::rename_workspace,
+            commands::workspaces::delete_workspace,
+            commands::workspaces::get_active_workspace,
+            commands::workspaces::switch_workspace,
+            commands::workspaces::get_workspace_members,
+            commands::workspaces::set_workspace_member,
+            commands::workspaces::remove_workspace_member,
+            commands::workspaces::transfer_tasks,
+            commands::users::get_users,
+            commands::users::get_current_user,
+            commands::users::create_user,
+            commands::users::update_user,
+            commands::users::set_pin,
+            commands::users::sign_in,
+            commands::users::sign_out,
             commands::tasks::debug_database,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+/// Work that needs the database, started once it is open
+pub fn start_background_work(app: &AppHandle) {
+    let state = app.state::<AppState>();
+
+    // A PIN-protected profile has to be unlocked again after a restart
+    if let Err(e) = state.user_service.lock_if_protected() {
+        tracing::error!("Failed to lock the user profile: {e}");
+    }
+
+    // Resume AI requests queued in earlier sessions
+    state.ai_queue_service.start_worker();
+
+    let reminders = Arc::clone(&state.reminder_service);
+    let sink = Arc::new(TauriReminderSink::new(app.clone()));
+    tauri::async_runtime::spawn(reminders.run_scheduler(sink));
+
+    let api_server = Arc::clone(&state.api_server);
+    tauri::async_runtime::spawn(api_server.run());
+
+    match state.device_sync_service.get_config() {
+        Ok(Some(_)) => {
+            if let Err(e) = state.device_sync_service.watch() {
+                tracing::error!("Failed to start device sync: {e:#}");
+            }
+        }
+        Ok(None) => {}
+        Err(e) => tracing::error!("Failed to read the device sync config: {e}"),
+    }
+}
@@ -1,3 +0,0 @@
-pub mod task_service;
-
-pub use task_service::TaskService;
@@ -1,16 +1,68 @@
 use crate::db::DbPool;
-use crate::services::TaskService;
+use crate::server::ApiServer;
+use crate::services::{AiQueueService, BreakdownService, CaldavSyncService, DeviceSyncService, EventBus, GraphService, IcsService, NoteService, ObsidianSyncService, PlannerService, PreferencesService, RecommendationService, ReminderService, TaskService, TodoTxtSyncService, UserService, WorkspaceService};
 use std::sync::Arc;
 
 pub struct AppState {
+    pub event_bus: Arc<EventBus>,
+    /// Holds the signed-in user, whose workspace role guards all other services
+    pub user_service: Arc<UserService>,
+    /// Holds the active workspace, which scopes all other services
+    pub workspace_service: Arc<WorkspaceService>,
     pub task_service: Arc<TaskService>,
+    pub recommendation_service: Arc<RecommendationService>,
+    pub planner_service: Arc<PlannerService>,
+    pub reminder_service: Arc<ReminderService>,
+    pub ics_service: Arc<IcsService>,
+    pub note_service: Arc<NoteService>,
+    pub graph_service: Arc<GraphService>,
+    pub ai_queue_service: Arc<AiQueueService>,
+    pub breakdown_service: Arc<BreakdownService>,
+    pub preferences_service: Arc<PreferencesService>,
+    pub caldav_sync_service: Arc<CaldavSyncService>,
+    pub todotxt_sync_service: Arc<TodoTxtSyncService>,
+    pub obsidian_sync_service: Arc<ObsidianSyncService>,
+    pub device_sync_service: Arc<DeviceSyncService>,
+    pub api_server: Arc<ApiServer>,
     pub db_pool: DbPool,
 }
 
 impl AppState {
     pub fn new(pool: DbPool) -> Self {
+        let event_bus = Arc::new(EventBus::new());
+        ReminderService::subscribe(&event_bus);
+        let ai_queue_service = Arc::new(AiQueueService::new(pool.clone()));
+        let task_service = Arc::new(TaskService::new(pool.clone(), Arc::clone(&event_bus)));
+        let preferences_service = Arc::new(PreferencesService::new(pool.clone(), Arc::clone(&event_bus)));
+        let api_server = Arc::new(ApiServer::new(
+            Arc::clone(&task_service),
+            Arc::clone(&preferences_service),
+            &event_bus,
+        ));
+
         Self {
-            task_service: Arc::new(TaskService::new(pool.clone())),
+            user_service: Arc::new(UserService::new(pool.clone())),
+            workspace_service: Arc::new(WorkspaceService::new(pool.clone(), Arc::clone(&event_bus))),
+            task_service,
+            recommendation_service: Arc::new(RecommendationService::new(pool.clone())),
+            planner_service: Arc::new(PlannerService::new(pool.clone())),
+            reminder_service: Arc::new(ReminderService::new(pool.clone())),
+            ics_service: Arc::new(IcsService::new(pool.clone(), Arc::clone(&event_bus))),
+            note_service: Arc::new(NoteService::new(pool.clone())),
+            graph_service: Arc::new(GraphService::new(pool.clone())),
+            breakdown_service: Arc::new(BreakdownService::new(
+                pool.clone(),
+                Arc::clone(&event_bus),
+                Arc::clone(&ai_queue_service),
+            )),
+            ai_queue_service,
+            preferences_service,
+            caldav_sync_service: Arc::new(CaldavSyncService::new(pool.clone(), Arc::clone(&event_bus))),
+            todotxt_sync_service: Arc::new(TodoTxtSyncService::new(pool.clone(), Arc::clone(&event_bus))),
+            obsidian_sync_service: Arc::new(ObsidianSyncService::new(pool.clone(), Arc::clone(&event_bus))),
+            device_sync_service: Arc::new(DeviceSyncService::new(pool.clone(), Arc::clone(&event_bus))),
+            api_server,
+            event_bus,
             db_pool: pool,
         }
     }
@@ -1,10 +1,14 @@
 import { invoke } from '@tauri-apps/api/core';
-import type { Task, CreateTaskInput, UpdateTaskInput, TaskWithSubtasks } from '$types/task';
+import type { Task, CreateTaskInput, UpdateTaskInput, TaskWithSubtasks, QuickAddPreview, SubtaskSuggestion, AiSettings, AiSettingsInput, AiUsage, ScoringWeights, TaskSuggestion, PlanSettings, TimeBlock, DayPlan, Reminder, CreateReminderInput, QuietHours, Preferences, Workspace, CreateWorkspaceInput, WorkspaceMember, WorkspaceRole, User, CreateUserInput, UpdateUserInput, DatabaseStatus } from '$types/task';
 
 export async function createTask(input: CreateTaskInput): Promise<Task> {
 	return invoke('create_task', { input });
 }
 
+export async function quickAdd(text: string): Promise<QuickAddPreview> {
+	return invoke('quick_add', { text });
+}
+
 export async function getTasks(status?: string): Promise<Task[]> {
 	return invoke('get_tasks', { status });
 }
@@ -34,13 +38,236 @@ export async function getSubtasks(parent_id: number): Promise<Task[]> {
 	return invoke('get_subtasks', { parentId: parent_id });
 }
 
+export async function getMyTasks(status?: string): Promise<Task[]> {
+	return invoke('get_my_tasks', { status });
+}
+
+/** Assign to a member of the workspace, or unassign with null */
+export async function assignTask(id: number, assigneeId: number | null): Promise<Task> {
+	return invoke('assign_task', { id, assigneeId });
+}
+
+export async function suggestNextTasks(
+	currentEnergy: string,
+	availableMinutes: number,
+	limit?: number,
+	weights?: Partial<ScoringWeights>
+): Promise<TaskSuggestion[]> {
+	return invoke('suggest_next_tasks', { currentEnergy, availableMinutes, limit, weights });
+}
+
+// Reminder APIs
+export async function addReminder(taskId: number, input: CreateReminderInput): Promise<Reminder> {
+	return invoke('add_reminder', { taskId, input });
+}
+
+export async function getReminders(taskId: number): Promise<Reminder[]> {
+	return invoke('get_reminders', { taskId });
+}
+
+export async function deleteReminder(id: number): Promise<void> {
+	return invoke('delete_reminder', { id });
+}
+
+export async function snoozeReminder(id: number, minutes: number): Promise<Reminder> {
+	return invoke('snooze_reminder', { id, minutes });
+}
+
+export async function getQuietHours(): Promise<QuietHours | null> {
+	return invoke('get_quiet_hours');
+}
+
+export async function setQuietHours(hours: QuietHours | null): Promise<QuietHours | null> {
+	return invoke('set_quiet_hours', { hours });
+}
+
+// Day planner APIs
+export async function planDay(date: string, settings?: Partial<PlanSettings>): Promise<DayPlan> {
+	return invoke('plan_day', { date, settings });
+}
+
+export async function reflowDay(
+	date: string,
+	fromTime?: string,
+	settings?: Partial<PlanSettings>
+): Promise<DayPlan> {
+	return invoke('reflow_day', { date, fromTime, settings });
+}
+
+export async function getDayPlan(date: string): Promise<DayPlan> {
+	return invoke('get_day_plan', { date });
+}
+
+export async function addFixedBlock(
+	date: string,
+	startTime: string,
+	endTime: string,
+	title: string
+): Promise<TimeBlock> {
+	return invoke('add_fixed_block', { date, startTime, endTime, title });
+}
+
+export async function deleteTimeBlock(id: number): Promise<void> {
+	return invoke('delete_time_block', { id });
+}
+
+// AI APIs
+export async function breakdownTask(taskId: number): Promise<SubtaskSuggestion[]> {
+	return invoke('breakdown_task', { taskId });
+}
+
+export async function createSubtasks(
+	parentTaskId: number,
+	subtasks: SubtaskSuggestion[]
+): Promise<Task[]> {
+	return invoke('create_subtasks', { parentTaskId, subtasks });
+}
+
+export async function getAiSettings(): Promise<AiSettings> {
+	return invoke('get_ai_settings');
+}
+
+export async function updateAiSettings(settings: AiSettingsInput): Promise<AiSettings> {
+	return invoke('update_ai_settings', { settings });
+}
+
+export async function getAiUsage(from?: string, to?: string): Promise<AiUsage[]> {
+	return invoke('get_ai_usage', { from, to });
+}
+
+// Preferences APIs
+export async function getPreferences(): Promise<Preferences> {
+	return invoke('get_preferences');
+}
+
+/** Saved preferences are also announced as a `preferences:changed` event */
+export async function updatePreferences(preferences: Preferences): Promise<Preferences> {
+	return invoke('update_preferences', { preferences });
+}
+
+/** A new bearer token for the local API; it cannot be read again later */
+export async function generateApiToken(): Promise<string> {
+	return invoke('generate_api_token');
+}
+
+// Database encryption APIs
+export async function getDatabaseStatus(): Promise<DatabaseStatus> {
+	return invoke('get_database_status');
+}
+
+export async function unlockDatabase(passphrase: string): Promise<void> {
+	return invoke('unlock_database', { passphrase });
+}
+
+/** Encrypt the database in place; from the next start on it has to be unlocked */
+export async function encryptDatabase(passphrase: string): Promise<DatabaseStatus> {
+	return invoke('encrypt_database', { passphrase });
+}
+
+export async function changeDatabasePassphrase(current: string, newPassphrase: string): Promise<void> {
+	return invoke('change_database_passphrase', { current, new: newPassphrase });
+}
+
+/** Copy the database to a new file, encrypted like the database itself */
+export async function backupDatabase(path: string): Promise<void> {
+	return invoke('backup_database', { path });
+}
+
+// Workspace APIs
+export async function getWorkspaces(): Promise<Workspace[]> {
+	return invoke('get_workspaces');
+}
+
+export async function createWorkspace(input: CreateWorkspaceInput): Promise<Workspace> {
+	return invoke('create_workspace', { input });
+}
+
+export async function renameWorkspace(id: number, name: string): Promise<Workspace> {
+	return invoke('rename_workspace', { id, name });
+}
+
+export async function deleteWorkspace(id: number): Promise<void> {
+	return invoke('delete_workspace', { id });
+}
+
+export async function getActiveWorkspace(): Promise<Workspace> {
+	return invoke('get_active_workspace');
+}
+
+/** Everything else is scoped to the active workspace, so reload after switching */
+export async function switchWorkspace(id: number): Promise<Workspace> {
+	return invoke('switch_workspace', { id });
+}
+
+/** Move or copy tasks with their subtasks; returns how many were transferred */
+export async function transferTasks(
+	taskIds: number[],
+	workspaceId: number,
+	copy: boolean
+): Promise<number> {
+	return invoke('transfer_tasks', { taskIds, workspaceId, copy });
+}
+
+export async function getWorkspaceMembers(workspaceId: number): Promise<WorkspaceMember[]> {
+	return invoke('get_workspace_members', { workspaceId });
+}
+
+/** Add a user to a workspace or change their role; owners only */
+export async function setWorkspaceMember(
+	workspaceId: number,
+	userId: number,
+	role: WorkspaceRole
+): Promise<WorkspaceMember> {
+	return invoke('set_workspace_member', { workspaceId, userId, role });
+}
+
+export async function removeWorkspaceMember(workspaceId: number, userId: number): Promise<void> {
+	return invoke('remove_workspace_member', { workspaceId, userId });
+}
+
+// User APIs
+export async function getUsers(): Promise<User[]> {
+	return invoke('get_users');
+}
+
+/** null while locked: sign in before calling anything else */
+export async function getCurrentUser(): Promise<User | null> {
+	return invoke('get_current_user');
+}
+
+export async function createUser(input: CreateUserInput): Promise<User> {
+	return invoke('create_user', { input });
+}
+
+export async function updateUser(id: number, input: UpdateUserInput): Promise<User> {
+	return invoke('update_user', { id, input });
+}
+
+/** Set, change or (with newPin null) remove a PIN */
+export async function setPin(
+	id: number,
+	currentPin: string | null,
+	newPin: string | null
+): Promise<User> {
+	return invoke('set_pin', { id, currentPin, newPin });
+}
+
+export async function signIn(id: number, pin?: string): Promise<User> {
+	return invoke('sign_in', { id, pin });
+}
+
+export async function signOut(): Promise<void> {
+	return invoke('sign_out');
+}
+
 // Kanban APIs
 export async function moveTaskToColumn(
 	taskId: number,
 	newStatus: string,
-	position: number
+	position: number,
+	expectedVersion?: number
 ): Promise<Task> {
-	return invoke('move_task_to_column', { taskId, newStatus, position });
+	return invoke('move_task_to_column', { taskId, newStatus, position, expectedVersion });
 }
 
 export async function getTasksByStatus(status: string): Promise<Task[]> {
@@ -1,5 +1,6 @@
 import { writable, derived } from 'svelte/store';
-import type { Task, CreateTaskInput, UpdateTaskInput } from '$types/task';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import type { Task, CreateTaskInput, UpdateTaskInput, EditError } from '$types/task';
 import * as taskApi from '$lib/api/tasks';
 
 // Store for all tasks
@@ -35,6 +36,26 @@ export const todayTasks = derived(tasks, ($tasks) => {
 	);
 });
 
+// Keep the store in sync with changes made elsewhere (other windows, background jobs)
+export async function subscribeToTaskEvents(): Promise<UnlistenFn> {
+	const upsert = (task: Task) =>
+		tasks.update((current) =>
+			current.some((t) => t.id === task.id)
+				? current.map((t) => (t.id === task.id ? task : t))
+				: [task, ...current]
+		);
+
+	const unlisteners = await Promise.all([
+		listen<Task>('task:created', (event) => upsert(event.payload)),
+		listen<Task>('task:updated', (event) => upsert(event.payload)),
+		listen<Task>('task:moved', (event) => upsert(event.payload)),
+		listen<Task>('task:deleted', (event) =>
+			tasks.update((current) => current.filter((t) => t.id !== event.payload.id))
+		)
+	]);
+	return () => unlisteners.forEach((unlisten) => unlisten());
+}
+
 // Actions
 export async function loadTasks(status?: string) {
 	loading.set(true);
@@ -74,7 +95,7 @@ export async function modifyTask(id: number, input: UpdateTaskInput) {
 		tasks.update((current) => current.map((t) => (t.id === id ? updatedTask : t)));
 		return updatedTask;
 	} catch (e) {
-		error.set(e instanceof Error ? e.message : 'Failed to update task');
+		error.set(e instanceof Error ? e.message : ((e as EditError).message ?? 'Failed to update task'));
 		console.error('Failed to update task:', e);
 		throw e;
 	} finally {
@@ -1,5 +1,6 @@
 export interface Task {
 	id: number;
+	uid: string;
 	user_id: number;
 	workspace_id: number;
 	title: string;
@@ -19,12 +20,16 @@ export interface Task {
 	tags?: string;
 	created_at: string;
 	updated_at: string;
+	assignee_id?: number;
+	/** Incremented on every change */
+	version: number;
 }
 
 export interface CreateTaskInput {
 	title: string;
 	description?: string;
 	project_id?: number;
+	priority?: number;
 	estimated_minutes?: number;
 	difficulty_level?: number;
 	energy_level?: string;
@@ -34,22 +39,41 @@ export interface CreateTaskInput {
 	tags?: string[];
 }
 
+export interface QuickAddPreview {
+	input: CreateTaskInput;
+	project_name?: string;
+}
+
+export interface SubtaskSuggestion {
+	title: string;
+	estimated_minutes?: number;
+	energy_level?: string;
+}
+
+/** Leave a field out to keep it, set it to null to clear it */
 export interface UpdateTaskInput {
 	title?: string;
-	description?: string;
-	project_id?: number;
+	description?: string | null;
+	project_id?: number | null;
 	status?: string;
 	priority?: number;
-	estimated_minutes?: number;
-	difficulty_level?: number;
-	energy_level?: string;
-	scheduled_date?: string;
-	due_date?: string;
-	parent_task_id?: number;
+	estimated_minutes?: number | null;
+	difficulty_level?: number | null;
+	energy_level?: string | null;
+	scheduled_date?: string | null;
+	due_date?: string | null;
+	parent_task_id?: number | null;
 	order_index?: number;
-	tags?: string[];
+	tags?: string[] | null;
+	/** Fail with a conflict if the task changed since this version */
+	expected_version?: number;
 }
 
+/** Error of `updateTask` and `moveTaskToColumn`; a conflict has the current task to merge with */
+export type EditError =
+	| { kind: 'conflict'; message: string; current: Task }
+	| { kind: 'failed'; message: string };
+
 export interface SubtaskProgress {
 	total: number;
 	completed: number;
@@ -61,3 +85,189 @@ export interface TaskWithSubtasks {
 	subtasks: Task[];
 	progress: SubtaskProgress;
 }
+
+export type AiProvider = 'anthropic' | 'openai_compatible';
+
+export interface AiSettings {
+	provider: AiProvider;
+	model: string;
+	base_url: string;
+	timeout_secs: number;
+}
+
+export interface AiSettingsInput {
+	provider: AiProvider;
+	model?: string;
+	base_url?: string;
+	/** Omit to keep the stored key, empty string to remove it */
+	api_key?: string;
+	timeout_secs?: number;
+}
+
+export interface AiUsage {
+	day: string;
+	feature: string;
+	model: string;
+	requests: number;
+	cache_hits: number;
+	input_tokens: number;
+	output_tokens: number;
+	cost_usd: number;
+}
+
+export interface ScoringWeights {
+	deadline: number;
+	priority: number;
+	energy: number;
+	time_fit: number;
+	age: number;
+}
+
+export interface TaskSuggestion {
+	task: Task;
+	score: number;
+	/** Each factor is between 0 and 1 */
+	factors: ScoringWeights;
+	reason: string;
+}
+
+/** Times are HH:MM */
+export interface PlanSettings {
+	work_start: string;
+	work_end: string;
+	peak_start: string;
+	peak_end: string;
+	break_after_minutes: number;
+	break_minutes: number;
+	default_task_minutes: number;
+}
+
+export interface TimeBlock {
+	id: number;
+	date: string;
+	start_time: string;
+	end_time: string;
+	kind: 'fixed' | 'task' | 'break';
+	task_id: number | null;
+	title: string;
+}
+
+export interface DayPlan {
+	date: string;
+	blocks: TimeBlock[];
+	overflow: Task[];
+}
+
+export interface Reminder {
+	id: number;
+	task_id: number;
+	remind_at: string | null;
+	minutes_before_due: number | null;
+	snoozed_until: string | null;
+	fired_at: string | null;
+	next_fire_at: string | null;
+}
+
+/** Either a local time (YYYY-MM-DDTHH:MM) or minutes before the due date */
+export interface CreateReminderInput {
+	remind_at?: string;
+	minutes_before_due?: number;
+}
+
+/** Payload of the `reminder` event */
+export interface ReminderNotification {
+	reminder_id: number;
+	task_id: number;
+	title: string;
+	due_date: string | null;
+	fire_at: string;
+}
+
+export interface QuietHours {
+	start: string;
+	end: string;
+}
+
+export interface PomodoroSettings {
+	focus_minutes: number;
+	short_break_minutes: number;
+	long_break_minutes: number;
+	sessions_before_long_break: number;
+}
+
+/** AI settings without the key; null means the provider's default */
+export interface AiPreferences {
+	provider: AiProvider | null;
+	model: string | null;
+	base_url: string | null;
+	timeout_secs: number | null;
+}
+
+/** Device-wide settings, shared by all users of the database */
+export interface Preferences {
+	theme: 'system' | 'light' | 'dark';
+	default_view: 'list' | 'kanban';
+	/** HH:MM at which a new day begins */
+	rollover_time: string;
+	working_hours: PlanSettings;
+	pomodoro: PomodoroSettings;
+	quiet_hours: QuietHours | null;
+	ai: AiPreferences;
+	api_server: ApiServerPreferences;
+}
+
+/** Local REST API on 127.0.0.1, off by default */
+export interface ApiServerPreferences {
+	enabled: boolean;
+	port: number;
+}
+
+/** An encrypted database is locked until `unlockDatabase` */
+export interface DatabaseStatus {
+	encrypted: boolean;
+	locked: boolean;
+}
+
+export type WorkspaceType = 'personal' | 'team';
+
+export interface Workspace {
+	id: number;
+	name: string;
+	workspace_type: WorkspaceType;
+	owner_user_id: number | null;
+	created_at: string;
+	updated_at: string;
+}
+
+export interface CreateWorkspaceInput {
+	name: string;
+	workspace_type?: WorkspaceType;
+}
+
+export type WorkspaceRole = 'owner' | 'editor' | 'viewer';
+
+export interface WorkspaceMember {
+	user_id: number;
+	display_name: string;
+	role: WorkspaceRole;
+}
+
+export interface User {
+	id: number;
+	display_name: string;
+	email?: string;
+	has_pin: boolean;
+	created_at: string;
+	updated_at: string;
+}
+
+export interface CreateUserInput {
+	display_name: string;
+	email?: string;
+	pin?: string;
+}
+
+export interface UpdateUserInput {
+	display_name?: string;
+	email?: string;
+}
@@ -0,0 +1,372 @@
+//! `zg`: Zweites Gehirn from the command line.
+//!
+//! Works on the app's database as the signed-in user and can be used while
+//! the app is open: the database runs in WAL mode and waits for locks held by
+//! the app instead of failing. An encrypted database is unlocked with the
+//! passphrase in `ZG_PASSPHRASE`.
+
+use anyhow::{bail, Result};
+use clap::{Parser, Subcommand};
+use serde::Serialize;
+use std::io::Write;
+use std::process::ExitCode;
+use std::sync::Arc;
+use zg_core::db::{self, encryption::Passphrase, models::*, DbPool};
+use zg_core::services::{EventBus, NoteService, ReminderService, TaskService};
+
+#[derive(Parser)]
+#[command(name = "zg", version, about = "Zweites Gehirn from the command line")]
+struct Cli {
+    /// Print JSON instead of a table
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Add a task written as in quick add, e.g. "call dentist tomorrow 3pm #health !2"
+    Add {
+        #[arg(required = true)]
+        text: Vec<String>,
+    },
+    /// List tasks
+    Ls {
+        /// todo, in_progress, completed, ...
+        #[arg(long)]
+        status: Option<String>,
+        /// today, overdue or a YYYY-MM-DD date: due on or before that day
+        #[arg(long)]
+        due: Option<String>,
+        #[arg(long)]
+        project: Option<i64>,
+    },
+    /// Complete a task
+    Done { id: i64 },
+    /// Show a task
+    Show {
+        id: i64,
+        /// Include all subtasks
+        #[arg(long)]
+        tree: bool,
+    },
+    /// Search tasks and notes
+    Search {
+        #[arg(required = true)]
+        query: Vec<String>,
+    },
+}
+
+/// Passphrase of an encrypted database
+const PASSPHRASE_VAR: &str = "ZG_PASSPHRASE";
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = open_database().and_then(|pool| run(cli, pool, &mut std::io::stdout().lock()));
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("zg: {e:#}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn open_database() -> Result<DbPool> {
+    let pool = db::init_database()?;
+    if pool.status().locked {
+        let Ok(passphrase) = std::env::var(PASSPHRASE_VAR) else {
+            bail!("The database is encrypted, set {PASSPHRASE_VAR} to its passphrase");
+        };
+        pool.unlock(Passphrase::new(passphrase)?)?;
+    }
+    Ok(pool)
+}
+
+fn run(cli: Cli, pool: DbPool, out: &mut impl Write) -> Result<()> {
+    let events = Arc::new(EventBus::new());
+    ReminderService::subscribe(&events);
+    let tasks = TaskService::new(pool.clone(), events);
+
+    match cli.command {
+        Command::Add { text } => {
+            let preview = tasks.parse_quick_add(&text.join(" "))?;
+            if let Some(name) = preview.project_name.filter(|_| preview.input.project_id.is_none()) {
+                eprintln!("zg: no project named {name}, the task was added without one");
+            }
+            let task = tasks.create_task(preview.input)?;
+            print_tasks(out, cli.json, &[task])
+        }
+        Command::Ls { status, due, project } => {
+            let query = TaskQuery {
+                status,
+                due,
+                search: None,
+                project_id: project,
+            };
+            print_tasks(out, cli.json, &tasks.query_tasks(&query)?)
+        }
+        Command::Done { id } => {
+            let task = tasks.complete_task(id)?;
+            print_tasks(out, cli.json, &[task])
+        }
+        Command::Show { id, tree } => {
+            let task = TaskTree::load(&tasks, tasks.get_task(id)?, tree)?;
+            if cli.json {
+                print_json(out, &task)
+            } else {
+                task.print(out, 0)
+            }
+        }
+        Command::Search { query } => {
+            let query = query.join(" ");
+            let found = SearchResults {
+                tasks: tasks.query_tasks(&TaskQuery {
+                    search: Some(query.clone()),
+                    ..Default::default()
+                })?,
+                notes: NoteService::new(pool).search_notes(&query)?,
+            };
+            if cli.json {
+                return print_json(out, &found);
+            }
+            print_tasks(out, false, &found.tasks)?;
+            writeln!(out)?;
+            print_table(
+                out,
+                ["ID", "NOTE"],
+                found.notes.iter().map(|n| [n.id.to_string(), n.title.clone()]),
+            )
+        }
+    }
+}
+
+/// A task with its subtasks, loaded only for `show --tree`
+#[derive(Serialize)]
+struct TaskTree {
+    #[serde(flatten)]
+    task: Task,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    subtasks: Option<Vec<TaskTree>>,
+}
+
+impl TaskTree {
+    fn load(tasks: &TaskService, task: Task, recursive: bool) -> Result<Self> {
+        let subtasks = if recursive {
+            let children = tasks.get_subtasks(task.id)?;
+            Some(
+                children
+                    .into_iter()
+                    .map(|child| Self::load(tasks, child, true))
+                    .collect::<Result<_>>()?,
+            )
+        } else {
+            None
+        };
+        Ok(Self { task, subtasks })
+    }
+
+    fn print(&self, out: &mut impl Write, depth: usize) -> Result<()> {
+        let task = &self.task;
+        if depth == 0 {
+            writeln!(out, "#{} {}", task.id, task.title)?;
+            writeln!(out, "status:   {}", task.status)?;
+            writeln!(out, "priority: {}", task.priority)?;
+            for (label, value) in [
+                ("due:      ", &task.due_date),
+                ("scheduled:", &task.scheduled_date),
+                ("tags:     ", &task.tags),
+            ] {
+                if let Some(value) = value {
+                    writeln!(out, "{label} {value}")?;
+                }
+            }
+            if let Some(description) = &task.description {
+                writeln!(out, "\n{description}")?;
+            }
+            if self.subtasks.as_ref().is_some_and(|s| !s.is_empty()) {
+                writeln!(out)?;
+            }
+        } else {
+            let mark = if task.status == "completed" { "x" } else { " " };
+            writeln!(out, "{}[{mark}] #{} {}", "  ".repeat(depth - 1), task.id, task.title)?;
+        }
+
+        for subtask in self.subtasks.iter().flatten() {
+            subtask.print(out, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+struct SearchResults {
+    tasks: Vec<Task>,
+    notes: Vec<Note>,
+}
+
+fn print_tasks(out: &mut impl Write, json: bool, tasks: &[Task]) -> Result<()> {
+    if json {
+        return print_json(out, &tasks);
+    }
+    print_table(
+        out,
+        ["ID", "STATUS", "PRI", "DUE", "TITLE"],
+        tasks.iter().map(|t| {
+            [
+                t.id.to_string(),
+                t.status.clone(),
+                t.priority.to_string(),
+                t.due_date.clone().unwrap_or_default(),
+                t.title.clone(),
+            ]
+        }),
+    )
+}
+
+fn print_json(out: &mut impl Write, value: &impl Serialize) -> Result<()> {
+    serde_json::to_writer_pretty(&mut *out, value)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Columns padded to their widest cell; the last one is not padded
+fn print_table<const N: usize>(
+    out: &mut impl Write,
+    header: [&str; N],
+    rows: impl Iterator<Item = [String; N]>,
+) -> Result<()> {
+    let rows: Vec<[String; N]> = rows.collect();
+    let mut widths = header.map(|h| h.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let header = header.map(String::from);
+    for row in std::iter::once(&header).chain(&rows) {
+        let mut line = String::new();
+        for (i, cell) in row.iter().enumerate() {
+            if i + 1 < N {
+                line.push_str(&format!("{cell:<width$}  ", width = widths[i]));
+            } else {
+                line.push_str(cell);
+            }
+        }
+        writeln!(out, "{}", line.trim_end())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    /// A fresh database file, removed when dropped
+    struct TempDatabase(PathBuf);
+
+    impl TempDatabase {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("zg-test-{name}-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            Self(dir)
+        }
+
+        fn pool(&self) -> DbPool {
+            db::init_database_at(&self.0.join("zweites-gehirn.db")).unwrap()
+        }
+    }
+
+    impl Drop for TempDatabase {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn zg(pool: &DbPool, args: &[&str]) -> String {
+        let cli = Cli::try_parse_from(std::iter::once("zg").chain(args.iter().copied())).unwrap();
+        let mut out = Vec::new();
+        run(cli, pool.clone(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn adds_lists_and_completes_tasks() {
+        let db = TempDatabase::new("tasks");
+        let pool = db.pool();
+
+        let added: Value = serde_json::from_str(&zg(&pool, &["add", "--json", "write", "report", "!3"])).unwrap();
+        let id = added[0]["id"].as_i64().unwrap();
+        assert_eq!(added[0]["title"], "write report");
+        assert_eq!(added[0]["priority"], 3);
+        zg(&pool, &["add", "call", "dentist"]);
+
+        let table = zg(&pool, &["ls", "--status", "todo"]);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("ID  STATUS  PRI  DUE"));
+        assert!(lines[1].ends_with("write report"));
+
+        zg(&pool, &["done", &id.to_string()]);
+        let open: Value = serde_json::from_str(&zg(&pool, &["ls", "--json", "--status", "todo"])).unwrap();
+        assert_eq!(open.as_array().unwrap().len(), 1);
+        assert_eq!(open[0]["title"], "call dentist");
+    }
+
+    #[test]
+    fn shows_the_subtask_tree() {
+        let db = TempDatabase::new("tree");
+        let pool = db.pool();
+        let tasks = TaskService::new(pool.clone(), Arc::new(EventBus::new()));
+        let create = |title: &str, parent_task_id| {
+            tasks
+                .create_task(CreateTaskInput {
+                    title: title.into(),
+                    parent_task_id,
+                    ..Default::default()
+                })
+                .unwrap()
+        };
+        let root = create("Move house", None);
+        let packing = create("Pack", Some(root.id));
+        create("Books", Some(packing.id));
+
+        let text = zg(&pool, &["show", &root.id.to_string(), "--tree"]);
+        assert!(text.starts_with(&format!("#{} Move house\n", root.id)));
+        assert!(text.contains(&format!("[ ] #{} Pack\n", packing.id)));
+        assert!(text.contains("  [ ] #"));
+
+        let tree: Value = serde_json::from_str(&zg(&pool, &["show", "--json", &root.id.to_string(), "--tree"])).unwrap();
+        assert_eq!(tree["subtasks"][0]["subtasks"][0]["title"], "Books");
+        let flat: Value = serde_json::from_str(&zg(&pool, &["show", "--json", &root.id.to_string()])).unwrap();
+        assert!(flat.get("subtasks").is_none());
+    }
+
+    #[test]
+    fn waits_for_a_write_of_the_app() {
+        let db = TempDatabase::new("busy");
+        let app = db.pool();
+        let cli = db.pool();
+
+        // The app holds the write lock for a moment
+        let (locked, wait) = std::sync::mpsc::channel();
+        let writer = std::thread::spawn(move || {
+            let mut conn = app.get().unwrap();
+            let tx = conn.transaction_with_behavior(rusqlite::TransactionBehavior::Immediate).unwrap();
+            locked.send(()).unwrap();
+            std::thread::sleep(Duration::from_millis(300));
+            tx.commit().unwrap();
+        });
+        wait.recv().unwrap();
+
+        let text = zg(&cli, &["add", "while", "the", "app", "writes"]);
+        assert!(text.contains("while the app writes"));
+        writer.join().unwrap();
+    }
+}
@@ -0,0 +1,96 @@
+//! Encryption at rest with SQLCipher. An encrypted database is opened with
+//! its passphrase; without one SQLCipher reads and writes plain SQLite files.
+
+use anyhow::Result;
+use rusqlite::{params, Connection, ErrorCode};
+use std::fmt;
+use std::io::Read;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// Every plaintext SQLite database starts with this header
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+#[derive(Debug, thiserror::Error)]
+pub enum EncryptionError {
+    #[error("The database is locked, unlock it with its passphrase")]
+    Locked,
+    #[error("Wrong passphrase")]
+    WrongPassphrase,
+    #[error("The database is already encrypted")]
+    AlreadyEncrypted,
+    #[error("The database is not encrypted")]
+    NotEncrypted,
+}
+
+/// Passphrase of an encrypted database. It has no `Display` and its `Debug`
+/// output is redacted, so it cannot end up in logs or error messages, and
+/// its memory is wiped when dropped.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Passphrase(Zeroizing<String>);
+
+impl Passphrase {
+    pub fn new(passphrase: String) -> Result<Self> {
+        let passphrase = Zeroizing::new(passphrase);
+        if passphrase.trim().is_empty() {
+            anyhow::bail!("The passphrase must not be empty");
+        }
+        Ok(Self(passphrase))
+    }
+
+    fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Passphrase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Passphrase(<redacted>)")
+    }
+}
+
+/// Whether the file at `path` is an encrypted database. A missing or empty
+/// file is a new plaintext database.
+pub fn is_encrypted(path: &Path) -> Result<bool> {
+    let mut header = [0u8; 16];
+    match std::fs::File::open(path) {
+        Ok(mut file) => match file.read_exact(&mut header) {
+            Ok(()) => Ok(&header != SQLITE_HEADER),
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(false),
+            Err(e) => Err(e.into()),
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Set the key of a freshly opened connection; must come before any query
+pub(crate) fn apply_key(conn: &Connection, passphrase: &Passphrase) -> rusqlite::Result<()> {
+    conn.pragma_update(None, "key", passphrase.expose())
+}
+
+/// Fail with `WrongPassphrase` unless the connection can read the database
+pub(crate) fn verify_key(conn: &Connection) -> Result<()> {
+    match conn.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(())) {
+        Err(rusqlite::Error::SqliteFailure(e, _)) if e.code == ErrorCode::NotADatabase => {
+            Err(EncryptionError::WrongPassphrase.into())
+        }
+        result => Ok(result?),
+    }
+}
+
+/// Copy the whole database of `conn` into a new file at `target`, encrypted
+/// with `passphrase` or in plaintext without one
+pub(crate) fn export(conn: &Connection, target: &Path, passphrase: Option<&Passphrase>) -> Result<()> {
+    if target.exists() {
+        anyhow::bail!("{} already exists", target.display());
+    }
+    conn.execute(
+        "ATTACH DATABASE ?1 AS export KEY ?2",
+        params![target.to_string_lossy(), passphrase.map_or("", Passphrase::expose)],
+    )?;
+    let exported = conn.query_row("SELECT sqlcipher_export('export')", [], |_| Ok(()));
+    conn.execute("DETACH DATABASE export", [])?;
+    exported?;
+    Ok(())
+}
@@ -0,0 +1,369 @@
+pub mod encryption;
+pub mod models;
+pub mod repositories;
+pub mod schema;
+
+use anyhow::Result;
+use encryption::{EncryptionError, Passphrase};
+use models::DatabaseStatus;
+use r2d2::{Pool, PooledConnection};
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{Connection, TransactionBehavior};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// How long a connection waits for a lock held by another process, e.g. the
+/// `zg` client writing while the app is open. WAL mode (set by the
+/// migrations) lets readers and one writer work side by side.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Enforce foreign keys, which SQLite turns off for every new connection,
+/// wait for locks, and take the write lock when a transaction begins: a
+/// transaction that reads first and then writes cannot wait for a writer
+/// that committed in between and would fail right away
+fn configure_connection(conn: &mut Connection) -> rusqlite::Result<()> {
+    conn.pragma_update(None, "foreign_keys", true)?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    conn.set_transaction_behavior(TransactionBehavior::Immediate);
+    Ok(())
+}
+
+/// Connection pool of the database. An encrypted database is locked, without
+/// connections, until it is unlocked with its passphrase.
+#[derive(Clone)]
+pub struct DbPool(Arc<RwLock<Connections>>);
+
+struct Connections {
+    /// `None` for in-memory test databases
+    path: Option<PathBuf>,
+    /// `None` while locked
+    pool: Option<Pool<SqliteConnectionManager>>,
+    passphrase: Option<Passphrase>,
+}
+
+impl DbPool {
+    fn new(path: Option<PathBuf>, pool: Option<Pool<SqliteConnectionManager>>) -> Self {
+        Self(Arc::new(RwLock::new(Connections {
+            path,
+            pool,
+            passphrase: None,
+        })))
+    }
+
+    pub fn get(&self) -> Result<PooledConnection<SqliteConnectionManager>> {
+        let pool = self.0.read().unwrap().pool.clone();
+        Ok(pool.ok_or(EncryptionError::Locked)?.get()?)
+    }
+
+    pub fn status(&self) -> DatabaseStatus {
+        let connections = self.0.read().unwrap();
+        DatabaseStatus {
+            locked: connections.pool.is_none(),
+            encrypted: connections.pool.is_none() || connections.passphrase.is_some(),
+        }
+    }
+
+    /// Open the encrypted database with its passphrase
+    pub fn unlock(&self, passphrase: Passphrase) -> Result<()> {
+        let mut connections = self.0.write().unwrap();
+        if connections.pool.is_some() {
+            anyhow::bail!("The database is not locked");
+        }
+        let path = connections.path()?.to_path_buf();
+
+        let conn = open_connection(&path, Some(&passphrase))?;
+        schema::run_migrations(&conn)?;
+        drop(conn);
+
+        connections.pool = Some(connect(&path, Some(&passphrase))?);
+        connections.passphrase = Some(passphrase);
+        tracing::info!("Database unlocked");
+        Ok(())
+    }
+
+    /// Encrypt the plaintext database in place
+    pub fn encrypt(&self, passphrase: Passphrase) -> Result<()> {
+        let mut connections = self.0.write().unwrap();
+        if connections.passphrase.is_some() {
+            return Err(EncryptionError::AlreadyEncrypted.into());
+        }
+        connections.rewrite(Some(passphrase))?;
+        tracing::info!("Database encrypted");
+        Ok(())
+    }
+
+    pub fn change_passphrase(&self, current: &Passphrase, new: Passphrase) -> Result<()> {
+        let mut connections = self.0.write().unwrap();
+        match &connections.passphrase {
+            None => return Err(EncryptionError::NotEncrypted.into()),
+            Some(passphrase) if passphrase != current => return Err(EncryptionError::WrongPassphrase.into()),
+            Some(_) => {}
+        }
+        connections.rewrite(Some(new))?;
+        tracing::info!("Database passphrase changed");
+        Ok(())
+    }
+
+    /// Copy the database to a new file at `target`, encrypted with the same
+    /// passphrase if the database is encrypted
+    pub fn backup_to(&self, target: &Path) -> Result<()> {
+        let passphrase = self.0.read().unwrap().passphrase.clone();
+        let conn = self.get()?;
+        encryption::export(&conn, target, passphrase.as_ref())
+    }
+}
+
+impl Connections {
+    fn path(&self) -> Result<&Path> {
+        self.path
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("An in-memory database cannot be encrypted"))
+    }
+
+    /// Rewrite the database file with `passphrase` and reopen the pool. Waits
+    /// for connections in use to be returned; other processes must not have
+    /// the database open.
+    fn rewrite(&mut self, passphrase: Option<Passphrase>) -> Result<()> {
+        let path = self.path()?.to_path_buf();
+        let pool = self.pool.take().ok_or(EncryptionError::Locked)?;
+        if let Err(e) = wait_until_idle(&pool) {
+            self.pool = Some(pool);
+            return Err(e);
+        }
+        drop(pool);
+
+        let rewritten = rewrite_file(&path, self.passphrase.as_ref(), passphrase.as_ref());
+        // Reopen with whichever key the file has now
+        let passphrase = if rewritten.is_ok() { passphrase } else { self.passphrase.clone() };
+        let pool = connect(&path, passphrase.as_ref())?;
+        // The copy is not in WAL mode yet
+        schema::run_migrations(&*pool.get()?)?;
+        self.pool = Some(pool);
+        self.passphrase = passphrase;
+        rewritten
+    }
+}
+
+fn wait_until_idle(pool: &Pool<SqliteConnectionManager>) -> Result<()> {
+    let started = Instant::now();
+    loop {
+        let state = pool.state();
+        if state.idle_connections == state.connections {
+            return Ok(());
+        }
+        if started.elapsed() > BUSY_TIMEOUT {
+            anyhow::bail!("The database is busy, try again");
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Export the database at `path` into a new file with the `new` key and put
+/// it in place of the original
+fn rewrite_file(path: &Path, current: Option<&Passphrase>, new: Option<&Passphrase>) -> Result<()> {
+    let target = path.with_extension("db.rewrite");
+    let _ = std::fs::remove_file(&target);
+
+    let conn = open_connection(path, current)?;
+    conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+    if let Err(e) = encryption::export(&conn, &target, new) {
+        let _ = std::fs::remove_file(&target);
+        return Err(e);
+    }
+    // Closing the last connection also removes the WAL files
+    drop(conn);
+
+    std::fs::rename(&target, path)?;
+    Ok(())
+}
+
+/// A single connection, with the key checked
+fn open_connection(path: &Path, passphrase: Option<&Passphrase>) -> Result<Connection> {
+    let mut conn = Connection::open(path)?;
+    if let Some(passphrase) = passphrase {
+        encryption::apply_key(&conn, passphrase)?;
+    }
+    configure_connection(&mut conn)?;
+    encryption::verify_key(&conn)?;
+    Ok(conn)
+}
+
+fn connect(path: &Path, passphrase: Option<&Passphrase>) -> Result<Pool<SqliteConnectionManager>> {
+    let passphrase = passphrase.cloned();
+    let manager = SqliteConnectionManager::file(path).with_init(move |conn| {
+        if let Some(passphrase) = &passphrase {
+            encryption::apply_key(conn, passphrase)?;
+        }
+        configure_connection(conn)
+    });
+    Ok(Pool::new(manager)?)
+}
+
+/// Initialize the database and return a connection pool
+pub fn init_database() -> Result<DbPool> {
+    init_database_at(&get_database_path()?)
+}
+
+/// Initialize the database at `db_path` and return a connection pool. An
+/// encrypted database stays locked until `DbPool::unlock`.
+pub fn init_database_at(db_path: &Path) -> Result<DbPool> {
+    // Ensure parent directory exists
+    if let Some(parent) = db_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    if encryption::is_encrypted(db_path)? {
+        tracing::info!("Database at {} is encrypted and locked", db_path.display());
+        return Ok(DbPool::new(Some(db_path.to_path_buf()), None));
+    }
+
+    let pool = connect(db_path, None)?;
+
+    // Run migrations
+    let conn = pool.get()?;
+    schema::run_migrations(&conn)?;
+    drop(conn);
+
+    tracing::info!("Database initialized at: {}", db_path.display());
+    Ok(DbPool::new(Some(db_path.to_path_buf()), Some(pool)))
+}
+
+/// Turn free-text user input into an FTS5 MATCH expression where every word
+/// must match as a prefix. Returns `None` if the input has no words.
+pub fn fts_query(input: &str) -> Option<String> {
+    let terms: Vec<String> = input
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// Get the path to the database file
+fn get_database_path() -> Result<PathBuf> {
+    let mut path = dirs::data_dir()
+        .ok_or_else(|| anyhow::anyhow!("Could not determine data directory"))?;
+
+    path.push("zweites-gehirn");
+    path.push("db");
+    path.push("zweites_gehirn.db");
+
+    Ok(path)
+}
+
+#[cfg(test)]
+pub fn init_test_database() -> Result<DbPool> {
+    use rusqlite::OpenFlags;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // Every pooled connection must see the same in-memory database, so use a
+    // uniquely named shared-cache URI instead of a private `:memory:` handle.
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let name = format!(
+        "file:zg_test_{}_{}?mode=memory&cache=shared",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::SeqCst)
+    );
+    let manager = SqliteConnectionManager::file(name)
+        .with_flags(
+            OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE | OpenFlags::SQLITE_OPEN_URI,
+        )
+        .with_init(configure_connection);
+    let pool = Pool::new(manager)?;
+    let conn = pool.get()?;
+    schema::run_migrations(&conn)?;
+    Ok(DbPool::new(None, Some(pool)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_database(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("zg-encryption-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir.join("zweites_gehirn.db")
+    }
+
+    fn passphrase(value: &str) -> Passphrase {
+        Passphrase::new(value.to_string()).unwrap()
+    }
+
+    fn count_tasks(pool: &DbPool) -> i64 {
+        pool.get()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM tasks", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn encrypts_a_plaintext_database_in_place() {
+        let path = temp_database("in-place");
+        let pool = init_database_at(&path).unwrap();
+        pool.get()
+            .unwrap()
+            .execute(
+                "INSERT INTO tasks (uid, user_id, workspace_id, title) VALUES ('a', 1, 1, 'See the doctor')",
+                [],
+            )
+            .unwrap();
+
+        pool.encrypt(passphrase("correct horse")).unwrap();
+        assert_eq!(pool.status(), DatabaseStatus { encrypted: true, locked: false });
+        assert!(encryption::is_encrypted(&path).unwrap());
+        assert_eq!(count_tasks(&pool), 1);
+        let file = std::fs::read(&path).unwrap();
+        assert!(!file.windows(14).any(|w| w == b"See the doctor"));
+
+        // Opened again, e.g. after a restart, it stays locked until unlocked
+        drop(pool);
+        let reopened = init_database_at(&path).unwrap();
+        assert_eq!(reopened.status(), DatabaseStatus { encrypted: true, locked: true });
+        let locked = reopened.get().unwrap_err();
+        assert!(matches!(locked.downcast_ref(), Some(EncryptionError::Locked)));
+        let wrong = reopened.unlock(passphrase("wrong")).unwrap_err();
+        assert!(matches!(wrong.downcast_ref(), Some(EncryptionError::WrongPassphrase)));
+
+        reopened.unlock(passphrase("correct horse")).unwrap();
+        assert_eq!(count_tasks(&reopened), 1);
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn changes_the_passphrase_and_keeps_backups_encrypted() {
+        let path = temp_database("rekey");
+        let pool = init_database_at(&path).unwrap();
+        pool.encrypt(passphrase("first")).unwrap();
+
+        let wrong = pool.change_passphrase(&passphrase("guess"), passphrase("second")).unwrap_err();
+        assert!(matches!(wrong.downcast_ref(), Some(EncryptionError::WrongPassphrase)));
+        pool.change_passphrase(&passphrase("first"), passphrase("second")).unwrap();
+        assert_eq!(count_tasks(&pool), 0);
+
+        let backup = path.with_file_name("backup.db");
+        pool.backup_to(&backup).unwrap();
+        assert!(encryption::is_encrypted(&backup).unwrap());
+        assert!(pool.backup_to(&backup).is_err());
+
+        drop(pool);
+        for (file, old, new) in [(&path, "first", "second"), (&backup, "first", "second")] {
+            let reopened = init_database_at(file).unwrap();
+            assert!(reopened.unlock(passphrase(old)).is_err());
+            reopened.unlock(passphrase(new)).unwrap();
+            assert_eq!(count_tasks(&reopened), 0);
+        }
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn never_shows_the_passphrase() {
+        let secret = passphrase("hunter2");
+        assert_eq!(format!("{secret:?}"), "Passphrase(<redacted>)");
+        assert!(Passphrase::new("  ".into()).is_err());
+    }
+}
@@ -0,0 +1,678 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Task {
+    pub id: i64,
+    /// Stable UUID, used to identify the task in exports and across devices
+    pub uid: String,
+    pub user_id: i64,
+    pub workspace_id: i64,
+    pub title: String,
+    pub description: Option<String>,
+    pub project_id: Option<i64>,
+    pub status: String,
+    pub priority: i32,
+    pub estimated_minutes: Option<i32>,
+    pub difficulty_level: Option<i32>,
+    pub energy_level: Option<String>,
+    pub scheduled_date: Option<String>,
+    pub due_date: Option<String>,
+    pub completed_at: Option<String>,
+    pub parent_task_id: Option<i64>,
+    pub order_index: i32,
+    pub column_position: i32,
+    pub tags: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    /// User the task is assigned to, a member of its workspace
+    pub assignee_id: Option<i64>,
+    /// Incremented on every change, to detect edits based on a stale copy
+    pub version: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CreateTaskInput {
+    pub title: String,
+    pub description: Option<String>,
+    pub project_id: Option<i64>,
+    pub priority: Option<i32>,
+    pub estimated_minutes: Option<i32>,
+    pub difficulty_level: Option<i32>,
+    pub energy_level: Option<String>,
+    pub scheduled_date: Option<String>,
+    pub due_date: Option<String>,
+    pub parent_task_id: Option<i64>,
+    pub tags: Option<Vec<String>>,
+}
+
+/// Change of one field in an update: left out, the field stays unchanged,
+/// `null` clears it and a value sets it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Patch<T> {
+    #[default]
+    Unchanged,
+    Clear,
+    Set(T),
+}
+
+impl<T> Patch<T> {
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Patch<U> {
+        match self {
+            Patch::Unchanged => Patch::Unchanged,
+            Patch::Clear => Patch::Clear,
+            Patch::Set(value) => Patch::Set(f(value)),
+        }
+    }
+}
+
+impl<T> From<Option<T>> for Patch<T> {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Patch::Clear, Patch::Set)
+    }
+}
+
+/// A missing field is `Unchanged` through `#[serde(default)]`
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Patch<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Option::<T>::deserialize(deserializer).map(Patch::from)
+    }
+}
+
+/// Fields left out stay unchanged. Title, status, priority and order cannot
+/// be cleared.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct UpdateTaskInput {
+    pub title: Patch<String>,
+    pub description: Patch<String>,
+    pub project_id: Patch<i64>,
+    pub status: Patch<String>,
+    pub priority: Patch<i32>,
+    pub estimated_minutes: Patch<i32>,
+    pub difficulty_level: Patch<i32>,
+    pub energy_level: Patch<String>,
+    pub scheduled_date: Patch<String>,
+    pub due_date: Patch<String>,
+    pub parent_task_id: Patch<i64>,
+    pub order_index: Patch<i32>,
+    pub tags: Patch<Vec<String>>,
+    /// Version the edit is based on; if the task has changed since, the
+    /// update fails with `TaskError::Conflict`
+    pub expected_version: Option<i64>,
+}
+
+/// Which LLM the AI features use; `provider` is `anthropic` or
+/// `openai_compatible` (OpenAI, Ollama, LM Studio, ...)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AiSettings {
+    pub provider: String,
+    pub model: String,
+    pub base_url: String,
+    #[serde(skip_serializing)]
+    pub api_key: Option<String>,
+    pub timeout_secs: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AiSettingsInput {
+    pub provider: String,
+    /// Defaults to the default model of the provider
+    pub model: Option<String>,
+    /// Defaults to the public API of the provider, or Ollama on localhost
+    pub base_url: Option<String>,
+    /// `None` keeps the stored key, an empty string removes it
+    pub api_key: Option<String>,
+    pub timeout_secs: Option<u32>,
+}
+
+/// AI usage of one feature with one model on one (local) day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AiUsage {
+    pub day: String,
+    pub feature: String,
+    pub model: String,
+    pub requests: i64,
+    pub cache_hits: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    /// Estimated from list prices; zero for local models
+    pub cost_usd: f64,
+}
+
+/// A subtask proposed by the AI, reviewed by the user before it is created
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubtaskSuggestion {
+    pub title: String,
+    pub estimated_minutes: Option<i32>,
+    pub energy_level: Option<String>,
+}
+
+/// A task parsed from quick-add text, for preview before saving
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuickAddPreview {
+    pub input: CreateTaskInput,
+    /// The `+project` as written; `input.project_id` is only set if it exists
+    pub project_name: Option<String>,
+}
+
+/// Filters for listing tasks; all of them must match
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TaskQuery {
+    pub status: Option<String>,
+    /// `today`, `overdue` or a `YYYY-MM-DD` date: due on or before that day
+    pub due: Option<String>,
+    /// Words that must all appear in the title or description
+    pub search: Option<String>,
+    pub project_id: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Project {
+    pub id: i64,
+    pub name: String,
+    pub color: String,
+    pub icon: Option<String>,
+    pub archived: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Note {
+    pub id: i64,
+    pub uid: String,
+    pub user_id: i64,
+    pub workspace_id: i64,
+    pub title: String,
+    pub body: String,
+    pub project_id: Option<i64>,
+    pub tags: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateNoteInput {
+    pub title: String,
+    pub body: Option<String>,
+    pub project_id: Option<i64>,
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateNoteInput {
+    pub title: Option<String>,
+    pub body: Option<String>,
+    pub project_id: Option<i64>,
+    pub tags: Option<Vec<String>>,
+}
+
+/// A wiki-link from a note to a note or task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Link {
+    pub id: i64,
+    pub source_type: String,
+    pub source_id: i64,
+    pub target_type: String,
+    pub target_ref: String,
+    pub target_id: Option<i64>,
+    pub alias: Option<String>,
+}
+
+/// An item linking to a note or task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Backlink {
+    pub source_type: String,
+    pub source_id: i64,
+    pub source_title: String,
+    pub alias: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateProjectInput {
+    pub name: String,
+    pub color: String,
+    pub icon: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Workspace {
+    pub id: i64,
+    pub name: String,
+    /// "personal" or "team"
+    pub workspace_type: String,
+    pub owner_user_id: Option<i64>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateWorkspaceInput {
+    pub name: String,
+    pub workspace_type: Option<String>,
+}
+
+/// A member of a workspace with their role: "owner", "editor" or "viewer"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceMember {
+    pub user_id: i64,
+    pub display_name: String,
+    pub role: String,
+}
+
+/// A local user profile. The PIN itself is never returned.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct User {
+    pub id: i64,
+    pub display_name: String,
+    pub email: Option<String>,
+    pub has_pin: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateUserInput {
+    pub display_name: String,
+    pub email: Option<String>,
+    /// 4 to 8 digits
+    pub pin: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateUserInput {
+    pub display_name: Option<String>,
+    pub email: Option<String>,
+}
+
+/// Weights of the factors of the "what next" score. Every factor is
+/// between 0 and 1, so the score is at most the sum of the weights.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoringWeights {
+    pub deadline: f64,
+    pub priority: f64,
+    pub energy: f64,
+    pub time_fit: f64,
+    pub age: f64,
+}
+
+impl Default for ScoringWeights {
+    fn default() -> Self {
+        Self {
+            deadline: 3.0,
+            priority: 2.0,
+            energy: 1.5,
+            time_fit: 1.5,
+            age: 0.5,
+        }
+    }
+}
+
+/// The factors behind a suggestion, each between 0 and 1
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScoreFactors {
+    pub deadline: f64,
+    pub priority: f64,
+    pub energy: f64,
+    pub time_fit: f64,
+    pub age: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskSuggestion {
+    pub task: Task,
+    pub score: f64,
+    pub factors: ScoreFactors,
+    /// Plain-language explanation, e.g. "Due tomorrow, high priority"
+    pub reason: String,
+}
+
+/// Rules for planning a day; times are `HH:MM`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlanSettings {
+    pub work_start: String,
+    pub work_end: String,
+    /// Hours of best focus, reserved for high-energy work where possible
+    pub peak_start: String,
+    pub peak_end: String,
+    /// A break is planned before focused work would exceed this
+    pub break_after_minutes: u32,
+    pub break_minutes: u32,
+    /// Assumed for tasks without an estimate
+    pub default_task_minutes: u32,
+}
+
+impl Default for PlanSettings {
+    fn default() -> Self {
+        Self {
+            work_start: "09:00".to_string(),
+            work_end: "17:00".to_string(),
+            peak_start: "09:00".to_string(),
+            peak_end: "12:00".to_string(),
+            break_after_minutes: 90,
+            break_minutes: 15,
+            default_task_minutes: 30,
+        }
+    }
+}
+
+/// A reminder for a task, at a fixed time or relative to the due date
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reminder {
+    pub id: i64,
+    pub task_id: i64,
+    /// Local time, `YYYY-MM-DDTHH:MM:SS`
+    pub remind_at: Option<String>,
+    pub minutes_before_due: Option<i32>,
+    pub snoozed_until: Option<String>,
+    pub fired_at: Option<String>,
+    /// When the reminder will fire, considering snoozing and quiet hours;
+    /// `None` once fired or if the task has no due date anymore
+    pub next_fire_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateReminderInput {
+    pub remind_at: Option<String>,
+    pub minutes_before_due: Option<i32>,
+}
+
+/// Whether the database is encrypted, and locked until its passphrase is given
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DatabaseStatus {
+    pub encrypted: bool,
+    pub locked: bool,
+}
+
+/// Payload of the `reminder` event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReminderNotification {
+    pub reminder_id: i64,
+    pub task_id: i64,
+    pub title: String,
+    pub due_date: Option<String>,
+    /// When the reminder was meant to fire; earlier than now for reminders
+    /// caught up after the app was closed
+    pub fire_at: String,
+}
+
+/// Times without reminders, `HH:MM`; may wrap around midnight
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start: String,
+    pub end: String,
+}
+
+/// Settings of the app, stored as versioned JSON. Fields missing from the
+/// stored JSON take their defaults. They are device-wide: all users of a
+/// database share them, like the AI key and the signed-in user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preferences {
+    /// "system", "light" or "dark"
+    pub theme: String,
+    /// "list" or "kanban"
+    pub default_view: String,
+    /// `HH:MM` at which a new day begins, for those working past midnight
+    pub rollover_time: String,
+    /// Used when planning a day without explicit settings
+    pub working_hours: PlanSettings,
+    pub pomodoro: PomodoroSettings,
+    pub quiet_hours: Option<QuietHours>,
+    pub ai: AiPreferences,
+    pub api_server: ApiServerPreferences,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            theme: "system".to_string(),
+            default_view: "list".to_string(),
+            rollover_time: "00:00".to_string(),
+            working_hours: PlanSettings::default(),
+            pomodoro: PomodoroSettings::default(),
+            quiet_hours: None,
+            ai: AiPreferences::default(),
+            api_server: ApiServerPreferences::default(),
+        }
+    }
+}
+
+/// Lengths of a pomodoro cycle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PomodoroSettings {
+    pub focus_minutes: u32,
+    pub short_break_minutes: u32,
+    pub long_break_minutes: u32,
+    /// Focus sessions before a long break
+    pub sessions_before_long_break: u32,
+}
+
+impl Default for PomodoroSettings {
+    fn default() -> Self {
+        Self {
+            focus_minutes: 25,
+            short_break_minutes: 5,
+            long_break_minutes: 15,
+            sessions_before_long_break: 4,
+        }
+    }
+}
+
+/// AI provider settings as chosen by the user; `None` stands for the default.
+/// The API key is stored apart and never part of the preferences.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AiPreferences {
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub base_url: Option<String>,
+    pub timeout_secs: Option<u32>,
+}
+
+/// The local REST API for scripts, listening on 127.0.0.1 only. Requests
+/// need the token from `generate_api_token` as bearer token.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiServerPreferences {
+    pub enabled: bool,
+    pub port: u16,
+}
+
+impl Default for ApiServerPreferences {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 7431,
+        }
+    }
+}
+
+/// A block of a day: a fixed appointment, a planned task or a break
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeBlock {
+    pub id: i64,
+    pub date: String,
+    pub start_time: String,
+    pub end_time: String,
+    pub kind: String,
+    pub task_id: Option<i64>,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DayPlan {
+    pub date: String,
+    pub blocks: Vec<TimeBlock>,
+    /// Tasks that did not fit into the day
+    pub overflow: Vec<Task>,
+}
+
+// Subtask types for Phase 2
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubtaskProgress {
+    pub total: i32,
+    pub completed: i32,
+    pub percentage: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskWithSubtasks {
+    pub task: Task,
+    pub subtasks: Vec<Task>,
+    pub progress: SubtaskProgress,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TodoTxtSyncReport {
+    pub created_in_app: usize,
+    pub updated_in_app: usize,
+    pub deleted_in_app: usize,
+    pub conflicts: usize,
+    pub lines: usize,
+    pub file_written: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IcsImportReport {
+    pub created: usize,
+    pub updated: usize,
+    pub skipped: usize,
+}
+
+/// `Debug` leaves out the password, so it cannot end up in logs
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CaldavConfig {
+    pub collection_url: String,
+    pub username: String,
+    #[serde(skip_serializing)]
+    pub password: String,
+    pub sync_token: Option<String>,
+    pub last_synced_at: Option<String>,
+}
+
+impl fmt::Debug for CaldavConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CaldavConfig")
+            .field("collection_url", &self.collection_url)
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .field("sync_token", &self.sync_token)
+            .field("last_synced_at", &self.last_synced_at)
+            .finish()
+    }
+}
+
+/// `Debug` leaves out the password, as for `CaldavConfig`
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CaldavConfigInput {
+    pub collection_url: String,
+    pub username: String,
+    pub password: String,
+}
+
+impl fmt::Debug for CaldavConfigInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CaldavConfigInput")
+            .field("collection_url", &self.collection_url)
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaldavSyncState {
+    pub task_id: i64,
+    pub href: String,
+    pub etag: Option<String>,
+    pub fingerprint: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaldavSyncReport {
+    pub pulled_created: usize,
+    pub pulled_updated: usize,
+    pub pulled_deleted: usize,
+    pub pushed_created: usize,
+    pub pushed_updated: usize,
+    pub pushed_deleted: usize,
+    /// Tasks changed on both sides; the local version was kept as a copy
+    pub conflicts: usize,
+    /// Uploads rejected because the remote changed meanwhile; retried next sync
+    pub deferred: usize,
+}
+
+/// Sync with other devices through a shared folder, set up in one workspace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceSyncConfig {
+    pub folder: String,
+    /// Identifies this device in the folder, new for every setup
+    pub device_id: String,
+    pub workspace_id: i64,
+    pub last_synced_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceSyncReport {
+    /// Field changes written to this device's log
+    pub sent: usize,
+    /// New field changes read from the other devices' logs
+    pub received: usize,
+    pub applied: usize,
+    /// Received changes of a field that changed later elsewhere; ignored
+    pub conflicts: usize,
+}
+
+/// A note, task, project or tag in the knowledge graph.
+/// `id` is prefixed with the kind, e.g. `note:12` or `tag:health`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphNode {
+    pub id: String,
+    pub kind: String,
+    pub label: String,
+    /// Number of edges in the whole graph, for sizing nodes
+    pub degree: usize,
+}
+
+/// Edge kinds: `link`, `parent`, `project` and `tag`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+    pub kind: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GraphData {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+    /// Set when the node limit cut the neighbourhood short
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObsidianSyncState {
+    /// Path of the Markdown file relative to the vault, with `/` separators
+    pub file_path: String,
+    pub content: String,
+    pub fingerprint: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObsidianSyncReport {
+    pub notes_created: usize,
+    pub notes_updated: usize,
+    pub notes_deleted: usize,
+    pub files_written: usize,
+    pub files_deleted: usize,
+    pub tasks_created: usize,
+    pub tasks_updated: usize,
+    /// Notes changed on both sides; the vault version went to a `.conflict.md` file
+    pub conflicts: usize,
+}
@@ -0,0 +1,217 @@
+use crate::db::{models::*, DbPool};
+use anyhow::Result;
+use rusqlite::{params, OptionalExtension};
+
+/// A queued AI request; `request` is the serialized prompt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AiJob {
+    pub id: i64,
+    pub feature: String,
+    pub prompt_hash: String,
+    pub request: String,
+    pub status: String,
+    pub attempts: u32,
+    pub next_attempt_at: String,
+    pub last_error: Option<String>,
+}
+
+/// Tokens and estimated cost of one AI request, for the usage ledger
+#[derive(Debug, Clone, PartialEq)]
+pub struct UsageEntry<'a> {
+    pub day: &'a str,
+    pub feature: &'a str,
+    pub model: &'a str,
+    pub cache_hit: bool,
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    pub cost_usd: f64,
+}
+
+/// The AI job queue, response cache and usage ledger
+pub struct AiRepository {
+    pool: DbPool,
+}
+
+impl AiRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    pub fn enqueue(&self, feature: &str, prompt_hash: &str, request: &str, now: &str) -> Result<i64> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            "INSERT INTO ai_jobs (feature, prompt_hash, request, next_attempt_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![feature, prompt_hash, request, now],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn get_job(&self, id: i64) -> Result<Option<AiJob>> {
+        let conn = self.pool.get()?;
+        let job = conn
+            .query_row(
+                "SELECT id, feature, prompt_hash, request, status, attempts, next_attempt_at, last_error
+                 FROM ai_jobs WHERE id = ?1",
+                [id],
+                row_to_job,
+            )
+            .optional()?;
+        Ok(job)
+    }
+
+    /// IDs of queued jobs due at `now`, oldest first
+    pub fn due_jobs(&self, now: &str) -> Result<Vec<i64>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT id FROM ai_jobs
+             WHERE status = 'queued' AND next_attempt_at <= ?1
+             ORDER BY next_attempt_at, id",
+        )?;
+        let ids: Result<Vec<i64>, _> = stmt.query_map([now], |row| row.get(0))?.collect();
+        Ok(ids?)
+    }
+
+    /// Mark a queued job as running. Returns false if it was taken already.
+    pub fn claim(&self, id: i64) -> Result<bool> {
+        let conn = self.pool.get()?;
+        let changed = conn.execute(
+            "UPDATE ai_jobs SET status = 'running', attempts = attempts + 1,
+                updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?1 AND status = 'queued'",
+            [id],
+        )?;
+        Ok(changed == 1)
+    }
+
+    /// Put jobs left running by a crash back into the queue
+    pub fn requeue_running(&self) -> Result<usize> {
+        let conn = self.pool.get()?;
+        let changed = conn.execute(
+            "UPDATE ai_jobs SET status = 'queued', updated_at = CURRENT_TIMESTAMP
+             WHERE status = 'running'",
+            [],
+        )?;
+        Ok(changed)
+    }
+
+    pub fn reschedule(&self, id: i64, next_attempt_at: &str, error: &str) -> Result<()> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            "UPDATE ai_jobs SET status = 'queued', next_attempt_at = ?2, last_error = ?3,
+                updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?1",
+            params![id, next_attempt_at, error],
+        )?;
+        Ok(())
+    }
+
+    pub fn fail(&self, id: i64, error: &str) -> Result<()> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            "UPDATE ai_jobs SET status = 'failed', last_error = ?2, updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?1",
+            params![id, error],
+        )?;
+        Ok(())
+    }
+
+    /// Store the response of a job in the cache and remove the job
+    pub fn complete(&self, id: i64, prompt_hash: &str, response: &str, model: &str) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO ai_cache (prompt_hash, response, model) VALUES (?1, ?2, ?3)",
+            params![prompt_hash, response, model],
+        )?;
+        tx.execute("DELETE FROM ai_jobs WHERE id = ?1", [id])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Cache the response to a prompt that was sent without a job
+    pub fn cache(&self, prompt_hash: &str, response: &str, model: &str) -> Result<()> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO ai_cache (prompt_hash, response, model) VALUES (?1, ?2, ?3)",
+            params![prompt_hash, response, model],
+        )?;
+        Ok(())
+    }
+
+    /// Cached response and model for a prompt
+    pub fn cached(&self, prompt_hash: &str) -> Result<Option<(String, String)>> {
+        let conn = self.pool.get()?;
+        let cached = conn
+            .query_row(
+                "SELECT response, model FROM ai_cache WHERE prompt_hash = ?1",
+                [prompt_hash],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        Ok(cached)
+    }
+
+    pub fn record_usage(&self, entry: &UsageEntry) -> Result<()> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            "INSERT INTO ai_usage
+                (day, feature, model, requests, cache_hits, input_tokens, output_tokens, cost_usd)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT (day, feature, model) DO UPDATE SET
+                requests = requests + excluded.requests,
+                cache_hits = cache_hits + excluded.cache_hits,
+                input_tokens = input_tokens + excluded.input_tokens,
+                output_tokens = output_tokens + excluded.output_tokens,
+                cost_usd = cost_usd + excluded.cost_usd",
+            params![
+                entry.day,
+                entry.feature,
+                entry.model,
+                !entry.cache_hit as i64,
+                entry.cache_hit as i64,
+                entry.input_tokens,
+                entry.output_tokens,
+                entry.cost_usd
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Usage between two days (inclusive), newest first
+    pub fn usage(&self, from: &str, to: &str) -> Result<Vec<AiUsage>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT day, feature, model, requests, cache_hits, input_tokens, output_tokens, cost_usd
+             FROM ai_usage WHERE day BETWEEN ?1 AND ?2
+             ORDER BY day DESC, feature, model",
+        )?;
+        let rows = stmt.query_map([from, to], |row| {
+            Ok(AiUsage {
+                day: row.get(0)?,
+                feature: row.get(1)?,
+                model: row.get(2)?,
+                requests: row.get(3)?,
+                cache_hits: row.get(4)?,
+                input_tokens: row.get(5)?,
+                output_tokens: row.get(6)?,
+                cost_usd: row.get(7)?,
+            })
+        })?;
+        let usage: Result<Vec<AiUsage>, _> = rows.collect();
+        Ok(usage?)
+    }
+}
+
+fn row_to_job(row: &rusqlite::Row) -> rusqlite::Result<AiJob> {
+    Ok(AiJob {
+        id: row.get(0)?,
+        feature: row.get(1)?,
+        prompt_hash: row.get(2)?,
+        request: row.get(3)?,
+        status: row.get(4)?,
+        attempts: row.get(5)?,
+        next_attempt_at: row.get(6)?,
+        last_error: row.get(7)?,
+    })
+}
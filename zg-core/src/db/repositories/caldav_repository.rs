@@ -0,0 +1,118 @@
+use crate::db::{models::*, schema::ACTIVE_WORKSPACE_SQL, DbPool};
+use anyhow::Result;
+use rusqlite::{params, OptionalExtension};
+
+/// CalDAV configuration and sync state. The collection is synced with the
+/// workspace it was configured in and is not visible from others.
+pub struct CaldavRepository {
+    pool: DbPool,
+}
+
+impl CaldavRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    pub fn get_config(&self) -> Result<Option<CaldavConfig>> {
+        let conn = self.pool.get()?;
+        let config = conn
+            .query_row(
+                &format!(
+                    "SELECT collection_url, username, password, sync_token, last_synced_at
+                     FROM caldav_config WHERE id = 1 AND workspace_id = {ACTIVE_WORKSPACE_SQL}"
+                ),
+                [],
+                |row| {
+                    Ok(CaldavConfig {
+                        collection_url: row.get(0)?,
+                        username: row.get(1)?,
+                        password: row.get(2)?,
+                        sync_token: row.get(3)?,
+                        last_synced_at: row.get(4)?,
+                    })
+                },
+            )
+            .optional()?;
+
+        Ok(config)
+    }
+
+    /// Store the collection to sync with. Pointing to a different collection,
+    /// or configuring it in another workspace, forgets the previous sync state.
+    pub fn save_config(&self, input: &CaldavConfigInput) -> Result<CaldavConfig> {
+        let previous_url = self.get_config()?.map(|c| c.collection_url);
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+
+        if previous_url.as_deref() != Some(input.collection_url.as_str()) {
+            tx.execute("DELETE FROM caldav_sync_state", [])?;
+            tx.execute("DELETE FROM caldav_config", [])?;
+        }
+        tx.execute(
+            &format!(
+                "INSERT INTO caldav_config (id, workspace_id, collection_url, username, password)
+                 VALUES (1, {ACTIVE_WORKSPACE_SQL}, ?1, ?2, ?3)
+                 ON CONFLICT(id) DO UPDATE SET
+                    collection_url = excluded.collection_url,
+                    username = excluded.username,
+                    password = excluded.password"
+            ),
+            params![input.collection_url, input.username, input.password],
+        )?;
+        tx.commit()?;
+
+        self.get_config()?
+            .ok_or_else(|| anyhow::anyhow!("CalDAV configuration was not saved"))
+    }
+
+    /// Remove the configuration and all sync state
+    pub fn clear_config(&self) -> Result<()> {
+        let conn = self.pool.get()?;
+        conn.execute("DELETE FROM caldav_sync_state", [])?;
+        conn.execute("DELETE FROM caldav_config", [])?;
+        Ok(())
+    }
+
+    pub fn finish_sync(&self, sync_token: Option<&str>) -> Result<()> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            "UPDATE caldav_config SET sync_token = ?1, last_synced_at = CURRENT_TIMESTAMP
+             WHERE id = 1",
+            [sync_token],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_states(&self) -> Result<Vec<CaldavSyncState>> {
+        let conn = self.pool.get()?;
+        let mut stmt =
+            conn.prepare("SELECT task_id, href, etag, fingerprint FROM caldav_sync_state")?;
+
+        let states = stmt.query_map([], |row| {
+            Ok(CaldavSyncState {
+                task_id: row.get(0)?,
+                href: row.get(1)?,
+                etag: row.get(2)?,
+                fingerprint: row.get(3)?,
+            })
+        })?;
+        let states: Result<Vec<CaldavSyncState>, _> = states.collect();
+        Ok(states?)
+    }
+
+    pub fn upsert_state(&self, state: &CaldavSyncState) -> Result<()> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO caldav_sync_state (task_id, href, etag, fingerprint)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![state.task_id, state.href, state.etag, state.fingerprint],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_state(&self, task_id: i64) -> Result<()> {
+        let conn = self.pool.get()?;
+        conn.execute("DELETE FROM caldav_sync_state WHERE task_id = ?1", [task_id])?;
+        Ok(())
+    }
+}
@@ -0,0 +1,323 @@
+use crate::db::{
+    models::{DeviceSyncConfig, Task},
+    repositories::{
+        project_repository::DEFAULT_PROJECT_COLOR,
+        task_repository::{TaskRepository, TASK_COLUMNS},
+    },
+    schema::{ACTIVE_WORKSPACE_SQL, UUID_V4_SQL},
+    DbPool,
+};
+use anyhow::Result;
+use rusqlite::{params, types::Value as SqlValue, Connection, OptionalExtension};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Workspace the device sync was set up in
+const SYNC_WORKSPACE_SQL: &str = "(SELECT workspace_id FROM device_sync_config WHERE id = 1)";
+
+/// Task columns synced as they are. The parent and project are synced as
+/// `parent` (its UID) and `project` (its name), since IDs differ per device.
+pub const TASK_FIELDS: &[&str] = &[
+    "title",
+    "description",
+    "status",
+    "priority",
+    "estimated_minutes",
+    "difficulty_level",
+    "energy_level",
+    "scheduled_date",
+    "due_date",
+    "completed_at",
+    "order_index",
+    "column_position",
+    "tags",
+];
+
+/// The synced fields of a task
+pub struct SyncedTask {
+    pub uid: String,
+    pub updated_at: String,
+    pub fields: Vec<(String, Value)>,
+}
+
+/// Value of a field as JSON and the clock of its latest change
+pub struct FieldState {
+    pub value: String,
+    pub clock: String,
+}
+
+/// Device sync configuration and the synced state of every task field
+pub struct DeviceSyncRepository {
+    pool: DbPool,
+}
+
+impl DeviceSyncRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    pub fn get_config(&self) -> Result<Option<DeviceSyncConfig>> {
+        let conn = self.pool.get()?;
+        let config = conn
+            .query_row(
+                "SELECT folder, device_id, workspace_id, last_synced_at FROM device_sync_config WHERE id = 1",
+                [],
+                |row| {
+                    Ok(DeviceSyncConfig {
+                        folder: row.get(0)?,
+                        device_id: row.get(1)?,
+                        workspace_id: row.get(2)?,
+                        last_synced_at: row.get(3)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(config)
+    }
+
+    /// Set up sync with `folder` for the active workspace, as a new device
+    pub fn save_config(&self, folder: &str) -> Result<DeviceSyncConfig> {
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+        Self::clear_in(&tx)?;
+        tx.execute(
+            &format!(
+                "INSERT INTO device_sync_config (id, workspace_id, folder, device_id)
+                 VALUES (1, {ACTIVE_WORKSPACE_SQL}, ?1, {UUID_V4_SQL})"
+            ),
+            [folder],
+        )?;
+        tx.commit()?;
+
+        self.get_config()?
+            .ok_or_else(|| anyhow::anyhow!("Device sync config was not saved"))
+    }
+
+    pub fn clear_config(&self) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+        Self::clear_in(&tx)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn clear_in(conn: &Connection) -> Result<()> {
+        conn.execute("DELETE FROM device_sync_fields", [])?;
+        conn.execute("DELETE FROM device_sync_cursors", [])?;
+        conn.execute("DELETE FROM device_sync_config", [])?;
+        Ok(())
+    }
+
+    pub fn get_clock_in(conn: &Connection) -> Result<Option<String>> {
+        let clock = conn.query_row("SELECT clock FROM device_sync_config WHERE id = 1", [], |row| row.get(0))?;
+        Ok(clock)
+    }
+
+    pub fn finish_sync_in(conn: &Connection, clock: &str) -> Result<()> {
+        conn.execute(
+            "UPDATE device_sync_config SET clock = ?1, last_synced_at = CURRENT_TIMESTAMP WHERE id = 1",
+            [clock],
+        )?;
+        Ok(())
+    }
+
+    /// Tasks of the synced workspace with their synced fields
+    pub fn tasks_in(conn: &Connection) -> Result<Vec<SyncedTask>> {
+        let columns: Vec<String> = TASK_FIELDS.iter().map(|field| format!("t.{field}")).collect();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT t.uid, t.updated_at, parent.uid, project.name, {}
+             FROM tasks t
+             LEFT JOIN tasks parent ON parent.id = t.parent_task_id
+             LEFT JOIN projects project ON project.id = t.project_id
+             WHERE t.workspace_id = {SYNC_WORKSPACE_SQL}
+             ORDER BY t.id",
+            columns.join(", ")
+        ))?;
+
+        let tasks = stmt
+            .query_map([], |row| {
+                let mut fields = vec![
+                    ("parent".to_string(), to_json(row.get(2)?)),
+                    ("project".to_string(), to_json(row.get(3)?)),
+                ];
+                for (i, field) in TASK_FIELDS.iter().enumerate() {
+                    fields.push((field.to_string(), to_json(row.get(i + 4)?)));
+                }
+                Ok(SyncedTask {
+                    uid: row.get(0)?,
+                    updated_at: row.get(1)?,
+                    fields,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(tasks)
+    }
+
+    /// The task with `uid` in the synced workspace
+    pub fn task_in(conn: &Connection, uid: &str) -> Result<Option<Task>> {
+        let task = conn
+            .query_row(
+                &format!("SELECT {TASK_COLUMNS} FROM tasks WHERE uid = ?1 AND workspace_id = {SYNC_WORKSPACE_SQL}"),
+                [uid],
+                TaskRepository::map_task_row,
+            )
+            .optional()?;
+        Ok(task)
+    }
+
+    /// Synced state by task UID and field
+    pub fn field_states_in(conn: &Connection) -> Result<HashMap<(String, String), FieldState>> {
+        let mut stmt = conn.prepare("SELECT uid, field, value, clock FROM device_sync_fields")?;
+        let states = stmt
+            .query_map([], |row| {
+                Ok((
+                    (row.get(0)?, row.get(1)?),
+                    FieldState {
+                        value: row.get(2)?,
+                        clock: row.get(3)?,
+                    },
+                ))
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(states)
+    }
+
+    pub fn save_field_in(conn: &Connection, uid: &str, field: &str, state: &FieldState) -> Result<()> {
+        conn.execute(
+            "INSERT INTO device_sync_fields (uid, field, value, clock) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(uid, field) DO UPDATE SET value = excluded.value, clock = excluded.clock",
+            params![uid, field, state.value, state.clock],
+        )?;
+        Ok(())
+    }
+
+    /// Drop the field states of a deleted task, except `keep`
+    pub fn forget_fields_in(conn: &Connection, uid: &str, keep: &str) -> Result<()> {
+        conn.execute(
+            "DELETE FROM device_sync_fields WHERE uid = ?1 AND field != ?2",
+            params![uid, keep],
+        )?;
+        Ok(())
+    }
+
+    /// Lines of a device's log merged so far
+    pub fn get_cursor_in(conn: &Connection, device_id: &str) -> Result<usize> {
+        let lines: Option<i64> = conn
+            .query_row(
+                "SELECT lines FROM device_sync_cursors WHERE device_id = ?1",
+                [device_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(lines.unwrap_or(0) as usize)
+    }
+
+    pub fn save_cursor_in(conn: &Connection, device_id: &str, lines: usize) -> Result<()> {
+        conn.execute(
+            "INSERT INTO device_sync_cursors (device_id, lines) VALUES (?1, ?2)
+             ON CONFLICT(device_id) DO UPDATE SET lines = excluded.lines",
+            params![device_id, lines as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Set a field of the task with `uid` in the synced workspace, creating
+    /// the task first if it is new. Unknown fields, e.g. from a newer
+    /// version, are ignored.
+    pub fn apply_field_in(conn: &Connection, uid: &str, field: &str, value: &Value) -> Result<()> {
+        conn.execute(
+            &format!(
+                "INSERT OR IGNORE INTO tasks (uid, title, user_id, workspace_id)
+                 VALUES (?1, '', COALESCE((SELECT owner_user_id FROM workspaces WHERE id = {SYNC_WORKSPACE_SQL}), 1),
+                         {SYNC_WORKSPACE_SQL})"
+            ),
+            [uid],
+        )?;
+
+        let value = match field {
+            "parent" => conn
+                .query_row(
+                    &format!("SELECT id FROM tasks WHERE uid = ?1 AND workspace_id = {SYNC_WORKSPACE_SQL}"),
+                    [value.as_str()],
+                    |row| row.get::<_, i64>(0),
+                )
+                .optional()?
+                .map_or(SqlValue::Null, SqlValue::Integer),
+            "project" => match value.as_str() {
+                Some(name) => SqlValue::Integer(Self::project_id_in(conn, name)?),
+                None => SqlValue::Null,
+            },
+            field if TASK_FIELDS.contains(&field) => to_sql(value),
+            _ => {
+                tracing::debug!("Ignoring unknown synced field {field}");
+                return Ok(());
+            }
+        };
+        let column = match field {
+            "parent" => "parent_task_id",
+            "project" => "project_id",
+            field => field,
+        };
+        conn.execute(
+            &format!(
+                "UPDATE tasks SET {column} = ?1, updated_at = CURRENT_TIMESTAMP
+                 WHERE uid = ?2 AND workspace_id = {SYNC_WORKSPACE_SQL}"
+            ),
+            params![value, uid],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_task_in(conn: &Connection, uid: &str) -> Result<()> {
+        conn.execute(
+            &format!("DELETE FROM tasks WHERE uid = ?1 AND workspace_id = {SYNC_WORKSPACE_SQL}"),
+            [uid],
+        )?;
+        Ok(())
+    }
+
+    /// The project named `name` in the synced workspace, created if missing
+    fn project_id_in(conn: &Connection, name: &str) -> Result<i64> {
+        let existing = conn
+            .query_row(
+                &format!(
+                    "SELECT id FROM projects WHERE workspace_id = {SYNC_WORKSPACE_SQL} AND name = ?1 COLLATE NOCASE
+                     ORDER BY id LIMIT 1"
+                ),
+                [name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(id) = existing {
+            return Ok(id);
+        }
+
+        conn.execute(
+            &format!("INSERT INTO projects (name, color, workspace_id) VALUES (?1, ?2, {SYNC_WORKSPACE_SQL})"),
+            params![name, DEFAULT_PROJECT_COLOR],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+}
+
+fn to_json(value: SqlValue) -> Value {
+    match value {
+        SqlValue::Null | SqlValue::Blob(_) => Value::Null,
+        SqlValue::Integer(i) => i.into(),
+        SqlValue::Real(f) => f.into(),
+        SqlValue::Text(text) => text.into(),
+    }
+}
+
+fn to_sql(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(i64::from(*b)),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(text) => SqlValue::Text(text.clone()),
+        other => SqlValue::Text(other.to_string()),
+    }
+}
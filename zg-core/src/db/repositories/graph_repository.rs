@@ -0,0 +1,101 @@
+use crate::db::{schema::ACTIVE_WORKSPACE_SQL, DbPool};
+use anyhow::Result;
+
+#[derive(Debug)]
+pub struct GraphNoteRow {
+    pub id: i64,
+    pub title: String,
+    pub project_id: Option<i64>,
+    /// JSON array, as stored
+    pub tags: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct GraphTaskRow {
+    pub id: i64,
+    pub title: String,
+    pub project_id: Option<i64>,
+    pub parent_task_id: Option<i64>,
+    /// JSON array, as stored
+    pub tags: Option<String>,
+}
+
+/// Raw rows the knowledge graph is built from
+#[derive(Debug, Default)]
+pub struct GraphRows {
+    pub notes: Vec<GraphNoteRow>,
+    pub tasks: Vec<GraphTaskRow>,
+    /// (id, name)
+    pub projects: Vec<(i64, String)>,
+    /// (source_type, source_id, target_type, target_id) of resolved links
+    pub links: Vec<(String, i64, String, i64)>,
+}
+
+/// Graph rows of the active workspace. Links are not filtered; those between
+/// items outside of it find no nodes and are dropped.
+pub struct GraphRepository {
+    pool: DbPool,
+}
+
+impl GraphRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Load everything needed for the graph with one query per table
+    pub fn load(&self) -> Result<GraphRows> {
+        let conn = self.pool.get()?;
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, title, project_id, tags FROM notes WHERE workspace_id = {ACTIVE_WORKSPACE_SQL}"
+        ))?;
+        let notes = stmt
+            .query_map([], |row| {
+                Ok(GraphNoteRow {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    project_id: row.get(2)?,
+                    tags: row.get(3)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, title, project_id, parent_task_id, tags FROM tasks
+             WHERE workspace_id = {ACTIVE_WORKSPACE_SQL}"
+        ))?;
+        let tasks = stmt
+            .query_map([], |row| {
+                Ok(GraphTaskRow {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    project_id: row.get(2)?,
+                    parent_task_id: row.get(3)?,
+                    tags: row.get(4)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, name FROM projects WHERE archived = 0 AND workspace_id = {ACTIVE_WORKSPACE_SQL}"
+        ))?;
+        let projects = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT source_type, source_id, target_type, target_id
+             FROM links WHERE target_id IS NOT NULL",
+        )?;
+        let links = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))?
+            .collect::<Result<_, _>>()?;
+
+        Ok(GraphRows {
+            notes,
+            tasks,
+            projects,
+            links,
+        })
+    }
+}
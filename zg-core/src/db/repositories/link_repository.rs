@@ -0,0 +1,130 @@
+use crate::db::{models::*, DbPool};
+use crate::formats::wikilinks::{LinkTarget, WikiLink};
+use anyhow::Result;
+use rusqlite::{params, OptionalExtension};
+
+pub const NOTE: &str = "note";
+pub const TASK: &str = "task";
+
+pub struct LinkRepository {
+    pool: DbPool,
+}
+
+impl LinkRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Replace all outgoing links of a source, resolving their targets in
+    /// the source's workspace
+    pub fn replace_for_source(
+        &self,
+        workspace_id: i64,
+        source_type: &str,
+        source_id: i64,
+        links: &[WikiLink],
+    ) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "DELETE FROM links WHERE source_type = ?1 AND source_id = ?2",
+            params![source_type, source_id],
+        )?;
+
+        for link in links {
+            let (target_type, target_ref, target_id): (&str, String, Option<i64>) = match &link.target {
+                LinkTarget::Note(title) => {
+                    let id = tx
+                        .query_row(
+                            "SELECT id FROM notes WHERE title = ?1 AND workspace_id = ?2",
+                            params![title, workspace_id],
+                            |row| row.get(0),
+                        )
+                        .optional()?;
+                    (NOTE, title.clone(), id)
+                }
+                LinkTarget::Task(task_id) => {
+                    let id = tx
+                        .query_row(
+                            "SELECT id FROM tasks WHERE id = ?1 AND workspace_id = ?2",
+                            [*task_id, workspace_id],
+                            |row| row.get(0),
+                        )
+                        .optional()?;
+                    (TASK, task_id.to_string(), id)
+                }
+            };
+
+            tx.execute(
+                "INSERT INTO links (source_type, source_id, target_type, target_ref, target_id, alias)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![source_type, source_id, target_type, target_ref, target_id, link.alias],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Point unresolved links written as `title` to a (new or renamed) note
+    pub fn resolve_note_title(&self, workspace_id: i64, title: &str, note_id: i64) -> Result<usize> {
+        let conn = self.pool.get()?;
+        let updated = conn.execute(
+            "UPDATE links SET target_id = ?1
+             WHERE target_type = 'note' AND target_id IS NULL AND target_ref = ?2
+               AND source_type = 'note'
+               AND source_id IN (SELECT id FROM notes WHERE workspace_id = ?3)",
+            params![note_id, title, workspace_id],
+        )?;
+        Ok(updated)
+    }
+
+    /// Outgoing links of a source, in no particular order
+    pub fn outgoing(&self, source_type: &str, source_id: i64) -> Result<Vec<Link>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, source_type, source_id, target_type, target_ref, target_id, alias
+             FROM links WHERE source_type = ?1 AND source_id = ?2 ORDER BY id",
+        )?;
+
+        let links = stmt.query_map(params![source_type, source_id], Self::map_link_row)?;
+        let links: Result<Vec<Link>, _> = links.collect();
+        Ok(links?)
+    }
+
+    /// Items linking to a note or task
+    pub fn backlinks(&self, workspace_id: i64, target_type: &str, target_id: i64) -> Result<Vec<Backlink>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT l.source_type, l.source_id, n.title, l.alias
+             FROM links l JOIN notes n ON l.source_type = 'note' AND n.id = l.source_id
+             WHERE l.target_type = ?1 AND l.target_id = ?2 AND n.workspace_id = ?3
+             ORDER BY n.title",
+        )?;
+
+        let backlinks = stmt.query_map(params![target_type, target_id, workspace_id], |row| {
+            Ok(Backlink {
+                source_type: row.get(0)?,
+                source_id: row.get(1)?,
+                source_title: row.get(2)?,
+                alias: row.get(3)?,
+            })
+        })?;
+        let backlinks: Result<Vec<Backlink>, _> = backlinks.collect();
+        Ok(backlinks?)
+    }
+
+    /// Helper to map row to Link
+    fn map_link_row(row: &rusqlite::Row) -> rusqlite::Result<Link> {
+        Ok(Link {
+            id: row.get(0)?,
+            source_type: row.get(1)?,
+            source_id: row.get(2)?,
+            target_type: row.get(3)?,
+            target_ref: row.get(4)?,
+            target_id: row.get(5)?,
+            alias: row.get(6)?,
+        })
+    }
+}
@@ -0,0 +1,31 @@
+pub mod ai_repository;
+pub mod caldav_repository;
+pub mod device_sync_repository;
+pub mod graph_repository;
+pub mod link_repository;
+pub mod note_repository;
+pub mod obsidian_repository;
+pub mod plan_repository;
+pub mod preferences_repository;
+pub mod project_repository;
+pub mod reminder_repository;
+pub mod task_repository;
+pub mod todotxt_repository;
+pub mod user_repository;
+pub mod workspace_repository;
+
+pub use ai_repository::AiRepository;
+pub use caldav_repository::CaldavRepository;
+pub use device_sync_repository::DeviceSyncRepository;
+pub use graph_repository::GraphRepository;
+pub use link_repository::LinkRepository;
+pub use note_repository::NoteRepository;
+pub use obsidian_repository::ObsidianStateRepository;
+pub use plan_repository::PlanRepository;
+pub use preferences_repository::PreferencesRepository;
+pub use project_repository::ProjectRepository;
+pub use reminder_repository::ReminderRepository;
+pub use task_repository::{TaskError, TaskRepository};
+pub use todotxt_repository::TodoTxtStateRepository;
+pub use user_repository::UserRepository;
+pub use workspace_repository::WorkspaceRepository;
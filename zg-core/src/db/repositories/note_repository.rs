@@ -0,0 +1,139 @@
+use crate::db::{
+    fts_query,
+    models::*,
+    schema::{CURRENT_USER_SQL, UUID_V4_SQL},
+    DbPool,
+};
+use anyhow::Result;
+use rusqlite::{params, OptionalExtension};
+
+const NOTE_COLUMNS: &str =
+    "id, uid, user_id, workspace_id, title, body, project_id, tags, created_at, updated_at";
+
+/// Notes, by workspace
+pub struct NoteRepository {
+    pool: DbPool,
+}
+
+impl NoteRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create a new note
+    pub fn create(&self, workspace_id: i64, input: &CreateNoteInput) -> Result<Note> {
+        let conn = self.pool.get()?;
+        let tags_json = input.tags.as_ref().map(serde_json::to_string).transpose()?;
+        conn.execute(
+            &format!(
+                "INSERT INTO notes (uid, user_id, workspace_id, title, body, project_id, tags)
+                 VALUES ({UUID_V4_SQL}, {CURRENT_USER_SQL}, ?1, ?2, ?3, ?4, ?5)"
+            ),
+            params![
+                workspace_id,
+                input.title,
+                input.body.as_deref().unwrap_or_default(),
+                input.project_id,
+                tags_json
+            ],
+        )?;
+
+        let id = conn.last_insert_rowid();
+        self.get_by_id(workspace_id, id)
+    }
+
+    /// Get note by ID
+    pub fn get_by_id(&self, workspace_id: i64, id: i64) -> Result<Note> {
+        let conn = self.pool.get()?;
+        let note = conn.query_row(
+            &format!("SELECT {NOTE_COLUMNS} FROM notes WHERE id = ?1 AND workspace_id = ?2"),
+            params![id, workspace_id],
+            Self::map_note_row,
+        )?;
+
+        Ok(note)
+    }
+
+    /// Find a note by title (case-insensitive)
+    pub fn find_by_title(&self, workspace_id: i64, title: &str) -> Result<Option<Note>> {
+        let conn = self.pool.get()?;
+        let note = conn
+            .query_row(
+                &format!("SELECT {NOTE_COLUMNS} FROM notes WHERE title = ?1 AND workspace_id = ?2"),
+                params![title, workspace_id],
+                Self::map_note_row,
+            )
+            .optional()?;
+
+        Ok(note)
+    }
+
+    /// Get all notes, most recently edited first
+    pub fn get_all(&self, workspace_id: i64) -> Result<Vec<Note>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {NOTE_COLUMNS} FROM notes WHERE workspace_id = ?1
+             ORDER BY updated_at DESC, id DESC"
+        ))?;
+
+        let notes = stmt.query_map([workspace_id], Self::map_note_row)?;
+        let notes: Result<Vec<Note>, _> = notes.collect();
+        Ok(notes?)
+    }
+
+    /// Full-text search over titles and bodies, best matches first
+    pub fn search(&self, workspace_id: i64, query: &str) -> Result<Vec<Note>> {
+        let Some(query) = fts_query(query) else {
+            return Ok(Vec::new());
+        };
+
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT n.id, n.uid, n.user_id, n.workspace_id, n.title, n.body, n.project_id, n.tags,
+                    n.created_at, n.updated_at
+             FROM notes_fts JOIN notes n ON n.id = notes_fts.rowid
+             WHERE notes_fts MATCH ?1 AND n.workspace_id = ?2
+             ORDER BY rank",
+        )?;
+
+        let notes = stmt.query_map(params![query, workspace_id], Self::map_note_row)?;
+        let notes: Result<Vec<Note>, _> = notes.collect();
+        Ok(notes?)
+    }
+
+    /// Write title, body, project and tags of a note
+    pub fn update(&self, workspace_id: i64, note: &Note) -> Result<Note> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            "UPDATE notes SET title = ?1, body = ?2, project_id = ?3, tags = ?4,
+             updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?5 AND workspace_id = ?6",
+            params![note.title, note.body, note.project_id, note.tags, note.id, workspace_id],
+        )?;
+
+        self.get_by_id(workspace_id, note.id)
+    }
+
+    /// Delete a note
+    pub fn delete(&self, workspace_id: i64, id: i64) -> Result<()> {
+        let conn = self.pool.get()?;
+        conn.execute("DELETE FROM notes WHERE id = ?1 AND workspace_id = ?2", [id, workspace_id])?;
+        Ok(())
+    }
+
+    /// Helper to map row to Note
+    fn map_note_row(row: &rusqlite::Row) -> rusqlite::Result<Note> {
+        Ok(Note {
+            id: row.get(0)?,
+            uid: row.get(1)?,
+            user_id: row.get(2)?,
+            workspace_id: row.get(3)?,
+            title: row.get(4)?,
+            body: row.get(5)?,
+            project_id: row.get(6)?,
+            tags: row.get(7)?,
+            created_at: row.get(8)?,
+            updated_at: row.get(9)?,
+        })
+    }
+}
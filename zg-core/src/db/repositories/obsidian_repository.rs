@@ -0,0 +1,74 @@
+use crate::db::{models::*, DbPool};
+use anyhow::Result;
+use rusqlite::params;
+use std::collections::HashMap;
+
+/// Stores the file and content of every note as of the last sync, per vault
+/// and workspace
+pub struct ObsidianStateRepository {
+    pool: DbPool,
+}
+
+impl ObsidianStateRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Load the sync state of a vault in a workspace, keyed by note ID
+    pub fn load(&self, workspace_id: i64, vault_path: &str) -> Result<HashMap<i64, ObsidianSyncState>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT note_id, file_path, content, fingerprint
+             FROM obsidian_sync_state WHERE vault_path = ?1 AND workspace_id = ?2",
+        )?;
+
+        let rows = stmt.query_map(params![vault_path, workspace_id], |row| {
+            Ok((
+                row.get(0)?,
+                ObsidianSyncState {
+                    file_path: row.get(1)?,
+                    content: row.get(2)?,
+                    fingerprint: row.get(3)?,
+                },
+            ))
+        })?;
+        let state: Result<HashMap<i64, ObsidianSyncState>, _> = rows.collect();
+        Ok(state?)
+    }
+
+    /// Replace the stored state of a vault with the result of a sync
+    pub fn replace(
+        &self,
+        workspace_id: i64,
+        vault_path: &str,
+        state: &HashMap<i64, ObsidianSyncState>,
+    ) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "DELETE FROM obsidian_sync_state WHERE vault_path = ?1 AND workspace_id = ?2",
+            params![vault_path, workspace_id],
+        )?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO obsidian_sync_state
+                    (workspace_id, vault_path, note_id, file_path, content, fingerprint)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?;
+            for (note_id, synced) in state {
+                stmt.execute(params![
+                    workspace_id,
+                    vault_path,
+                    note_id,
+                    synced.file_path,
+                    synced.content,
+                    synced.fingerprint
+                ])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+use crate::db::{models::*, schema::ACTIVE_WORKSPACE_SQL, DbPool};
+use anyhow::Result;
+use rusqlite::{params, OptionalExtension};
+
+pub const FIXED: &str = "fixed";
+pub const TASK: &str = "task";
+pub const BREAK: &str = "break";
+
+/// A block to be inserted by `replace_plan`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewBlock {
+    pub start_time: String,
+    pub end_time: String,
+    pub kind: &'static str,
+    pub task_id: Option<i64>,
+    pub title: String,
+}
+
+/// Time blocks and overflow of the day plans of the active workspace
+pub struct PlanRepository {
+    pool: DbPool,
+}
+
+impl PlanRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// All blocks of a day, in order
+    pub fn blocks(&self, date: &str) -> Result<Vec<TimeBlock>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, date, start_time, end_time, kind, task_id, title
+             FROM time_blocks WHERE date = ?1 AND workspace_id = {ACTIVE_WORKSPACE_SQL}
+             ORDER BY start_time, id"
+        ))?;
+        let blocks = stmt.query_map([date], row_to_block)?;
+        let blocks: Result<Vec<TimeBlock>, _> = blocks.collect();
+        Ok(blocks?)
+    }
+
+    pub fn add_block(&self, date: &str, block: &NewBlock) -> Result<TimeBlock> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            &format!(
+                "INSERT INTO time_blocks (workspace_id, date, start_time, end_time, kind, task_id, title)
+                 VALUES ({ACTIVE_WORKSPACE_SQL}, ?1, ?2, ?3, ?4, ?5, ?6)"
+            ),
+            params![date, block.start_time, block.end_time, block.kind, block.task_id, block.title],
+        )?;
+        let block = conn.query_row(
+            "SELECT id, date, start_time, end_time, kind, task_id, title FROM time_blocks WHERE id = ?1",
+            [conn.last_insert_rowid()],
+            row_to_block,
+        )?;
+        Ok(block)
+    }
+
+    pub fn delete_block(&self, id: i64) -> Result<()> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            &format!("DELETE FROM time_blocks WHERE id = ?1 AND workspace_id = {ACTIVE_WORKSPACE_SQL}"),
+            [id],
+        )?;
+        Ok(())
+    }
+
+    /// Task IDs that did not fit into the plan of a day
+    pub fn overflow(&self, date: &str) -> Result<Vec<i64>> {
+        let conn = self.pool.get()?;
+        let overflow: Option<String> = conn
+            .query_row(
+                &format!(
+                    "SELECT overflow FROM day_plans WHERE date = ?1 AND workspace_id = {ACTIVE_WORKSPACE_SQL}"
+                ),
+                [date],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match overflow {
+            Some(json) => Ok(serde_json::from_str(&json)?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Replace the planned (task and break) blocks of a day, except those in
+    /// `keep`, and store the overflow
+    pub fn replace_plan(&self, date: &str, keep: &[i64], blocks: &[NewBlock], overflow: &[i64]) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+
+        let keep_json = serde_json::to_string(keep)?;
+        tx.execute(
+            &format!(
+                "DELETE FROM time_blocks
+                 WHERE date = ?1 AND workspace_id = {ACTIVE_WORKSPACE_SQL} AND kind IN ('task', 'break')
+                   AND id NOT IN (SELECT value FROM json_each(?2))"
+            ),
+            params![date, keep_json],
+        )?;
+        {
+            let mut stmt = tx.prepare(&format!(
+                "INSERT INTO time_blocks (workspace_id, date, start_time, end_time, kind, task_id, title)
+                 VALUES ({ACTIVE_WORKSPACE_SQL}, ?1, ?2, ?3, ?4, ?5, ?6)"
+            ))?;
+            for block in blocks {
+                stmt.execute(params![
+                    date,
+                    block.start_time,
+                    block.end_time,
+                    block.kind,
+                    block.task_id,
+                    block.title
+                ])?;
+            }
+        }
+        tx.execute(
+            &format!(
+                "INSERT INTO day_plans (workspace_id, date, overflow) VALUES ({ACTIVE_WORKSPACE_SQL}, ?1, ?2)
+                 ON CONFLICT (workspace_id, date) DO UPDATE
+                 SET overflow = excluded.overflow, planned_at = CURRENT_TIMESTAMP"
+            ),
+            params![date, serde_json::to_string(overflow)?],
+        )?;
+
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+fn row_to_block(row: &rusqlite::Row) -> rusqlite::Result<TimeBlock> {
+    Ok(TimeBlock {
+        id: row.get(0)?,
+        date: row.get(1)?,
+        start_time: row.get(2)?,
+        end_time: row.get(3)?,
+        kind: row.get(4)?,
+        task_id: row.get(5)?,
+        title: row.get(6)?,
+    })
+}
@@ -0,0 +1,101 @@
+use crate::db::{models::Preferences, DbPool};
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+
+/// Version of the stored preferences JSON, bumped on incompatible changes
+pub const PREFERENCES_VERSION: u32 = 1;
+
+/// Settings stored in the single row of `user_preferences`. They belong to
+/// the device, not to a user: switching users keeps them.
+pub struct PreferencesRepository {
+    pool: DbPool,
+}
+
+impl PreferencesRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    pub fn get(&self) -> Result<Preferences> {
+        let conn = self.pool.get()?;
+        let stored: Option<String> = conn.query_row(
+            "SELECT preferences FROM user_preferences WHERE id = 1",
+            [],
+            |row| row.get(0),
+        )?;
+
+        match stored {
+            Some(json) => from_stored(&json),
+            None => Ok(Preferences::default()),
+        }
+    }
+
+    pub fn save_in(conn: &Connection, preferences: &Preferences) -> Result<()> {
+        let mut value = serde_json::to_value(preferences)?;
+        value["version"] = PREFERENCES_VERSION.into();
+        conn.execute(
+            "UPDATE user_preferences SET preferences = ?1, updated_at = CURRENT_TIMESTAMP
+             WHERE id = 1",
+            [value.to_string()],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_ai_api_key(&self) -> Result<Option<String>> {
+        let conn = self.pool.get()?;
+        let key = conn.query_row(
+            "SELECT ai_api_key FROM user_preferences WHERE id = 1",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(key)
+    }
+
+    /// SHA-256 of the API server token, if one was generated
+    pub fn get_api_token_hash(&self) -> Result<Option<String>> {
+        let conn = self.pool.get()?;
+        let hash = conn.query_row(
+            "SELECT api_token_hash FROM user_preferences WHERE id = 1",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(hash)
+    }
+
+    pub fn save_api_token_hash(&self, hash: &str) -> Result<()> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            "UPDATE user_preferences SET api_token_hash = ?1, updated_at = CURRENT_TIMESTAMP
+             WHERE id = 1",
+            [hash],
+        )?;
+        Ok(())
+    }
+
+    /// Random hex string of 32 bytes
+    pub fn random_token(&self) -> Result<String> {
+        let conn = self.pool.get()?;
+        let token = conn.query_row("SELECT lower(hex(randomblob(32)))", [], |row| row.get(0))?;
+        Ok(token)
+    }
+
+    pub fn save_ai_api_key_in(conn: &Connection, api_key: Option<&str>) -> Result<()> {
+        conn.execute(
+            "UPDATE user_preferences SET ai_api_key = ?1, updated_at = CURRENT_TIMESTAMP
+             WHERE id = 1",
+            params![api_key],
+        )?;
+        Ok(())
+    }
+}
+
+/// Preferences from their stored JSON. Fields written by a newer version are
+/// ignored, missing ones take their defaults.
+fn from_stored(json: &str) -> Result<Preferences> {
+    let value: serde_json::Value = serde_json::from_str(json).context("Stored preferences are not JSON")?;
+    let version = value["version"].as_u64().unwrap_or(1);
+    if version > PREFERENCES_VERSION.into() {
+        tracing::warn!("Preferences were saved by a newer version ({version}), unknown fields are ignored");
+    }
+    serde_json::from_value(value).context("Stored preferences are malformed")
+}
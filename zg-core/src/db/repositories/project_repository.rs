@@ -0,0 +1,161 @@
+use crate::db::{models::*, repositories::WorkspaceRepository, schema::ACTIVE_WORKSPACE_SQL, DbPool};
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+
+/// Color used for projects created implicitly (e.g. from a `+project` token)
+pub const DEFAULT_PROJECT_COLOR: &str = "#6366f1";
+
+const PROJECT_COLUMNS: &str = "id, name, color, icon, archived, created_at, updated_at";
+
+/// Projects of the active workspace, or of the workspace passed to the
+/// `_in_workspace` methods
+pub struct ProjectRepository {
+    pool: DbPool,
+}
+
+impl ProjectRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create a new project
+    pub fn create(&self, input: CreateProjectInput) -> Result<Project> {
+        self.create_in_workspace(self.active_workspace_id()?, input)
+    }
+
+    pub fn create_in_workspace(&self, workspace_id: i64, input: CreateProjectInput) -> Result<Project> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            "INSERT INTO projects (workspace_id, name, color, icon) VALUES (?1, ?2, ?3, ?4)",
+            params![workspace_id, input.name, input.color, input.icon],
+        )?;
+
+        let id = conn.last_insert_rowid();
+        let project = conn.query_row(
+            &format!("SELECT {PROJECT_COLUMNS} FROM projects WHERE id = ?1"),
+            [id],
+            Self::map_project_row,
+        )?;
+        Ok(project)
+    }
+
+    /// Get project by ID
+    pub fn get_by_id(&self, id: i64) -> Result<Project> {
+        let conn = self.pool.get()?;
+        let project = conn.query_row(
+            &format!("SELECT {PROJECT_COLUMNS} FROM projects WHERE id = ?1 AND workspace_id = {ACTIVE_WORKSPACE_SQL}"),
+            [id],
+            Self::map_project_row,
+        )?;
+
+        Ok(project)
+    }
+
+    /// Get all projects, including archived ones
+    pub fn get_all(&self) -> Result<Vec<Project>> {
+        self.get_all_in_workspace(self.active_workspace_id()?)
+    }
+
+    pub fn get_all_in_workspace(&self, workspace_id: i64) -> Result<Vec<Project>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {PROJECT_COLUMNS} FROM projects WHERE workspace_id = ?1 ORDER BY name"
+        ))?;
+
+        let projects = stmt.query_map([workspace_id], Self::map_project_row)?;
+        let projects: Result<Vec<Project>, _> = projects.collect();
+        Ok(projects?)
+    }
+
+    /// Find a project by name (case-insensitive)
+    pub fn find_by_name(&self, name: &str) -> Result<Option<Project>> {
+        self.find_by_name_in_workspace(self.active_workspace_id()?, name)
+    }
+
+    pub fn find_by_name_in_workspace(&self, workspace_id: i64, name: &str) -> Result<Option<Project>> {
+        let conn = self.pool.get()?;
+        let project = conn
+            .query_row(
+                &format!(
+                    "SELECT {PROJECT_COLUMNS} FROM projects WHERE name = ?1 COLLATE NOCASE AND workspace_id = ?2
+                     ORDER BY id LIMIT 1"
+                ),
+                params![name, workspace_id],
+                Self::map_project_row,
+            )
+            .optional()?;
+
+        Ok(project)
+    }
+
+    /// Find a project by name, creating it with the default color if missing
+    pub fn find_or_create(&self, name: &str) -> Result<Project> {
+        self.find_or_create_in_workspace(self.active_workspace_id()?, name)
+    }
+
+    pub fn find_or_create_in_workspace(&self, workspace_id: i64, name: &str) -> Result<Project> {
+        if let Some(project) = self.find_by_name_in_workspace(workspace_id, name)? {
+            return Ok(project);
+        }
+
+        self.create_in_workspace(
+            workspace_id,
+            CreateProjectInput {
+                name: name.to_string(),
+                color: DEFAULT_PROJECT_COLOR.to_string(),
+                icon: None,
+            },
+        )
+    }
+
+    /// ID of the project with the same name as `project_id` in another
+    /// workspace, created with the same color if missing
+    pub fn counterpart_in(conn: &Connection, project_id: i64, workspace_id: i64) -> Result<Option<i64>> {
+        let project: Option<(String, String, Option<String>)> = conn
+            .query_row(
+                "SELECT name, color, icon FROM projects WHERE id = ?1",
+                [project_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()?;
+        let Some((name, color, icon)) = project else {
+            return Ok(None);
+        };
+
+        let existing = conn
+            .query_row(
+                "SELECT id FROM projects WHERE workspace_id = ?1 AND name = ?2 COLLATE NOCASE
+                 ORDER BY id LIMIT 1",
+                params![workspace_id, name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if existing.is_some() {
+            return Ok(existing);
+        }
+
+        conn.execute(
+            "INSERT INTO projects (workspace_id, name, color, icon) VALUES (?1, ?2, ?3, ?4)",
+            params![workspace_id, name, color, icon],
+        )?;
+        Ok(Some(conn.last_insert_rowid()))
+    }
+
+    fn active_workspace_id(&self) -> Result<i64> {
+        let conn = self.pool.get()?;
+        WorkspaceRepository::active_id_in(&conn)
+    }
+
+    /// Helper to map row to Project
+    fn map_project_row(row: &rusqlite::Row) -> rusqlite::Result<Project> {
+        Ok(Project {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            color: row.get(2)?,
+            icon: row.get(3)?,
+            archived: row.get(4)?,
+            created_at: row.get(5)?,
+            updated_at: row.get(6)?,
+        })
+    }
+}
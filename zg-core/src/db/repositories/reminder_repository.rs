@@ -0,0 +1,117 @@
+use crate::db::{models::*, DbPool};
+use anyhow::Result;
+use rusqlite::{params, Connection};
+
+const REMINDER_COLUMNS: &str =
+    "r.id, r.task_id, r.remind_at, r.minutes_before_due, r.snoozed_until, r.fired_at";
+
+/// An unfired reminder of an open task, with what is needed to fire it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingReminder {
+    pub reminder: Reminder,
+    pub title: String,
+    pub due_date: Option<String>,
+}
+
+pub struct ReminderRepository {
+    pool: DbPool,
+}
+
+impl ReminderRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    pub fn create(&self, task_id: i64, input: &CreateReminderInput) -> Result<Reminder> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            "INSERT INTO reminders (task_id, remind_at, minutes_before_due) VALUES (?1, ?2, ?3)",
+            params![task_id, input.remind_at, input.minutes_before_due],
+        )?;
+        self.get_by_id(conn.last_insert_rowid())
+    }
+
+    pub fn get_by_id(&self, id: i64) -> Result<Reminder> {
+        let conn = self.pool.get()?;
+        let reminder = conn.query_row(
+            &format!("SELECT {REMINDER_COLUMNS} FROM reminders r WHERE r.id = ?1"),
+            [id],
+            map_reminder_row,
+        )?;
+        Ok(reminder)
+    }
+
+    pub fn for_task(&self, task_id: i64) -> Result<Vec<Reminder>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {REMINDER_COLUMNS} FROM reminders r WHERE r.task_id = ?1 ORDER BY r.id"
+        ))?;
+        let reminders = stmt.query_map([task_id], map_reminder_row)?;
+        let reminders: Result<Vec<Reminder>, _> = reminders.collect();
+        Ok(reminders?)
+    }
+
+    /// Unfired reminders of tasks that are not completed, in all workspaces:
+    /// reminders fire whichever workspace is active
+    pub fn pending(&self) -> Result<Vec<PendingReminder>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {REMINDER_COLUMNS}, t.title, t.due_date
+             FROM reminders r JOIN tasks t ON t.id = r.task_id
+             WHERE r.fired_at IS NULL AND t.status != 'completed'
+             ORDER BY r.id"
+        ))?;
+        let pending = stmt.query_map([], |row| {
+            Ok(PendingReminder {
+                reminder: map_reminder_row(row)?,
+                title: row.get(6)?,
+                due_date: row.get(7)?,
+            })
+        })?;
+        let pending: Result<Vec<PendingReminder>, _> = pending.collect();
+        Ok(pending?)
+    }
+
+    /// Postpone a reminder; a fired one becomes pending again
+    pub fn snooze(&self, id: i64, until: &str) -> Result<Reminder> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            "UPDATE reminders SET snoozed_until = ?2, fired_at = NULL WHERE id = ?1",
+            params![id, until],
+        )?;
+        self.get_by_id(id)
+    }
+
+    pub fn mark_fired(&self, id: i64, at: &str) -> Result<()> {
+        let conn = self.pool.get()?;
+        conn.execute("UPDATE reminders SET fired_at = ?2 WHERE id = ?1", params![id, at])?;
+        Ok(())
+    }
+
+    /// Mark all unfired reminders of a task as fired
+    pub fn dismiss_for_task_in(conn: &Connection, task_id: i64, at: &str) -> Result<()> {
+        conn.execute(
+            "UPDATE reminders SET fired_at = ?2 WHERE task_id = ?1 AND fired_at IS NULL",
+            params![task_id, at],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete(&self, id: i64) -> Result<()> {
+        let conn = self.pool.get()?;
+        conn.execute("DELETE FROM reminders WHERE id = ?1", [id])?;
+        Ok(())
+    }
+}
+
+fn map_reminder_row(row: &rusqlite::Row) -> rusqlite::Result<Reminder> {
+    Ok(Reminder {
+        id: row.get(0)?,
+        task_id: row.get(1)?,
+        remind_at: row.get(2)?,
+        minutes_before_due: row.get(3)?,
+        snoozed_until: row.get(4)?,
+        fired_at: row.get(5)?,
+        next_fire_at: None,
+    })
+}
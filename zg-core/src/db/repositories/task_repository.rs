@@ -0,0 +1,582 @@
+use crate::db::{
+    models::*,
+    repositories::WorkspaceRepository,
+    schema::{ACTIVE_WORKSPACE_SQL, CURRENT_USER_SQL, UUID_V4_SQL},
+    DbPool,
+};
+use anyhow::{bail, Result};
+use chrono::NaiveDate;
+use rusqlite::{params, params_from_iter, types::Value as SqlValue, Connection, OptionalExtension};
+
+/// Columns selected for every task query, in the order `map_task_row` expects
+pub const TASK_COLUMNS: &str = "id, uid, user_id, workspace_id, title, description, project_id,
+    status, priority, estimated_minutes, difficulty_level,
+    energy_level, scheduled_date, due_date, completed_at,
+    parent_task_id, order_index, column_position, tags, created_at, updated_at, assignee_id, version";
+
+/// The assignee of a task being transferred to workspace `?2`, if a member there
+const ASSIGNEE_IN_TARGET_SQL: &str = "CASE WHEN assignee_id IN
+    (SELECT user_id FROM workspace_members WHERE workspace_id = ?2) THEN assignee_id END";
+
+#[derive(Debug, thiserror::Error)]
+pub enum TaskError {
+    /// The task changed after the version an edit was based on. Holds the
+    /// current task, so the edit can be merged with it.
+    #[error("The task was changed in the meantime (now version {})", .0.version)]
+    Conflict(Box<Task>),
+}
+
+/// Tasks of the active workspace. Methods ending in `_in` run on a given
+/// connection, so that they can be part of a transaction.
+pub struct TaskRepository {
+    pool: DbPool,
+}
+
+impl TaskRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create a new task
+    pub fn create(&self, input: CreateTaskInput) -> Result<Task> {
+        let conn = self.pool.get()?;
+        Self::create_in(&conn, input)
+    }
+
+    pub fn create_in(conn: &Connection, input: CreateTaskInput) -> Result<Task> {
+        Self::create_in_workspace(conn, WorkspaceRepository::active_id_in(conn)?, input)
+    }
+
+    pub fn create_in_workspace(conn: &Connection, workspace_id: i64, input: CreateTaskInput) -> Result<Task> {
+        let tags_json = input.tags.map(|t| serde_json::to_string(&t).unwrap());
+
+        conn.execute(
+            &format!(
+                "INSERT INTO tasks (
+                    uid, user_id, workspace_id, title, description, project_id, priority,
+                    estimated_minutes, difficulty_level, energy_level, scheduled_date, due_date,
+                    parent_task_id, tags
+                ) VALUES (
+                    {UUID_V4_SQL}, {CURRENT_USER_SQL}, ?12,
+                    ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11
+                )"
+            ),
+            params![
+                input.title,
+                input.description,
+                input.project_id,
+                input.priority.unwrap_or(0),
+                input.estimated_minutes,
+                input.difficulty_level,
+                input.energy_level,
+                input.scheduled_date,
+                input.due_date,
+                input.parent_task_id,
+                tags_json,
+                workspace_id,
+            ],
+        )?;
+
+        let id = conn.last_insert_rowid();
+        Self::get_by_id_in_workspace(conn, workspace_id, id)
+    }
+
+    /// Get task by ID
+    pub fn get_by_id(&self, id: i64) -> Result<Task> {
+        let conn = self.pool.get()?;
+        Self::get_by_id_in(&conn, id)
+    }
+
+    pub fn get_by_id_in(conn: &Connection, id: i64) -> Result<Task> {
+        Self::get_by_id_in_workspace(conn, WorkspaceRepository::active_id_in(conn)?, id)
+    }
+
+    pub fn get_by_id_in_workspace(conn: &Connection, workspace_id: i64, id: i64) -> Result<Task> {
+        let task = conn.query_row(
+            &format!("SELECT {TASK_COLUMNS} FROM tasks WHERE id = ?1 AND workspace_id = ?2"),
+            params![id, workspace_id],
+            Self::map_task_row,
+        )?;
+
+        Ok(task)
+    }
+
+    /// Get task by its stable UID
+    pub fn find_by_uid(&self, uid: &str) -> Result<Option<Task>> {
+        let conn = self.pool.get()?;
+        let task = conn
+            .query_row(
+                &format!(
+                    "SELECT {TASK_COLUMNS} FROM tasks
+                     WHERE uid = ?1 AND workspace_id = {ACTIVE_WORKSPACE_SQL}"
+                ),
+                [uid],
+                Self::map_task_row,
+            )
+            .optional()?;
+
+        Ok(task)
+    }
+
+    /// Replace the UID of a task, e.g. to keep the UID of an imported item
+    pub fn set_uid_in(conn: &Connection, workspace_id: i64, id: i64, uid: &str) -> Result<Task> {
+        conn.execute(
+            "UPDATE tasks SET uid = ?1 WHERE id = ?2 AND workspace_id = ?3",
+            params![uid, id, workspace_id],
+        )?;
+        Self::get_by_id_in_workspace(conn, workspace_id, id)
+    }
+
+    /// Tasks assigned to the signed-in user, optionally with a status
+    pub fn get_assigned_to_current(&self, status: Option<&str>) -> Result<Vec<Task>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {TASK_COLUMNS} FROM tasks
+             WHERE workspace_id = {ACTIVE_WORKSPACE_SQL} AND assignee_id = {CURRENT_USER_SQL}
+               AND (?1 IS NULL OR status = ?1)
+             ORDER BY due_date IS NULL, due_date, priority DESC, created_at"
+        ))?;
+
+        let tasks = stmt.query_map([status], Self::map_task_row)?;
+        let tasks: Result<Vec<Task>, _> = tasks.collect();
+        Ok(tasks?)
+    }
+
+    /// All tasks of a workspace, e.g. the one a sync is bound to
+    pub fn get_all_in_workspace(&self, workspace_id: i64) -> Result<Vec<Task>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {TASK_COLUMNS} FROM tasks
+             WHERE workspace_id = ?1
+             ORDER BY column_position, order_index, created_at DESC"
+        ))?;
+
+        let tasks = stmt.query_map([workspace_id], Self::map_task_row)?;
+        let tasks: Result<Vec<Task>, _> = tasks.collect();
+        Ok(tasks?)
+    }
+
+    /// Get all tasks with optional filters
+    pub fn get_all(&self, status: Option<String>) -> Result<Vec<Task>> {
+        let conn = self.pool.get()?;
+
+        let query = match status {
+            Some(_) => format!(
+                "SELECT {TASK_COLUMNS} FROM tasks
+                 WHERE workspace_id = {ACTIVE_WORKSPACE_SQL} AND status = ?1
+                 ORDER BY column_position, order_index, created_at DESC"
+            ),
+            None => format!(
+                "SELECT {TASK_COLUMNS} FROM tasks
+                 WHERE workspace_id = {ACTIVE_WORKSPACE_SQL}
+                 ORDER BY column_position, order_index, created_at DESC"
+            ),
+        };
+
+        let mut stmt = conn.prepare(&query)?;
+
+        let tasks = if let Some(status_val) = status {
+            stmt.query_map([status_val], Self::map_task_row)?
+        } else {
+            stmt.query_map([], Self::map_task_row)?
+        };
+
+        let tasks: Result<Vec<Task>, _> = tasks.collect();
+        Ok(tasks?)
+    }
+
+    /// Apply the changes of `input` in one statement, after validating them
+    pub fn update_in(conn: &Connection, id: i64, input: UpdateTaskInput) -> Result<Task> {
+        Self::validate_update_in(conn, id, &input)?;
+
+        let completed = match &input.status {
+            Patch::Set(status) => Some(status == "completed"),
+            _ => None,
+        };
+        let tags = input.tags.map(|tags| serde_json::to_string(&tags).unwrap());
+        let changes: [(&str, Patch<SqlValue>); 13] = [
+            ("title", input.title.map(SqlValue::from)),
+            ("description", input.description.map(SqlValue::from)),
+            ("project_id", input.project_id.map(SqlValue::from)),
+            ("status", input.status.map(SqlValue::from)),
+            ("priority", input.priority.map(SqlValue::from)),
+            ("estimated_minutes", input.estimated_minutes.map(SqlValue::from)),
+            ("difficulty_level", input.difficulty_level.map(SqlValue::from)),
+            ("energy_level", input.energy_level.map(SqlValue::from)),
+            ("scheduled_date", input.scheduled_date.map(SqlValue::from)),
+            ("due_date", input.due_date.map(SqlValue::from)),
+            ("parent_task_id", input.parent_task_id.map(SqlValue::from)),
+            ("order_index", input.order_index.map(SqlValue::from)),
+            ("tags", tags.map(SqlValue::from)),
+        ];
+
+        let mut updates = Vec::new();
+        let mut params = Vec::new();
+        for (column, change) in changes {
+            let value = match change {
+                Patch::Unchanged => continue,
+                Patch::Clear => SqlValue::Null,
+                Patch::Set(value) => value,
+            };
+            updates.push(format!("{column} = ?"));
+            params.push(value);
+        }
+        if let Some(completed) = completed {
+            // Completion time is kept while a completed task stays completed
+            updates.push("completed_at = CASE WHEN ? THEN COALESCE(completed_at, CURRENT_TIMESTAMP) END".to_string());
+            params.push(SqlValue::from(completed));
+        }
+        updates.push("updated_at = CURRENT_TIMESTAMP".to_string());
+
+        let mut query = format!(
+            "UPDATE tasks SET {} WHERE id = ? AND workspace_id = {ACTIVE_WORKSPACE_SQL}",
+            updates.join(", ")
+        );
+        params.push(SqlValue::Integer(id));
+        if let Some(version) = input.expected_version {
+            query.push_str(" AND version = ?");
+            params.push(SqlValue::Integer(version));
+        }
+
+        let changed = conn.execute(&query, params_from_iter(params))?;
+        Self::changed_or_conflict_in(conn, id, changed)
+    }
+
+    fn validate_update_in(conn: &Connection, id: i64, input: &UpdateTaskInput) -> Result<()> {
+        let required = [
+            ("title", matches!(input.title, Patch::Clear)),
+            ("status", matches!(input.status, Patch::Clear)),
+            ("priority", matches!(input.priority, Patch::Clear)),
+            ("order_index", matches!(input.order_index, Patch::Clear)),
+        ];
+        if let Some((field, _)) = required.iter().find(|(_, cleared)| *cleared) {
+            bail!("The {field} of a task cannot be cleared");
+        }
+
+        if let Patch::Set(title) = &input.title {
+            if title.trim().is_empty() {
+                bail!("The title must not be empty");
+            }
+        }
+        if let Patch::Set(status) = &input.status {
+            check_status(status)?;
+        }
+        if let Patch::Set(priority) = input.priority {
+            if !(0..=4).contains(&priority) {
+                bail!("The priority must be between 0 and 4");
+            }
+        }
+        if let Patch::Set(minutes) = input.estimated_minutes {
+            if minutes <= 0 {
+                bail!("The estimate must be at least one minute");
+            }
+        }
+        if let Patch::Set(difficulty) = input.difficulty_level {
+            if !(1..=5).contains(&difficulty) {
+                bail!("The difficulty must be between 1 and 5");
+            }
+        }
+        if let Patch::Set(energy) = &input.energy_level {
+            if !matches!(energy.as_str(), "low" | "medium" | "high") {
+                bail!("The energy level must be low, medium or high");
+            }
+        }
+        for (field, date) in [("scheduled_date", &input.scheduled_date), ("due_date", &input.due_date)] {
+            if let Patch::Set(date) = date {
+                let day = date.get(..10).unwrap_or_default();
+                if NaiveDate::parse_from_str(day, "%Y-%m-%d").is_err() {
+                    bail!("The {field} must start with a YYYY-MM-DD date, not {date:?}");
+                }
+            }
+        }
+
+        if let Patch::Set(project_id) = input.project_id {
+            let exists: bool = conn.query_row(
+                &format!("SELECT EXISTS(SELECT 1 FROM projects WHERE id = ?1 AND workspace_id = {ACTIVE_WORKSPACE_SQL})"),
+                [project_id],
+                |row| row.get(0),
+            )?;
+            if !exists {
+                bail!("Project {project_id} does not exist in this workspace");
+            }
+        }
+        if let Patch::Set(parent_id) = input.parent_task_id {
+            // The parent must be in the workspace and not the task or one of its subtasks
+            let (exists, cycle): (bool, bool) = conn.query_row(
+                &format!(
+                    "WITH RECURSIVE ancestors(id) AS (
+                        SELECT ?1
+                        UNION SELECT t.parent_task_id FROM tasks t JOIN ancestors a ON t.id = a.id
+                        WHERE t.parent_task_id IS NOT NULL
+                     )
+                     SELECT EXISTS(SELECT 1 FROM tasks WHERE id = ?1 AND workspace_id = {ACTIVE_WORKSPACE_SQL}),
+                            EXISTS(SELECT 1 FROM ancestors WHERE id = ?2)"
+                ),
+                [parent_id, id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+            if !exists {
+                bail!("Task {parent_id} does not exist in this workspace");
+            }
+            if cycle {
+                bail!("A task cannot be a subtask of itself or of its own subtasks");
+            }
+        }
+        Ok(())
+    }
+
+    /// Overwrite all user-editable fields of a task with the given values.
+    ///
+    /// Used by sync services, which reconcile a complete external representation
+    /// of a task and therefore need to clear fields as well as set them.
+    pub fn overwrite(&self, id: i64, task: &Task) -> Result<Task> {
+        let conn = self.pool.get()?;
+        Self::overwrite_in(&conn, WorkspaceRepository::active_id_in(&conn)?, id, task)
+    }
+
+    pub fn overwrite_in(conn: &Connection, workspace_id: i64, id: i64, task: &Task) -> Result<Task> {
+        conn.execute(
+            "UPDATE tasks SET
+                title = ?1, description = ?2, project_id = ?3, status = ?4,
+                priority = ?5, estimated_minutes = ?6, difficulty_level = ?7,
+                energy_level = ?8, scheduled_date = ?9, due_date = ?10,
+                completed_at = ?11, parent_task_id = ?12, tags = ?13,
+                updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?14 AND workspace_id = ?15",
+            params![
+                task.title,
+                task.description,
+                task.project_id,
+                task.status,
+                task.priority,
+                task.estimated_minutes,
+                task.difficulty_level,
+                task.energy_level,
+                task.scheduled_date,
+                task.due_date,
+                task.completed_at,
+                task.parent_task_id,
+                task.tags,
+                id,
+                workspace_id,
+            ],
+        )?;
+
+        Self::get_by_id_in_workspace(conn, workspace_id, id)
+    }
+
+    /// Delete a task
+    pub fn delete(&self, id: i64) -> Result<()> {
+        let conn = self.pool.get()?;
+        Self::delete_in(&conn, id)
+    }
+
+    pub fn delete_in(conn: &Connection, id: i64) -> Result<()> {
+        Self::delete_in_workspace(conn, WorkspaceRepository::active_id_in(conn)?, id)
+    }
+
+    pub fn delete_in_workspace(conn: &Connection, workspace_id: i64, id: i64) -> Result<()> {
+        conn.execute("DELETE FROM tasks WHERE id = ?1 AND workspace_id = ?2", [id, workspace_id])?;
+        Ok(())
+    }
+
+    #[cfg(test)]
+    pub fn complete(&self, id: i64) -> Result<Task> {
+        let conn = self.pool.get()?;
+        Self::complete_in(&conn, id)
+    }
+
+    /// Complete a task
+    pub fn complete_in(conn: &Connection, id: i64) -> Result<Task> {
+        conn.execute(
+            &format!(
+                "UPDATE tasks SET status = 'completed', completed_at = CURRENT_TIMESTAMP
+                 WHERE id = ?1 AND workspace_id = {ACTIVE_WORKSPACE_SQL}"
+            ),
+            [id],
+        )?;
+        Self::get_by_id_in(conn, id)
+    }
+
+    /// Assign a task to a user, or unassign it with `None`
+    pub fn assign_in(conn: &Connection, id: i64, assignee_id: Option<i64>) -> Result<Task> {
+        conn.execute(
+            &format!(
+                "UPDATE tasks SET assignee_id = ?2, updated_at = CURRENT_TIMESTAMP
+                 WHERE id = ?1 AND workspace_id = {ACTIVE_WORKSPACE_SQL}"
+            ),
+            params![id, assignee_id],
+        )?;
+        Self::get_by_id_in(conn, id)
+    }
+
+    /// Set status and Kanban column position, if the task is still at
+    /// `expected_version` when given. Tasks get their completion time when
+    /// moved to completed and lose it when moved out.
+    pub fn move_to_column_in(
+        conn: &Connection,
+        id: i64,
+        status: &str,
+        position: i32,
+        expected_version: Option<i64>,
+    ) -> Result<Task> {
+        check_status(status)?;
+        let changed = conn.execute(
+            &format!(
+                "UPDATE tasks SET status = ?1, column_position = ?2,
+                    completed_at = CASE WHEN ?5 THEN COALESCE(completed_at, CURRENT_TIMESTAMP) END,
+                    updated_at = CURRENT_TIMESTAMP
+                 WHERE id = ?3 AND workspace_id = {ACTIVE_WORKSPACE_SQL}
+                   AND (?4 IS NULL OR version = ?4)"
+            ),
+            params![status, position, id, expected_version, status == "completed"],
+        )?;
+        Self::changed_or_conflict_in(conn, id, changed)
+    }
+
+    /// The task after an update of `changed` rows; if none changed although
+    /// the task exists, its version did not match
+    fn changed_or_conflict_in(conn: &Connection, id: i64, changed: usize) -> Result<Task> {
+        let task = Self::get_by_id_in(conn, id)?;
+        if changed == 0 {
+            return Err(TaskError::Conflict(Box::new(task)).into());
+        }
+        Ok(task)
+    }
+
+    /// Get subtasks for a parent task
+    pub fn get_subtasks(&self, parent_id: i64) -> Result<Vec<Task>> {
+        let conn = self.pool.get()?;
+        Self::get_subtasks_in(&conn, parent_id)
+    }
+
+    pub fn get_subtasks_in(conn: &Connection, parent_id: i64) -> Result<Vec<Task>> {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {TASK_COLUMNS} FROM tasks
+             WHERE parent_task_id = ?1 AND workspace_id = {ACTIVE_WORKSPACE_SQL}
+             ORDER BY order_index, created_at"
+        ))?;
+
+        let tasks = stmt.query_map([parent_id], Self::map_task_row)?;
+        let tasks: Result<Vec<Task>, _> = tasks.collect();
+        Ok(tasks?)
+    }
+
+    /// Move a task to another workspace, where it gets the given project and
+    /// parent. It is taken out of the day plans of its current workspace, and
+    /// unassigned unless the assignee is a member of the other workspace.
+    pub fn move_to_workspace_in(
+        conn: &Connection,
+        id: i64,
+        workspace_id: i64,
+        project_id: Option<i64>,
+        parent_task_id: Option<i64>,
+    ) -> Result<()> {
+        conn.execute(
+            &format!(
+                "DELETE FROM time_blocks WHERE task_id = ?1 AND workspace_id = {ACTIVE_WORKSPACE_SQL}"
+            ),
+            [id],
+        )?;
+        conn.execute(
+            &format!(
+                "UPDATE tasks SET workspace_id = ?2, project_id = ?3, parent_task_id = ?4,
+                    assignee_id = {ASSIGNEE_IN_TARGET_SQL}, updated_at = CURRENT_TIMESTAMP
+                 WHERE id = ?1 AND workspace_id = {ACTIVE_WORKSPACE_SQL}"
+            ),
+            params![id, workspace_id, project_id, parent_task_id],
+        )?;
+        Ok(())
+    }
+
+    /// Copy a task into another workspace, with the given project and parent.
+    /// Returns the ID of the copy.
+    pub fn copy_to_workspace_in(
+        conn: &Connection,
+        id: i64,
+        workspace_id: i64,
+        project_id: Option<i64>,
+        parent_task_id: Option<i64>,
+    ) -> Result<i64> {
+        conn.execute(
+            &format!(
+                "INSERT INTO tasks (
+                    uid, user_id, workspace_id, title, description, project_id, status, priority,
+                    estimated_minutes, difficulty_level, energy_level, scheduled_date, due_date,
+                    completed_at, parent_task_id, order_index, column_position, tags, assignee_id
+                 )
+                 SELECT {UUID_V4_SQL}, user_id, ?2, title, description, ?3, status, priority,
+                    estimated_minutes, difficulty_level, energy_level, scheduled_date, due_date,
+                    completed_at, ?4, order_index, column_position, tags, {ASSIGNEE_IN_TARGET_SQL}
+                 FROM tasks WHERE id = ?1 AND workspace_id = {ACTIVE_WORKSPACE_SQL}"
+            ),
+            params![id, workspace_id, project_id, parent_task_id],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Get task with all subtasks and progress
+    pub fn get_with_subtasks(&self, id: i64) -> Result<TaskWithSubtasks> {
+        let task = self.get_by_id(id)?;
+        let subtasks = self.get_subtasks(id)?;
+
+        let total = subtasks.len() as i32;
+        let completed = subtasks
+            .iter()
+            .filter(|t| t.status == "completed")
+            .count() as i32;
+        let percentage = if total > 0 {
+            (completed as f32 / total as f32) * 100.0
+        } else {
+            0.0
+        };
+
+        let progress = SubtaskProgress {
+            total,
+            completed,
+            percentage,
+        };
+
+        Ok(TaskWithSubtasks {
+            task,
+            subtasks,
+            progress,
+        })
+    }
+
+    /// Helper to map row to Task
+    pub fn map_task_row(row: &rusqlite::Row) -> rusqlite::Result<Task> {
+        Ok(Task {
+            id: row.get(0)?,
+            uid: row.get(1)?,
+            user_id: row.get(2)?,
+            workspace_id: row.get(3)?,
+            title: row.get(4)?,
+            description: row.get(5)?,
+            project_id: row.get(6)?,
+            status: row.get(7)?,
+            priority: row.get(8)?,
+            estimated_minutes: row.get(9)?,
+            difficulty_level: row.get(10)?,
+            energy_level: row.get(11)?,
+            scheduled_date: row.get(12)?,
+            due_date: row.get(13)?,
+            completed_at: row.get(14)?,
+            parent_task_id: row.get(15)?,
+            order_index: row.get(16)?,
+            column_position: row.get(17)?,
+            tags: row.get(18)?,
+            created_at: row.get(19)?,
+            updated_at: row.get(20)?,
+            assignee_id: row.get(21)?,
+            version: row.get(22)?,
+        })
+    }
+}
+
+fn check_status(status: &str) -> Result<()> {
+    if !matches!(status, "todo" | "in_progress" | "completed") {
+        bail!("The status must be todo, in_progress or completed");
+    }
+    Ok(())
+}
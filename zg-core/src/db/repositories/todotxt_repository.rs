@@ -0,0 +1,53 @@
+use crate::db::DbPool;
+use anyhow::Result;
+use rusqlite::params;
+use std::collections::HashMap;
+
+/// Stores the canonical todo.txt line of every task as of the last sync, per
+/// file and workspace
+pub struct TodoTxtStateRepository {
+    pool: DbPool,
+}
+
+impl TodoTxtStateRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Load the last synced line of every task for a file in a workspace,
+    /// keyed by task ID
+    pub fn load(&self, workspace_id: i64, file_path: &str) -> Result<HashMap<i64, String>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT task_id, line FROM todotxt_sync_state
+             WHERE file_path = ?1 AND workspace_id = ?2",
+        )?;
+
+        let rows = stmt.query_map(params![file_path, workspace_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        let state: Result<HashMap<i64, String>, _> = rows.collect();
+        Ok(state?)
+    }
+
+    /// Replace the stored state of a file with the result of a sync
+    pub fn replace(&self, workspace_id: i64, file_path: &str, state: &HashMap<i64, String>) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "DELETE FROM todotxt_sync_state WHERE file_path = ?1 AND workspace_id = ?2",
+            params![file_path, workspace_id],
+        )?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO todotxt_sync_state (workspace_id, file_path, task_id, line)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for (task_id, line) in state {
+                stmt.execute(params![workspace_id, file_path, task_id, line])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,161 @@
+use crate::db::{models::*, DbPool};
+use anyhow::Result;
+use rusqlite::params;
+
+const USER_COLUMNS: &str = "id, display_name, email, pin_hash IS NOT NULL, created_at, updated_at";
+
+/// Local user profiles and who is signed in
+pub struct UserRepository {
+    pool: DbPool,
+}
+
+impl UserRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    pub fn create(&self, display_name: &str, email: Option<&str>, pin_hash: Option<&str>) -> Result<User> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            "INSERT INTO users (display_name, email, pin_hash) VALUES (?1, ?2, ?3)",
+            params![display_name, email, pin_hash],
+        )?;
+
+        let id = conn.last_insert_rowid();
+        self.get_by_id(id)
+    }
+
+    pub fn get_by_id(&self, id: i64) -> Result<User> {
+        let conn = self.pool.get()?;
+        let user = conn.query_row(
+            &format!("SELECT {USER_COLUMNS} FROM users WHERE id = ?1"),
+            [id],
+            map_user_row,
+        )?;
+
+        Ok(user)
+    }
+
+    pub fn get_all(&self) -> Result<Vec<User>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {USER_COLUMNS} FROM users ORDER BY display_name COLLATE NOCASE"
+        ))?;
+
+        let users = stmt.query_map([], map_user_row)?;
+        let users: Result<Vec<User>, _> = users.collect();
+        Ok(users?)
+    }
+
+    pub fn update(&self, id: i64, display_name: &str, email: Option<&str>) -> Result<User> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            "UPDATE users SET display_name = ?2, email = ?3, updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?1",
+            params![id, display_name, email],
+        )?;
+        self.get_by_id(id)
+    }
+
+    pub fn pin_hash(&self, id: i64) -> Result<Option<String>> {
+        let conn = self.pool.get()?;
+        let hash = conn.query_row("SELECT pin_hash FROM users WHERE id = ?1", [id], |row| row.get(0))?;
+        Ok(hash)
+    }
+
+    pub fn set_pin_hash(&self, id: i64, pin_hash: Option<&str>) -> Result<()> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            "UPDATE users SET pin_hash = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = ?1",
+            params![id, pin_hash],
+        )?;
+        Ok(())
+    }
+
+    /// Seconds until the PIN of a user may be tried again, `None` if it may
+    /// be tried now
+    pub fn pin_locked_for(&self, id: i64) -> Result<Option<i64>> {
+        let conn = self.pool.get()?;
+        let seconds = conn.query_row(
+            "SELECT unixepoch(pin_locked_until) - unixepoch() FROM users
+             WHERE id = ?1 AND pin_locked_until > datetime('now')",
+            [id],
+            |row| row.get(0),
+        );
+        match seconds {
+            Ok(seconds) => Ok(Some(seconds)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Count a wrong PIN and return the number of wrong PINs in a row
+    pub fn record_failed_pin(&self, id: i64) -> Result<u32> {
+        let conn = self.pool.get()?;
+        let attempts = conn.query_row(
+            "UPDATE users SET failed_pin_attempts = failed_pin_attempts + 1 WHERE id = ?1
+             RETURNING failed_pin_attempts",
+            [id],
+            |row| row.get(0),
+        )?;
+        Ok(attempts)
+    }
+
+    /// Refuse the PIN of a user for the next `seconds`
+    pub fn lock_pin(&self, id: i64, seconds: u64) -> Result<()> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            "UPDATE users SET pin_locked_until = datetime('now', '+' || ?2 || ' seconds') WHERE id = ?1",
+            params![id, seconds],
+        )?;
+        Ok(())
+    }
+
+    pub fn reset_failed_pins(&self, id: i64) -> Result<()> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            "UPDATE users SET failed_pin_attempts = 0, pin_locked_until = NULL WHERE id = ?1",
+            [id],
+        )?;
+        Ok(())
+    }
+
+    /// The signed-in user, `None` while signed out
+    pub fn current_id(&self) -> Result<Option<i64>> {
+        let conn = self.pool.get()?;
+        let id = conn.query_row(
+            "SELECT current_user_id FROM user_preferences WHERE id = 1",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(id)
+    }
+
+    pub fn set_current(&self, id: Option<i64>) -> Result<()> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            "UPDATE user_preferences SET current_user_id = ?1, updated_at = CURRENT_TIMESTAMP
+             WHERE id = 1",
+            [id],
+        )?;
+        Ok(())
+    }
+
+    /// Random hex string to salt PIN hashes with
+    pub fn random_salt(&self) -> Result<String> {
+        let conn = self.pool.get()?;
+        let salt = conn.query_row("SELECT lower(hex(randomblob(16)))", [], |row| row.get(0))?;
+        Ok(salt)
+    }
+}
+
+fn map_user_row(row: &rusqlite::Row) -> rusqlite::Result<User> {
+    Ok(User {
+        id: row.get(0)?,
+        display_name: row.get(1)?,
+        email: row.get(2)?,
+        has_pin: row.get(3)?,
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+    })
+}
@@ -0,0 +1,198 @@
+use crate::db::{models::*, DbPool};
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+
+const WORKSPACE_COLUMNS: &str = "id, name, workspace_type, owner_user_id, created_at, updated_at";
+
+/// Workspaces, their members and which workspace is active. All workspace
+/// data is scoped by `schema::ACTIVE_WORKSPACE_SQL`, or by an explicit
+/// workspace ID where a sync must stay in the workspace it started in.
+pub struct WorkspaceRepository {
+    pool: DbPool,
+}
+
+impl WorkspaceRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create a workspace owned by `owner_id`
+    pub fn create(&self, name: &str, workspace_type: &str, owner_id: i64) -> Result<Workspace> {
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO workspaces (name, workspace_type, owner_user_id) VALUES (?1, ?2, ?3)",
+            params![name, workspace_type, owner_id],
+        )?;
+        let id = tx.last_insert_rowid();
+        tx.execute(
+            "INSERT INTO workspace_members (workspace_id, user_id, role) VALUES (?1, ?2, 'owner')",
+            params![id, owner_id],
+        )?;
+        tx.commit()?;
+
+        self.get_by_id(id)
+    }
+
+    pub fn get_by_id(&self, id: i64) -> Result<Workspace> {
+        let conn = self.pool.get()?;
+        let workspace = conn.query_row(
+            &format!("SELECT {WORKSPACE_COLUMNS} FROM workspaces WHERE id = ?1"),
+            [id],
+            map_workspace_row,
+        )?;
+
+        Ok(workspace)
+    }
+
+    /// Workspaces of which a user is a member, oldest first
+    pub fn get_for_user(&self, user_id: i64) -> Result<Vec<Workspace>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {WORKSPACE_COLUMNS} FROM workspaces
+             WHERE id IN (SELECT workspace_id FROM workspace_members WHERE user_id = ?1)
+             ORDER BY id"
+        ))?;
+
+        let workspaces = stmt.query_map([user_id], map_workspace_row)?;
+        let workspaces: Result<Vec<Workspace>, _> = workspaces.collect();
+        Ok(workspaces?)
+    }
+
+    pub fn rename(&self, id: i64, name: &str) -> Result<Workspace> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            "UPDATE workspaces SET name = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = ?1",
+            params![id, name],
+        )?;
+        self.get_by_id(id)
+    }
+
+    /// Delete a workspace with all its tasks, notes, projects, plans and sync
+    /// state, device sync included
+    pub fn delete(&self, id: i64) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+
+        // Tables that gained their workspace column later have no foreign key
+        // to it; notes, tasks with their reminders, members and the device
+        // sync config follow through their cascades. Synced fields are kept
+        // by UID, which other workspaces may share, so they are only cleared
+        // when this is the synced workspace.
+        for statement in [
+            "DELETE FROM device_sync_fields
+             WHERE uid IN (SELECT uid FROM tasks WHERE workspace_id = ?1)
+               AND EXISTS (SELECT 1 FROM device_sync_config WHERE workspace_id = ?1)",
+            "DELETE FROM caldav_sync_state WHERE task_id IN (SELECT id FROM tasks WHERE workspace_id = ?1)",
+            "DELETE FROM caldav_config WHERE workspace_id = ?1",
+            "DELETE FROM todotxt_sync_state WHERE workspace_id = ?1",
+            "DELETE FROM obsidian_sync_state WHERE workspace_id = ?1",
+            "DELETE FROM time_blocks WHERE workspace_id = ?1",
+            "DELETE FROM day_plans WHERE workspace_id = ?1",
+            "DELETE FROM workspaces WHERE id = ?1",
+            "DELETE FROM projects WHERE workspace_id = ?1",
+        ] {
+            tx.execute(statement, [id])?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Members of a workspace, owners first
+    pub fn members(&self, workspace_id: i64) -> Result<Vec<WorkspaceMember>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT m.user_id, u.display_name, m.role
+             FROM workspace_members m JOIN users u ON u.id = m.user_id
+             WHERE m.workspace_id = ?1
+             ORDER BY CASE m.role WHEN 'owner' THEN 0 WHEN 'editor' THEN 1 ELSE 2 END,
+                      u.display_name COLLATE NOCASE",
+        )?;
+
+        let members = stmt.query_map([workspace_id], |row| {
+            Ok(WorkspaceMember {
+                user_id: row.get(0)?,
+                display_name: row.get(1)?,
+                role: row.get(2)?,
+            })
+        })?;
+        let members: Result<Vec<WorkspaceMember>, _> = members.collect();
+        Ok(members?)
+    }
+
+    /// Role of a user in a workspace, `None` if not a member
+    pub fn role_of(&self, workspace_id: i64, user_id: i64) -> Result<Option<String>> {
+        let conn = self.pool.get()?;
+        let role = conn
+            .query_row(
+                "SELECT role FROM workspace_members WHERE workspace_id = ?1 AND user_id = ?2",
+                params![workspace_id, user_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(role)
+    }
+
+    /// Add a member or change their role
+    pub fn set_member(&self, workspace_id: i64, user_id: i64, role: &str) -> Result<()> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            "INSERT INTO workspace_members (workspace_id, user_id, role) VALUES (?1, ?2, ?3)
+             ON CONFLICT (workspace_id, user_id) DO UPDATE SET role = excluded.role",
+            params![workspace_id, user_id, role],
+        )?;
+        Ok(())
+    }
+
+    /// Remove a member; their tasks in the workspace are unassigned
+    pub fn remove_member(&self, workspace_id: i64, user_id: i64) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM workspace_members WHERE workspace_id = ?1 AND user_id = ?2",
+            params![workspace_id, user_id],
+        )?;
+        tx.execute(
+            "UPDATE tasks SET assignee_id = NULL WHERE workspace_id = ?1 AND assignee_id = ?2",
+            params![workspace_id, user_id],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn active_id(&self) -> Result<i64> {
+        let conn = self.pool.get()?;
+        Self::active_id_in(&conn)
+    }
+
+    pub fn active_id_in(conn: &Connection) -> Result<i64> {
+        let id = conn.query_row(
+            "SELECT active_workspace_id FROM user_preferences WHERE id = 1",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(id)
+    }
+
+    pub fn set_active(&self, id: i64) -> Result<()> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            "UPDATE user_preferences SET active_workspace_id = ?1, updated_at = CURRENT_TIMESTAMP
+             WHERE id = 1",
+            [id],
+        )?;
+        Ok(())
+    }
+}
+
+fn map_workspace_row(row: &rusqlite::Row) -> rusqlite::Result<Workspace> {
+    Ok(Workspace {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        workspace_type: row.get(2)?,
+        owner_user_id: row.get(3)?,
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+    })
+}
@@ -0,0 +1,982 @@
+use anyhow::Result;
+use rusqlite::Connection;
+
+/// SQL expression producing a random (version 4) UUID string
+pub const UUID_V4_SQL: &str = "lower(
+    hex(randomblob(4)) || '-' || hex(randomblob(2)) || '-4' ||
+    substr(hex(randomblob(2)), 2) || '-' ||
+    substr('89ab', 1 + (abs(random()) % 4), 1) || substr(hex(randomblob(2)), 2) || '-' ||
+    hex(randomblob(6))
+)";
+
+/// SQL expression for the ID of the active workspace, which scopes queries
+/// on workspace data (tasks, notes, projects, plans and sync state). Syncs
+/// that must stay in the workspace they started in pass its ID instead.
+pub const ACTIVE_WORKSPACE_SQL: &str =
+    "(SELECT active_workspace_id FROM user_preferences WHERE id = 1)";
+
+/// SQL expression for the ID of the signed-in user; NULL while signed out
+pub const CURRENT_USER_SQL: &str = "(SELECT current_user_id FROM user_preferences WHERE id = 1)";
+
+/// Run all database migrations
+pub fn run_migrations(conn: &Connection) -> Result<()> {
+    conn.execute("PRAGMA foreign_keys = ON", [])?;
+
+    // Set journal mode (this PRAGMA returns results, so we use pragma_update)
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+
+    // Version tracking
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            applied_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    let current_version = get_current_version(conn)?;
+
+    // Apply migrations in order
+    if current_version < 1 {
+        migration_v001(conn)?;
+    }
+    if current_version < 2 {
+        migration_v002(conn)?;
+    }
+    if current_version < 3 {
+        migration_v003(conn)?;
+    }
+    if current_version < 4 {
+        migration_v004(conn)?;
+    }
+    if current_version < 5 {
+        migration_v005(conn)?;
+    }
+    if current_version < 6 {
+        migration_v006(conn)?;
+    }
+    if current_version < 7 {
+        migration_v007(conn)?;
+    }
+    if current_version < 8 {
+        migration_v008(conn)?;
+    }
+    if current_version < 9 {
+        migration_v009(conn)?;
+    }
+    if current_version < 10 {
+        migration_v010(conn)?;
+    }
+    if current_version < 11 {
+        migration_v011(conn)?;
+    }
+    if current_version < 12 {
+        migration_v012(conn)?;
+    }
+    if current_version < 13 {
+        migration_v013(conn)?;
+    }
+    if current_version < 14 {
+        migration_v014(conn)?;
+    }
+    if current_version < 15 {
+        migration_v015(conn)?;
+    }
+    if current_version < 16 {
+        migration_v016(conn)?;
+    }
+
+    if current_version < 17 {
+        migration_v017(conn)?;
+    }
+
+    if current_version < 18 {
+        migration_v018(conn)?;
+    }
+    if current_version < 19 {
+        migration_v019(conn)?;
+    }
+    if current_version < 20 {
+        migration_v020(conn)?;
+    }
+
+    Ok(())
+}
+
+fn get_current_version(conn: &Connection) -> Result<i32> {
+    let version: Result<i32, _> = conn.query_row(
+        "SELECT MAX(version) FROM schema_version",
+        [],
+        |row| row.get(0),
+    );
+
+    Ok(version.unwrap_or(0))
+}
+
+fn set_version(conn: &Connection, version: i32) -> Result<()> {
+    conn.execute(
+        "INSERT INTO schema_version (version) VALUES (?1)",
+        [version],
+    )?;
+    Ok(())
+}
+
+/// Migration V001: Initial schema
+fn migration_v001(conn: &Connection) -> Result<()> {
+    tracing::info!("Running migration v001: Initial schema");
+
+    // Users table (for multi-user support, single default user for now)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS users (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            email TEXT UNIQUE,
+            display_name TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    // Workspaces (single personal workspace for Phase 1)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS workspaces (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            workspace_type TEXT NOT NULL DEFAULT 'personal',
+            owner_user_id INTEGER,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (owner_user_id) REFERENCES users(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Projects
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS projects (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            color TEXT NOT NULL,
+            icon TEXT,
+            archived INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    // Tasks
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS tasks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL DEFAULT 1,
+            workspace_id INTEGER NOT NULL DEFAULT 1,
+            title TEXT NOT NULL,
+            description TEXT,
+            project_id INTEGER,
+            status TEXT NOT NULL DEFAULT 'todo',
+            priority INTEGER DEFAULT 0,
+            estimated_minutes INTEGER,
+            difficulty_level INTEGER,
+            energy_level TEXT,
+            scheduled_date TEXT,
+            due_date TEXT,
+            completed_at TEXT,
+            parent_task_id INTEGER,
+            order_index INTEGER DEFAULT 0,
+            tags TEXT,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+            FOREIGN KEY (workspace_id) REFERENCES workspaces(id) ON DELETE CASCADE,
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE SET NULL,
+            FOREIGN KEY (parent_task_id) REFERENCES tasks(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Indexes for tasks
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_tasks_status ON tasks(status)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_tasks_scheduled_date ON tasks(scheduled_date)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_tasks_due_date ON tasks(due_date)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_tasks_user_id ON tasks(user_id)", [])?;
+
+    // Full-text search
+    conn.execute(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS tasks_fts USING fts5(
+            title,
+            description,
+            content=tasks,
+            content_rowid=id
+        )",
+        [],
+    )?;
+
+    // FTS triggers
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS tasks_fts_insert AFTER INSERT ON tasks BEGIN
+            INSERT INTO tasks_fts(rowid, title, description)
+            VALUES (new.id, new.title, new.description);
+        END",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS tasks_fts_update AFTER UPDATE ON tasks BEGIN
+            UPDATE tasks_fts
+            SET title = new.title, description = new.description
+            WHERE rowid = new.id;
+        END",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS tasks_fts_delete AFTER DELETE ON tasks BEGIN
+            DELETE FROM tasks_fts WHERE rowid = old.id;
+        END",
+        [],
+    )?;
+
+    // User preferences
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS user_preferences (
+            id INTEGER PRIMARY KEY,
+            user_id INTEGER NOT NULL DEFAULT 1,
+            theme TEXT DEFAULT 'system',
+            default_view TEXT DEFAULT 'list',
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Create default user and workspace for single-user mode
+    conn.execute(
+        "INSERT OR IGNORE INTO users (id, display_name) VALUES (1, 'Default User')",
+        [],
+    )?;
+
+    conn.execute(
+        "INSERT OR IGNORE INTO workspaces (id, name, workspace_type, owner_user_id)
+         VALUES (1, 'Personal', 'personal', 1)",
+        [],
+    )?;
+
+    set_version(conn, 1)?;
+    tracing::info!("Migration v001 completed");
+    Ok(())
+}
+
+/// Migration v002: Add Kanban support
+fn migration_v002(conn: &Connection) -> Result<()> {
+    tracing::info!("Running migration v002: Kanban support");
+
+    // Add column_position for Kanban sorting
+    conn.execute(
+        "ALTER TABLE tasks ADD COLUMN column_position INTEGER DEFAULT 0",
+        [],
+    )?;
+
+    // Add index for parent_task_id (for subtasks performance)
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_tasks_parent_id ON tasks(parent_task_id)",
+        [],
+    )?;
+
+    set_version(conn, 2)?;
+    tracing::info!("Migration v002 completed");
+    Ok(())
+}
+
+/// Migration v003: Fix full-text search triggers
+///
+/// `tasks_fts` is an external content table, which must not be changed with
+/// UPDATE/DELETE statements (this corrupts the index). Entries are removed with
+/// the special 'delete' command instead, and the index is rebuilt once.
+fn migration_v003(conn: &Connection) -> Result<()> {
+    tracing::info!("Running migration v003: Fix full-text search triggers");
+
+    conn.execute("DROP TRIGGER IF EXISTS tasks_fts_update", [])?;
+    conn.execute("DROP TRIGGER IF EXISTS tasks_fts_delete", [])?;
+
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS tasks_fts_update AFTER UPDATE ON tasks BEGIN
+            INSERT INTO tasks_fts(tasks_fts, rowid, title, description)
+            VALUES ('delete', old.id, old.title, old.description);
+            INSERT INTO tasks_fts(rowid, title, description)
+            VALUES (new.id, new.title, new.description);
+        END",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS tasks_fts_delete AFTER DELETE ON tasks BEGIN
+            INSERT INTO tasks_fts(tasks_fts, rowid, title, description)
+            VALUES ('delete', old.id, old.title, old.description);
+        END",
+        [],
+    )?;
+
+    conn.execute("INSERT INTO tasks_fts(tasks_fts) VALUES ('rebuild')", [])?;
+
+    set_version(conn, 3)?;
+    tracing::info!("Migration v003 completed");
+    Ok(())
+}
+
+/// Migration v004: todo.txt sync state
+fn migration_v004(conn: &Connection) -> Result<()> {
+    tracing::info!("Running migration v004: todo.txt sync state");
+
+    // Last synced (canonical) line per task and file, used to detect which
+    // side changed since the previous sync
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS todotxt_sync_state (
+            file_path TEXT NOT NULL,
+            task_id INTEGER NOT NULL,
+            line TEXT NOT NULL,
+            synced_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (file_path, task_id)
+        )",
+        [],
+    )?;
+
+    set_version(conn, 4)?;
+    tracing::info!("Migration v004 completed");
+    Ok(())
+}
+
+/// Migration v005: Stable task UIDs
+fn migration_v005(conn: &Connection) -> Result<()> {
+    tracing::info!("Running migration v005: Stable task UIDs");
+
+    conn.execute("ALTER TABLE tasks ADD COLUMN uid TEXT", [])?;
+    conn.execute(
+        &format!("UPDATE tasks SET uid = {UUID_V4_SQL} WHERE uid IS NULL"),
+        [],
+    )?;
+    conn.execute("CREATE UNIQUE INDEX IF NOT EXISTS idx_tasks_uid ON tasks(uid)", [])?;
+
+    set_version(conn, 5)?;
+    tracing::info!("Migration v005 completed");
+    Ok(())
+}
+
+/// Migration v006: CalDAV sync
+fn migration_v006(conn: &Connection) -> Result<()> {
+    tracing::info!("Running migration v006: CalDAV sync");
+
+    // Single task collection the app syncs with
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS caldav_config (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            collection_url TEXT NOT NULL,
+            username TEXT NOT NULL,
+            password TEXT NOT NULL,
+            sync_token TEXT,
+            last_synced_at TEXT
+        )",
+        [],
+    )?;
+
+    // Remote resource of every synced task, with the ETag and local
+    // fingerprint as of the last sync
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS caldav_sync_state (
+            task_id INTEGER PRIMARY KEY,
+            href TEXT NOT NULL UNIQUE,
+            etag TEXT,
+            fingerprint TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    set_version(conn, 6)?;
+    tracing::info!("Migration v006 completed");
+    Ok(())
+}
+
+/// Migration v007: Markdown notes and wiki-links
+fn migration_v007(conn: &Connection) -> Result<()> {
+    tracing::info!("Running migration v007: Notes and links");
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS notes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            uid TEXT NOT NULL UNIQUE,
+            user_id INTEGER NOT NULL DEFAULT 1,
+            workspace_id INTEGER NOT NULL DEFAULT 1,
+            title TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            body TEXT NOT NULL DEFAULT '',
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+            FOREIGN KEY (workspace_id) REFERENCES workspaces(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Full-text search (external content, maintained like tasks_fts)
+    conn.execute(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS notes_fts USING fts5(
+            title,
+            body,
+            content=notes,
+            content_rowid=id
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS notes_fts_insert AFTER INSERT ON notes BEGIN
+            INSERT INTO notes_fts(rowid, title, body)
+            VALUES (new.id, new.title, new.body);
+        END",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS notes_fts_update AFTER UPDATE ON notes BEGIN
+            INSERT INTO notes_fts(notes_fts, rowid, title, body)
+            VALUES ('delete', old.id, old.title, old.body);
+            INSERT INTO notes_fts(rowid, title, body)
+            VALUES (new.id, new.title, new.body);
+        END",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS notes_fts_delete AFTER DELETE ON notes BEGIN
+            INSERT INTO notes_fts(notes_fts, rowid, title, body)
+            VALUES ('delete', old.id, old.title, old.body);
+        END",
+        [],
+    )?;
+
+    // Wiki-links found in note bodies. target_ref is the link as written
+    // (note title or task ID); target_id stays NULL while the target is missing.
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS links (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            source_type TEXT NOT NULL,
+            source_id INTEGER NOT NULL,
+            target_type TEXT NOT NULL,
+            target_ref TEXT NOT NULL COLLATE NOCASE,
+            target_id INTEGER,
+            alias TEXT,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_links_source ON links(source_type, source_id)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_links_target ON links(target_type, target_id)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_links_target_ref ON links(target_type, target_ref)", [])?;
+
+    // Links into deleted items become unresolved again
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS links_note_delete AFTER DELETE ON notes BEGIN
+            DELETE FROM links WHERE source_type = 'note' AND source_id = old.id;
+            UPDATE links SET target_id = NULL WHERE target_type = 'note' AND target_id = old.id;
+        END",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS links_task_delete AFTER DELETE ON tasks BEGIN
+            UPDATE links SET target_id = NULL WHERE target_type = 'task' AND target_id = old.id;
+        END",
+        [],
+    )?;
+
+    set_version(conn, 7)?;
+    tracing::info!("Migration v007 completed");
+    Ok(())
+}
+
+/// Migration v008: Note metadata and Obsidian vault sync
+fn migration_v008(conn: &Connection) -> Result<()> {
+    tracing::info!("Running migration v008: Obsidian vault sync");
+
+    conn.execute(
+        "ALTER TABLE notes ADD COLUMN project_id INTEGER REFERENCES projects(id) ON DELETE SET NULL",
+        [],
+    )?;
+    conn.execute("ALTER TABLE notes ADD COLUMN tags TEXT", [])?;
+
+    // File and content of every synced note as of the last sync, per vault
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS obsidian_sync_state (
+            vault_path TEXT NOT NULL,
+            note_id INTEGER NOT NULL,
+            file_path TEXT NOT NULL,
+            content TEXT NOT NULL,
+            fingerprint TEXT NOT NULL,
+            synced_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (vault_path, note_id)
+        )",
+        [],
+    )?;
+
+    set_version(conn, 8)?;
+    tracing::info!("Migration v008 completed");
+    Ok(())
+}
+
+/// Migration v009: AI provider settings
+fn migration_v009(conn: &Connection) -> Result<()> {
+    tracing::info!("Running migration v009: AI provider settings");
+
+    // NULL means the default of the provider
+    for column in [
+        "ai_provider TEXT",
+        "ai_model TEXT",
+        "ai_base_url TEXT",
+        "ai_api_key TEXT",
+        "ai_timeout_secs INTEGER",
+    ] {
+        conn.execute(&format!("ALTER TABLE user_preferences ADD COLUMN {column}"), [])?;
+    }
+
+    conn.execute(
+        "INSERT OR IGNORE INTO user_preferences (id, user_id) VALUES (1, 1)",
+        [],
+    )?;
+
+    set_version(conn, 9)?;
+    tracing::info!("Migration v009 completed");
+    Ok(())
+}
+
+/// Migration v010: AI job queue, response cache and usage ledger
+fn migration_v010(conn: &Connection) -> Result<()> {
+    tracing::info!("Running migration v010: AI job queue");
+
+    // Jobs are deleted once done; their response lives on in the cache
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS ai_jobs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            feature TEXT NOT NULL,
+            prompt_hash TEXT NOT NULL,
+            request TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'queued' CHECK(status IN ('queued', 'running', 'failed')),
+            attempts INTEGER NOT NULL DEFAULT 0,
+            next_attempt_at TEXT NOT NULL,
+            last_error TEXT,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_ai_jobs_due ON ai_jobs(status, next_attempt_at)",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS ai_cache (
+            prompt_hash TEXT PRIMARY KEY,
+            response TEXT NOT NULL,
+            model TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    // One row per local day, feature and model
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS ai_usage (
+            day TEXT NOT NULL,
+            feature TEXT NOT NULL,
+            model TEXT NOT NULL,
+            requests INTEGER NOT NULL DEFAULT 0,
+            cache_hits INTEGER NOT NULL DEFAULT 0,
+            input_tokens INTEGER NOT NULL DEFAULT 0,
+            output_tokens INTEGER NOT NULL DEFAULT 0,
+            cost_usd REAL NOT NULL DEFAULT 0,
+            PRIMARY KEY (day, feature, model)
+        )",
+        [],
+    )?;
+
+    set_version(conn, 10)?;
+    tracing::info!("Migration v010 completed");
+    Ok(())
+}
+
+/// Migration v011: Day plans
+fn migration_v011(conn: &Connection) -> Result<()> {
+    tracing::info!("Running migration v011: Day plans");
+
+    // Fixed blocks are entered by the user; task and break blocks make up the
+    // plan and are replaced when the day is planned again
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS time_blocks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            date TEXT NOT NULL,
+            start_time TEXT NOT NULL,
+            end_time TEXT NOT NULL,
+            kind TEXT NOT NULL CHECK(kind IN ('fixed', 'task', 'break')),
+            task_id INTEGER REFERENCES tasks(id) ON DELETE CASCADE,
+            title TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_time_blocks_date ON time_blocks(date, start_time)",
+        [],
+    )?;
+
+    // Tasks that did not fit, as a JSON array of task IDs
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS day_plans (
+            date TEXT PRIMARY KEY,
+            overflow TEXT NOT NULL DEFAULT '[]',
+            planned_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    set_version(conn, 11)?;
+    tracing::info!("Migration v011 completed");
+    Ok(())
+}
+
+/// Migration v012: Reminders
+fn migration_v012(conn: &Connection) -> Result<()> {
+    tracing::info!("Running migration v012: Reminders");
+
+    // Either an absolute time or minutes before the due date of the task;
+    // times are local, like due dates
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS reminders (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+            remind_at TEXT,
+            minutes_before_due INTEGER,
+            snoozed_until TEXT,
+            fired_at TEXT,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            CHECK ((remind_at IS NULL) != (minutes_before_due IS NULL))
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_reminders_task ON reminders(task_id)",
+        [],
+    )?;
+
+    // HH:MM, may wrap around midnight; NULL for none
+    conn.execute("ALTER TABLE user_preferences ADD COLUMN quiet_hours_start TEXT", [])?;
+    conn.execute("ALTER TABLE user_preferences ADD COLUMN quiet_hours_end TEXT", [])?;
+
+    set_version(conn, 12)?;
+    tracing::info!("Migration v012 completed");
+    Ok(())
+}
+
+/// Migration v013: Workspace scoping
+fn migration_v013(conn: &Connection) -> Result<()> {
+    tracing::info!("Running migration v013: Workspace scoping");
+
+    conn.execute(
+        "ALTER TABLE user_preferences ADD COLUMN active_workspace_id INTEGER NOT NULL DEFAULT 1",
+        [],
+    )?;
+    for table in [
+        "projects",
+        "time_blocks",
+        "todotxt_sync_state",
+        "obsidian_sync_state",
+        "caldav_config",
+    ] {
+        conn.execute(
+            &format!("ALTER TABLE {table} ADD COLUMN workspace_id INTEGER NOT NULL DEFAULT 1"),
+            [],
+        )?;
+    }
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_tasks_workspace ON tasks(workspace_id)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_projects_workspace ON projects(workspace_id)", [])?;
+
+    // Note titles are unique per workspace rather than globally. Rebuilding
+    // the table drops its triggers, so they are created again.
+    conn.execute(
+        "CREATE TABLE notes_scoped (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            uid TEXT NOT NULL UNIQUE,
+            user_id INTEGER NOT NULL DEFAULT 1,
+            workspace_id INTEGER NOT NULL DEFAULT 1,
+            title TEXT NOT NULL COLLATE NOCASE,
+            body TEXT NOT NULL DEFAULT '',
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            project_id INTEGER REFERENCES projects(id) ON DELETE SET NULL,
+            tags TEXT,
+            UNIQUE (workspace_id, title),
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+            FOREIGN KEY (workspace_id) REFERENCES workspaces(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    conn.execute(
+        "INSERT INTO notes_scoped
+            (id, uid, user_id, workspace_id, title, body, created_at, updated_at, project_id, tags)
+         SELECT id, uid, user_id, workspace_id, title, body, created_at, updated_at, project_id, tags
+         FROM notes",
+        [],
+    )?;
+    conn.execute("DROP TABLE notes", [])?;
+    conn.execute("ALTER TABLE notes_scoped RENAME TO notes", [])?;
+
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS notes_fts_insert AFTER INSERT ON notes BEGIN
+            INSERT INTO notes_fts(rowid, title, body)
+            VALUES (new.id, new.title, new.body);
+        END",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS notes_fts_update AFTER UPDATE ON notes BEGIN
+            INSERT INTO notes_fts(notes_fts, rowid, title, body)
+            VALUES ('delete', old.id, old.title, old.body);
+            INSERT INTO notes_fts(rowid, title, body)
+            VALUES (new.id, new.title, new.body);
+        END",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS notes_fts_delete AFTER DELETE ON notes BEGIN
+            INSERT INTO notes_fts(notes_fts, rowid, title, body)
+            VALUES ('delete', old.id, old.title, old.body);
+        END",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS links_note_delete AFTER DELETE ON notes BEGIN
+            DELETE FROM links WHERE source_type = 'note' AND source_id = old.id;
+            UPDATE links SET target_id = NULL WHERE target_type = 'note' AND target_id = old.id;
+        END",
+        [],
+    )?;
+
+    // One day plan per workspace and date
+    conn.execute(
+        "CREATE TABLE day_plans_scoped (
+            workspace_id INTEGER NOT NULL DEFAULT 1,
+            date TEXT NOT NULL,
+            overflow TEXT NOT NULL DEFAULT '[]',
+            planned_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (workspace_id, date)
+        )",
+        [],
+    )?;
+    conn.execute(
+        "INSERT INTO day_plans_scoped (date, overflow, planned_at)
+         SELECT date, overflow, planned_at FROM day_plans",
+        [],
+    )?;
+    conn.execute("DROP TABLE day_plans", [])?;
+    conn.execute("ALTER TABLE day_plans_scoped RENAME TO day_plans", [])?;
+
+    set_version(conn, 13)?;
+    tracing::info!("Migration v013 completed");
+    Ok(())
+}
+
+/// Migration v014: User profiles, workspace roles and assignees
+fn migration_v014(conn: &Connection) -> Result<()> {
+    tracing::info!("Running migration v014: User profiles and roles");
+
+    // Salted SHA-256 of the optional PIN, as "salt$hash"
+    conn.execute("ALTER TABLE users ADD COLUMN pin_hash TEXT", [])?;
+    conn.execute("ALTER TABLE user_preferences ADD COLUMN current_user_id INTEGER DEFAULT 1", [])?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS workspace_members (
+            workspace_id INTEGER NOT NULL REFERENCES workspaces(id) ON DELETE CASCADE,
+            user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            role TEXT NOT NULL CHECK(role IN ('owner', 'editor', 'viewer')),
+            added_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (workspace_id, user_id)
+        )",
+        [],
+    )?;
+    conn.execute(
+        "INSERT OR IGNORE INTO workspace_members (workspace_id, user_id, role)
+         SELECT id, COALESCE(owner_user_id, 1), 'owner' FROM workspaces",
+        [],
+    )?;
+
+    conn.execute(
+        "ALTER TABLE tasks ADD COLUMN assignee_id INTEGER REFERENCES users(id) ON DELETE SET NULL",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_tasks_assignee ON tasks(assignee_id)", [])?;
+
+    set_version(conn, 14)?;
+    tracing::info!("Migration v014 completed");
+    Ok(())
+}
+
+/// Migration v015: Typed preferences as versioned JSON
+fn migration_v015(conn: &Connection) -> Result<()> {
+    tracing::info!("Running migration v015: Typed preferences");
+
+    // NULL means all defaults. The AI key keeps its own column so that it
+    // never travels with the preferences.
+    conn.execute("ALTER TABLE user_preferences ADD COLUMN preferences TEXT", [])?;
+    conn.execute(
+        "UPDATE user_preferences SET preferences = json_object(
+            'version', 1,
+            'theme', COALESCE(theme, 'system'),
+            'default_view', COALESCE(default_view, 'list'),
+            'quiet_hours', CASE WHEN quiet_hours_start IS NOT NULL AND quiet_hours_end IS NOT NULL
+                THEN json_object('start', quiet_hours_start, 'end', quiet_hours_end) END,
+            'ai', json_object(
+                'provider', ai_provider,
+                'model', ai_model,
+                'base_url', ai_base_url,
+                'timeout_secs', ai_timeout_secs
+            )
+        )",
+        [],
+    )?;
+    for column in [
+        "theme",
+        "default_view",
+        "ai_provider",
+        "ai_model",
+        "ai_base_url",
+        "ai_timeout_secs",
+        "quiet_hours_start",
+        "quiet_hours_end",
+    ] {
+        conn.execute(&format!("ALTER TABLE user_preferences DROP COLUMN {column}"), [])?;
+    }
+
+    set_version(conn, 15)?;
+    tracing::info!("Migration v015 completed");
+    Ok(())
+}
+
+/// Migration v016: Token of the local API server
+fn migration_v016(conn: &Connection) -> Result<()> {
+    tracing::info!("Running migration v016: API server token");
+
+    // Only the SHA-256 of the token is kept
+    conn.execute("ALTER TABLE user_preferences ADD COLUMN api_token_hash TEXT", [])?;
+
+    set_version(conn, 16)?;
+    tracing::info!("Migration v016 completed");
+    Ok(())
+}
+
+/// Migration V017: Sync between devices through a shared folder
+fn migration_v017(conn: &Connection) -> Result<()> {
+    tracing::info!("Running migration v017: device sync");
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS device_sync_config (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            workspace_id INTEGER NOT NULL REFERENCES workspaces(id) ON DELETE CASCADE,
+            folder TEXT NOT NULL,
+            device_id TEXT NOT NULL,
+            -- Latest hybrid logical clock timestamp issued or seen
+            clock TEXT,
+            last_synced_at TEXT
+        )",
+        [],
+    )?;
+
+    // Latest value of every synced task field and the clock of its change
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS device_sync_fields (
+            uid TEXT NOT NULL,
+            field TEXT NOT NULL,
+            value TEXT NOT NULL,
+            clock TEXT NOT NULL,
+            PRIMARY KEY (uid, field)
+        )",
+        [],
+    )?;
+
+    // Lines of each other device's log that were merged
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS device_sync_cursors (
+            device_id TEXT PRIMARY KEY,
+            lines INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    set_version(conn, 17)?;
+    tracing::info!("Migration v017 completed");
+    Ok(())
+}
+
+/// Migration V018: Task versions for optimistic concurrency control
+fn migration_v018(conn: &Connection) -> Result<()> {
+    tracing::info!("Running migration v018: task versions");
+
+    conn.execute("ALTER TABLE tasks ADD COLUMN version INTEGER NOT NULL DEFAULT 1", [])?;
+
+    // The version trigger below updates tasks from inside other updates, before
+    // the search index has seen their change; only title and description
+    // updates may touch the index
+    conn.execute("DROP TRIGGER IF EXISTS tasks_fts_update", [])?;
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS tasks_fts_update AFTER UPDATE OF title, description ON tasks BEGIN
+            INSERT INTO tasks_fts(tasks_fts, rowid, title, description)
+            VALUES ('delete', old.id, old.title, old.description);
+            INSERT INTO tasks_fts(rowid, title, description)
+            VALUES (new.id, new.title, new.description);
+        END",
+        [],
+    )?;
+
+    // Every write counts, whoever makes it: the app, `zg`, the API or a sync
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS tasks_version AFTER UPDATE ON tasks
+         WHEN NEW.version = OLD.version
+         BEGIN
+            UPDATE tasks SET version = OLD.version + 1 WHERE id = NEW.id;
+         END",
+        [],
+    )?;
+
+    set_version(conn, 18)?;
+    tracing::info!("Migration v018 completed");
+    Ok(())
+}
+
+/// Migration V019: Task UIDs unique per workspace
+fn migration_v019(conn: &Connection) -> Result<()> {
+    tracing::info!("Running migration v019: task UIDs per workspace");
+
+    // The same calendar can be imported into more than one workspace
+    conn.execute("DROP INDEX IF EXISTS idx_tasks_uid", [])?;
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_tasks_uid ON tasks(workspace_id, uid)",
+        [],
+    )?;
+
+    set_version(conn, 19)?;
+    tracing::info!("Migration v019 completed");
+    Ok(())
+}
+
+/// Migration V020: Backoff after wrong PINs
+fn migration_v020(conn: &Connection) -> Result<()> {
+    tracing::info!("Running migration v020: PIN backoff");
+
+    // Kept in the database so that restarting the app or using `zg` does not
+    // reset them
+    conn.execute("ALTER TABLE users ADD COLUMN failed_pin_attempts INTEGER NOT NULL DEFAULT 0", [])?;
+    conn.execute("ALTER TABLE users ADD COLUMN pin_locked_until TEXT", [])?;
+
+    set_version(conn, 20)?;
+    tracing::info!("Migration v020 completed");
+    Ok(())
+}
@@ -0,0 +1,447 @@
+//! Minimal iCalendar (RFC 5545) reader and writer.
+//!
+//! Handles the generic structure only: content lines with folding, property
+//! parameters, text escaping, nested components and DATE/DATE-TIME values.
+//! Mapping components to tasks is done by `IcsService`.
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+/// Maximum length of a content line in octets, excluding the line break
+const MAX_LINE_OCTETS: usize = 75;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Property {
+    pub name: String,
+    pub params: Vec<(String, String)>,
+    pub value: String,
+}
+
+impl Property {
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Value of a TEXT property with escapes resolved
+    pub fn text(&self) -> String {
+        unescape_text(&self.value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Component {
+    pub name: String,
+    pub properties: Vec<Property>,
+    pub components: Vec<Component>,
+}
+
+impl Component {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            properties: Vec::new(),
+            components: Vec::new(),
+        }
+    }
+
+    /// Add a property with a raw (already escaped) value
+    pub fn add(&mut self, name: &str, value: impl Into<String>) -> &mut Self {
+        self.add_with_params(name, &[], value)
+    }
+
+    pub fn add_with_params(
+        &mut self,
+        name: &str,
+        params: &[(&str, &str)],
+        value: impl Into<String>,
+    ) -> &mut Self {
+        self.properties.push(Property {
+            name: name.to_string(),
+            params: params
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            value: value.into(),
+        });
+        self
+    }
+
+    /// Add a TEXT property, escaping the value
+    pub fn add_text(&mut self, name: &str, value: &str) -> &mut Self {
+        self.add(name, escape_text(value))
+    }
+
+    /// Add a DATE or DATE-TIME property
+    pub fn add_date(&mut self, name: &str, value: &IcsDateTime) -> &mut Self {
+        if value.is_date() {
+            self.add_with_params(name, &[("VALUE", "DATE")], value.to_ics())
+        } else {
+            self.add(name, value.to_ics())
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Property> {
+        self.properties
+            .iter()
+            .find(|p| p.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Property> + 'a {
+        self.properties
+            .iter()
+            .filter(move |p| p.name.eq_ignore_ascii_case(name))
+    }
+
+    /// All nested components with the given name, at any depth
+    pub fn find_all<'a>(&'a self, name: &str) -> Vec<&'a Component> {
+        let mut found = Vec::new();
+        for component in &self.components {
+            if component.name.eq_ignore_ascii_case(name) {
+                found.push(component);
+            }
+            found.extend(component.find_all(name));
+        }
+        found
+    }
+}
+
+/// A DATE or DATE-TIME value. Values with a TZID are treated as floating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IcsDateTime {
+    Date(NaiveDate),
+    Floating(NaiveDateTime),
+    Utc(DateTime<Utc>),
+}
+
+impl IcsDateTime {
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if let Some(utc) = value.strip_suffix('Z') {
+            return NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")
+                .ok()
+                .map(|dt| Self::Utc(dt.and_utc()));
+        }
+        if value.len() == 8 {
+            return NaiveDate::parse_from_str(value, "%Y%m%d").ok().map(Self::Date);
+        }
+        NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+            .ok()
+            .map(Self::Floating)
+    }
+
+    pub fn to_ics(self) -> String {
+        match self {
+            Self::Date(date) => date.format("%Y%m%d").to_string(),
+            Self::Floating(dt) => dt.format("%Y%m%dT%H%M%S").to_string(),
+            Self::Utc(dt) => dt.format("%Y%m%dT%H%M%SZ").to_string(),
+        }
+    }
+
+    pub fn is_date(self) -> bool {
+        matches!(self, Self::Date(_))
+    }
+
+    pub fn date(self) -> NaiveDate {
+        match self {
+            Self::Date(date) => date,
+            Self::Floating(dt) => dt.date(),
+            Self::Utc(dt) => dt.with_timezone(&Local).date_naive(),
+        }
+    }
+
+    /// Local wall-clock time (midnight for dates)
+    pub fn to_local(self) -> NaiveDateTime {
+        match self {
+            Self::Date(date) => date.and_hms_opt(0, 0, 0).unwrap(),
+            Self::Floating(dt) => dt,
+            Self::Utc(dt) => dt.with_timezone(&Local).naive_local(),
+        }
+    }
+
+    pub fn to_utc(self) -> DateTime<Utc> {
+        match self {
+            Self::Utc(dt) => dt,
+            other => Local
+                .from_local_datetime(&other.to_local())
+                .earliest()
+                .map(|dt| dt.with_timezone(&Utc))
+                .unwrap_or_else(|| other.to_local().and_utc()),
+        }
+    }
+}
+
+/// Parse an iCalendar stream into its top-level components (usually one VCALENDAR)
+pub fn parse(input: &str) -> Result<Vec<Component>> {
+    let mut stack: Vec<Component> = Vec::new();
+    let mut roots = Vec::new();
+
+    for line in unfold(input) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let property = parse_content_line(&line)?;
+
+        if property.name.eq_ignore_ascii_case("BEGIN") {
+            stack.push(Component::new(&property.value.to_ascii_uppercase()));
+        } else if property.name.eq_ignore_ascii_case("END") {
+            let Some(component) = stack.pop() else {
+                bail!("Unexpected END:{}", property.value);
+            };
+            if !component.name.eq_ignore_ascii_case(&property.value) {
+                bail!("END:{} does not match BEGIN:{}", property.value, component.name);
+            }
+            match stack.last_mut() {
+                Some(parent) => parent.components.push(component),
+                None => roots.push(component),
+            }
+        } else if let Some(current) = stack.last_mut() {
+            current.properties.push(property);
+        } else {
+            bail!("Property {} outside of a component", property.name);
+        }
+    }
+
+    if let Some(open) = stack.last() {
+        bail!("Missing END:{}", open.name);
+    }
+    Ok(roots)
+}
+
+/// Serialize a component with CRLF line endings and folded lines
+pub fn serialize(component: &Component) -> String {
+    let mut out = String::new();
+    write_component(component, &mut out);
+    out
+}
+
+fn write_component(component: &Component, out: &mut String) {
+    write_line(&format!("BEGIN:{}", component.name), out);
+    for property in &component.properties {
+        let mut line = property.name.clone();
+        for (name, value) in &property.params {
+            line.push(';');
+            line.push_str(name);
+            line.push('=');
+            if value.contains([':', ';', ',']) {
+                line.push_str(&format!("\"{value}\""));
+            } else {
+                line.push_str(value);
+            }
+        }
+        line.push(':');
+        line.push_str(&property.value);
+        write_line(&line, out);
+    }
+    for child in &component.components {
+        write_component(child, out);
+    }
+    write_line(&format!("END:{}", component.name), out);
+}
+
+/// Write a content line, folding it at 75 octets without splitting characters
+fn write_line(line: &str, out: &mut String) {
+    let mut octets = 0;
+    for ch in line.chars() {
+        if octets + ch.len_utf8() > MAX_LINE_OCTETS {
+            out.push_str("\r\n ");
+            octets = 1;
+        }
+        out.push(ch);
+        octets += ch.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+fn unfold(input: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in input.split('\n') {
+        let raw = raw.strip_suffix('\r').unwrap_or(raw);
+        match (raw.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(raw.to_string()),
+        }
+    }
+    lines
+}
+
+fn parse_content_line(line: &str) -> Result<Property> {
+    // The value starts at the first colon outside of a quoted parameter value
+    let mut in_quotes = false;
+    let mut split = None;
+    for (i, ch) in line.char_indices() {
+        match ch {
+            '"' => in_quotes = !in_quotes,
+            ':' if !in_quotes => {
+                split = Some(i);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let Some(split) = split else {
+        bail!("Invalid content line: {line}");
+    };
+
+    let (head, value) = (&line[..split], &line[split + 1..]);
+    let mut parts = split_unquoted(head, ';').into_iter();
+    let name = parts.next().unwrap_or_default().to_ascii_uppercase();
+    if name.is_empty() {
+        bail!("Missing property name: {line}");
+    }
+
+    let params = parts
+        .filter_map(|param| {
+            let (key, value) = param.split_once('=')?;
+            Some((key.to_ascii_uppercase(), value.trim_matches('"').to_string()))
+        })
+        .collect();
+
+    Ok(Property {
+        name,
+        params,
+        value: value.to_string(),
+    })
+}
+
+fn split_unquoted(input: &str, separator: char) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for ch in input.chars() {
+        if ch == '"' {
+            in_quotes = !in_quotes;
+        }
+        if ch == separator && !in_quotes {
+            parts.push(std::mem::take(&mut current));
+        } else {
+            current.push(ch);
+        }
+    }
+    parts.push(current);
+    parts
+}
+
+/// Escape a TEXT value
+pub fn escape_text(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '\\' => out.push_str("\\\\"),
+            ';' => out.push_str("\\;"),
+            ',' => out.push_str("\\,"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// Resolve escapes in a TEXT value
+pub fn unescape_text(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            out.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Split a multi-valued TEXT value (e.g. CATEGORIES) on unescaped commas
+pub fn split_text_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut escaped = false;
+    for ch in value.chars() {
+        if escaped {
+            current.push('\\');
+            current.push(ch);
+            escaped = false;
+        } else if ch == '\\' {
+            escaped = true;
+        } else if ch == ',' {
+            items.push(unescape_text(&std::mem::take(&mut current)));
+        } else {
+            current.push(ch);
+        }
+    }
+    items.push(unescape_text(&current));
+    items.retain(|item| !item.trim().is_empty());
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_components_with_folding_and_params() {
+        let input = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VTODO\r\nUID:abc@example.com\r\n\
+                     SUMMARY:Call dentist\\, then\r\n  book appointment\r\n\
+                     DUE;VALUE=DATE:20261020\r\nX-NOTE;LABEL=\"a:b\":value\r\nEND:VTODO\r\nEND:VCALENDAR\r\n";
+
+        let roots = parse(input).unwrap();
+        assert_eq!(roots.len(), 1);
+        let todos = roots[0].find_all("VTODO");
+        assert_eq!(todos.len(), 1);
+
+        let todo = todos[0];
+        assert_eq!(todo.get("summary").unwrap().text(), "Call dentist, then book appointment");
+        assert_eq!(todo.get("DUE").unwrap().param("value"), Some("DATE"));
+        assert_eq!(todo.get("X-NOTE").unwrap().param("LABEL"), Some("a:b"));
+        assert_eq!(todo.get("X-NOTE").unwrap().value, "value");
+    }
+
+    #[test]
+    fn serialize_folds_long_lines_and_round_trips() {
+        let mut todo = Component::new("VTODO");
+        todo.add_text("SUMMARY", &"Sehr lange Aufgabe mit Umlauten äöü; ".repeat(5));
+        todo.add_with_params("X-LINK", &[("LABEL", "a:b")], "x");
+        let mut calendar = Component::new("VCALENDAR");
+        calendar.add("VERSION", "2.0");
+        calendar.components.push(todo);
+
+        let text = serialize(&calendar);
+        assert!(text.split("\r\n").all(|line| line.len() <= MAX_LINE_OCTETS));
+        assert_eq!(parse(&text).unwrap(), vec![calendar]);
+    }
+
+    #[test]
+    fn text_escaping_round_trips() {
+        let value = "a, b; c\\d\nnext line";
+        assert_eq!(unescape_text(&escape_text(value)), value);
+    }
+
+    #[test]
+    fn splits_categories_on_unescaped_commas() {
+        assert_eq!(split_text_list("work,a\\,b, ,home"), vec!["work", "a,b", "home"]);
+    }
+
+    #[test]
+    fn parses_date_values() {
+        assert_eq!(
+            IcsDateTime::parse("20261020"),
+            Some(IcsDateTime::Date(NaiveDate::from_ymd_opt(2026, 10, 20).unwrap()))
+        );
+        assert!(matches!(IcsDateTime::parse("20261020T150000"), Some(IcsDateTime::Floating(_))));
+        assert_eq!(IcsDateTime::parse("20261020T150000Z").unwrap().to_ics(), "20261020T150000Z");
+        assert_eq!(IcsDateTime::parse("2026-10-20"), None);
+    }
+
+    #[test]
+    fn rejects_unbalanced_components() {
+        assert!(parse("BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nEND:VCALENDAR\r\n").is_err());
+        assert!(parse("BEGIN:VCALENDAR\r\n").is_err());
+    }
+}
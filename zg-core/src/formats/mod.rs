@@ -0,0 +1,5 @@
+pub mod ics;
+pub mod obsidian;
+pub mod quick_add;
+pub mod todotxt;
+pub mod wikilinks;
@@ -0,0 +1,385 @@
+//! Obsidian Markdown files: YAML frontmatter and task lines in the format of
+//! the Tasks plugin (https://publish.obsidian.md/tasks), e.g.
+//! `- [ ] Call dentist #health ⏫ ⏳ 2026-10-19 📅 2026-10-20 🆔 <uid>`.
+//!
+//! Only the small YAML subset Obsidian writes for properties is understood:
+//! `key: value` pairs and lists, inline (`[a, b]`) or as `- item` lines.
+//! Other keys are kept verbatim so they survive a round trip.
+
+use chrono::NaiveDate;
+
+const DUE: &str = "📅";
+const SCHEDULED: &str = "⏳";
+const DONE: &str = "✅";
+const ID: &str = "🆔";
+
+/// Priority markers from highest to lowest, with the matching task priority
+const PRIORITIES: [(&str, i32); 5] = [("🔺", 4), ("⏫", 3), ("🔼", 2), ("🔽", 1), ("⏬", 1)];
+
+/// Other Tasks plugin fields; they and their values are kept as they are
+const OTHER_FIELDS: [&str; 6] = ["➕", "🛫", "❌", "🔁", "⛔", "🏁"];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Frontmatter {
+    pub id: Option<String>,
+    pub tags: Vec<String>,
+    pub project: Option<String>,
+    pub created: Option<String>,
+    pub updated: Option<String>,
+    /// Lines of all other properties, in their original order
+    pub extra: Vec<String>,
+}
+
+/// Split a Markdown file into its frontmatter and body
+pub fn parse_document(content: &str) -> (Frontmatter, String) {
+    let Some(rest) = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))
+    else {
+        return (Frontmatter::default(), content.to_string());
+    };
+
+    let mut yaml = Vec::new();
+    let mut body = None;
+    let mut remaining = rest;
+    while !remaining.is_empty() {
+        let (line, next) = match remaining.find('\n') {
+            Some(end) => (&remaining[..end], &remaining[end + 1..]),
+            None => (remaining, ""),
+        };
+        remaining = next;
+        if line.trim_end() == "---" {
+            body = Some(remaining);
+            break;
+        }
+        yaml.push(line.trim_end_matches('\r'));
+    }
+
+    match body {
+        Some(body) => (parse_frontmatter(&yaml), body.to_string()),
+        // Unterminated: not frontmatter after all
+        None => (Frontmatter::default(), content.to_string()),
+    }
+}
+
+/// Render a Markdown file; the frontmatter is omitted when it is empty
+pub fn render_document(frontmatter: &Frontmatter, body: &str) -> String {
+    let mut lines = Vec::new();
+    if let Some(id) = &frontmatter.id {
+        lines.push(format!("id: {}", yaml_scalar(id)));
+    }
+    if !frontmatter.tags.is_empty() {
+        let tags: Vec<String> = frontmatter.tags.iter().map(|t| yaml_scalar(t)).collect();
+        lines.push(format!("tags: [{}]", tags.join(", ")));
+    }
+    if let Some(project) = &frontmatter.project {
+        lines.push(format!("project: {}", yaml_scalar(project)));
+    }
+    if let Some(created) = &frontmatter.created {
+        lines.push(format!("created: {}", yaml_scalar(created)));
+    }
+    if let Some(updated) = &frontmatter.updated {
+        lines.push(format!("updated: {}", yaml_scalar(updated)));
+    }
+    lines.extend(frontmatter.extra.iter().cloned());
+
+    if lines.is_empty() {
+        return body.to_string();
+    }
+    format!("---\n{}\n---\n{body}", lines.join("\n"))
+}
+
+fn parse_frontmatter(lines: &[&str]) -> Frontmatter {
+    let mut frontmatter = Frontmatter::default();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        i += 1;
+
+        // Continuation lines of the property, e.g. block list items
+        let start = i;
+        while i < lines.len() && (lines[i].starts_with(' ') || lines[i].starts_with('\t')) {
+            i += 1;
+        }
+        let continuation = &lines[start..i];
+
+        let Some((key, value)) = line.split_once(':').filter(|_| !line.starts_with(' ')) else {
+            frontmatter.extra.push(line.to_string());
+            frontmatter.extra.extend(continuation.iter().map(|l| l.to_string()));
+            continue;
+        };
+        let value = value.trim();
+
+        match key.trim() {
+            "id" => frontmatter.id = Some(unquote(value)).filter(|v| !v.is_empty()),
+            "project" => frontmatter.project = Some(unquote(value)).filter(|v| !v.is_empty()),
+            "created" => frontmatter.created = Some(unquote(value)).filter(|v| !v.is_empty()),
+            "updated" => frontmatter.updated = Some(unquote(value)).filter(|v| !v.is_empty()),
+            "tags" | "tag" => {
+                let items: Vec<String> = if let Some(list) =
+                    value.strip_prefix('[').and_then(|v| v.strip_suffix(']'))
+                {
+                    split_inline_list(list)
+                } else if value.is_empty() {
+                    continuation
+                        .iter()
+                        .filter_map(|l| l.trim().strip_prefix('-'))
+                        .map(|item| unquote(item.trim()))
+                        .collect()
+                } else {
+                    value.split(',').map(|item| unquote(item.trim())).collect()
+                };
+                frontmatter.tags = items
+                    .into_iter()
+                    .map(|t| t.trim_start_matches('#').to_string())
+                    .filter(|t| !t.is_empty())
+                    .collect();
+            }
+            _ => {
+                frontmatter.extra.push(line.to_string());
+                frontmatter.extra.extend(continuation.iter().map(|l| l.to_string()));
+            }
+        }
+    }
+
+    frontmatter
+}
+
+fn split_inline_list(list: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+
+    for c in list.chars() {
+        match quote {
+            Some(q) => {
+                if c == q {
+                    quote = None;
+                }
+                current.push(c);
+            }
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                current.push(c);
+            }
+            None if c == ',' => {
+                items.push(unquote(current.trim()));
+                current.clear();
+            }
+            None => current.push(c),
+        }
+    }
+    if !current.trim().is_empty() {
+        items.push(unquote(current.trim()));
+    }
+    items
+}
+
+fn unquote(value: &str) -> String {
+    if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        value[1..value.len() - 1].replace("\\\"", "\"").replace("\\\\", "\\")
+    } else if value.len() >= 2 && value.starts_with('\'') && value.ends_with('\'') {
+        value[1..value.len() - 1].replace("''", "'")
+    } else {
+        value.to_string()
+    }
+}
+
+fn yaml_scalar(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value.trim() == value
+        && !value.starts_with(['-', '?', '!', '&', '*', '@', '`', '{', '|', '>', '%'])
+        && !value.contains([':', '#', ',', '[', ']', '"', '\'', '{', '}']);
+    if plain {
+        value.to_string()
+    } else {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+/// A checklist line with Tasks plugin fields
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TaskLine {
+    /// Leading whitespace and list marker, e.g. `  - `
+    pub prefix: String,
+    /// Character between the brackets: ` `, `/`, `x` or `-` (cancelled)
+    pub status: char,
+    pub title: String,
+    pub tags: Vec<String>,
+    pub priority: i32,
+    pub scheduled: Option<String>,
+    pub due: Option<String>,
+    pub done: Option<String>,
+    pub id: Option<String>,
+    /// Other fields with their values, e.g. `🔁 every week`, in original order
+    pub extras: Vec<String>,
+}
+
+impl TaskLine {
+    pub fn is_completed(&self) -> bool {
+        matches!(self.status, 'x' | 'X')
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.status == '-'
+    }
+}
+
+/// Parse a checklist line. Returns `None` for any other line.
+pub fn parse_task_line(line: &str) -> Option<TaskLine> {
+    let indent_len = line.len() - line.trim_start().len();
+    let rest = &line[indent_len..];
+    let marker = rest.chars().next().filter(|c| matches!(c, '-' | '*' | '+'))?;
+    let rest = rest[1..].strip_prefix(' ')?;
+    let mut chars = rest.chars();
+    if chars.next() != Some('[') {
+        return None;
+    }
+    let status = chars.next()?;
+    if chars.next() != Some(']') {
+        return None;
+    }
+    let text = chars.as_str();
+    if !text.is_empty() && !text.starts_with(' ') {
+        return None;
+    }
+
+    let mut task = TaskLine {
+        prefix: format!("{}{marker} ", &line[..indent_len]),
+        status,
+        ..TaskLine::default()
+    };
+
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+    let mut title = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = tokens[i].trim_end_matches('\u{fe0f}');
+        let value = tokens.get(i + 1).copied().filter(|v| is_date(v));
+
+        if let Some((_, priority)) = PRIORITIES.iter().find(|(marker, _)| *marker == token) {
+            task.priority = *priority;
+        } else if let (DUE | SCHEDULED | DONE, Some(date)) = (token, value) {
+            let date = Some(date.to_string());
+            match token {
+                DUE => task.due = date,
+                SCHEDULED => task.scheduled = date,
+                _ => task.done = date,
+            }
+            i += 1;
+        } else if token == ID && i + 1 < tokens.len() {
+            task.id = Some(tokens[i + 1].to_string());
+            i += 1;
+        } else if OTHER_FIELDS.contains(&token) {
+            // The value runs up to the next field
+            let mut extra = vec![token];
+            while i + 1 < tokens.len() && !is_field(tokens[i + 1]) {
+                i += 1;
+                extra.push(tokens[i]);
+            }
+            task.extras.push(extra.join(" "));
+        } else if let Some(tag) = token.strip_prefix('#').filter(|t| !t.is_empty()) {
+            task.tags.push(tag.to_string());
+        } else {
+            title.push(tokens[i]);
+        }
+        i += 1;
+    }
+
+    task.title = title.join(" ");
+    Some(task)
+}
+
+/// Serialize a task line in a fixed field order
+pub fn serialize_task_line(task: &TaskLine) -> String {
+    let mut parts = vec![format!("{}[{}]", task.prefix, task.status)];
+    if !task.title.is_empty() {
+        parts.push(task.title.clone());
+    }
+    parts.extend(task.tags.iter().map(|t| format!("#{t}")));
+    if let Some((marker, _)) = PRIORITIES.iter().find(|(_, p)| *p == task.priority.min(4)) {
+        parts.push(marker.to_string());
+    }
+    parts.extend(task.extras.iter().cloned());
+    for (field, value) in [(SCHEDULED, &task.scheduled), (DUE, &task.due), (DONE, &task.done)] {
+        if let Some(value) = value {
+            parts.push(format!("{field} {value}"));
+        }
+    }
+    if let Some(id) = &task.id {
+        parts.push(format!("{ID} {id}"));
+    }
+    parts.join(" ")
+}
+
+fn is_field(token: &str) -> bool {
+    let token = token.trim_end_matches('\u{fe0f}');
+    [DUE, SCHEDULED, DONE, ID].contains(&token)
+        || OTHER_FIELDS.contains(&token)
+        || PRIORITIES.iter().any(|(marker, _)| *marker == token)
+}
+
+fn is_date(token: &str) -> bool {
+    NaiveDate::parse_from_str(token, "%Y-%m-%d").is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frontmatter_round_trip_keeps_unknown_properties() {
+        let content = "---\nid: abc\ntags:\n  - work\n  - \"#deep\"\naliases: [Idea]\nproject: \"Home: Garden\"\n---\n# Body\n";
+        let (frontmatter, body) = parse_document(content);
+
+        assert_eq!(frontmatter.id.as_deref(), Some("abc"));
+        assert_eq!(frontmatter.tags, vec!["work", "deep"]);
+        assert_eq!(frontmatter.project.as_deref(), Some("Home: Garden"));
+        assert_eq!(frontmatter.extra, vec!["aliases: [Idea]"]);
+        assert_eq!(body, "# Body\n");
+
+        let rendered = render_document(&frontmatter, &body);
+        assert_eq!(
+            rendered,
+            "---\nid: abc\ntags: [work, deep]\nproject: \"Home: Garden\"\naliases: [Idea]\n---\n# Body\n"
+        );
+        assert_eq!(parse_document(&rendered), (frontmatter, body));
+    }
+
+    #[test]
+    fn documents_without_frontmatter() {
+        let (frontmatter, body) = parse_document("---\nnot closed");
+        assert_eq!(frontmatter, Frontmatter::default());
+        assert_eq!(body, "---\nnot closed");
+        assert_eq!(render_document(&Frontmatter::default(), "text"), "text");
+    }
+
+    #[test]
+    fn parses_tasks_plugin_fields() {
+        let task = parse_task_line("  - [x] Call dentist #health ⏫ 🔁 every week ➕ 2026-10-01 📅 2026-10-20 ✅ 2026-10-19 🆔 a1").unwrap();
+
+        assert_eq!(task.prefix, "  - ");
+        assert!(task.is_completed());
+        assert_eq!(task.title, "Call dentist");
+        assert_eq!(task.tags, vec!["health"]);
+        assert_eq!(task.priority, 3);
+        assert_eq!(task.extras, vec!["🔁 every week", "➕ 2026-10-01"]);
+        assert_eq!(task.due.as_deref(), Some("2026-10-20"));
+        assert_eq!(task.done.as_deref(), Some("2026-10-19"));
+        assert_eq!(task.id.as_deref(), Some("a1"));
+
+        assert_eq!(
+            serialize_task_line(&task),
+            "  - [x] Call dentist #health ⏫ 🔁 every week ➕ 2026-10-01 📅 2026-10-20 ✅ 2026-10-19 🆔 a1"
+        );
+    }
+
+    #[test]
+    fn ignores_other_lines() {
+        assert!(parse_task_line("- plain item").is_none());
+        assert!(parse_task_line("[ ] no marker").is_none());
+        assert!(parse_task_line("- [link](url)").is_none());
+        assert_eq!(parse_task_line("* [ ]").unwrap().title, "");
+    }
+}
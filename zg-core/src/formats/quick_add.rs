@@ -0,0 +1,428 @@
+//! Parser for quick-add task entry in English and German, e.g.
+//! `call dentist tomorrow 3pm #health !2 ~15m low-energy` or
+//! `Steuererklärung bis nächsten Freitag +Finanzen ~2h`.
+//!
+//! Recognized tokens are removed from the text; what remains is the title.
+//!
+//! - `#tag`, `+project` (dashes stand for spaces)
+//! - `!1`..`!4` or `!`..`!!!!` for the priority
+//! - `~15m`, `~2h`, `~1h30m`, `~1.5h` for the estimate
+//! - `low-energy`, `energy:high`, `hohe-energie`, `energie:niedrig`
+//! - dates: `today`/`heute`, `tomorrow`/`morgen`, `übermorgen`, weekdays
+//!   (`friday`, `next friday`, `am Freitag`), `next week`/`nächste Woche`,
+//!   `in 3 days`/`in drei Tagen`, `2026-10-20`, `20.10.` and `20.10.2026`
+//! - times: `3pm`, `3:30 pm`, `15:00`, `15 Uhr`, `at noon`
+//!
+//! Dates are scheduled dates, unless introduced by `due`, `by`, `until`,
+//! `bis` or `fällig`.
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QuickAdd {
+    pub title: String,
+    pub tags: Vec<String>,
+    pub project: Option<String>,
+    pub priority: Option<i32>,
+    pub estimated_minutes: Option<i32>,
+    pub energy_level: Option<String>,
+    /// `YYYY-MM-DD`, or `YYYY-MM-DDTHH:MM:SS` with a time
+    pub scheduled_date: Option<String>,
+    pub due_date: Option<String>,
+}
+
+const DUE_WORDS: [&str; 6] = ["due", "by", "until", "bis", "fällig", "faellig"];
+const DATE_CONNECTORS: [&str; 4] = ["on", "am", "this", "diesen"];
+const NEXT_WORDS: [&str; 9] = [
+    "next", "coming", "nächsten", "nächste", "nächster", "naechsten", "naechste", "kommenden",
+    "kommende",
+];
+const TIME_CONNECTORS: [&str; 3] = ["at", "um", "@"];
+
+/// Parse quick-add text. Relative dates and times are resolved against `now`;
+/// a time without a date means today, or tomorrow if it has already passed.
+pub fn parse(text: &str, now: NaiveDateTime) -> QuickAdd {
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+    let words: Vec<String> = tokens.iter().map(|t| normalize(t)).collect();
+    let today = now.date();
+
+    let mut result = QuickAdd::default();
+    let mut title = Vec::new();
+    let mut scheduled = None;
+    let mut due = None;
+    let mut time = None;
+
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = tokens[i];
+        let word = words[i].as_str();
+
+        if let Some(tag) = token.strip_prefix('#').map(trim_punctuation).filter(|t| !t.is_empty()) {
+            result.tags.push(tag.to_string());
+        } else if let Some(project) = token.strip_prefix('+').map(trim_punctuation).filter(|p| !p.is_empty()) {
+            result.project = Some(project.replace('-', " "));
+        } else if let Some(priority) = parse_priority(token) {
+            result.priority = Some(priority);
+        } else if let Some(minutes) = word.strip_prefix('~').and_then(parse_duration) {
+            result.estimated_minutes = Some(minutes);
+        } else if let Some(energy) = parse_energy(word) {
+            result.energy_level = Some(energy.to_string());
+        } else if let Some((len, date)) = DUE_WORDS
+            .contains(&word)
+            .then(|| date_after_connectors(&words[i + 1..], today))
+            .flatten()
+        {
+            due = Some(date);
+            i += 1 + len;
+            continue;
+        } else if let Some((len, date)) = date_after_connectors(&words[i..], today) {
+            scheduled = Some(date);
+            i += len;
+            continue;
+        } else if let Some((len, parsed)) = time_after_connectors(&words[i..]) {
+            time = Some(parsed);
+            i += len;
+            continue;
+        } else {
+            title.push(token);
+        }
+        i += 1;
+    }
+
+    if let Some(time) = time {
+        let with_time = |date: NaiveDate| Some(date.and_time(time).format("%Y-%m-%dT%H:%M:%S").to_string());
+        match (scheduled, due) {
+            (None, Some(date)) => {
+                result.due_date = with_time(date);
+                due = None;
+            }
+            (Some(date), _) => {
+                result.scheduled_date = with_time(date);
+                scheduled = None;
+            }
+            (None, None) => {
+                let date = if time > now.time() { today } else { today + Duration::days(1) };
+                result.scheduled_date = with_time(date);
+            }
+        }
+    }
+    if let Some(date) = scheduled {
+        result.scheduled_date = Some(date.format("%Y-%m-%d").to_string());
+    }
+    if let Some(date) = due {
+        result.due_date = Some(date.format("%Y-%m-%d").to_string());
+    }
+
+    result.title = title.join(" ");
+    result
+}
+
+/// Lowercase and without trailing punctuation, keeping the dot of `20.10.`
+fn normalize(token: &str) -> String {
+    let lower = token.to_lowercase();
+    if parse_day_month(&lower).is_some() {
+        lower
+    } else {
+        trim_punctuation(&lower).to_string()
+    }
+}
+
+fn trim_punctuation(token: &str) -> &str {
+    token.trim_end_matches([',', '.', ';', '!', '?', ')']).trim_start_matches('(')
+}
+
+fn parse_priority(word: &str) -> Option<i32> {
+    let rest = word.strip_prefix('!')?;
+    if rest.is_empty() {
+        return Some(1);
+    }
+    if rest.chars().all(|c| c == '!') {
+        return Some((rest.len() as i32 + 1).min(4));
+    }
+    rest.parse().ok().filter(|p| (1..=4).contains(p))
+}
+
+/// `15m`, `15min`, `2h`, `1h30m`, `1.5h`, `1,5h`
+fn parse_duration(value: &str) -> Option<i32> {
+    let value = value.replace(',', ".");
+    let (hours, minutes) = match value.split_once('h') {
+        Some((hours, rest)) => (hours, rest.trim_end_matches("in").trim_end_matches('m')),
+        None => ("0", value.trim_end_matches("in").trim_end_matches('m')),
+    };
+    if value == minutes && !value.ends_with('m') && !value.ends_with("min") {
+        return None;
+    }
+
+    let hours: f64 = if hours.is_empty() { 0.0 } else { hours.parse().ok()? };
+    let minutes: f64 = if minutes.is_empty() { 0.0 } else { minutes.parse().ok()? };
+    let total = (hours * 60.0 + minutes).round() as i32;
+    (total > 0).then_some(total)
+}
+
+fn parse_energy(word: &str) -> Option<&'static str> {
+    let level = word
+        .strip_suffix("-energy")
+        .or_else(|| word.strip_suffix("-energie"))
+        .or_else(|| word.strip_prefix("energy:"))
+        .or_else(|| word.strip_prefix("energie:"))?;
+
+    match level {
+        "low" | "niedrig" | "niedrige" | "wenig" | "geringe" => Some("low"),
+        "medium" | "mid" | "mittel" | "mittlere" => Some("medium"),
+        "high" | "hoch" | "hohe" | "viel" => Some("high"),
+        _ => None,
+    }
+}
+
+/// A date, optionally introduced by `on`, `am`, `this` etc.
+/// Returns the number of words used.
+fn date_after_connectors(words: &[String], today: NaiveDate) -> Option<(usize, NaiveDate)> {
+    let first = words.first()?;
+    if DATE_CONNECTORS.contains(&first.as_str()) {
+        if let Some((len, date)) = parse_date(&words[1..], today) {
+            return Some((len + 1, date));
+        }
+    }
+    parse_date(words, today)
+}
+
+fn parse_date(words: &[String], today: NaiveDate) -> Option<(usize, NaiveDate)> {
+    let word = |i: usize| words.get(i).map(String::as_str);
+    let first = word(0)?;
+
+    match first {
+        "today" | "heute" => return Some((1, today)),
+        "tomorrow" | "morgen" => return Some((1, today + Duration::days(1))),
+        "übermorgen" | "uebermorgen" => return Some((1, today + Duration::days(2))),
+        "day" if word(1) == Some("after") && word(2) == Some("tomorrow") => {
+            return Some((3, today + Duration::days(2)))
+        }
+        "in" => {
+            let count = word(1).and_then(parse_count)?;
+            let days = match word(2)? {
+                "day" | "days" | "tag" | "tagen" | "tage" => count,
+                "week" | "weeks" | "woche" | "wochen" => count * 7,
+                _ => return None,
+            };
+            return Some((3, today + Duration::days(days)));
+        }
+        _ => {}
+    }
+
+    if NEXT_WORDS.contains(&first) {
+        if matches!(word(1), Some("week" | "woche")) {
+            return Some((2, next_weekday(today, Weekday::Mon)));
+        }
+        let weekday = word(1).and_then(parse_weekday)?;
+        return Some((2, next_weekday(today, weekday)));
+    }
+    if let Some(weekday) = parse_weekday(first) {
+        return Some((1, next_weekday(today, weekday)));
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(first, "%Y-%m-%d") {
+        return Some((1, date));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(first.trim_end_matches('.'), "%d.%m.%Y") {
+        return Some((1, date));
+    }
+    let (day, month) = parse_day_month(first)?;
+    let this_year = NaiveDate::from_ymd_opt(today.year(), month, day)?;
+    if this_year >= today {
+        Some((1, this_year))
+    } else {
+        Some((1, NaiveDate::from_ymd_opt(today.year() + 1, month, day)?))
+    }
+}
+
+/// `20.10.`
+fn parse_day_month(word: &str) -> Option<(u32, u32)> {
+    let (day, rest) = word.split_once('.')?;
+    let month = rest.strip_suffix('.')?;
+    if day.is_empty() || day.len() > 2 || month.is_empty() || month.len() > 2 {
+        return None;
+    }
+    Some((day.parse().ok()?, month.parse().ok()?))
+}
+
+fn parse_count(word: &str) -> Option<i64> {
+    if let Ok(n) = word.parse() {
+        return Some(n);
+    }
+    let n = match word {
+        "a" | "an" | "one" | "ein" | "einem" | "einer" | "eine" | "einen" => 1,
+        "two" | "zwei" => 2,
+        "three" | "drei" => 3,
+        "four" | "vier" => 4,
+        "five" | "fünf" | "fuenf" => 5,
+        "six" | "sechs" => 6,
+        "seven" | "sieben" => 7,
+        "eight" | "acht" => 8,
+        "nine" | "neun" => 9,
+        "ten" | "zehn" => 10,
+        _ => return None,
+    };
+    Some(n)
+}
+
+fn parse_weekday(word: &str) -> Option<Weekday> {
+    let weekday = match word {
+        "monday" | "mon" | "montag" => Weekday::Mon,
+        "tuesday" | "tue" | "dienstag" => Weekday::Tue,
+        "wednesday" | "wed" | "mittwoch" => Weekday::Wed,
+        "thursday" | "thu" | "donnerstag" => Weekday::Thu,
+        "friday" | "fri" | "freitag" => Weekday::Fri,
+        "saturday" | "samstag" | "sonnabend" => Weekday::Sat,
+        "sunday" | "sonntag" => Weekday::Sun,
+        _ => return None,
+    };
+    Some(weekday)
+}
+
+/// The next given weekday after `today`, never today itself
+fn next_weekday(today: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let ahead = (weekday.num_days_from_monday() as i64 - today.weekday().num_days_from_monday() as i64 + 7) % 7;
+    today + Duration::days(if ahead == 0 { 7 } else { ahead })
+}
+
+/// A time, optionally introduced by `at` or `um`
+fn time_after_connectors(words: &[String]) -> Option<(usize, NaiveTime)> {
+    let first = words.first()?;
+    if TIME_CONNECTORS.contains(&first.as_str()) {
+        if let Some((len, time)) = parse_time(&words[1..]) {
+            return Some((len + 1, time));
+        }
+    }
+    parse_time(words)
+}
+
+fn parse_time(words: &[String]) -> Option<(usize, NaiveTime)> {
+    let first = words.first()?.as_str();
+    let second = words.get(1).map(String::as_str);
+
+    match first {
+        "noon" | "mittags" => return Some((1, NaiveTime::from_hms_opt(12, 0, 0)?)),
+        "midnight" | "mitternacht" => return Some((1, NaiveTime::from_hms_opt(0, 0, 0)?)),
+        _ => {}
+    }
+
+    // 3pm, 3:30pm, or with a separate "pm"
+    for suffix in ["am", "pm"] {
+        let (clock, len) = match first.strip_suffix(suffix) {
+            Some(clock) if !clock.is_empty() => (clock, 1),
+            _ if second == Some(suffix) => (first, 2),
+            _ => continue,
+        };
+        let (hour, minute) = parse_clock(clock)?;
+        if !(1..=12).contains(&hour) {
+            return None;
+        }
+        let hour = match (suffix, hour) {
+            ("am", 12) => 0,
+            ("am", h) => h,
+            ("pm", 12) => 12,
+            (_, h) => h + 12,
+        };
+        return Some((len, NaiveTime::from_hms_opt(hour, minute, 0)?));
+    }
+
+    // 15 Uhr, 15:30 Uhr
+    if second == Some("uhr") {
+        let (hour, minute) = parse_clock(first)?;
+        return Some((2, NaiveTime::from_hms_opt(hour, minute, 0)?));
+    }
+
+    // 15:00, 15:00h
+    let clock = first.strip_suffix('h').unwrap_or(first);
+    if clock.contains(':') {
+        let (hour, minute) = parse_clock(clock)?;
+        return Some((1, NaiveTime::from_hms_opt(hour, minute, 0)?));
+    }
+    None
+}
+
+fn parse_clock(clock: &str) -> Option<(u32, u32)> {
+    let (hour, minute) = match clock.split_once([':', '.']) {
+        Some((hour, minute)) if minute.len() == 2 => (hour, minute),
+        Some(_) => return None,
+        None => (clock, "0"),
+    };
+    if hour.is_empty() || hour.len() > 2 {
+        return None;
+    }
+    let (hour, minute): (u32, u32) = (hour.parse().ok()?, minute.parse().ok()?);
+    (hour < 24 && minute < 60).then_some((hour, minute))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wednesday afternoon
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 14)
+            .unwrap()
+            .and_hms_opt(14, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn parses_the_full_example() {
+        let parsed = parse("call dentist tomorrow 3pm #health !2 ~15m low-energy", now());
+        assert_eq!(
+            parsed,
+            QuickAdd {
+                title: "call dentist".to_string(),
+                tags: vec!["health".to_string()],
+                project: None,
+                priority: Some(2),
+                estimated_minutes: Some(15),
+                energy_level: Some("low".to_string()),
+                scheduled_date: Some("2026-10-15T15:00:00".to_string()),
+                due_date: None,
+            }
+        );
+    }
+
+    #[test]
+    fn parses_german() {
+        let parsed = parse("Steuererklärung abgeben bis nächsten Freitag um 10 Uhr +Finanzen-Privat ~1,5h hohe-energie !!!", now());
+        assert_eq!(parsed.title, "Steuererklärung abgeben");
+        assert_eq!(parsed.due_date.as_deref(), Some("2026-10-16T10:00:00"));
+        assert_eq!(parsed.scheduled_date, None);
+        assert_eq!(parsed.project.as_deref(), Some("Finanzen Privat"));
+        assert_eq!(parsed.estimated_minutes, Some(90));
+        assert_eq!(parsed.energy_level.as_deref(), Some("high"));
+        assert_eq!(parsed.priority, Some(3));
+
+        assert_eq!(parse("Müll rausbringen morgen", now()).scheduled_date.as_deref(), Some("2026-10-15"));
+        assert_eq!(parse("Einkaufen übermorgen", now()).scheduled_date.as_deref(), Some("2026-10-16"));
+        assert_eq!(parse("Oma anrufen in drei Tagen", now()).scheduled_date.as_deref(), Some("2026-10-17"));
+        assert_eq!(parse("Arzt am 3.11. 9:30", now()).scheduled_date.as_deref(), Some("2026-11-03T09:30:00"));
+        assert_eq!(parse("Urlaub nächste Woche", now()).scheduled_date.as_deref(), Some("2026-10-19"));
+    }
+
+    #[test]
+    fn parses_english_dates_and_times() {
+        let date = |text: &str| parse(text, now()).scheduled_date;
+
+        assert_eq!(date("review on friday").as_deref(), Some("2026-10-16"));
+        assert_eq!(date("standup next wednesday").as_deref(), Some("2026-10-21"));
+        assert_eq!(date("gym in 2 weeks").as_deref(), Some("2026-10-28"));
+        assert_eq!(date("lunch at noon").as_deref(), Some("2026-10-15T12:00:00"));
+        assert_eq!(date("call 4:30 pm").as_deref(), Some("2026-10-14T16:30:00"));
+        assert_eq!(date("renew passport 2027-01-05").as_deref(), Some("2027-01-05"));
+        assert_eq!(parse("report due 20.10.", now()).due_date.as_deref(), Some("2026-10-20"));
+    }
+
+    #[test]
+    fn leaves_ordinary_words_in_the_title() {
+        let parsed = parse("do laundry, then buy 2 apples and a gift for mo", now());
+        assert_eq!(parsed.title, "do laundry, then buy 2 apples and a gift for mo");
+        assert_eq!(parsed.scheduled_date, None);
+
+        let parsed = parse("read chapter 5 by the fire", now());
+        assert_eq!(parsed.title, "read chapter 5 by the fire");
+        assert_eq!(parsed, QuickAdd { title: parsed.title.clone(), ..QuickAdd::default() });
+    }
+}
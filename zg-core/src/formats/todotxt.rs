@@ -0,0 +1,246 @@
+//! Parser and serializer for the todo.txt format
+//! (https://github.com/todotxt/todo.txt).
+//!
+//! Works on single lines and knows nothing about the database, so the format
+//! can be tested in isolation. Serialization is canonical: projects, contexts
+//! and `key:value` pairs are moved behind the text, in a fixed order, so that
+//! `serialize(parse(line))` is stable and can be compared between syncs.
+
+use chrono::NaiveDate;
+
+/// Key used to store the stable task ID on a line, e.g. `zg:42`
+pub const ID_KEY: &str = "zg";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TodoItem {
+    pub completed: bool,
+    pub priority: Option<char>,
+    pub completion_date: Option<String>,
+    pub creation_date: Option<String>,
+    pub text: String,
+    pub projects: Vec<String>,
+    pub contexts: Vec<String>,
+    pub due: Option<String>,
+    pub id: Option<i64>,
+    /// Any other `key:value` pairs, kept in their original order
+    pub extras: Vec<(String, String)>,
+}
+
+/// Parse a single todo.txt line. Returns `None` for blank lines.
+pub fn parse_line(line: &str) -> Option<TodoItem> {
+    let mut tokens = line.split_whitespace().peekable();
+    tokens.peek()?;
+
+    let mut item = TodoItem::default();
+
+    if tokens.peek() == Some(&"x") {
+        item.completed = true;
+        tokens.next();
+    }
+
+    if let Some(priority) = tokens.peek().and_then(|t| parse_priority(t)) {
+        item.priority = Some(priority);
+        tokens.next();
+    }
+
+    // Completed tasks carry the completion date first, then the creation date
+    let mut dates = Vec::new();
+    while dates.len() < if item.completed { 2 } else { 1 } {
+        match tokens.peek() {
+            Some(token) if is_date(token) => {
+                dates.push(token.to_string());
+                tokens.next();
+            }
+            _ => break,
+        }
+    }
+    if item.completed {
+        let mut dates = dates.into_iter();
+        item.completion_date = dates.next();
+        item.creation_date = dates.next();
+    } else {
+        item.creation_date = dates.pop();
+    }
+
+    let mut words = Vec::new();
+    for token in tokens {
+        if let Some(project) = token.strip_prefix('+').filter(|p| !p.is_empty()) {
+            item.projects.push(project.to_string());
+        } else if let Some(context) = token.strip_prefix('@').filter(|c| !c.is_empty()) {
+            item.contexts.push(context.to_string());
+        } else if let Some((key, value)) = parse_key_value(token) {
+            match key {
+                "due" if is_date(value) => item.due = Some(value.to_string()),
+                ID_KEY => match value.parse() {
+                    Ok(id) => item.id = Some(id),
+                    Err(_) => item.extras.push((key.to_string(), value.to_string())),
+                },
+                // Completed tasks keep their priority as `pri:A`
+                "pri" if item.priority.is_none() && parse_priority(&format!("({value})")).is_some() => {
+                    item.priority = value.chars().next();
+                }
+                _ => item.extras.push((key.to_string(), value.to_string())),
+            }
+        } else {
+            words.push(token);
+        }
+    }
+    item.text = words.join(" ");
+
+    Some(item)
+}
+
+/// Serialize an item into its canonical todo.txt line
+pub fn serialize_line(item: &TodoItem) -> String {
+    let mut parts: Vec<String> = Vec::new();
+
+    if item.completed {
+        parts.push("x".to_string());
+        // A creation date is only valid on completed tasks after a completion date
+        if let Some(completion_date) = &item.completion_date {
+            parts.push(completion_date.clone());
+            if let Some(creation_date) = &item.creation_date {
+                parts.push(creation_date.clone());
+            }
+        }
+    } else {
+        if let Some(priority) = item.priority {
+            parts.push(format!("({priority})"));
+        }
+        if let Some(creation_date) = &item.creation_date {
+            parts.push(creation_date.clone());
+        }
+    }
+
+    if !item.text.is_empty() {
+        parts.push(item.text.clone());
+    }
+    parts.extend(item.projects.iter().map(|p| format!("+{p}")));
+    parts.extend(item.contexts.iter().map(|c| format!("@{c}")));
+    if let Some(due) = &item.due {
+        parts.push(format!("due:{due}"));
+    }
+    if item.completed {
+        if let Some(priority) = item.priority {
+            parts.push(format!("pri:{priority}"));
+        }
+    }
+    parts.extend(item.extras.iter().map(|(k, v)| format!("{k}:{v}")));
+    if let Some(id) = item.id {
+        parts.push(format!("{ID_KEY}:{id}"));
+    }
+
+    parts.join(" ")
+}
+
+/// Replace whitespace so a name can be used as a single `+project`/`@context` token
+pub fn to_token(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join("-")
+}
+
+fn parse_priority(token: &str) -> Option<char> {
+    let mut chars = token.chars();
+    match (chars.next(), chars.next(), chars.next(), chars.next()) {
+        (Some('('), Some(p), Some(')'), None) if p.is_ascii_uppercase() => Some(p),
+        _ => None,
+    }
+}
+
+fn parse_key_value(token: &str) -> Option<(&str, &str)> {
+    let (key, value) = token.split_once(':')?;
+    // Leave URLs such as `https://example.com` in the text
+    if key.is_empty() || value.is_empty() || value.contains(':') || value.starts_with("//") {
+        return None;
+    }
+    Some((key, value))
+}
+
+fn is_date(token: &str) -> bool {
+    token.len() == 10 && NaiveDate::parse_from_str(token, "%Y-%m-%d").is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_all_fields() {
+        let item = parse_line(
+            "(A) 2026-10-01 Call dentist +health @phone due:2026-10-20 rec:1w zg:42",
+        )
+        .unwrap();
+
+        assert!(!item.completed);
+        assert_eq!(item.priority, Some('A'));
+        assert_eq!(item.creation_date.as_deref(), Some("2026-10-01"));
+        assert_eq!(item.text, "Call dentist");
+        assert_eq!(item.projects, vec!["health"]);
+        assert_eq!(item.contexts, vec!["phone"]);
+        assert_eq!(item.due.as_deref(), Some("2026-10-20"));
+        assert_eq!(item.extras, vec![("rec".to_string(), "1w".to_string())]);
+        assert_eq!(item.id, Some(42));
+    }
+
+    #[test]
+    fn parses_completed_dates_and_priority() {
+        let item = parse_line("x 2026-10-19 2026-10-01 Pay rent pri:B zg:7").unwrap();
+
+        assert!(item.completed);
+        assert_eq!(item.completion_date.as_deref(), Some("2026-10-19"));
+        assert_eq!(item.creation_date.as_deref(), Some("2026-10-01"));
+        assert_eq!(item.priority, Some('B'));
+        assert_eq!(item.text, "Pay rent");
+    }
+
+    #[test]
+    fn blank_lines_are_skipped() {
+        assert_eq!(parse_line(""), None);
+        assert_eq!(parse_line("   \t"), None);
+    }
+
+    #[test]
+    fn urls_stay_in_text() {
+        let item = parse_line("Read https://example.com/a later").unwrap();
+        assert_eq!(item.text, "Read https://example.com/a later");
+        assert!(item.extras.is_empty());
+    }
+
+    #[test]
+    fn canonical_lines_round_trip() {
+        let lines = [
+            "Buy milk",
+            "(C) Buy milk",
+            "(A) 2026-10-01 Call dentist +health @phone due:2026-10-20 zg:42",
+            "2026-10-01 Plan trip +travel +family @home",
+            "x 2026-10-19 2026-10-01 Pay rent pri:B zg:7",
+            "x 2026-10-19 Water plants @home",
+            "x Done without dates",
+            "Review PR t:2026-10-18 zg:3",
+        ];
+
+        for line in lines {
+            let item = parse_line(line).unwrap();
+            assert_eq!(serialize_line(&item), line);
+            assert_eq!(parse_line(&serialize_line(&item)).unwrap(), item);
+        }
+    }
+
+    #[test]
+    fn serialization_is_canonical() {
+        let item = parse_line("(B)  Call +work   mom @phone  due:2026-10-20").unwrap();
+        assert_eq!(serialize_line(&item), "(B) Call mom +work @phone due:2026-10-20");
+    }
+
+    #[test]
+    fn invalid_values_are_kept_as_extras() {
+        let item = parse_line("Task due:someday zg:abc").unwrap();
+        assert_eq!(item.due, None);
+        assert_eq!(item.id, None);
+        assert_eq!(serialize_line(&item), "Task due:someday zg:abc");
+    }
+
+    #[test]
+    fn names_become_single_tokens() {
+        assert_eq!(to_token("Home  Office"), "Home-Office");
+    }
+}
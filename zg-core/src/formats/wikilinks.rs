@@ -0,0 +1,186 @@
+//! Wiki-links in Markdown notes: `[[Note Title]]`, `[[Note Title|alias]]`,
+//! `[[Note Title#Heading]]` and `[[task:123]]`.
+//!
+//! Links inside inline code and fenced code blocks are ignored.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkTarget {
+    Note(String),
+    Task(i64),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WikiLink {
+    pub target: LinkTarget,
+    pub heading: Option<String>,
+    pub alias: Option<String>,
+}
+
+/// A link together with its byte range in the source text
+struct Span {
+    start: usize,
+    end: usize,
+    inner: String,
+}
+
+/// All wiki-links in a Markdown text, in order of appearance
+pub fn parse_links(body: &str) -> Vec<WikiLink> {
+    find_spans(body)
+        .into_iter()
+        .filter_map(|span| parse_inner(&span.inner))
+        .collect()
+}
+
+/// Rewrite links to the note `old_title` so they point to `new_title`,
+/// keeping headings and aliases. Titles are matched case-insensitively.
+pub fn rename_note_links(body: &str, old_title: &str, new_title: &str) -> String {
+    let mut out = String::with_capacity(body.len());
+    let mut last = 0;
+
+    for span in find_spans(body) {
+        let Some(link) = parse_inner(&span.inner) else {
+            continue;
+        };
+        let LinkTarget::Note(title) = &link.target else {
+            continue;
+        };
+        if title.to_lowercase() != old_title.to_lowercase() {
+            continue;
+        }
+
+        out.push_str(&body[last..span.start]);
+        out.push_str("[[");
+        out.push_str(new_title);
+        if let Some(heading) = &link.heading {
+            out.push('#');
+            out.push_str(heading);
+        }
+        if let Some(alias) = &link.alias {
+            out.push('|');
+            out.push_str(alias);
+        }
+        out.push_str("]]");
+        last = span.end;
+    }
+
+    out.push_str(&body[last..]);
+    out
+}
+
+fn parse_inner(inner: &str) -> Option<WikiLink> {
+    let (target, alias) = match inner.split_once('|') {
+        Some((target, alias)) => (target, Some(alias.trim().to_string()).filter(|a| !a.is_empty())),
+        None => (inner, None),
+    };
+    let (target, heading) = match target.split_once('#') {
+        Some((target, heading)) => (target, Some(heading.trim().to_string()).filter(|h| !h.is_empty())),
+        None => (target, None),
+    };
+
+    let target = target.trim();
+    if target.is_empty() {
+        return None;
+    }
+
+    let target = match target
+        .strip_prefix("task:")
+        .and_then(|id| id.trim().parse().ok())
+    {
+        Some(id) => LinkTarget::Task(id),
+        None => LinkTarget::Note(target.to_string()),
+    };
+
+    Some(WikiLink {
+        target,
+        heading,
+        alias,
+    })
+}
+
+fn find_spans(body: &str) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut offset = 0;
+    let mut in_fence = false;
+
+    for line in body.split_inclusive('\n') {
+        if line.trim_start().starts_with("```") || line.trim_start().starts_with("~~~") {
+            in_fence = !in_fence;
+        } else if !in_fence {
+            find_spans_in_line(line, offset, &mut spans);
+        }
+        offset += line.len();
+    }
+
+    spans
+}
+
+fn find_spans_in_line(line: &str, offset: usize, spans: &mut Vec<Span>) {
+    let bytes = line.as_bytes();
+    let mut i = 0;
+    let mut in_code = false;
+
+    while i < bytes.len() {
+        if bytes[i] == b'`' {
+            in_code = !in_code;
+            i += 1;
+            continue;
+        }
+        if !in_code && bytes[i..].starts_with(b"[[") {
+            if let Some(close) = line[i + 2..].find("]]") {
+                let inner = &line[i + 2..i + 2 + close];
+                if !inner.contains("[[") && !inner.contains('\n') {
+                    spans.push(Span {
+                        start: offset + i,
+                        end: offset + i + 2 + close + 2,
+                        inner: inner.to_string(),
+                    });
+                    i += 2 + close + 2;
+                    continue;
+                }
+            }
+        }
+        i += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(title: &str) -> LinkTarget {
+        LinkTarget::Note(title.to_string())
+    }
+
+    #[test]
+    fn parses_note_and_task_links() {
+        let links = parse_links("See [[Project Ideas]], [[task:42]] and [[Reading List#Books|books]].");
+
+        assert_eq!(links.len(), 3);
+        assert_eq!(links[0].target, note("Project Ideas"));
+        assert_eq!(links[1].target, LinkTarget::Task(42));
+        assert_eq!(links[2].target, note("Reading List"));
+        assert_eq!(links[2].heading.as_deref(), Some("Books"));
+        assert_eq!(links[2].alias.as_deref(), Some("books"));
+    }
+
+    #[test]
+    fn ignores_code_and_empty_links() {
+        let body = "`[[Inline]]` [[ ]] [[Real]]\n```\n[[Fenced]]\n```\n[[task:abc]]";
+        let targets: Vec<LinkTarget> = parse_links(body).into_iter().map(|l| l.target).collect();
+        assert_eq!(targets, vec![note("Real"), note("task:abc")]);
+    }
+
+    #[test]
+    fn renames_links_keeping_heading_and_alias() {
+        let body = "[[Old]] and [[old#Part|see here]] but not [[Older]] or `[[Old]]`";
+        assert_eq!(
+            rename_note_links(body, "Old", "New Name"),
+            "[[New Name]] and [[New Name#Part|see here]] but not [[Older]] or `[[Old]]`"
+        );
+    }
+
+    #[test]
+    fn renames_non_ascii_titles_case_insensitively() {
+        assert_eq!(rename_note_links("[[Über Uns]]", "über uns", "Team"), "[[Team]]");
+    }
+}
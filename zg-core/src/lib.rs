@@ -0,0 +1,8 @@
+//! Models, database, services and file formats of Zweites Gehirn, without
+//! any Tauri dependency. The app, its local API server and the `zg`
+//! command-line client are built on top of it.
+
+pub mod db;
+pub mod formats;
+pub mod server;
+pub mod services;
@@ -0,0 +1,245 @@
+//! Local REST API for scripts and automation.
+//!
+//! Listens on 127.0.0.1 only, when enabled in the preferences, and requires
+//! the token from `generate_api_token` as bearer token. Requests go through
+//! `TaskService`, so the workspace, roles and events apply as in the app.
+
+use crate::db::{encryption::EncryptionError, models::*, repositories::TaskError};
+use crate::services::{access::AccessError, event_bus::DomainEvent, EventBus, PreferencesService, TaskService};
+use anyhow::{Context, Result};
+use axum::{
+    extract::{Path, Query, Request, State},
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use std::net::Ipv4Addr;
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, oneshot};
+
+#[derive(Clone)]
+struct ApiState {
+    tasks: Arc<TaskService>,
+    preferences: Arc<PreferencesService>,
+}
+
+/// Runs the server as configured in the preferences and restarts it when
+/// they change
+pub struct ApiServer {
+    state: ApiState,
+    changes: Mutex<Option<mpsc::UnboundedReceiver<ApiServerPreferences>>>,
+}
+
+struct Running {
+    port: u16,
+    shutdown: oneshot::Sender<()>,
+}
+
+impl ApiServer {
+    pub fn new(tasks: Arc<TaskService>, preferences: Arc<PreferencesService>, bus: &EventBus) -> Self {
+        let (sender, changes) = mpsc::unbounded_channel();
+        bus.subscribe_async("api-server", move |event| {
+            let sender = sender.clone();
+            async move {
+                if let DomainEvent::PreferencesChanged(preferences) = event {
+                    let _ = sender.send(preferences.api_server);
+                }
+                Ok(())
+            }
+        });
+
+        Self {
+            state: ApiState { tasks, preferences },
+            changes: Mutex::new(Some(changes)),
+        }
+    }
+
+    /// Serve while enabled, following changes of the preferences
+    pub async fn run(self: Arc<Self>) {
+        let Some(mut changes) = self.changes.lock().unwrap().take() else {
+            return;
+        };
+        let mut settings = match self.state.preferences.get_preferences() {
+            Ok(preferences) => preferences.api_server,
+            Err(e) => {
+                tracing::error!("Failed to read the API server settings: {e:#}");
+                ApiServerPreferences::default()
+            }
+        };
+
+        let mut running: Option<Running> = None;
+        loop {
+            let wanted = settings.enabled.then_some(settings.port);
+            if running.as_ref().map(|r| r.port) != wanted {
+                if let Some(stopped) = running.take() {
+                    let _ = stopped.shutdown.send(());
+                    tracing::info!("API server stopped");
+                }
+                if let Some(port) = wanted {
+                    match listen(port).await {
+                        Ok(listener) => {
+                            let shutdown = spawn(listener, self.state.clone());
+                            tracing::info!("API server listening on 127.0.0.1:{port}");
+                            running = Some(Running { port, shutdown });
+                        }
+                        Err(e) => tracing::error!("Failed to start the API server: {e:#}"),
+                    }
+                }
+            }
+
+            match changes.recv().await {
+                Some(changed) => settings = changed,
+                None => break,
+            }
+        }
+    }
+}
+
+async fn listen(port: u16) -> Result<TcpListener> {
+    TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+        .await
+        .with_context(|| format!("Port {port} is not available"))
+}
+
+/// Serve on `listener` until the returned sender is used or dropped
+fn spawn(listener: TcpListener, state: ApiState) -> oneshot::Sender<()> {
+    let (shutdown, stopped) = oneshot::channel::<()>();
+    tokio::spawn(async move {
+        let server = axum::serve(listener, router(state)).with_graceful_shutdown(async {
+            let _ = stopped.await;
+        });
+        if let Err(e) = server.await {
+            tracing::error!("API server failed: {e}");
+        }
+    });
+    shutdown
+}
+
+fn router(state: ApiState) -> Router {
+    Router::new()
+        .route("/api/tasks", get(list_tasks).post(create_task))
+        .route("/api/tasks/quick-add", post(quick_add))
+        .route(
+            "/api/tasks/{id}",
+            get(get_task).patch(update_task).delete(delete_task),
+        )
+        .route("/api/tasks/{id}/complete", post(complete_task))
+        .layer(middleware::from_fn_with_state(state.clone(), authenticate))
+        .with_state(state)
+}
+
+async fn authenticate(State(state): State<ApiState>, request: Request, next: Next) -> Response {
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string());
+    let Some(token) = token else {
+        return error_response(StatusCode::UNAUTHORIZED, "Bearer token required");
+    };
+
+    let preferences = Arc::clone(&state.preferences);
+    match blocking(move || preferences.verify_api_token(&token)).await {
+        Ok(true) => next.run(request).await,
+        Ok(false) => error_response(StatusCode::UNAUTHORIZED, "Invalid token"),
+        Err(e) => e.into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct QuickAddRequest {
+    text: String,
+}
+
+async fn list_tasks(State(state): State<ApiState>, Query(query): Query<TaskQuery>) -> Result<Json<Vec<Task>>, ApiError> {
+    let tasks = blocking(move || state.tasks.query_tasks(&query)).await?;
+    Ok(Json(tasks))
+}
+
+async fn get_task(State(state): State<ApiState>, Path(id): Path<i64>) -> Result<Json<Task>, ApiError> {
+    let task = blocking(move || state.tasks.get_task(id)).await?;
+    Ok(Json(task))
+}
+
+async fn create_task(
+    State(state): State<ApiState>,
+    Json(input): Json<CreateTaskInput>,
+) -> Result<(StatusCode, Json<Task>), ApiError> {
+    let task = blocking(move || state.tasks.create_task(input)).await?;
+    Ok((StatusCode::CREATED, Json(task)))
+}
+
+/// Create a task from quick-add text such as "call dentist tomorrow #health"
+async fn quick_add(
+    State(state): State<ApiState>,
+    Json(request): Json<QuickAddRequest>,
+) -> Result<(StatusCode, Json<Task>), ApiError> {
+    let task = blocking(move || {
+        let preview = state.tasks.parse_quick_add(&request.text)?;
+        state.tasks.create_task(preview.input)
+    })
+    .await?;
+    Ok((StatusCode::CREATED, Json(task)))
+}
+
+async fn update_task(
+    State(state): State<ApiState>,
+    Path(id): Path<i64>,
+    Json(input): Json<UpdateTaskInput>,
+) -> Result<Json<Task>, ApiError> {
+    let task = blocking(move || state.tasks.update_task(id, input)).await?;
+    Ok(Json(task))
+}
+
+async fn delete_task(State(state): State<ApiState>, Path(id): Path<i64>) -> Result<StatusCode, ApiError> {
+    blocking(move || state.tasks.delete_task(id)).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn complete_task(State(state): State<ApiState>, Path(id): Path<i64>) -> Result<Json<Task>, ApiError> {
+    let task = blocking(move || state.tasks.complete_task(id)).await?;
+    Ok(Json(task))
+}
+
+/// Run a blocking service call off the async workers
+async fn blocking<T: Send + 'static>(work: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T, ApiError> {
+    match tokio::task::spawn_blocking(work).await {
+        Ok(result) => result.map_err(ApiError),
+        Err(e) => Err(ApiError(e.into())),
+    }
+}
+
+/// Service errors as JSON `{"error": "..."}` with a fitting status. A
+/// conflict also has the current task as `"current"`.
+struct ApiError(anyhow::Error);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        if let Some(TaskError::Conflict(current)) = self.0.downcast_ref::<TaskError>() {
+            let body = serde_json::json!({ "error": self.0.to_string(), "current": current });
+            return (StatusCode::CONFLICT, Json(body)).into_response();
+        }
+        let status = if self.0.downcast_ref::<AccessError>().is_some() {
+            StatusCode::FORBIDDEN
+        } else if matches!(self.0.downcast_ref::<EncryptionError>(), Some(EncryptionError::Locked)) {
+            StatusCode::LOCKED
+        } else if matches!(
+            self.0.downcast_ref::<rusqlite::Error>(),
+            Some(rusqlite::Error::QueryReturnedNoRows)
+        ) {
+            StatusCode::NOT_FOUND
+        } else {
+            StatusCode::BAD_REQUEST
+        };
+        error_response(status, &format!("{:#}", self.0))
+    }
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    (status, Json(serde_json::json!({ "error": message }))).into_response()
+}
@@ -0,0 +1,92 @@
+//! Workspace roles, enforced by the services on every operation
+
+use crate::db::{
+    repositories::{UserRepository, WorkspaceRepository},
+    DbPool,
+};
+use anyhow::{bail, Result};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    /// Can read everything in the workspace
+    Viewer,
+    /// Can also change tasks, notes, plans and sync
+    Editor,
+    /// Can also rename and delete the workspace and manage its members
+    Owner,
+}
+
+impl Role {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Editor => "editor",
+            Role::Owner => "owner",
+        }
+    }
+
+    pub fn parse(role: &str) -> Result<Self> {
+        match role {
+            "viewer" => Ok(Role::Viewer),
+            "editor" => Ok(Role::Editor),
+            "owner" => Ok(Role::Owner),
+            other => bail!("Unknown role: {other}"),
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AccessError {
+    #[error("No user is signed in")]
+    SignedOut,
+    #[error("This needs the {0} role in the workspace")]
+    Forbidden(Role),
+}
+
+/// Checks the role of the signed-in user in a workspace
+pub struct Access {
+    users: UserRepository,
+    workspaces: WorkspaceRepository,
+}
+
+impl Access {
+    pub fn new(pool: DbPool) -> Self {
+        Self {
+            users: UserRepository::new(pool.clone()),
+            workspaces: WorkspaceRepository::new(pool),
+        }
+    }
+
+    /// Fail unless the signed-in user has at least `role` in the active workspace
+    pub fn require(&self, role: Role) -> Result<()> {
+        self.require_in(self.workspaces.active_id()?, role)
+    }
+
+    /// Fail unless the signed-in user has at least `role` in a workspace
+    pub fn require_in(&self, workspace_id: i64, role: Role) -> Result<()> {
+        let user_id = self.current_user()?;
+        let current = match self.workspaces.role_of(workspace_id, user_id)? {
+            Some(current) => Role::parse(&current)?,
+            None => return Err(AccessError::Forbidden(role).into()),
+        };
+        if current < role {
+            return Err(AccessError::Forbidden(role).into());
+        }
+        Ok(())
+    }
+
+    /// The signed-in user
+    pub fn current_user(&self) -> Result<i64> {
+        match self.users.current_id()? {
+            Some(id) => Ok(id),
+            None => Err(AccessError::SignedOut.into()),
+        }
+    }
+}
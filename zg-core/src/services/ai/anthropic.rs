@@ -0,0 +1,132 @@
+//! Provider for the Anthropic Messages API
+
+use super::http::HttpTransport;
+use super::provider::{self, LlmError, LlmFuture, LlmProvider, LlmRequest, LlmResponse};
+use anyhow::Context;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+const API_VERSION: &str = "2023-06-01";
+
+pub struct AnthropicProvider {
+    transport: Arc<dyn HttpTransport>,
+    base_url: String,
+    api_key: String,
+    model: String,
+}
+
+impl AnthropicProvider {
+    pub fn new(transport: Arc<dyn HttpTransport>, base_url: &str, api_key: &str, model: &str) -> Self {
+        Self {
+            transport,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: api_key.to_string(),
+            model: model.to_string(),
+        }
+    }
+}
+
+impl LlmProvider for AnthropicProvider {
+    fn cache_key(&self) -> String {
+        format!("anthropic:{}", self.model)
+    }
+
+    fn complete<'a>(&'a self, request: &'a LlmRequest) -> LlmFuture<'a> {
+        Box::pin(async move {
+            let body = json!({
+                "model": self.model,
+                "max_tokens": request.max_tokens,
+                "system": request.system,
+                "messages": [{ "role": "user", "content": request.message }],
+            });
+            let headers = [
+                ("x-api-key".to_string(), self.api_key.clone()),
+                ("anthropic-version".to_string(), API_VERSION.to_string()),
+            ];
+
+            let url = format!("{}/v1/messages", self.base_url);
+            let response = self
+                .transport
+                .post_json(&url, &headers, body.to_string())
+                .await
+                .map_err(|e| LlmError::Unreachable(format!("Anthropic API request failed: {e:#}")))?;
+            let reply: Value = serde_json::from_str(&response.body).unwrap_or(Value::Null);
+
+            if response.status != 200 {
+                let message = reply["error"]["message"].as_str().unwrap_or(&response.body);
+                return Err(provider::status_error(
+                    &response,
+                    format!("Anthropic API error ({}): {message}", response.status),
+                ));
+            }
+
+            let text: String = reply["content"]
+                .as_array()
+                .context("Unexpected Anthropic API response")?
+                .iter()
+                .filter(|block| block["type"] == "text")
+                .filter_map(|block| block["text"].as_str())
+                .collect();
+
+            Ok(LlmResponse {
+                text,
+                model: reply["model"].as_str().unwrap_or(&self.model).to_string(),
+                input_tokens: reply["usage"]["input_tokens"].as_u64().unwrap_or(0) as u32,
+                rate_limit_reset: provider::exhausted_reset(&response),
+                output_tokens: reply["usage"]["output_tokens"].as_u64().unwrap_or(0) as u32,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::ai::http::ReqwestTransport;
+    use crate::services::ai::mock_server::MockHttpServer;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn sends_messages_request_and_reports_errors() {
+        let server = MockHttpServer::start().await;
+        let transport = Arc::new(ReqwestTransport::new(Duration::from_secs(5)).unwrap());
+        let provider = AnthropicProvider::new(transport, server.url(), "test-key", "test-model");
+        let request = LlmRequest {
+            system: "Be brief".to_string(),
+            message: "Hello".to_string(),
+            max_tokens: 64,
+        };
+
+        server.respond(
+            200,
+            r#"{"model":"test-model","content":[{"type":"text","text":"Hi"},{"type":"text","text":"!"}],
+                "usage":{"input_tokens":120,"output_tokens":80}}"#,
+        );
+        let response = provider.complete(&request).await.unwrap();
+        assert_eq!(
+            response,
+            LlmResponse {
+                text: "Hi!".to_string(),
+                model: "test-model".to_string(),
+                input_tokens: 120,
+                output_tokens: 80,
+                rate_limit_reset: None,
+            }
+        );
+
+        let recorded = &server.requests()[0];
+        assert_eq!(recorded.path, "/v1/messages");
+        assert_eq!(recorded.header("x-api-key"), Some("test-key"));
+        assert_eq!(recorded.header("anthropic-version"), Some("2023-06-01"));
+        let body: Value = serde_json::from_str(&recorded.body).unwrap();
+        assert_eq!(body["system"], "Be brief");
+        assert_eq!(body["max_tokens"], 64);
+
+        server.respond(
+            401,
+            r#"{"type":"error","error":{"type":"authentication_error","message":"invalid x-api-key"}}"#,
+        );
+        let error = provider.complete(&request).await.unwrap_err();
+        assert_eq!(error.to_string(), "Anthropic API error (401): invalid x-api-key");
+    }
+}
@@ -0,0 +1,69 @@
+//! HTTP layer of the AI clients, behind a trait so tests can use a local
+//! mock server or a canned transport
+
+use anyhow::Result;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+pub type HttpFuture<'a> = Pin<Box<dyn Future<Output = Result<HttpResponse>> + Send + 'a>>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl HttpResponse {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+pub trait HttpTransport: Send + Sync {
+    /// POST a JSON body and return status and body of the response
+    fn post_json<'a>(&'a self, url: &'a str, headers: &'a [(String, String)], body: String) -> HttpFuture<'a>;
+}
+
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    pub fn new(timeout: Duration) -> Result<Self> {
+        Ok(Self {
+            client: reqwest::Client::builder().timeout(timeout).build()?,
+        })
+    }
+}
+
+impl HttpTransport for ReqwestTransport {
+    fn post_json<'a>(&'a self, url: &'a str, headers: &'a [(String, String)], body: String) -> HttpFuture<'a> {
+        Box::pin(async move {
+            let mut request = self
+                .client
+                .post(url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body);
+            for (name, value) in headers {
+                request = request.header(name.as_str(), value.as_str());
+            }
+
+            let response = request.send().await?;
+            let headers = response
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+                .collect();
+            Ok(HttpResponse {
+                status: response.status().as_u16(),
+                headers,
+                body: response.text().await?,
+            })
+        })
+    }
+}
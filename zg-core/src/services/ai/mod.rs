@@ -0,0 +1,237 @@
+pub mod anthropic;
+pub mod http;
+#[cfg(test)]
+mod mock_server;
+pub mod openai;
+pub mod provider;
+pub mod queue;
+
+use crate::db::{models::*, repositories::TaskRepository, DbPool};
+use crate::services::access::{Access, Role};
+use crate::services::{EventBus, TaskService};
+use anyhow::{bail, Result};
+use provider::LlmRequest;
+use queue::AiQueueService;
+use serde::Deserialize;
+use std::sync::Arc;
+
+const BREAKDOWN_SYSTEM_PROMPT: &str = "You help people with ADHD get started on tasks. \
+Break the task into 3 to 8 small, concrete subtasks that each take 5 to 60 minutes. \
+Start every subtask with a verb and make the first one easy to start. \
+Answer only with JSON of the form \
+{\"subtasks\": [{\"title\": \"...\", \"estimated_minutes\": 15, \"energy_level\": \"low\"}]} \
+where energy_level is \"low\", \"medium\" or \"high\". \
+Write the subtasks in the language of the task.";
+
+const BREAKDOWN_MAX_TOKENS: u32 = 1024;
+const BREAKDOWN_FEATURE: &str = "breakdown";
+
+/// Longest estimate accepted for a single subtask
+const MAX_SUBTASK_MINUTES: i32 = 8 * 60;
+
+/// Splits tasks into subtasks with the help of the configured LLM.
+///
+/// Suggestions are only returned; subtasks are created in a second step, after
+/// the user has reviewed (and possibly edited) them.
+pub struct BreakdownService {
+    tasks: TaskRepository,
+    task_service: TaskService,
+    ai: Arc<AiQueueService>,
+    access: Access,
+}
+
+impl BreakdownService {
+    pub fn new(pool: DbPool, events: Arc<EventBus>, ai: Arc<AiQueueService>) -> Self {
+        Self {
+            access: Access::new(pool.clone()),
+            tasks: TaskRepository::new(pool.clone()),
+            task_service: TaskService::new(pool, events),
+            ai,
+        }
+    }
+
+    /// Ask for subtasks of a task, without saving anything
+    pub async fn breakdown_task(&self, task_id: i64) -> Result<Vec<SubtaskSuggestion>> {
+        self.access.require(Role::Editor)?;
+        let task = self.tasks.get_by_id(task_id)?;
+        let existing = self.tasks.get_subtasks(task_id)?;
+
+        let mut message = format!("Task: {}", task.title);
+        if let Some(description) = task.description.as_deref().filter(|d| !d.trim().is_empty()) {
+            message.push_str(&format!("\n\nDetails:\n{description}"));
+        }
+        if !existing.is_empty() {
+            message.push_str("\n\nExisting subtasks, do not repeat them:");
+            for subtask in &existing {
+                message.push_str(&format!("\n- {}", subtask.title));
+            }
+        }
+
+        let request = LlmRequest {
+            system: BREAKDOWN_SYSTEM_PROMPT.to_string(),
+            message,
+            max_tokens: BREAKDOWN_MAX_TOKENS,
+        };
+        // Not queued: the suggestions are only of use while the user waits
+        let reply = self.ai.complete_now(BREAKDOWN_FEATURE, request).await?;
+
+        let suggestions = parse_suggestions(&reply.text)?;
+        tracing::info!("Suggested {} subtasks for task {task_id}", suggestions.len());
+        Ok(suggestions)
+    }
+
+    /// Create reviewed suggestions as subtasks, in the project of the parent
+    pub fn create_subtasks(&self, parent_task_id: i64, subtasks: Vec<SubtaskSuggestion>) -> Result<Vec<Task>> {
+        self.access.require(Role::Editor)?;
+        let parent = self.tasks.get_by_id(parent_task_id)?;
+
+        let inputs = subtasks
+            .into_iter()
+            .filter(|s| !s.title.trim().is_empty())
+            .map(|s| CreateTaskInput {
+                title: s.title.trim().to_string(),
+                project_id: parent.project_id,
+                estimated_minutes: s.estimated_minutes,
+                energy_level: s.energy_level,
+                parent_task_id: Some(parent.id),
+                ..Default::default()
+            })
+            .collect();
+        self.task_service.create_tasks(inputs)
+    }
+}
+
+#[derive(Deserialize)]
+struct BreakdownReply {
+    subtasks: Vec<RawSuggestion>,
+}
+
+#[derive(Deserialize)]
+struct RawSuggestion {
+    title: String,
+    #[serde(default)]
+    estimated_minutes: Option<f64>,
+    #[serde(default)]
+    energy_level: Option<String>,
+}
+
+/// Read the JSON object from a reply, tolerating text or code fences around it
+fn parse_suggestions(reply: &str) -> Result<Vec<SubtaskSuggestion>> {
+    let (Some(start), Some(end)) = (reply.find('{'), reply.rfind('}')) else {
+        bail!("The reply contained no subtasks");
+    };
+    let parsed: BreakdownReply = serde_json::from_str(&reply[start..=end])
+        .map_err(|e| anyhow::anyhow!("Could not read the suggested subtasks: {e}"))?;
+
+    let suggestions: Vec<SubtaskSuggestion> = parsed
+        .subtasks
+        .into_iter()
+        .filter(|s| !s.title.trim().is_empty())
+        .map(|s| SubtaskSuggestion {
+            title: s.title.trim().to_string(),
+            estimated_minutes: s
+                .estimated_minutes
+                .map(|m| (m.round() as i32).clamp(1, MAX_SUBTASK_MINUTES)),
+            energy_level: s
+                .energy_level
+                .map(|e| e.trim().to_lowercase())
+                .filter(|e| matches!(e.as_str(), "low" | "medium" | "high")),
+        })
+        .collect();
+
+    if suggestions.is_empty() {
+        bail!("The reply contained no subtasks");
+    }
+    Ok(suggestions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_test_database;
+    use crate::services::event_bus::DomainEvent;
+    use provider::{FakeProvider, LlmProvider};
+    use std::sync::Mutex;
+
+    fn service(pool: DbPool, provider: Arc<dyn LlmProvider>) -> BreakdownService {
+        service_with_events(pool, provider, Arc::new(EventBus::new()))
+    }
+
+    fn service_with_events(pool: DbPool, provider: Arc<dyn LlmProvider>, events: Arc<EventBus>) -> BreakdownService {
+        let ai = Arc::new(AiQueueService::with_provider(pool.clone(), provider));
+        BreakdownService::new(pool, events, ai)
+    }
+
+    fn create_task(pool: &DbPool, title: &str, description: Option<&str>) -> Task {
+        TaskRepository::new(pool.clone())
+            .create(CreateTaskInput {
+                title: title.to_string(),
+                description: description.map(String::from),
+                ..Default::default()
+            })
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn suggests_and_creates_subtasks() {
+        let pool = init_test_database().unwrap();
+        let provider = Arc::new(FakeProvider::new(&[
+            "Here you go:\n```json\n{\"subtasks\": [\
+             {\"title\": \"Open the garage door\", \"estimated_minutes\": 2, \"energy_level\": \"Low\"},\
+             {\"title\": \"Sort tools\", \"estimated_minutes\": 900, \"energy_level\": \"extreme\"},\
+             {\"title\": \" \"}]}\n```",
+        ]));
+        let events = Arc::new(EventBus::new());
+        let created_titles = Arc::new(Mutex::new(Vec::new()));
+        let recorder = Arc::clone(&created_titles);
+        events.subscribe("recorder", move |_, event| {
+            if let DomainEvent::TaskCreated(task) = event {
+                recorder.lock().unwrap().push(task.title.clone());
+            }
+            Ok(())
+        });
+        let service = service_with_events(pool.clone(), provider.clone(), events);
+        let task = create_task(&pool, "Clean the garage", Some("Before winter"));
+
+        let suggestions = service.breakdown_task(task.id).await.unwrap();
+        assert_eq!(
+            suggestions,
+            vec![
+                SubtaskSuggestion {
+                    title: "Open the garage door".to_string(),
+                    estimated_minutes: Some(2),
+                    energy_level: Some("low".to_string()),
+                },
+                SubtaskSuggestion {
+                    title: "Sort tools".to_string(),
+                    estimated_minutes: Some(MAX_SUBTASK_MINUTES),
+                    energy_level: None,
+                },
+            ]
+        );
+
+        let request = &provider.requests()[0];
+        assert_eq!(request.system, BREAKDOWN_SYSTEM_PROMPT);
+        assert!(request.message.contains("Clean the garage\n\nDetails:\nBefore winter"));
+
+        let created = service.create_subtasks(task.id, suggestions).unwrap();
+        assert_eq!(created.len(), 2);
+        assert!(created.iter().all(|t| t.parent_task_id == Some(task.id)));
+        assert_eq!(created[0].energy_level.as_deref(), Some("low"));
+        assert_eq!(*created_titles.lock().unwrap(), ["Open the garage door", "Sort tools"]);
+
+        // Existing subtasks are passed on so they are not suggested again
+        service.breakdown_task(task.id).await.unwrap();
+        assert!(provider.requests()[1].message.contains("do not repeat them:\n- Open the garage door"));
+    }
+
+    #[tokio::test]
+    async fn rejects_unusable_replies() {
+        let pool = init_test_database().unwrap();
+        let provider = Arc::new(FakeProvider::new(&["Sorry, I can't help with that."]));
+        let service = service(pool.clone(), provider);
+        let task = create_task(&pool, "Write thesis", None);
+
+        assert!(service.breakdown_task(task.id).await.is_err());
+    }
+}
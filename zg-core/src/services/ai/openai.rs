@@ -0,0 +1,135 @@
+//! Provider for OpenAI-compatible chat completion endpoints, such as Ollama,
+//! LM Studio or llama.cpp
+
+use super::http::HttpTransport;
+use super::provider::{self, LlmError, LlmFuture, LlmProvider, LlmRequest, LlmResponse};
+use anyhow::Context;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+pub struct OpenAiCompatibleProvider {
+    transport: Arc<dyn HttpTransport>,
+    base_url: String,
+    api_key: Option<String>,
+    model: String,
+}
+
+impl OpenAiCompatibleProvider {
+    /// `base_url` includes the version path, e.g. `http://localhost:11434/v1`
+    pub fn new(transport: Arc<dyn HttpTransport>, base_url: &str, api_key: Option<&str>, model: &str) -> Self {
+        Self {
+            transport,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: api_key.filter(|k| !k.is_empty()).map(String::from),
+            model: model.to_string(),
+        }
+    }
+}
+
+impl LlmProvider for OpenAiCompatibleProvider {
+    fn cache_key(&self) -> String {
+        format!("openai_compatible:{}:{}", self.base_url, self.model)
+    }
+
+    fn complete<'a>(&'a self, request: &'a LlmRequest) -> LlmFuture<'a> {
+        Box::pin(async move {
+            let body = json!({
+                "model": self.model,
+                "max_tokens": request.max_tokens,
+                "messages": [
+                    { "role": "system", "content": request.system },
+                    { "role": "user", "content": request.message },
+                ],
+            });
+            // Local servers usually need no key
+            let headers: Vec<(String, String)> = self
+                .api_key
+                .iter()
+                .map(|key| ("authorization".to_string(), format!("Bearer {key}")))
+                .collect();
+
+            let url = format!("{}/chat/completions", self.base_url);
+            let response = self
+                .transport
+                .post_json(&url, &headers, body.to_string())
+                .await
+                .map_err(|e| LlmError::Unreachable(format!("AI request failed: {e:#}")))?;
+            let reply: Value = serde_json::from_str(&response.body).unwrap_or(Value::Null);
+
+            if response.status != 200 {
+                let message = reply["error"]["message"]
+                    .as_str()
+                    .or_else(|| reply["error"].as_str())
+                    .unwrap_or(&response.body);
+                return Err(provider::status_error(
+                    &response,
+                    format!("AI API error ({}): {message}", response.status),
+                ));
+            }
+
+            let text = reply["choices"][0]["message"]["content"]
+                .as_str()
+                .context("Unexpected AI API response")?
+                .to_string();
+
+            Ok(LlmResponse {
+                text,
+                model: reply["model"].as_str().unwrap_or(&self.model).to_string(),
+                input_tokens: reply["usage"]["prompt_tokens"].as_u64().unwrap_or(0) as u32,
+                rate_limit_reset: provider::exhausted_reset(&response),
+                output_tokens: reply["usage"]["completion_tokens"].as_u64().unwrap_or(0) as u32,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::ai::http::ReqwestTransport;
+    use crate::services::ai::mock_server::MockHttpServer;
+    use std::time::Duration;
+
+    fn request() -> LlmRequest {
+        LlmRequest {
+            system: "Be brief".to_string(),
+            message: "Hello".to_string(),
+            max_tokens: 64,
+        }
+    }
+
+    #[tokio::test]
+    async fn sends_chat_completion_and_reads_usage() {
+        let server = MockHttpServer::start().await;
+        let transport = Arc::new(ReqwestTransport::new(Duration::from_secs(5)).unwrap());
+        let provider = OpenAiCompatibleProvider::new(transport, &format!("{}/v1/", server.url()), None, "llama3.1");
+
+        server.respond(
+            200,
+            r#"{"model":"llama3.1:8b","choices":[{"message":{"role":"assistant","content":"Hi!"}}],
+                "usage":{"prompt_tokens":12,"completion_tokens":3}}"#,
+        );
+        let response = provider.complete(&request()).await.unwrap();
+        assert_eq!(
+            response,
+            LlmResponse {
+                text: "Hi!".to_string(),
+                model: "llama3.1:8b".to_string(),
+                input_tokens: 12,
+                output_tokens: 3,
+                rate_limit_reset: None,
+            }
+        );
+
+        let recorded = &server.requests()[0];
+        assert_eq!(recorded.path, "/v1/chat/completions");
+        assert_eq!(recorded.header("authorization"), None);
+        let body: Value = serde_json::from_str(&recorded.body).unwrap();
+        assert_eq!(body["messages"][0]["content"], "Be brief");
+        assert_eq!(body["messages"][1]["content"], "Hello");
+
+        server.respond(404, r#"{"error":"model \"llama3.1\" not found"}"#);
+        let error = provider.complete(&request()).await.unwrap_err();
+        assert_eq!(error.to_string(), "AI API error (404): model \"llama3.1\" not found");
+    }
+}
@@ -0,0 +1,321 @@
+//! The LLM abstraction all AI features go through
+
+use super::anthropic::AnthropicProvider;
+use super::http::{HttpResponse, ReqwestTransport};
+use super::openai::OpenAiCompatibleProvider;
+use crate::db::{models::AiSettings, repositories::PreferencesRepository, DbPool};
+use crate::services::preferences_service::load_ai_settings;
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub const ANTHROPIC: &str = "anthropic";
+pub const OPENAI_COMPATIBLE: &str = "openai_compatible";
+pub const PROVIDERS: [&str; 2] = [ANTHROPIC, OPENAI_COMPATIBLE];
+
+pub type LlmFuture<'a> = Pin<Box<dyn Future<Output = Result<LlmResponse>> + Send + 'a>>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LlmRequest {
+    pub system: String,
+    pub message: String,
+    pub max_tokens: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LlmResponse {
+    pub text: String,
+    pub model: String,
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    /// Set when the provider reported that no requests are left; time until
+    /// the limit resets
+    pub rate_limit_reset: Option<Duration>,
+}
+
+/// Failures worth retrying later. Any other error is final.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum LlmError {
+    /// The provider could not be reached, e.g. while offline
+    #[error("{0}")]
+    Unreachable(String),
+    #[error("{message}")]
+    RateLimited {
+        message: String,
+        retry_after: Option<Duration>,
+    },
+    /// Server errors and overload
+    #[error("{0}")]
+    Unavailable(String),
+}
+
+pub trait LlmProvider: Send + Sync {
+    /// Identifies provider and model, so cached replies of one model are not
+    /// served for another
+    fn cache_key(&self) -> String;
+
+    /// Send a single user message and return the reply
+    fn complete<'a>(&'a self, request: &'a LlmRequest) -> LlmFuture<'a>;
+}
+
+/// Turn an unsuccessful response into an error, retryable where it makes sense
+pub(super) fn status_error(response: &HttpResponse, message: String) -> anyhow::Error {
+    match response.status {
+        429 => LlmError::RateLimited {
+            message,
+            retry_after: retry_after(response, Utc::now()),
+        }
+        .into(),
+        500..=599 => LlmError::Unavailable(message).into(),
+        _ => anyhow::anyhow!(message),
+    }
+}
+
+/// Time until requests may be sent again, if the response says so. Reads
+/// `retry-after` and the rate limit headers of Anthropic and OpenAI.
+pub(super) fn retry_after(response: &HttpResponse, now: DateTime<Utc>) -> Option<Duration> {
+    if let Some(seconds) = response.header("retry-after").and_then(|v| v.parse::<u64>().ok()) {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let anthropic_reset = ["anthropic-ratelimit-requests-reset", "anthropic-ratelimit-tokens-reset"]
+        .iter()
+        .filter_map(|name| response.header(name))
+        .filter_map(|value| DateTime::parse_from_rfc3339(value).ok())
+        .filter_map(|reset| (reset.with_timezone(&Utc) - now).to_std().ok())
+        .max();
+    if anthropic_reset.is_some() {
+        return anthropic_reset;
+    }
+
+    ["x-ratelimit-reset-requests", "x-ratelimit-reset-tokens"]
+        .iter()
+        .filter_map(|name| response.header(name))
+        .filter_map(parse_reset_duration)
+        .max()
+}
+
+/// Time until the limit resets when a successful response used up the last
+/// request of the window
+pub(super) fn exhausted_reset(response: &HttpResponse) -> Option<Duration> {
+    let exhausted = ["anthropic-ratelimit-requests-remaining", "x-ratelimit-remaining-requests"]
+        .iter()
+        .any(|name| response.header(name) == Some("0"));
+    if exhausted {
+        retry_after(response, Utc::now())
+    } else {
+        None
+    }
+}
+
+/// Parse durations like `1s`, `6m0s` or `250ms`
+fn parse_reset_duration(value: &str) -> Option<Duration> {
+    let mut total = Duration::ZERO;
+    let mut rest = value.trim();
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit() && c != '.')?;
+        let number: f64 = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+        let unit_end = rest.find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len());
+        let seconds = match &rest[..unit_end] {
+            "ms" => number / 1000.0,
+            "s" => number,
+            "m" => number * 60.0,
+            "h" => number * 3600.0,
+            _ => return None,
+        };
+        total += Duration::from_secs_f64(seconds);
+        rest = &rest[unit_end..];
+    }
+    Some(total)
+}
+
+pub fn default_model(provider: &str) -> &'static str {
+    match provider {
+        OPENAI_COMPATIBLE => "llama3.1",
+        _ => "claude-3-5-haiku-latest",
+    }
+}
+
+pub fn default_base_url(provider: &str) -> &'static str {
+    match provider {
+        // Ollama's OpenAI-compatible endpoint
+        OPENAI_COMPATIBLE => "http://localhost:11434/v1",
+        _ => "https://api.anthropic.com",
+    }
+}
+
+/// Build the provider described by the settings
+pub fn create_provider(settings: &AiSettings) -> Result<Arc<dyn LlmProvider>> {
+    let transport = Arc::new(ReqwestTransport::new(Duration::from_secs(
+        settings.timeout_secs.into(),
+    ))?);
+
+    match settings.provider.as_str() {
+        ANTHROPIC => {
+            let Some(api_key) = settings.api_key.as_deref() else {
+                bail!("No Anthropic API key configured");
+            };
+            Ok(Arc::new(AnthropicProvider::new(
+                transport,
+                &settings.base_url,
+                api_key,
+                &settings.model,
+            )))
+        }
+        OPENAI_COMPATIBLE => Ok(Arc::new(OpenAiCompatibleProvider::new(
+            transport,
+            &settings.base_url,
+            settings.api_key.as_deref(),
+            &settings.model,
+        ))),
+        other => bail!("Unknown AI provider: {other}"),
+    }
+}
+
+/// Hands out the provider configured in the preferences at the time of the
+/// call, so changed settings apply without a restart
+pub struct ProviderSource {
+    preferences: PreferencesRepository,
+    fixed: Option<Arc<dyn LlmProvider>>,
+}
+
+impl ProviderSource {
+    pub fn new(pool: DbPool) -> Self {
+        Self {
+            preferences: PreferencesRepository::new(pool),
+            fixed: None,
+        }
+    }
+
+    /// Always use the given provider
+    #[cfg(test)]
+    pub fn fixed(pool: DbPool, provider: Arc<dyn LlmProvider>) -> Self {
+        Self {
+            preferences: PreferencesRepository::new(pool),
+            fixed: Some(provider),
+        }
+    }
+
+    pub fn get(&self) -> Result<Arc<dyn LlmProvider>> {
+        match &self.fixed {
+            Some(provider) => Ok(Arc::clone(provider)),
+            None => create_provider(&load_ai_settings(&self.preferences)?),
+        }
+    }
+}
+
+/// Deterministic provider for tests: replies with the queued results in
+/// order, then keeps repeating the last one, and records every request
+#[cfg(test)]
+pub struct FakeProvider {
+    replies: std::sync::Mutex<std::collections::VecDeque<Result<String, LlmError>>>,
+    requests: std::sync::Mutex<Vec<LlmRequest>>,
+}
+
+#[cfg(test)]
+impl FakeProvider {
+    pub fn new(replies: &[&str]) -> Self {
+        Self {
+            replies: std::sync::Mutex::new(replies.iter().map(|r| Ok(r.to_string())).collect()),
+            requests: std::sync::Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Fail the next request
+    pub fn fail_next(&self, error: LlmError) {
+        self.replies.lock().unwrap().push_front(Err(error));
+    }
+
+    pub fn requests(&self) -> Vec<LlmRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+impl LlmProvider for FakeProvider {
+    fn cache_key(&self) -> String {
+        "fake".to_string()
+    }
+
+    fn complete<'a>(&'a self, request: &'a LlmRequest) -> LlmFuture<'a> {
+        self.requests.lock().unwrap().push(request.clone());
+        let mut replies = self.replies.lock().unwrap();
+        let reply = if replies.len() > 1 {
+            replies.pop_front()
+        } else {
+            replies.front().cloned()
+        };
+
+        Box::pin(async move {
+            let Some(reply) = reply else {
+                bail!("FakeProvider has no reply queued");
+            };
+            let text = reply?;
+            Ok(LlmResponse {
+                input_tokens: (request.system.len() + request.message.len()) as u32 / 4,
+                output_tokens: text.len() as u32 / 4,
+                model: "fake".to_string(),
+                text,
+                rate_limit_reset: None,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(status: u16, headers: &[(&str, &str)]) -> HttpResponse {
+        HttpResponse {
+            status,
+            headers: headers
+                .iter()
+                .map(|(n, v)| (n.to_string(), v.to_string()))
+                .collect(),
+            body: String::new(),
+        }
+    }
+
+    #[test]
+    fn reads_rate_limit_headers() {
+        let now = DateTime::parse_from_rfc3339("2026-03-02T10:00:00Z").unwrap().with_timezone(&Utc);
+
+        let limited = response(429, &[("Retry-After", "20")]);
+        assert_eq!(retry_after(&limited, now), Some(Duration::from_secs(20)));
+
+        let anthropic = response(
+            429,
+            &[
+                ("anthropic-ratelimit-requests-reset", "2026-03-02T10:00:30Z"),
+                ("anthropic-ratelimit-tokens-reset", "2026-03-02T10:01:00Z"),
+            ],
+        );
+        assert_eq!(retry_after(&anthropic, now), Some(Duration::from_secs(60)));
+
+        let openai = response(429, &[("x-ratelimit-reset-requests", "1m30s"), ("x-ratelimit-reset-tokens", "250ms")]);
+        assert_eq!(retry_after(&openai, now), Some(Duration::from_secs(90)));
+
+        assert_eq!(retry_after(&response(429, &[]), now), None);
+        assert_eq!(exhausted_reset(&response(200, &[("retry-after", "5")])), None);
+        assert_eq!(
+            exhausted_reset(&response(200, &[("x-ratelimit-remaining-requests", "0"), ("x-ratelimit-reset-requests", "2s")])),
+            Some(Duration::from_secs(2))
+        );
+
+        let error = status_error(&limited, "slow down".to_string());
+        assert_eq!(
+            error.downcast_ref::<LlmError>(),
+            Some(&LlmError::RateLimited {
+                message: "slow down".to_string(),
+                retry_after: Some(Duration::from_secs(20)),
+            })
+        );
+        assert!(status_error(&response(401, &[]), "no".to_string()).downcast_ref::<LlmError>().is_none());
+    }
+}
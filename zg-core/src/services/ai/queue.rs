@@ -0,0 +1,475 @@
+//! Persistent queue for AI requests.
+//!
+//! Requests are answered from a cache keyed by a hash of model and prompt where
+//! possible. Everything else becomes a job in SQLite that is tried right away
+//! and, after temporary failures, retried in the background with exponential
+//! backoff, so requests made while offline go out once the provider is
+//! reachable again. Requests someone is waiting for skip the queue and fail
+//! right away instead, since nobody would see a late reply.
+
+use super::provider::{LlmError, LlmProvider, LlmRequest, LlmResponse, ProviderSource};
+use crate::db::{
+    models::AiUsage,
+    repositories::{ai_repository::UsageEntry, AiRepository},
+    DbPool,
+};
+use anyhow::{anyhow, bail, Result};
+use chrono::{Local, NaiveDateTime, TimeZone, Utc};
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const BASE_RETRY_DELAY: Duration = Duration::from_secs(5);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(10 * 60);
+/// Retries while unreachable are capped lower, so work resumes soon after
+/// the connection is back
+const MAX_UNREACHABLE_RETRY_DELAY: Duration = Duration::from_secs(60);
+/// Attempts before rate-limited or failing requests are given up. Requests
+/// that never reached the provider are retried indefinitely.
+const MAX_ATTEMPTS: u32 = 8;
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// List prices in USD per million input and output tokens, by model family.
+/// Other models, such as local ones, are counted as free.
+const PRICES: [(&str, f64, f64); 3] = [("haiku", 0.80, 4.0), ("sonnet", 3.0, 15.0), ("opus", 15.0, 75.0)];
+
+enum JobOutcome {
+    Done(LlmResponse),
+    Retrying(anyhow::Error),
+    Failed(anyhow::Error),
+    /// Taken by someone else in the meantime
+    Skipped,
+}
+
+pub struct AiQueueService {
+    repository: AiRepository,
+    providers: ProviderSource,
+    paused_until: Mutex<Option<NaiveDateTime>>,
+}
+
+impl AiQueueService {
+    pub fn new(pool: DbPool) -> Self {
+        Self {
+            repository: AiRepository::new(pool.clone()),
+            providers: ProviderSource::new(pool),
+            paused_until: Mutex::new(None),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_provider(pool: DbPool, provider: Arc<dyn LlmProvider>) -> Self {
+        Self {
+            repository: AiRepository::new(pool.clone()),
+            providers: ProviderSource::fixed(pool, provider),
+            paused_until: Mutex::new(None),
+        }
+    }
+
+    /// Answer a request from the cache, or queue it and try it right away.
+    /// After a temporary failure the job stays queued and its reply ends up
+    /// in the cache once a retry succeeds.
+    pub async fn complete(&self, feature: &str, request: LlmRequest) -> Result<LlmResponse> {
+        self.complete_at(feature, request, Utc::now().naive_utc()).await
+    }
+
+    /// Answer a request from the cache or the provider without queueing it,
+    /// for requests someone is waiting for. Failures are returned as they
+    /// are, to be retried by the user.
+    pub async fn complete_now(&self, feature: &str, request: LlmRequest) -> Result<LlmResponse> {
+        self.complete_now_at(feature, request, Utc::now().naive_utc()).await
+    }
+
+    /// Run the queued jobs that are due, oldest first. Returns the number of
+    /// jobs completed.
+    pub async fn run_due(&self) -> Result<usize> {
+        self.run_due_at(Utc::now().naive_utc()).await
+    }
+
+    /// Usage per day, feature and model between two days (inclusive)
+    pub fn get_usage(&self, from: &str, to: &str) -> Result<Vec<AiUsage>> {
+        self.repository.usage(from, to)
+    }
+
+    /// Work off the queue in the background, including jobs left over from
+    /// earlier runs
+    pub fn start_worker(self: &Arc<Self>) {
+        match self.repository.requeue_running() {
+            Ok(0) => {}
+            Ok(n) => tracing::info!("Requeued {n} interrupted AI jobs"),
+            Err(e) => tracing::error!("Failed to requeue interrupted AI jobs: {e:#}"),
+        }
+
+        let service = Arc::clone(self);
+        std::thread::spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                Ok(runtime) => runtime,
+                Err(e) => {
+                    tracing::error!("Failed to start the AI queue: {e}");
+                    return;
+                }
+            };
+            runtime.block_on(async move {
+                loop {
+                    match service.run_due().await {
+                        Ok(0) => {}
+                        Ok(n) => tracing::info!("Completed {n} queued AI jobs"),
+                        Err(e) => tracing::error!("AI queue run failed: {e:#}"),
+                    }
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                }
+            });
+        });
+    }
+
+    async fn complete_at(&self, feature: &str, request: LlmRequest, now: NaiveDateTime) -> Result<LlmResponse> {
+        let provider = self.providers.get()?;
+        let prompt_hash = prompt_hash(&provider.cache_key(), &request);
+        if let Some(response) = self.cached(feature, &prompt_hash, now)? {
+            return Ok(response);
+        }
+
+        let job_id = self.repository.enqueue(
+            feature,
+            &prompt_hash,
+            &serde_json::to_string(&request)?,
+            &timestamp(now),
+        )?;
+        if let Some(until) = self.paused_until(now) {
+            self.repository
+                .reschedule(job_id, &timestamp(until), "Waiting for the rate limit to reset")?;
+            bail!("The AI rate limit is reached; the request was queued and will be retried");
+        }
+
+        match self.run_job(job_id, provider.as_ref(), now).await? {
+            JobOutcome::Done(response) => Ok(response),
+            JobOutcome::Retrying(error) => Err(anyhow!("{error}; the request was queued and will be retried")),
+            JobOutcome::Failed(error) => Err(error),
+            JobOutcome::Skipped => bail!("AI job {job_id} was taken by another worker"),
+        }
+    }
+
+    async fn complete_now_at(&self, feature: &str, request: LlmRequest, now: NaiveDateTime) -> Result<LlmResponse> {
+        let provider = self.providers.get()?;
+        let prompt_hash = prompt_hash(&provider.cache_key(), &request);
+        if let Some(response) = self.cached(feature, &prompt_hash, now)? {
+            return Ok(response);
+        }
+        if self.paused_until(now).is_some() {
+            bail!("The AI rate limit is reached; try again in a minute");
+        }
+
+        match provider.complete(&request).await {
+            Ok(response) => {
+                self.repository.cache(&prompt_hash, &response.text, &response.model)?;
+                self.record_reply(feature, &response, now)?;
+                Ok(response)
+            }
+            Err(error) => {
+                if let Some(LlmError::RateLimited { retry_after, .. }) = error.downcast_ref::<LlmError>() {
+                    self.pause_until(now + retry_after.unwrap_or(BASE_RETRY_DELAY));
+                }
+                Err(error)
+            }
+        }
+    }
+
+    /// The cached reply to a prompt, counted as a cache hit
+    fn cached(&self, feature: &str, prompt_hash: &str, now: NaiveDateTime) -> Result<Option<LlmResponse>> {
+        let Some((text, model)) = self.repository.cached(prompt_hash)? else {
+            return Ok(None);
+        };
+        self.repository.record_usage(&UsageEntry {
+            day: &local_day(now),
+            feature,
+            model: &model,
+            cache_hit: true,
+            input_tokens: 0,
+            output_tokens: 0,
+            cost_usd: 0.0,
+        })?;
+        Ok(Some(LlmResponse {
+            text,
+            model,
+            ..Default::default()
+        }))
+    }
+
+    /// Count a reply from the provider and respect its rate limit
+    fn record_reply(&self, feature: &str, response: &LlmResponse, now: NaiveDateTime) -> Result<()> {
+        self.repository.record_usage(&UsageEntry {
+            day: &local_day(now),
+            feature,
+            model: &response.model,
+            cache_hit: false,
+            input_tokens: response.input_tokens,
+            output_tokens: response.output_tokens,
+            cost_usd: estimate_cost(&response.model, response.input_tokens, response.output_tokens),
+        })?;
+        if let Some(reset) = response.rate_limit_reset {
+            self.pause_until(now + reset);
+        }
+        Ok(())
+    }
+
+    async fn run_due_at(&self, now: NaiveDateTime) -> Result<usize> {
+        if self.paused_until(now).is_some() {
+            return Ok(0);
+        }
+        let due = self.repository.due_jobs(&timestamp(now))?;
+        if due.is_empty() {
+            return Ok(0);
+        }
+        // Without a usable provider (e.g. no API key yet) the jobs just wait
+        let provider = match self.providers.get() {
+            Ok(provider) => provider,
+            Err(e) => {
+                tracing::warn!("{} AI jobs waiting: {e:#}", due.len());
+                return Ok(0);
+            }
+        };
+
+        let mut completed = 0;
+        for job_id in due {
+            if self.paused_until(now).is_some() {
+                break;
+            }
+            match self.run_job(job_id, provider.as_ref(), now).await? {
+                JobOutcome::Done(_) => completed += 1,
+                // The next jobs would most likely fail the same way
+                JobOutcome::Retrying(_) => break,
+                JobOutcome::Failed(_) | JobOutcome::Skipped => {}
+            }
+        }
+        Ok(completed)
+    }
+
+    async fn run_job(&self, job_id: i64, provider: &dyn LlmProvider, now: NaiveDateTime) -> Result<JobOutcome> {
+        let Some(job) = self.repository.get_job(job_id)? else {
+            return Ok(JobOutcome::Skipped);
+        };
+        if !self.repository.claim(job_id)? {
+            return Ok(JobOutcome::Skipped);
+        }
+        let attempts = job.attempts + 1;
+        let request: LlmRequest = serde_json::from_str(&job.request)?;
+
+        let error = match provider.complete(&request).await {
+            Ok(response) => {
+                self.repository
+                    .complete(job_id, &job.prompt_hash, &response.text, &response.model)?;
+                self.record_reply(&job.feature, &response, now)?;
+                return Ok(JobOutcome::Done(response));
+            }
+            Err(error) => error,
+        };
+
+        let delay = match error.downcast_ref::<LlmError>() {
+            Some(LlmError::Unreachable(_)) => Some(backoff(attempts).min(MAX_UNREACHABLE_RETRY_DELAY)),
+            Some(LlmError::RateLimited { retry_after, .. }) if attempts < MAX_ATTEMPTS => {
+                let delay = retry_after.unwrap_or_else(|| backoff(attempts));
+                self.pause_until(now + delay);
+                Some(delay)
+            }
+            Some(LlmError::Unavailable(_)) if attempts < MAX_ATTEMPTS => Some(backoff(attempts)),
+            _ => None,
+        };
+
+        match delay {
+            Some(delay) => {
+                tracing::warn!(
+                    "AI job {job_id} ({}) failed, retrying in {}s: {error}",
+                    job.feature,
+                    delay.as_secs()
+                );
+                self.repository
+                    .reschedule(job_id, &timestamp(now + delay), &error.to_string())?;
+                Ok(JobOutcome::Retrying(error))
+            }
+            None => {
+                tracing::error!("AI job {job_id} ({}) failed: {error:#}", job.feature);
+                self.repository.fail(job_id, &format!("{error:#}"))?;
+                Ok(JobOutcome::Failed(error))
+            }
+        }
+    }
+
+    fn paused_until(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut paused = self.paused_until.lock().unwrap();
+        if paused.is_some_and(|until| until <= now) {
+            *paused = None;
+        }
+        *paused
+    }
+
+    fn pause_until(&self, until: NaiveDateTime) {
+        let mut paused = self.paused_until.lock().unwrap();
+        if paused.is_none_or(|current| current < until) {
+            *paused = Some(until);
+        }
+    }
+}
+
+fn backoff(attempts: u32) -> Duration {
+    BASE_RETRY_DELAY
+        .saturating_mul(2u32.saturating_pow(attempts.saturating_sub(1)))
+        .min(MAX_RETRY_DELAY)
+}
+
+fn prompt_hash(cache_key: &str, request: &LlmRequest) -> String {
+    let mut hasher = Sha256::new();
+    for part in [cache_key, &request.system, &request.message, &request.max_tokens.to_string()] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    hasher.finalize().iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn estimate_cost(model: &str, input_tokens: u32, output_tokens: u32) -> f64 {
+    let model = model.to_lowercase();
+    PRICES
+        .iter()
+        .find(|(family, _, _)| model.contains(family))
+        .map(|(_, input, output)| (input_tokens as f64 * input + output_tokens as f64 * output) / 1_000_000.0)
+        .unwrap_or(0.0)
+}
+
+fn timestamp(time: NaiveDateTime) -> String {
+    time.format(TIMESTAMP_FORMAT).to_string()
+}
+
+fn local_day(now: NaiveDateTime) -> String {
+    Utc.from_utc_datetime(&now)
+        .with_timezone(&Local)
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_test_database;
+    use crate::services::ai::provider::FakeProvider;
+
+    fn request(message: &str) -> LlmRequest {
+        LlmRequest {
+            system: "Be brief".to_string(),
+            message: message.to_string(),
+            max_tokens: 64,
+        }
+    }
+
+    fn at(time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(time, TIMESTAMP_FORMAT).unwrap()
+    }
+
+    #[tokio::test]
+    async fn caches_replies_and_records_usage() {
+        let pool = init_test_database().unwrap();
+        let provider = Arc::new(FakeProvider::new(&["Hello there"]));
+        let queue = AiQueueService::with_provider(pool, provider.clone());
+        let now = at("2026-03-02 12:00:00");
+
+        let first = queue.complete_at("breakdown", request("Hi"), now).await.unwrap();
+        let second = queue.complete_at("breakdown", request("Hi"), now).await.unwrap();
+        assert_eq!(first.text, "Hello there");
+        assert_eq!(second.text, "Hello there");
+        assert_eq!(provider.requests().len(), 1);
+
+        queue.complete_at("breakdown", request("Bye"), now).await.unwrap();
+        assert_eq!(provider.requests().len(), 2);
+
+        let day = local_day(now);
+        let usage = queue.get_usage(&day, &day).unwrap();
+        assert_eq!(usage.len(), 1);
+        assert_eq!((usage[0].requests, usage[0].cache_hits), (2, 1));
+        assert_eq!(usage[0].input_tokens, first.input_tokens as i64 * 2);
+        assert!(queue.repository.due_jobs(&timestamp(now)).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn retries_queued_jobs_with_backoff() {
+        let pool = init_test_database().unwrap();
+        let provider = Arc::new(FakeProvider::new(&["Done"]));
+        let queue = AiQueueService::with_provider(pool, provider.clone());
+        let now = at("2026-03-02 12:00:00");
+
+        // Offline: the job stays queued
+        provider.fail_next(LlmError::Unreachable("connection refused".to_string()));
+        let error = queue.complete_at("breakdown", request("Hi"), now).await.unwrap_err();
+        assert!(error.to_string().contains("queued"));
+        assert_eq!(queue.run_due_at(now).await.unwrap(), 0);
+
+        // Still offline on the next attempt, which waits twice as long
+        provider.fail_next(LlmError::Unreachable("connection refused".to_string()));
+        assert_eq!(queue.run_due_at(at("2026-03-02 12:00:05")).await.unwrap(), 0);
+        let job = queue.repository.get_job(1).unwrap().unwrap();
+        assert_eq!((job.attempts, job.next_attempt_at.as_str()), (2, "2026-03-02 12:00:15"));
+
+        assert_eq!(queue.run_due_at(at("2026-03-02 12:00:15")).await.unwrap(), 1);
+        assert!(queue.repository.get_job(1).unwrap().is_none());
+
+        // The reply is served from the cache now
+        let response = queue.complete_at("breakdown", request("Hi"), now).await.unwrap();
+        assert_eq!(response.text, "Done");
+        assert_eq!(provider.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn respects_rate_limits_and_gives_up_on_errors() {
+        let pool = init_test_database().unwrap();
+        let provider = Arc::new(FakeProvider::new(&["Done"]));
+        let queue = AiQueueService::with_provider(pool, provider.clone());
+        let now = at("2026-03-02 12:00:00");
+
+        provider.fail_next(LlmError::RateLimited {
+            message: "Too many requests".to_string(),
+            retry_after: Some(Duration::from_secs(30)),
+        });
+        assert!(queue.complete_at("plan", request("A"), now).await.is_err());
+
+        // Paused until the limit resets: new requests wait without being sent
+        assert!(queue.complete_at("plan", request("B"), at("2026-03-02 12:00:10")).await.is_err());
+        assert_eq!(provider.requests().len(), 1);
+        assert_eq!(queue.run_due_at(at("2026-03-02 12:00:20")).await.unwrap(), 0);
+        assert_eq!(queue.run_due_at(at("2026-03-02 12:00:30")).await.unwrap(), 2);
+
+        for _ in 0..MAX_ATTEMPTS {
+            provider.fail_next(LlmError::Unavailable("overloaded".to_string()));
+        }
+        assert!(queue.complete_at("plan", request("C"), now).await.is_err());
+        for day in 1..MAX_ATTEMPTS {
+            queue.run_due_at(now + chrono::Duration::days(day.into())).await.unwrap();
+        }
+        let job = queue.repository.get_job(3).unwrap().unwrap();
+        assert_eq!((job.status.as_str(), job.attempts), ("failed", MAX_ATTEMPTS));
+    }
+
+    #[tokio::test]
+    async fn interactive_requests_fail_without_queueing() {
+        let pool = init_test_database().unwrap();
+        let provider = Arc::new(FakeProvider::new(&["Done"]));
+        let queue = AiQueueService::with_provider(pool, provider.clone());
+        let now = at("2026-03-02 12:00:00");
+
+        provider.fail_next(LlmError::Unreachable("connection refused".to_string()));
+        let error = queue.complete_now_at("breakdown", request("Hi"), now).await.unwrap_err();
+        assert!(!error.to_string().contains("queued"));
+        assert!(queue.repository.get_job(1).unwrap().is_none());
+
+        let response = queue.complete_now_at("breakdown", request("Hi"), now).await.unwrap();
+        assert_eq!(response.text, "Done");
+        let cached = queue.complete_now_at("breakdown", request("Hi"), now).await.unwrap();
+        assert_eq!(cached.text, "Done");
+        assert_eq!(provider.requests().len(), 2);
+    }
+
+    #[test]
+    fn estimates_cost_by_model_family() {
+        assert_eq!(estimate_cost("claude-3-5-haiku-20241022", 1_000_000, 0), 0.80);
+        assert_eq!(estimate_cost("claude-sonnet-4-5", 0, 1_000_000), 15.0);
+        assert_eq!(estimate_cost("llama3.1", 1_000_000, 1_000_000), 0.0);
+        assert_eq!(backoff(1), BASE_RETRY_DELAY);
+        assert_eq!(backoff(20), MAX_RETRY_DELAY);
+    }
+}
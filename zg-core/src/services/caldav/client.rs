@@ -0,0 +1,264 @@
+//! Minimal CalDAV/WebDAV client for a single task collection
+
+use anyhow::{bail, Context, Result};
+use reqwest::{header, Method, StatusCode, Url};
+use std::time::Duration;
+
+const DAV_NS: &str = "DAV:";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A resource in the collection with its current ETag
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteResource {
+    pub href: String,
+    pub etag: Option<String>,
+}
+
+/// Remote changes since a sync token
+#[derive(Debug, Clone, Default)]
+pub struct RemoteChanges {
+    pub changed: Vec<RemoteResource>,
+    pub removed: Vec<String>,
+    pub sync_token: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PutOutcome {
+    Stored { etag: Option<String> },
+    /// The ETag precondition failed: the resource changed (or exists) remotely
+    PreconditionFailed,
+}
+
+pub struct CaldavClient {
+    http: reqwest::Client,
+    collection: Url,
+    username: String,
+    password: String,
+}
+
+impl CaldavClient {
+    pub fn new(collection_url: &str, username: &str, password: &str) -> Result<Self> {
+        let mut collection = Url::parse(collection_url).context("Invalid CalDAV collection URL")?;
+        // Relative hrefs must resolve inside the collection
+        if !collection.path().ends_with('/') {
+            collection.set_path(&format!("{}/", collection.path()));
+        }
+
+        Ok(Self {
+            http: reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build()?,
+            collection,
+            username: username.to_string(),
+            password: password.to_string(),
+        })
+    }
+
+    /// Href for a new resource in the collection
+    pub fn href_for(&self, name: &str) -> String {
+        format!("{}{}.ics", self.collection.path(), urlencode(name))
+    }
+
+    /// Changes since `sync_token` via WebDAV sync (RFC 6578). Returns `None` if
+    /// the server does not support it or rejected the token.
+    pub async fn sync_collection(&self, sync_token: Option<&str>) -> Result<Option<RemoteChanges>> {
+        let body = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<d:sync-collection xmlns:d="DAV:">
+  <d:sync-token>{}</d:sync-token>
+  <d:sync-level>1</d:sync-level>
+  <d:prop><d:getetag/></d:prop>
+</d:sync-collection>"#,
+            xml_escape(sync_token.unwrap_or_default())
+        );
+
+        let response = self
+            .request(Method::from_bytes(b"REPORT")?, self.collection.clone())
+            .header(header::CONTENT_TYPE, "application/xml; charset=utf-8")
+            .body(body)
+            .send()
+            .await?;
+        if response.status() != StatusCode::MULTI_STATUS {
+            tracing::info!("CalDAV sync-collection returned {}, falling back", response.status());
+            return Ok(None);
+        }
+
+        let xml = response.text().await?;
+        let doc = roxmltree::Document::parse(&xml).context("Invalid multistatus response")?;
+        let mut changes = RemoteChanges {
+            sync_token: doc
+                .descendants()
+                .find(|n| n.has_tag_name((DAV_NS, "sync-token")))
+                .and_then(|n| n.text())
+                .map(|t| t.trim().to_string()),
+            ..Default::default()
+        };
+
+        for entry in self.parse_responses(&doc) {
+            match entry {
+                (href, Some(status), _) if status == StatusCode::NOT_FOUND => changes.removed.push(href),
+                (href, _, etag) => changes.changed.push(RemoteResource { href, etag }),
+            }
+        }
+        Ok(Some(changes))
+    }
+
+    /// All resources in the collection (PROPFIND, depth 1)
+    pub async fn list(&self) -> Result<Vec<RemoteResource>> {
+        let body = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:"><d:prop><d:getetag/><d:resourcetype/></d:prop></d:propfind>"#;
+
+        let response = self
+            .request(Method::from_bytes(b"PROPFIND")?, self.collection.clone())
+            .header("Depth", "1")
+            .header(header::CONTENT_TYPE, "application/xml; charset=utf-8")
+            .body(body)
+            .send()
+            .await?;
+        if response.status() != StatusCode::MULTI_STATUS {
+            bail!("CalDAV PROPFIND failed with {}", response.status());
+        }
+
+        let xml = response.text().await?;
+        let doc = roxmltree::Document::parse(&xml).context("Invalid multistatus response")?;
+        Ok(self
+            .parse_responses(&doc)
+            .into_iter()
+            .map(|(href, _, etag)| RemoteResource { href, etag })
+            .collect())
+    }
+
+    /// Fetch a resource. Returns `None` if it no longer exists.
+    pub async fn get(&self, href: &str) -> Result<Option<(String, Option<String>)>> {
+        let response = self.request(Method::GET, self.resolve(href)?).send().await?;
+        match response.status() {
+            StatusCode::NOT_FOUND | StatusCode::GONE => Ok(None),
+            status if status.is_success() => {
+                let etag = etag_header(&response);
+                Ok(Some((response.text().await?, etag)))
+            }
+            status => bail!("CalDAV GET {href} failed with {status}"),
+        }
+    }
+
+    /// Upload a resource. With `etag` the upload only succeeds if the remote is
+    /// unchanged, without it only if the resource does not exist yet.
+    pub async fn put(&self, href: &str, body: String, etag: Option<&str>) -> Result<PutOutcome> {
+        let request = self
+            .request(Method::PUT, self.resolve(href)?)
+            .header(header::CONTENT_TYPE, "text/calendar; charset=utf-8")
+            .body(body);
+        let request = match etag {
+            Some(etag) => request.header(header::IF_MATCH, etag),
+            None => request.header(header::IF_NONE_MATCH, "*"),
+        };
+
+        let response = request.send().await?;
+        match response.status() {
+            StatusCode::PRECONDITION_FAILED => Ok(PutOutcome::PreconditionFailed),
+            status if status.is_success() => Ok(PutOutcome::Stored {
+                etag: etag_header(&response),
+            }),
+            status => bail!("CalDAV PUT {href} failed with {status}"),
+        }
+    }
+
+    /// Delete a resource if it is unchanged. Returns false if the ETag no longer matches.
+    pub async fn delete(&self, href: &str, etag: Option<&str>) -> Result<bool> {
+        let mut request = self.request(Method::DELETE, self.resolve(href)?);
+        if let Some(etag) = etag {
+            request = request.header(header::IF_MATCH, etag);
+        }
+
+        let response = request.send().await?;
+        match response.status() {
+            StatusCode::PRECONDITION_FAILED => Ok(false),
+            StatusCode::NOT_FOUND | StatusCode::GONE => Ok(true),
+            status if status.is_success() => Ok(true),
+            status => bail!("CalDAV DELETE {href} failed with {status}"),
+        }
+    }
+
+    fn request(&self, method: Method, url: Url) -> reqwest::RequestBuilder {
+        self.http
+            .request(method, url)
+            .basic_auth(&self.username, Some(&self.password))
+    }
+
+    fn resolve(&self, href: &str) -> Result<Url> {
+        Ok(self.collection.join(href)?)
+    }
+
+    /// (href, status, etag) of every member resource in a multistatus response
+    fn parse_responses(
+        &self,
+        doc: &roxmltree::Document,
+    ) -> Vec<(String, Option<StatusCode>, Option<String>)> {
+        let mut entries = Vec::new();
+
+        for response in doc
+            .descendants()
+            .filter(|n| n.has_tag_name((DAV_NS, "response")))
+        {
+            let Some(href) = child_text(response, "href") else {
+                continue;
+            };
+            // Skip the collection itself and sub-collections
+            let path = self.resolve(&href).map(|u| u.path().to_string()).unwrap_or_default();
+            if path.ends_with('/') {
+                continue;
+            }
+
+            let status = child_text(response, "status").and_then(|s| parse_status(&s));
+            let etag = response
+                .descendants()
+                .find(|n| n.has_tag_name((DAV_NS, "getetag")))
+                .and_then(|n| n.text())
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty());
+
+            entries.push((path, status, etag));
+        }
+
+        entries
+    }
+}
+
+fn child_text(node: roxmltree::Node, name: &str) -> Option<String> {
+    node.children()
+        .find(|n| n.has_tag_name((DAV_NS, name)))
+        .and_then(|n| n.text())
+        .map(|t| t.trim().to_string())
+}
+
+/// Parse an HTTP status line such as `HTTP/1.1 404 Not Found`
+fn parse_status(line: &str) -> Option<StatusCode> {
+    line.split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok())
+        .and_then(|code| StatusCode::from_u16(code).ok())
+}
+
+fn etag_header(response: &reqwest::Response) -> Option<String> {
+    response
+        .headers()
+        .get(header::ETAG)
+        .and_then(|v| v.to_str().ok())
+        .map(String::from)
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn urlencode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            other => format!("%{other:02X}"),
+        })
+        .collect()
+}
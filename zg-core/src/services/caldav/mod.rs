@@ -0,0 +1,328 @@
+mod client;
+
+use client::{CaldavClient, PutOutcome, RemoteChanges};
+
+use crate::db::{
+    models::*,
+    repositories::{CaldavRepository, TaskRepository},
+    DbPool,
+};
+use crate::formats::ics::{self, Component, IcsDateTime};
+use crate::services::ics_service;
+use crate::services::access::{Access, Role};
+use crate::services::{EventBus, TaskService};
+use anyhow::{Context, Result};
+use chrono::Utc;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Two-way sync of tasks with a CalDAV task list (VTODO collection).
+///
+/// Remote changes are detected through ETags, using WebDAV sync tokens when
+/// the server supports them and a full listing otherwise. Local changes are
+/// detected by comparing a fingerprint of the synced fields with the one
+/// stored at the last sync. If a task changed on both sides, the remote
+/// version is applied and the local version is kept as a separate task.
+pub struct CaldavSyncService {
+    tasks: TaskRepository,
+    task_service: TaskService,
+    caldav: CaldavRepository,
+    access: Access,
+}
+
+impl CaldavSyncService {
+    pub fn new(pool: DbPool, events: Arc<EventBus>) -> Self {
+        Self {
+            access: Access::new(pool.clone()),
+            tasks: TaskRepository::new(pool.clone()),
+            task_service: TaskService::new(pool.clone(), events),
+            caldav: CaldavRepository::new(pool),
+        }
+    }
+
+    pub fn get_config(&self) -> Result<Option<CaldavConfig>> {
+        self.access.require(Role::Viewer)?;
+        self.caldav.get_config()
+    }
+
+    pub fn configure(&self, input: CaldavConfigInput) -> Result<CaldavConfig> {
+        self.access.require(Role::Editor)?;
+        // Fail early on malformed URLs
+        CaldavClient::new(&input.collection_url, &input.username, &input.password)?;
+        self.caldav.save_config(&input)
+    }
+
+    pub fn disconnect(&self) -> Result<()> {
+        self.access.require(Role::Editor)?;
+        self.caldav.clear_config()
+    }
+
+    /// Pull remote changes, then push local ones
+    pub async fn sync(&self) -> Result<CaldavSyncReport> {
+        self.access.require(Role::Editor)?;
+        let config = self
+            .caldav
+            .get_config()?
+            .context("CalDAV sync is not configured")?;
+        let client = CaldavClient::new(&config.collection_url, &config.username, &config.password)?;
+        let mut report = CaldavSyncReport::default();
+
+        let sync_token = self.pull(&client, config.sync_token.as_deref(), &mut report).await?;
+        self.push(&client, &mut report).await?;
+        self.caldav.finish_sync(sync_token.as_deref())?;
+
+        tracing::info!("CalDAV sync finished: {:?}", report);
+        Ok(report)
+    }
+
+    async fn pull(
+        &self,
+        client: &CaldavClient,
+        sync_token: Option<&str>,
+        report: &mut CaldavSyncReport,
+    ) -> Result<Option<String>> {
+        let states: HashMap<String, CaldavSyncState> = self
+            .caldav
+            .get_states()?
+            .into_iter()
+            .map(|s| (s.href.clone(), s))
+            .collect();
+
+        let changes = match client.sync_collection(sync_token).await? {
+            Some(changes) => changes,
+            None => {
+                // No (valid) sync token support: diff a full listing instead
+                let listed = client.list().await?;
+                let listed_hrefs: HashSet<&str> = listed.iter().map(|r| r.href.as_str()).collect();
+                RemoteChanges {
+                    removed: states
+                        .keys()
+                        .filter(|href| !listed_hrefs.contains(href.as_str()))
+                        .cloned()
+                        .collect(),
+                    changed: listed,
+                    sync_token: None,
+                }
+            }
+        };
+
+        let mut parents = Vec::new();
+        for resource in &changes.changed {
+            let state = states.get(&resource.href);
+            if resource.etag.is_some() && state.and_then(|s| s.etag.as_ref()) == resource.etag.as_ref() {
+                continue;
+            }
+
+            let Some((body, etag)) = client.get(&resource.href).await? else {
+                continue;
+            };
+            let Some(todo) = first_vtodo(&body)? else {
+                continue;
+            };
+            let etag = etag.or_else(|| resource.etag.clone());
+
+            let task = self.apply_remote(&resource.href, state, &todo, report)?;
+            self.caldav.upsert_state(&CaldavSyncState {
+                task_id: task.id,
+                href: resource.href.clone(),
+                etag,
+                fingerprint: self.fingerprint(&task)?,
+            })?;
+            if let Some(parent_uid) = ics_service::parent_uid(&todo) {
+                parents.push((task.id, parent_uid));
+            }
+        }
+
+        for href in &changes.removed {
+            let Some(state) = states.get(href) else {
+                continue;
+            };
+            self.caldav.delete_state(state.task_id)?;
+
+            let Ok(task) = self.tasks.get_by_id(state.task_id) else {
+                continue;
+            };
+            if self.fingerprint(&task)? == state.fingerprint {
+                self.task_service.delete_task(task.id)?;
+                report.pulled_deleted += 1;
+            }
+            // Otherwise the local edit wins and the task is uploaded again
+        }
+
+        for (task_id, parent_uid) in parents {
+            if let Some(parent) = self.tasks.find_by_uid(&parent_uid)? {
+                let mut task = self.tasks.get_by_id(task_id)?;
+                if parent.id != task.id && task.parent_task_id != Some(parent.id) {
+                    task.parent_task_id = Some(parent.id);
+                    let task = self.task_service.overwrite_task(task_id, &task)?;
+                    self.refresh_fingerprint(&task)?;
+                }
+            }
+        }
+
+        Ok(changes.sync_token)
+    }
+
+    /// Apply a changed remote VTODO and return the resulting local task
+    fn apply_remote(
+        &self,
+        href: &str,
+        state: Option<&CaldavSyncState>,
+        todo: &Component,
+        report: &mut CaldavSyncReport,
+    ) -> Result<Task> {
+        let uid = todo.get("UID").map(|p| p.text());
+        let local = match state {
+            Some(state) => self.tasks.get_by_id(state.task_id).ok(),
+            None => match &uid {
+                Some(uid) => self.tasks.find_by_uid(uid)?,
+                None => None,
+            },
+        };
+
+        let Some(task) = local else {
+            // New remotely, or changed remotely after a local delete
+            report.pulled_created += 1;
+            return self.task_service.import_task(uid.as_deref(), |task| {
+                ics_service::apply_component(task, todo);
+                Ok(())
+            });
+        };
+
+        let changed_locally = match state {
+            Some(state) => self.fingerprint(&task)? != state.fingerprint,
+            None => false,
+        };
+        if changed_locally {
+            self.keep_conflict_copy(&task)?;
+            report.conflicts += 1;
+            tracing::warn!("CalDAV conflict on {href}, kept local version as a copy");
+        }
+
+        let mut updated = task.clone();
+        ics_service::apply_component(&mut updated, todo);
+        report.pulled_updated += 1;
+        self.task_service.overwrite_task(task.id, &updated)
+    }
+
+    /// Save the local version of a conflicting task as a new task
+    fn keep_conflict_copy(&self, task: &Task) -> Result<Task> {
+        self.task_service.import_task(None, |copy| {
+            *copy = Task {
+                id: copy.id,
+                uid: copy.uid.clone(),
+                title: format!("{} (conflict)", task.title),
+                ..task.clone()
+            };
+            Ok(())
+        })
+    }
+
+    async fn push(&self, client: &CaldavClient, report: &mut CaldavSyncReport) -> Result<()> {
+        let tasks = self.tasks.get_all(None)?;
+        let uids: HashMap<i64, String> = tasks.iter().map(|t| (t.id, t.uid.clone())).collect();
+        let states: HashMap<i64, CaldavSyncState> = self
+            .caldav
+            .get_states()?
+            .into_iter()
+            .map(|s| (s.task_id, s))
+            .collect();
+        let now = IcsDateTime::Utc(Utc::now());
+
+        for task in &tasks {
+            let fingerprint = self.fingerprint(task)?;
+            let state = states.get(&task.id);
+            if state.is_some_and(|s| s.fingerprint == fingerprint) {
+                continue;
+            }
+
+            let mut calendar = ics_service::new_calendar();
+            calendar.components.push(ics_service::task_to_vtodo(task, &uids, &now));
+            let body = ics::serialize(&calendar);
+
+            let href = match state {
+                Some(state) => state.href.clone(),
+                None => client.href_for(&task.uid),
+            };
+            let etag = state.and_then(|s| s.etag.as_deref());
+            // A synced resource without a known ETag is overwritten unconditionally
+            let outcome = match (state, etag) {
+                (Some(_), None) => client.put(&href, body, Some("*")).await?,
+                _ => client.put(&href, body, etag).await?,
+            };
+
+            match outcome {
+                PutOutcome::Stored { etag } => {
+                    self.caldav.upsert_state(&CaldavSyncState {
+                        task_id: task.id,
+                        href,
+                        etag,
+                        fingerprint,
+                    })?;
+                    if state.is_some() {
+                        report.pushed_updated += 1;
+                    } else {
+                        report.pushed_created += 1;
+                    }
+                }
+                PutOutcome::PreconditionFailed => report.deferred += 1,
+            }
+        }
+
+        // Synced tasks that were deleted locally
+        let task_ids: HashSet<i64> = tasks.iter().map(|t| t.id).collect();
+        for state in states.values().filter(|s| !task_ids.contains(&s.task_id)) {
+            if client.delete(&state.href, state.etag.as_deref()).await? {
+                self.caldav.delete_state(state.task_id)?;
+                report.pushed_deleted += 1;
+            } else {
+                report.deferred += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fingerprint of the fields that are synced, to detect local changes
+    fn fingerprint(&self, task: &Task) -> Result<String> {
+        let parent_uid = match task.parent_task_id {
+            Some(id) => self.tasks.get_by_id(id).ok().map(|p| p.uid),
+            None => None,
+        };
+
+        Ok(serde_json::json!([
+            task.title,
+            task.description,
+            task.status,
+            task.priority,
+            task.scheduled_date,
+            task.due_date,
+            task.completed_at,
+            task.tags,
+            parent_uid,
+        ])
+        .to_string())
+    }
+
+    fn refresh_fingerprint(&self, task: &Task) -> Result<()> {
+        if let Some(mut state) = self
+            .caldav
+            .get_states()?
+            .into_iter()
+            .find(|s| s.task_id == task.id)
+        {
+            state.fingerprint = self.fingerprint(task)?;
+            self.caldav.upsert_state(&state)?;
+        }
+        Ok(())
+    }
+}
+
+fn first_vtodo(body: &str) -> Result<Option<Component>> {
+    let roots = ics::parse(body)?;
+    Ok(roots
+        .iter()
+        .flat_map(|root| root.find_all("VTODO"))
+        .next()
+        .cloned())
+}
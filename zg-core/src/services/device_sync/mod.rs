@@ -0,0 +1,549 @@
+mod hlc;
+mod oplog;
+
+pub use hlc::Hlc;
+use oplog::{Operation, DELETED};
+
+use crate::db::{
+    models::*,
+    repositories::{device_sync_repository::FieldState, DeviceSyncRepository},
+    DbPool,
+};
+use crate::services::access::{Access, Role};
+use crate::services::event_bus::{status_events, DomainEvent, EventBus};
+use anyhow::{Context, Result};
+use chrono::{NaiveDateTime, Utc};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use rusqlite::Connection;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+
+/// Quiet period after a change in the folder before syncing, so a log
+/// arriving in several writes only triggers one sync
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// How often a watched folder is synced without changes in it, to send the
+/// local changes
+const SYNC_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Sync of tasks between devices through a shared folder, e.g. one kept in
+/// sync by Syncthing, without ever copying the database file.
+///
+/// Every device appends its changes, one task field at a time, to its own
+/// log in the folder and merges the logs of the others. Changes carry hybrid
+/// logical clock timestamps and every field keeps its latest change, so all
+/// devices end up with the same tasks whatever order they merge in. Local
+/// changes are found by comparing the tasks with the field values of the
+/// last sync and are stamped with the task's last update. A deletion wins
+/// over changes from other devices.
+pub struct DeviceSyncService {
+    pool: DbPool,
+    repository: DeviceSyncRepository,
+    access: Access,
+    events: Arc<EventBus>,
+    watcher: Mutex<Option<RecommendedWatcher>>,
+}
+
+type FieldStates = HashMap<(String, String), FieldState>;
+
+impl DeviceSyncService {
+    pub fn new(pool: DbPool, events: Arc<EventBus>) -> Self {
+        Self {
+            access: Access::new(pool.clone()),
+            repository: DeviceSyncRepository::new(pool.clone()),
+            pool,
+            events,
+            watcher: Mutex::new(None),
+        }
+    }
+
+    pub fn get_config(&self) -> Result<Option<DeviceSyncConfig>> {
+        self.access.require(Role::Viewer)?;
+        self.repository.get_config()
+    }
+
+    /// Sync the tasks of the active workspace through `folder`. Every setup
+    /// joins as a new device and sends all tasks on the first sync.
+    pub fn configure(&self, folder: &Path) -> Result<DeviceSyncConfig> {
+        self.access.require(Role::Editor)?;
+        std::fs::create_dir_all(folder)
+            .with_context(|| format!("Cannot create the sync folder {}", folder.display()))?;
+        self.unwatch();
+        self.repository.save_config(&folder.to_string_lossy())
+    }
+
+    pub fn disconnect(&self) -> Result<()> {
+        self.access.require(Role::Editor)?;
+        self.unwatch();
+        self.repository.clear_config()
+    }
+
+    /// Send the local changes, then merge those of the other devices
+    pub fn sync(&self) -> Result<DeviceSyncReport> {
+        let config = self.repository.get_config()?.context("Device sync is not set up")?;
+        self.access.require_in(config.workspace_id, Role::Editor)?;
+        let folder = PathBuf::from(&config.folder);
+        if !folder.is_dir() {
+            anyhow::bail!("The sync folder {} does not exist", folder.display());
+        }
+
+        let report = self.events.transaction(&self.pool, |tx, events| {
+            let mut clock = match DeviceSyncRepository::get_clock_in(tx)? {
+                Some(clock) => clock.parse()?,
+                None => Hlc::zero(&config.device_id),
+            };
+            let mut fields = DeviceSyncRepository::field_states_in(tx)?;
+            let mut report = DeviceSyncReport::default();
+
+            let sent = capture(tx, &mut clock, &mut fields)?;
+            oplog::append(&oplog::log_path(&folder, &config.device_id), &sent)?;
+            report.sent = sent.len();
+
+            let mut received = Vec::new();
+            for (device, path) in oplog::other_logs(&folder, &config.device_id)? {
+                let merged = DeviceSyncRepository::get_cursor_in(tx, &device)?;
+                let (operations, lines) = oplog::read_after(&path, merged)?;
+                received.extend(operations);
+                DeviceSyncRepository::save_cursor_in(tx, &device, lines)?;
+            }
+            report.received = received.len();
+
+            // In clock order, but parents last: a subtask may come before its
+            // parent, which has to exist for the subtask to point to it
+            let mut received: Vec<(Hlc, Operation)> = received
+                .into_iter()
+                .filter_map(|operation| match operation.clock.parse() {
+                    Ok(stamp) => Some((stamp, operation)),
+                    Err(e) => {
+                        tracing::warn!("Skipping synced change: {e:#}");
+                        None
+                    }
+                })
+                .collect();
+            received.sort_by(|a, b| {
+                let parents = (a.1.field == "parent").cmp(&(b.1.field == "parent"));
+                parents.then(a.0.cmp(&b.0))
+            });
+
+            let mut previous = Vec::new();
+            let mut seen = HashSet::new();
+            for (_, operation) in &received {
+                if seen.insert(operation.uid.as_str()) {
+                    previous.push((operation.uid.as_str(), DeviceSyncRepository::task_in(tx, &operation.uid)?));
+                }
+            }
+            for (stamp, operation) in &received {
+                clock = clock.observe(stamp);
+                merge(tx, operation, &mut fields, &mut report)?;
+            }
+            for (uid, previous) in previous {
+                events.extend(task_events(previous, DeviceSyncRepository::task_in(tx, uid)?));
+            }
+
+            DeviceSyncRepository::finish_sync_in(tx, &clock.to_string())?;
+            Ok(report)
+        })?;
+
+        tracing::info!(
+            "Device sync: {} sent, {} received, {} applied, {} conflicts",
+            report.sent,
+            report.received,
+            report.applied,
+            report.conflicts
+        );
+        Ok(report)
+    }
+
+    /// Sync once, then whenever another device's log changes and every
+    /// minute. Replaces any previous watch.
+    pub fn watch(self: &Arc<Self>) -> Result<()> {
+        self.sync()?;
+        let config = self.repository.get_config()?.context("Device sync is not set up")?;
+        let own_log = oplog::log_path(Path::new(&config.folder), &config.device_id);
+
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            if let Ok(event) = event {
+                if event.paths.iter().any(|p| *p != own_log) {
+                    let _ = tx.send(());
+                }
+            }
+        })?;
+        watcher.watch(Path::new(&config.folder), RecursiveMode::NonRecursive)?;
+
+        // The thread ends when the watcher (and with it the sender) is dropped
+        let service = Arc::clone(self);
+        std::thread::spawn(move || loop {
+            match rx.recv_timeout(SYNC_INTERVAL) {
+                Ok(()) => while rx.recv_timeout(WATCH_DEBOUNCE).is_ok() {},
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+            if let Err(e) = service.sync() {
+                tracing::error!("Device sync failed: {e:#}");
+            }
+        });
+
+        *self.watcher.lock().unwrap() = Some(watcher);
+        Ok(())
+    }
+
+    /// Stop watching the sync folder, if watched
+    pub fn unwatch(&self) {
+        self.watcher.lock().unwrap().take();
+    }
+}
+
+/// Operations for the fields that changed since the last sync, and for
+/// deleted tasks
+fn capture(conn: &Connection, clock: &mut Hlc, fields: &mut FieldStates) -> Result<Vec<Operation>> {
+    let mut operations = Vec::new();
+    let mut present = HashSet::new();
+
+    for task in DeviceSyncRepository::tasks_in(conn)? {
+        present.insert(task.uid.clone());
+        if fields.contains_key(&(task.uid.clone(), DELETED.to_string())) {
+            continue;
+        }
+        let changed_at = changed_millis(&task.updated_at);
+        for (field, value) in task.fields {
+            let key = (task.uid.clone(), field);
+            let text = value.to_string();
+            if fields.get(&key).is_some_and(|state| state.value == text) {
+                continue;
+            }
+            *clock = clock.tick(changed_at);
+            operations.push(record(conn, fields, key, value, clock)?);
+        }
+    }
+
+    let deleted: BTreeSet<String> = fields
+        .keys()
+        .map(|(uid, _)| uid.clone())
+        .filter(|uid| !present.contains(uid) && !fields.contains_key(&(uid.clone(), DELETED.to_string())))
+        .collect();
+    for uid in deleted {
+        *clock = clock.tick(Utc::now().timestamp_millis());
+        operations.push(record(conn, fields, (uid.clone(), DELETED.to_string()), true.into(), clock)?);
+        forget(conn, fields, &uid)?;
+    }
+
+    Ok(operations)
+}
+
+/// Remember a local change as synced and return its operation
+fn record(
+    conn: &Connection,
+    fields: &mut FieldStates,
+    (uid, field): (String, String),
+    value: serde_json::Value,
+    clock: &Hlc,
+) -> Result<Operation> {
+    let state = FieldState {
+        value: value.to_string(),
+        clock: clock.to_string(),
+    };
+    DeviceSyncRepository::save_field_in(conn, &uid, &field, &state)?;
+    fields.insert((uid.clone(), field.clone()), state);
+    Ok(Operation {
+        clock: clock.to_string(),
+        uid,
+        field,
+        value,
+    })
+}
+
+/// Apply an operation from another device unless the field changed later
+fn merge(
+    conn: &Connection,
+    operation: &Operation,
+    fields: &mut FieldStates,
+    report: &mut DeviceSyncReport,
+) -> Result<()> {
+    let uid = &operation.uid;
+    if fields.contains_key(&(uid.clone(), DELETED.to_string())) {
+        return Ok(());
+    }
+
+    let key = (uid.clone(), operation.field.clone());
+    let value = operation.value.to_string();
+    if let Some(state) = fields.get(&key) {
+        // The text form of clocks sorts like the clocks
+        if state.clock >= operation.clock {
+            if state.value != value {
+                report.conflicts += 1;
+            }
+            return Ok(());
+        }
+    }
+
+    if operation.field == DELETED {
+        DeviceSyncRepository::delete_task_in(conn, uid)?;
+        forget(conn, fields, uid)?;
+    } else {
+        DeviceSyncRepository::apply_field_in(conn, uid, &operation.field, &operation.value)?;
+    }
+    let state = FieldState {
+        value,
+        clock: operation.clock.clone(),
+    };
+    DeviceSyncRepository::save_field_in(conn, uid, &operation.field, &state)?;
+    fields.insert(key, state);
+    report.applied += 1;
+    Ok(())
+}
+
+/// The events of a task merged from `previous` into `task`
+fn task_events(previous: Option<Task>, task: Option<Task>) -> Vec<DomainEvent> {
+    match (previous, task) {
+        (None, Some(task)) => vec![DomainEvent::TaskCreated(task)],
+        (Some(previous), Some(task)) if previous.version != task.version => {
+            let mut events = vec![DomainEvent::TaskUpdated(task.clone())];
+            events.extend(status_events(&previous, &task));
+            events
+        }
+        (Some(previous), None) => vec![DomainEvent::TaskDeleted(previous)],
+        _ => Vec::new(),
+    }
+}
+
+/// Drop the field states of a deleted task, keeping its deletion
+fn forget(conn: &Connection, fields: &mut FieldStates, uid: &str) -> Result<()> {
+    DeviceSyncRepository::forget_fields_in(conn, uid, DELETED)?;
+    fields.retain(|(field_uid, field), _| field_uid != uid || field == DELETED);
+    Ok(())
+}
+
+/// Milliseconds of a task's `updated_at` (UTC), or now if malformed
+fn changed_millis(updated_at: &str) -> i64 {
+    NaiveDateTime::parse_from_str(updated_at, "%Y-%m-%d %H:%M:%S")
+        .map(|at| at.and_utc().timestamp_millis())
+        .unwrap_or_else(|_| Utc::now().timestamp_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_database_at;
+    use rusqlite::OptionalExtension;
+
+    /// A device with its own database, syncing through a shared folder
+    struct Device {
+        pool: DbPool,
+        sync: DeviceSyncService,
+    }
+
+    impl Device {
+        fn new(dir: &Path, name: &str) -> Self {
+            let pool = init_database_at(&dir.join(format!("{name}.db"))).unwrap();
+            let sync = DeviceSyncService::new(pool.clone(), Arc::new(EventBus::new()));
+            sync.configure(&dir.join("shared")).unwrap();
+            Self { pool, sync }
+        }
+
+        fn execute(&self, sql: &str, params: impl rusqlite::Params) {
+            self.pool.get().unwrap().execute(sql, params).unwrap();
+        }
+
+        fn add(&self, uid: &str, title: &str, updated_at: &str) {
+            self.execute(
+                "INSERT INTO tasks (uid, title, updated_at) VALUES (?1, ?2, ?3)",
+                [uid, title, updated_at],
+            );
+        }
+
+        fn field(&self, uid: &str, column: &str) -> Option<String> {
+            self.pool
+                .get()
+                .unwrap()
+                .query_row(
+                    &format!("SELECT CAST({column} AS TEXT) FROM tasks WHERE uid = ?1"),
+                    [uid],
+                    |row| row.get(0),
+                )
+                .optional()
+                .unwrap()
+                .flatten()
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("zg-device-sync-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn syncs_tasks_between_two_devices() {
+        let dir = temp_dir("two");
+        let laptop = Device::new(&dir, "laptop");
+        let desktop = Device::new(&dir, "desktop");
+
+        laptop.add("move", "Move house", "2030-01-01 10:00:00");
+        laptop.add("pack", "Pack books", "2030-01-01 10:00:00");
+        laptop.execute("INSERT INTO projects (name, color) VALUES ('Home', '#fff')", []);
+        laptop.execute(
+            "UPDATE tasks SET parent_task_id = (SELECT id FROM tasks WHERE uid = 'move'),
+                              project_id = (SELECT id FROM projects WHERE name = 'Home')
+             WHERE uid = 'pack'",
+            [],
+        );
+        let sent = laptop.sync.sync().unwrap();
+        assert!(sent.sent > 0);
+
+        let received = desktop.sync.sync().unwrap();
+        assert_eq!(received.received, sent.sent);
+        assert_eq!(desktop.field("pack", "title").as_deref(), Some("Pack books"));
+        let parent = desktop.field("pack", "(SELECT uid FROM tasks p WHERE p.id = tasks.parent_task_id)");
+        assert_eq!(parent.as_deref(), Some("move"));
+        let project = desktop.field("pack", "(SELECT name FROM projects p WHERE p.id = tasks.project_id)");
+        assert_eq!(project.as_deref(), Some("Home"));
+
+        // Merged changes are not sent back
+        assert_eq!(desktop.sync.sync().unwrap().sent, 0);
+
+        desktop.execute(
+            "UPDATE tasks SET status = 'completed', updated_at = '2030-01-02 08:00:00' WHERE uid = 'pack'",
+            [],
+        );
+        assert_eq!(desktop.sync.sync().unwrap().sent, 1);
+        assert_eq!(laptop.sync.sync().unwrap().applied, 1);
+        assert_eq!(laptop.field("pack", "status").as_deref(), Some("completed"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn keeps_subtasks_of_parents_sent_later() {
+        let dir = temp_dir("parents");
+        let laptop = Device::new(&dir, "laptop");
+        let desktop = Device::new(&dir, "desktop");
+
+        // The subtask has the lower ID, so its parent is sent after it
+        laptop.add("pack", "Pack books", "2030-01-01 10:00:00");
+        laptop.add("move", "Move house", "2030-01-01 10:00:00");
+        laptop.execute(
+            "UPDATE tasks SET parent_task_id = (SELECT id FROM tasks WHERE uid = 'move') WHERE uid = 'pack'",
+            [],
+        );
+        laptop.sync.sync().unwrap();
+        desktop.sync.sync().unwrap();
+        assert_eq!(desktop.sync.sync().unwrap().sent, 0);
+        laptop.sync.sync().unwrap();
+
+        for device in [&laptop, &desktop] {
+            let parent = device.field("pack", "(SELECT uid FROM tasks p WHERE p.id = tasks.parent_task_id)");
+            assert_eq!(parent.as_deref(), Some("move"));
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn publishes_merged_changes() {
+        let dir = temp_dir("events");
+        let laptop = Device::new(&dir, "laptop");
+        let events = Arc::new(EventBus::new());
+        let published = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&published);
+        events.subscribe("log", move |_, event| {
+            log.lock().unwrap().push(event.to_string());
+            Ok(())
+        });
+        let pool = init_database_at(&dir.join("desktop.db")).unwrap();
+        let desktop = DeviceSyncService::new(pool.clone(), events);
+        desktop.configure(&dir.join("shared")).unwrap();
+
+        laptop.add("report", "Write report", "2030-01-01 10:00:00");
+        laptop.sync.sync().unwrap();
+        desktop.sync().unwrap();
+        laptop.execute("UPDATE tasks SET status = 'completed', updated_at = '2030-01-02 10:00:00'", []);
+        laptop.sync.sync().unwrap();
+        desktop.sync().unwrap();
+        laptop.execute("DELETE FROM tasks", []);
+        laptop.sync.sync().unwrap();
+        desktop.sync().unwrap();
+
+        assert_eq!(
+            *published.lock().unwrap(),
+            [
+                "task 1 created",
+                "task 1 updated",
+                "task 1 status todo -> completed",
+                "task 1 completed",
+                "task 1 deleted",
+            ]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn resolves_concurrent_changes_per_field() {
+        let dir = temp_dir("conflicts");
+        let laptop = Device::new(&dir, "laptop");
+        let desktop = Device::new(&dir, "desktop");
+        laptop.add("report", "Write report", "2030-01-01 10:00:00");
+        laptop.sync.sync().unwrap();
+        desktop.sync.sync().unwrap();
+
+        // Both edit the title, each device also edits another field
+        laptop.execute(
+            "UPDATE tasks SET title = 'Write the report', priority = 3, updated_at = '2030-01-01 11:00:00'",
+            [],
+        );
+        desktop.execute(
+            "UPDATE tasks SET title = 'Write Q3 report', description = 'Numbers from finance',
+                              updated_at = '2030-01-01 12:00:00'",
+            [],
+        );
+        laptop.sync.sync().unwrap();
+        desktop.sync.sync().unwrap();
+        let merged = laptop.sync.sync().unwrap();
+        assert_eq!(merged.conflicts, 0);
+
+        for device in [&laptop, &desktop] {
+            assert_eq!(device.field("report", "title").as_deref(), Some("Write Q3 report"));
+            assert_eq!(device.field("report", "priority").as_deref(), Some("3"));
+            assert_eq!(device.field("report", "description").as_deref(), Some("Numbers from finance"));
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn deletions_win_over_concurrent_changes() {
+        let dir = temp_dir("deletions");
+        let laptop = Device::new(&dir, "laptop");
+        let desktop = Device::new(&dir, "desktop");
+        laptop.add("old", "Old errand", "2030-01-01 10:00:00");
+        laptop.sync.sync().unwrap();
+        desktop.sync.sync().unwrap();
+
+        laptop.execute("DELETE FROM tasks WHERE uid = 'old'", []);
+        desktop.execute("UPDATE tasks SET title = 'Renamed', updated_at = '2031-01-01 10:00:00'", []);
+        laptop.sync.sync().unwrap();
+        desktop.sync.sync().unwrap();
+        laptop.sync.sync().unwrap();
+
+        assert_eq!(laptop.field("old", "title"), None);
+        assert_eq!(desktop.field("old", "title"), None);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn leaves_a_partly_written_line_for_later() {
+        let dir = temp_dir("partial");
+        let log = dir.join("other.jsonl");
+        std::fs::write(
+            &log,
+            "{\"clock\":\"000000000000001-00000000-other\",\"uid\":\"a\",\"field\":\"title\",\"value\":\"A\"}\n{\"clock\":",
+        )
+        .unwrap();
+
+        let (operations, lines) = oplog::read_after(&log, 0).unwrap();
+        assert_eq!(operations.len(), 1);
+        assert_eq!(lines, 1);
+        assert!(oplog::read_after(&log, lines).unwrap().0.is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
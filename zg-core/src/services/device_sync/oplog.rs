@@ -0,0 +1,81 @@
+//! The log files in the shared folder: one per device, `<device id>.jsonl`,
+//! only ever appended to by that device. Every line is one operation.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Field of a deletion; a deleted task is never brought back
+pub const DELETED: &str = "deleted";
+
+/// A change of one field of a task, or its deletion
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Operation {
+    /// Hybrid logical clock timestamp of the change
+    pub clock: String,
+    pub uid: String,
+    pub field: String,
+    pub value: serde_json::Value,
+}
+
+pub fn log_path(folder: &Path, device_id: &str) -> PathBuf {
+    folder.join(format!("{device_id}.jsonl"))
+}
+
+/// Logs of all devices in the folder other than `device_id`
+pub fn other_logs(folder: &Path, device_id: &str) -> Result<Vec<(String, PathBuf)>> {
+    let mut logs = Vec::new();
+    for entry in std::fs::read_dir(folder)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("jsonl") {
+            continue;
+        }
+        let Some(device) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        if device != device_id {
+            logs.push((device.to_string(), path.clone()));
+        }
+    }
+    logs.sort();
+    Ok(logs)
+}
+
+pub fn append(path: &Path, operations: &[Operation]) -> Result<()> {
+    if operations.is_empty() {
+        return Ok(());
+    }
+    let mut lines = String::new();
+    for operation in operations {
+        lines.push_str(&serde_json::to_string(operation)?);
+        lines.push('\n');
+    }
+
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(lines.as_bytes())?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Operations after the first `skip` lines, and the number of lines read in
+/// total. A last line without newline may still be arriving and is left for
+/// the next read; malformed lines are skipped.
+pub fn read_after(path: &Path, skip: usize) -> Result<(Vec<Operation>, usize)> {
+    let content = std::fs::read_to_string(path)?;
+    let complete = content.rfind('\n').map_or("", |end| &content[..=end]);
+
+    let mut operations = Vec::new();
+    let mut lines = 0;
+    for line in complete.lines() {
+        lines += 1;
+        if lines <= skip || line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(operation) => operations.push(operation),
+            Err(e) => tracing::warn!("Skipping malformed line {lines} of {}: {e}", path.display()),
+        }
+    }
+    Ok((operations, lines.max(skip)))
+}
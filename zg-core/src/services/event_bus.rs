@@ -0,0 +1,311 @@
+//! In-process bus for domain events.
+//!
+//! Services publish events from inside the transaction of the change. Sync
+//! subscribers run in that transaction, each in its own savepoint, so their
+//! writes are atomic with the change. Async subscribers get the events after
+//! the commit, in order, on a worker of their own. A failing or panicking
+//! subscriber is logged and affects neither the change nor other subscribers.
+
+use crate::db::{
+    models::{Preferences, Task},
+    DbPool,
+};
+use anyhow::Result;
+use rusqlite::{Connection, Transaction};
+use std::fmt;
+use std::future::Future;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::RwLock;
+use tokio::sync::mpsc;
+
+#[derive(Debug, Clone)]
+pub enum DomainEvent {
+    TaskCreated(Task),
+    /// Any change to a task other than a move or deletion
+    TaskUpdated(Task),
+    /// Moved to another Kanban column or position
+    TaskMoved(Task),
+    TaskStatusChanged { task: Task, previous_status: String },
+    TaskCompleted(Task),
+    /// Carries the task as it was before deletion
+    TaskDeleted(Task),
+    PreferencesChanged(Preferences),
+}
+
+impl DomainEvent {
+    /// The task of a task event
+    pub fn task(&self) -> Option<&Task> {
+        match self {
+            DomainEvent::TaskCreated(task)
+            | DomainEvent::TaskUpdated(task)
+            | DomainEvent::TaskMoved(task)
+            | DomainEvent::TaskStatusChanged { task, .. }
+            | DomainEvent::TaskCompleted(task)
+            | DomainEvent::TaskDeleted(task) => Some(task),
+            DomainEvent::PreferencesChanged(_) => None,
+        }
+    }
+
+    /// Name of the matching frontend event. Status changes and completions
+    /// come with an update or move event and have none of their own.
+    pub fn frontend_name(&self) -> Option<&'static str> {
+        match self {
+            DomainEvent::TaskCreated(_) => Some("task:created"),
+            DomainEvent::TaskUpdated(_) => Some("task:updated"),
+            DomainEvent::TaskMoved(_) => Some("task:moved"),
+            DomainEvent::TaskDeleted(_) => Some("task:deleted"),
+            DomainEvent::PreferencesChanged(_) => Some("preferences:changed"),
+            DomainEvent::TaskStatusChanged { .. } | DomainEvent::TaskCompleted(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for DomainEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let id = self.task().map_or(0, |task| task.id);
+        match self {
+            DomainEvent::TaskCreated(_) => write!(f, "task {id} created"),
+            DomainEvent::TaskUpdated(_) => write!(f, "task {id} updated"),
+            DomainEvent::TaskMoved(_) => write!(f, "task {id} moved"),
+            DomainEvent::TaskStatusChanged { task, previous_status } => {
+                write!(f, "task {id} status {previous_status} -> {}", task.status)
+            }
+            DomainEvent::TaskCompleted(_) => write!(f, "task {id} completed"),
+            DomainEvent::TaskDeleted(_) => write!(f, "task {id} deleted"),
+            DomainEvent::PreferencesChanged(_) => write!(f, "preferences changed"),
+        }
+    }
+}
+
+type SyncHandler = Box<dyn Fn(&Connection, &DomainEvent) -> Result<()> + Send + Sync>;
+type AsyncResult = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+
+struct SyncSubscriber {
+    name: String,
+    handler: SyncHandler,
+}
+
+#[derive(Default)]
+pub struct EventBus {
+    sync_subscribers: RwLock<Vec<SyncSubscriber>>,
+    async_subscribers: RwLock<Vec<mpsc::UnboundedSender<DomainEvent>>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `handler` inside the transaction of every change
+    pub fn subscribe(
+        &self,
+        name: &str,
+        handler: impl Fn(&Connection, &DomainEvent) -> Result<()> + Send + Sync + 'static,
+    ) {
+        self.sync_subscribers.write().unwrap().push(SyncSubscriber {
+            name: name.to_string(),
+            handler: Box::new(handler),
+        });
+    }
+
+    /// Run `handler` after every committed change, one event at a time
+    pub fn subscribe_async<F, Fut>(&self, name: &str, handler: F)
+    where
+        F: Fn(DomainEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let (tx, mut rx) = mpsc::unbounded_channel::<DomainEvent>();
+        let handler = move |event| -> AsyncResult { Box::pin(handler(event)) };
+        let name = name.to_string();
+
+        // The worker ends when the bus, and with it the sender, is dropped
+        std::thread::spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                Ok(runtime) => runtime,
+                Err(e) => {
+                    tracing::error!("Failed to start event subscriber {name}: {e}");
+                    return;
+                }
+            };
+            runtime.block_on(async move {
+                while let Some(event) = rx.recv().await {
+                    // A task of its own, so that a panic only loses this event
+                    match tokio::spawn(handler(event)).await {
+                        Ok(Ok(())) => {}
+                        Ok(Err(e)) => tracing::error!("Event subscriber {name} failed: {e:#}"),
+                        Err(e) => tracing::error!("Event subscriber {name} panicked: {e}"),
+                    }
+                }
+            });
+        });
+
+        self.async_subscribers.write().unwrap().push(tx);
+    }
+
+    /// Run `work` in a transaction. Events it collects are handed to the sync
+    /// subscribers before the commit and to the async ones after it.
+    pub fn transaction<T>(
+        &self,
+        pool: &DbPool,
+        work: impl FnOnce(&Transaction, &mut Vec<DomainEvent>) -> Result<T>,
+    ) -> Result<T> {
+        let mut conn = pool.get()?;
+        let mut tx = conn.transaction()?;
+        let mut events = Vec::new();
+        let value = work(&tx, &mut events)?;
+
+        for event in &events {
+            tracing::debug!("Publishing {event}");
+            self.run_sync_subscribers(&mut tx, event);
+        }
+        tx.commit()?;
+
+        let subscribers = self.async_subscribers.read().unwrap();
+        for event in events {
+            for subscriber in subscribers.iter() {
+                let _ = subscriber.send(event.clone());
+            }
+        }
+        Ok(value)
+    }
+
+    fn run_sync_subscribers(&self, tx: &mut Transaction, event: &DomainEvent) {
+        for subscriber in self.sync_subscribers.read().unwrap().iter() {
+            let savepoint = match tx.savepoint() {
+                Ok(savepoint) => savepoint,
+                Err(e) => {
+                    tracing::error!("Event subscriber {} skipped: {e}", subscriber.name);
+                    continue;
+                }
+            };
+
+            // Dropping the savepoint without commit rolls back its writes
+            match catch_unwind(AssertUnwindSafe(|| (subscriber.handler)(&savepoint, event))) {
+                Ok(Ok(())) => {
+                    if let Err(e) = savepoint.commit() {
+                        tracing::error!("Event subscriber {} failed: {e}", subscriber.name);
+                    }
+                }
+                Ok(Err(e)) => tracing::error!("Event subscriber {} failed: {e:#}", subscriber.name),
+                Err(_) => tracing::error!("Event subscriber {} panicked", subscriber.name),
+            }
+        }
+    }
+}
+
+/// The status events implied by a change from `previous` to `task`
+pub fn status_events(previous: &Task, task: &Task) -> Vec<DomainEvent> {
+    let mut events = Vec::new();
+    if previous.status != task.status {
+        events.push(DomainEvent::TaskStatusChanged {
+            task: task.clone(),
+            previous_status: previous.status.clone(),
+        });
+        if task.status == "completed" {
+            events.push(DomainEvent::TaskCompleted(task.clone()));
+        }
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{init_test_database, repositories::TaskRepository};
+    use std::time::Duration;
+
+    fn new_task(title: &str) -> crate::db::models::CreateTaskInput {
+        crate::db::models::CreateTaskInput {
+            title: title.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn isolates_failing_sync_subscribers() {
+        let pool = init_test_database().unwrap();
+        pool.get()
+            .unwrap()
+            .execute("CREATE TABLE history (task_id INTEGER, note TEXT)", [])
+            .unwrap();
+
+        let bus = EventBus::new();
+        bus.subscribe("history", |conn, event| {
+            conn.execute(
+                "INSERT INTO history (task_id, note) VALUES (?1, 'created')",
+                [event.task().unwrap().id],
+            )?;
+            Ok(())
+        });
+        bus.subscribe("broken", |conn, event| {
+            conn.execute(
+                "INSERT INTO history (task_id, note) VALUES (?1, 'broken')",
+                [event.task().unwrap().id],
+            )?;
+            anyhow::bail!("out of coffee")
+        });
+        bus.subscribe("panicking", |_, _| panic!("boom"));
+
+        let task = bus
+            .transaction(&pool, |tx, events| {
+                let task = TaskRepository::create_in(tx, new_task("Water plants"))?;
+                events.push(DomainEvent::TaskCreated(task.clone()));
+                Ok(task)
+            })
+            .unwrap();
+
+        // The change and the working subscriber's write are committed; the
+        // failing subscriber's write is rolled back
+        let notes: Vec<String> = pool
+            .get()
+            .unwrap()
+            .prepare("SELECT note FROM history WHERE task_id = ?1")
+            .unwrap()
+            .query_map([task.id], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(notes, vec!["created"]);
+        assert!(TaskRepository::new(pool).get_by_id(task.id).is_ok());
+    }
+
+    #[test]
+    fn delivers_to_async_subscribers_after_commit() {
+        let pool = init_test_database().unwrap();
+        let bus = EventBus::new();
+        let (tx, rx) = std::sync::mpsc::channel();
+        bus.subscribe_async("failing", |_| async { anyhow::bail!("offline") });
+        bus.subscribe_async("recorder", move |event: DomainEvent| {
+            let tx = tx.clone();
+            async move {
+                tx.send(event.frontend_name())?;
+                Ok(())
+            }
+        });
+
+        bus.transaction(&pool, |conn, events| {
+            let task = TaskRepository::create_in(conn, new_task("Call mum"))?;
+            let moved = TaskRepository::move_to_column_in(conn, task.id, "completed", 0, None)?;
+            events.push(DomainEvent::TaskCreated(task.clone()));
+            events.push(DomainEvent::TaskMoved(moved.clone()));
+            events.extend(status_events(&task, &moved));
+            Ok(())
+        })
+        .unwrap();
+
+        let received: Vec<Option<&str>> = (0..4)
+            .map(|_| rx.recv_timeout(Duration::from_secs(5)).unwrap())
+            .collect();
+        assert_eq!(received, vec![Some("task:created"), Some("task:moved"), None, None]);
+
+        // Nothing is delivered for a rolled back change
+        let result: Result<()> = bus.transaction(&pool, |conn, events| {
+            let task = TaskRepository::create_in(conn, new_task("Never"))?;
+            events.push(DomainEvent::TaskCreated(task));
+            anyhow::bail!("validation failed")
+        });
+        assert!(result.is_err());
+        assert!(rx.recv_timeout(Duration::from_millis(200)).is_err());
+    }
+}
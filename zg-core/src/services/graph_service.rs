@@ -0,0 +1,441 @@
+use crate::db::{
+    models::*,
+    repositories::{
+        graph_repository::{GraphRows, GraphTaskRow},
+        GraphRepository,
+    },
+    DbPool,
+};
+use crate::services::access::{Access, Role};
+use anyhow::{bail, Result};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+const DEFAULT_DEPTH: u32 = 2;
+const DEFAULT_MAX_NODES: usize = 2000;
+const DEFAULT_HUB_COUNT: usize = 20;
+
+/// Graph queries over notes, tasks, projects and tags.
+///
+/// The graph is built in memory from a few bulk queries per request; with
+/// adjacency lists, traversals stay linear in the size of the graph.
+pub struct GraphService {
+    repository: GraphRepository,
+    access: Access,
+}
+
+impl GraphService {
+    pub fn new(pool: DbPool) -> Self {
+        Self {
+            access: Access::new(pool.clone()),
+            repository: GraphRepository::new(pool),
+        }
+    }
+
+    /// Nodes and edges within `depth` hops of `start`, or the whole graph
+    /// without a start node. At most `max_nodes` nodes are returned.
+    pub fn get_graph(
+        &self,
+        start: Option<&str>,
+        depth: Option<u32>,
+        max_nodes: Option<usize>,
+    ) -> Result<GraphData> {
+        self.access.require(Role::Viewer)?;
+        let graph = Graph::build(self.repository.load()?);
+        let max_nodes = max_nodes.unwrap_or(DEFAULT_MAX_NODES);
+
+        match start {
+            Some(start) => graph.neighbourhood(start, depth.unwrap_or(DEFAULT_DEPTH), max_nodes),
+            None => {
+                let all: Vec<usize> = (0..graph.nodes.len().min(max_nodes)).collect();
+                Ok(graph.subgraph(&all, graph.nodes.len() > max_nodes))
+            }
+        }
+    }
+
+    /// Notes without any wiki-links in or out
+    pub fn get_orphan_notes(&self) -> Result<Vec<GraphNode>> {
+        self.access.require(Role::Viewer)?;
+        let graph = Graph::build(self.repository.load()?);
+        Ok(graph
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(index, node)| node.kind == "note" && !graph.has_edge(*index, "link"))
+            .map(|(_, node)| node.clone())
+            .collect())
+    }
+
+    /// Notes and tasks with the most connections
+    pub fn get_hubs(&self, limit: Option<usize>) -> Result<Vec<GraphNode>> {
+        self.access.require(Role::Viewer)?;
+        let graph = Graph::build(self.repository.load()?);
+        let mut hubs: Vec<GraphNode> = graph
+            .nodes
+            .into_iter()
+            .filter(|node| (node.kind == "note" || node.kind == "task") && node.degree > 0)
+            .collect();
+
+        hubs.sort_by(|a, b| b.degree.cmp(&a.degree).then_with(|| a.label.cmp(&b.label)));
+        hubs.truncate(limit.unwrap_or(DEFAULT_HUB_COUNT));
+        Ok(hubs)
+    }
+
+    /// The nodes and edges along a shortest path, or `None` if unconnected
+    pub fn get_shortest_path(&self, from: &str, to: &str) -> Result<Option<GraphData>> {
+        self.access.require(Role::Viewer)?;
+        Graph::build(self.repository.load()?).shortest_path(from, to)
+    }
+}
+
+struct Graph {
+    nodes: Vec<GraphNode>,
+    index: HashMap<String, usize>,
+    /// (source, target, kind)
+    edges: Vec<(usize, usize, &'static str)>,
+    /// Neighbour node and edge index, in both directions
+    adjacency: Vec<Vec<(usize, usize)>>,
+}
+
+impl Graph {
+    fn build(rows: GraphRows) -> Self {
+        let mut graph = Self {
+            nodes: Vec::new(),
+            index: HashMap::new(),
+            edges: Vec::new(),
+            adjacency: Vec::new(),
+        };
+
+        for note in &rows.notes {
+            graph.add_node(format!("note:{}", note.id), "note", note.title.clone());
+        }
+        for (id, name) in rows.projects {
+            graph.add_node(format!("project:{id}"), "project", name);
+        }
+        for task in &rows.tasks {
+            graph.add_node(format!("task:{}", task.id), "task", task.title.clone());
+        }
+
+        let mut seen = HashSet::new();
+        for GraphTaskRow {
+            id,
+            project_id,
+            parent_task_id,
+            tags,
+            ..
+        } in &rows.tasks
+        {
+            let task = graph.index[&format!("task:{id}")];
+
+            if let Some(parent) = parent_task_id.and_then(|p| graph.find(&format!("task:{p}"))) {
+                graph.add_edge(parent, task, "parent", &mut seen);
+            }
+            graph.add_project_and_tags(task, *project_id, tags.as_deref(), &mut seen);
+        }
+        for note in &rows.notes {
+            let index = graph.index[&format!("note:{}", note.id)];
+            graph.add_project_and_tags(index, note.project_id, note.tags.as_deref(), &mut seen);
+        }
+
+        for (source_type, source_id, target_type, target_id) in &rows.links {
+            let source = graph.find(&format!("{source_type}:{source_id}"));
+            let target = graph.find(&format!("{target_type}:{target_id}"));
+            if let (Some(source), Some(target)) = (source, target) {
+                graph.add_edge(source, target, "link", &mut seen);
+            }
+        }
+
+        graph
+    }
+
+    fn find(&self, id: &str) -> Option<usize> {
+        self.index.get(id).copied()
+    }
+
+    fn has_edge(&self, node: usize, kind: &str) -> bool {
+        self.adjacency[node].iter().any(|&(_, edge)| self.edges[edge].2 == kind)
+    }
+
+    /// Edges from a task or note to its project and its tags (a JSON array),
+    /// adding tag nodes as needed. Tags differing only in case are one node.
+    fn add_project_and_tags(
+        &mut self,
+        node: usize,
+        project_id: Option<i64>,
+        tags: Option<&str>,
+        seen: &mut HashSet<(usize, usize, &'static str)>,
+    ) {
+        if let Some(project) = project_id.and_then(|p| self.find(&format!("project:{p}"))) {
+            self.add_edge(node, project, "project", seen);
+        }
+
+        let tags: Vec<String> = tags.and_then(|json| serde_json::from_str(json).ok()).unwrap_or_default();
+        for tag in tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+            let id = format!("tag:{}", tag.to_lowercase());
+            let tag = match self.find(&id) {
+                Some(existing) => existing,
+                None => self.add_node(id, "tag", tag.to_string()),
+            };
+            self.add_edge(node, tag, "tag", seen);
+        }
+    }
+
+    fn add_node(&mut self, id: String, kind: &str, label: String) -> usize {
+        let index = self.nodes.len();
+        self.index.insert(id.clone(), index);
+        self.nodes.push(GraphNode {
+            id,
+            kind: kind.to_string(),
+            label,
+            degree: 0,
+        });
+        self.adjacency.push(Vec::new());
+        index
+    }
+
+    fn add_edge(
+        &mut self,
+        source: usize,
+        target: usize,
+        kind: &'static str,
+        seen: &mut HashSet<(usize, usize, &'static str)>,
+    ) {
+        if source == target || !seen.insert((source, target, kind)) {
+            return;
+        }
+
+        let edge = self.edges.len();
+        self.edges.push((source, target, kind));
+        self.adjacency[source].push((target, edge));
+        self.adjacency[target].push((source, edge));
+        self.nodes[source].degree += 1;
+        self.nodes[target].degree += 1;
+    }
+
+    fn node(&self, id: &str) -> Result<usize> {
+        match self.find(id) {
+            Some(index) => Ok(index),
+            None => bail!("Unknown graph node: {id}"),
+        }
+    }
+
+    /// Breadth-first search from `start`, nearest nodes first
+    fn neighbourhood(&self, start: &str, depth: u32, max_nodes: usize) -> Result<GraphData> {
+        let start = self.node(start)?;
+        let mut distance = HashMap::from([(start, 0)]);
+        let mut order = vec![start];
+        let mut queue = VecDeque::from([start]);
+        let mut truncated = false;
+
+        'search: while let Some(current) = queue.pop_front() {
+            if distance[&current] == depth {
+                continue;
+            }
+            for &(next, _) in &self.adjacency[current] {
+                if distance.contains_key(&next) {
+                    continue;
+                }
+                if order.len() >= max_nodes {
+                    truncated = true;
+                    break 'search;
+                }
+                distance.insert(next, distance[&current] + 1);
+                order.push(next);
+                queue.push_back(next);
+            }
+        }
+
+        Ok(self.subgraph(&order, truncated))
+    }
+
+    fn shortest_path(&self, from: &str, to: &str) -> Result<Option<GraphData>> {
+        let (from, to) = (self.node(from)?, self.node(to)?);
+        let mut previous: HashMap<usize, (usize, usize)> = HashMap::new();
+        let mut queue = VecDeque::from([from]);
+        let mut visited = HashSet::from([from]);
+
+        while let Some(current) = queue.pop_front() {
+            if current == to {
+                break;
+            }
+            for &(next, edge) in &self.adjacency[current] {
+                if visited.insert(next) {
+                    previous.insert(next, (current, edge));
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        if !visited.contains(&to) {
+            return Ok(None);
+        }
+
+        let mut nodes = vec![to];
+        let mut edges = Vec::new();
+        let mut current = to;
+        while let Some(&(before, edge)) = previous.get(&current) {
+            nodes.push(before);
+            edges.push(edge);
+            current = before;
+        }
+        nodes.reverse();
+        edges.reverse();
+
+        Ok(Some(GraphData {
+            nodes: nodes.into_iter().map(|n| self.nodes[n].clone()).collect(),
+            edges: edges.into_iter().map(|e| self.edge(e)).collect(),
+            truncated: false,
+        }))
+    }
+
+    /// The given nodes with all edges between them
+    fn subgraph(&self, nodes: &[usize], truncated: bool) -> GraphData {
+        let included: HashSet<usize> = nodes.iter().copied().collect();
+        let mut edges: Vec<usize> = nodes
+            .iter()
+            .flat_map(|&n| self.adjacency[n].iter())
+            .filter(|(next, _)| included.contains(next))
+            .map(|&(_, edge)| edge)
+            .collect();
+        edges.sort_unstable();
+        edges.dedup();
+
+        GraphData {
+            nodes: nodes.iter().map(|&n| self.nodes[n].clone()).collect(),
+            edges: edges.into_iter().map(|e| self.edge(e)).collect(),
+            truncated,
+        }
+    }
+
+    fn edge(&self, index: usize) -> GraphEdge {
+        let (source, target, kind) = self.edges[index];
+        GraphEdge {
+            source: self.nodes[source].id.clone(),
+            target: self.nodes[target].id.clone(),
+            kind: kind.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::repositories::graph_repository::GraphNoteRow;
+
+    fn task(id: i64, title: &str, project_id: Option<i64>, parent: Option<i64>, tags: Option<&str>) -> GraphTaskRow {
+        GraphTaskRow {
+            id,
+            title: title.to_string(),
+            project_id,
+            parent_task_id: parent,
+            tags: tags.map(String::from),
+        }
+    }
+
+    fn note(id: i64, title: &str, project_id: Option<i64>, tags: Option<&str>) -> GraphNoteRow {
+        GraphNoteRow {
+            id,
+            title: title.to_string(),
+            project_id,
+            tags: tags.map(String::from),
+        }
+    }
+
+    fn rows() -> GraphRows {
+        GraphRows {
+            notes: vec![
+                note(1, "Hub", None, None),
+                note(2, "Leaf", Some(100), Some(r#"["HEALTH", "Diet"]"#)),
+                note(3, "Orphan", None, None),
+            ],
+            tasks: vec![
+                task(10, "Parent", Some(100), None, Some(r#"["Health"]"#)),
+                task(11, "Child", None, Some(10), Some(r#"["health"]"#)),
+                task(12, "Far", None, Some(11), None),
+            ],
+            projects: vec![(100, "Home".to_string())],
+            links: vec![
+                ("note".to_string(), 2, "note".to_string(), 1),
+                ("note".to_string(), 1, "task".to_string(), 10),
+                ("note".to_string(), 1, "task".to_string(), 10),
+            ],
+        }
+    }
+
+    fn ids(data: &GraphData) -> Vec<&str> {
+        data.nodes.iter().map(|n| n.id.as_str()).collect()
+    }
+
+    #[test]
+    fn builds_typed_edges_and_merges_tags() {
+        let graph = Graph::build(rows());
+        let kinds: Vec<&str> = graph.edges.iter().map(|e| e.2).collect();
+
+        assert_eq!(graph.nodes.len(), 9);
+        assert_eq!(
+            kinds,
+            vec!["project", "tag", "parent", "tag", "parent", "project", "tag", "tag", "link", "link"]
+        );
+        assert_eq!(graph.nodes[graph.index["tag:health"]].degree, 3);
+        assert_eq!(graph.nodes[graph.index["project:100"]].degree, 2);
+        assert_eq!(graph.nodes[graph.index["note:2"]].degree, 4);
+        assert_eq!(graph.nodes[graph.index["note:3"]].degree, 0);
+    }
+
+    #[test]
+    fn neighbourhood_respects_depth_and_node_limit() {
+        let graph = Graph::build(rows());
+
+        let one_hop = graph.neighbourhood("note:1", 1, 100).unwrap();
+        assert_eq!(ids(&one_hop), vec!["note:1", "note:2", "task:10"]);
+        assert_eq!(one_hop.edges.len(), 2);
+
+        let two_hops = graph.neighbourhood("note:1", 2, 100).unwrap();
+        assert!(ids(&two_hops).contains(&"task:11"));
+        assert!(!ids(&two_hops).contains(&"task:12"));
+
+        let limited = graph.neighbourhood("note:1", 5, 4).unwrap();
+        assert_eq!(limited.nodes.len(), 4);
+        assert!(limited.truncated);
+
+        assert!(graph.neighbourhood("note:99", 1, 100).is_err());
+    }
+
+    #[test]
+    fn loads_notes_and_links_from_the_database() {
+        let pool = crate::db::init_test_database().unwrap();
+        let notes = crate::services::NoteService::new(pool.clone());
+        let service = GraphService::new(pool);
+
+        for (title, body) in [("A", "[[B]]"), ("B", ""), ("C", "")] {
+            notes
+                .create_note(CreateNoteInput {
+                    title: title.to_string(),
+                    body: Some(body.to_string()),
+                    project_id: None,
+                    tags: None,
+                })
+                .unwrap();
+        }
+
+        let orphans = service.get_orphan_notes().unwrap();
+        assert_eq!(orphans.len(), 1);
+        assert_eq!(orphans[0].label, "C");
+        assert_eq!(service.get_hubs(Some(1)).unwrap()[0].degree, 1);
+        assert_eq!(service.get_graph(None, None, None).unwrap().edges.len(), 1);
+    }
+
+    #[test]
+    fn finds_shortest_paths() {
+        let graph = Graph::build(rows());
+
+        let path = graph.shortest_path("note:1", "task:12").unwrap().unwrap();
+        assert_eq!(ids(&path), vec!["note:1", "task:10", "task:11", "task:12"]);
+        assert_eq!(path.edges.len(), 3);
+
+        // Through the tag shared with the subtask
+        let path = graph.shortest_path("note:2", "task:12").unwrap().unwrap();
+        assert_eq!(ids(&path), vec!["note:2", "tag:health", "task:11", "task:12"]);
+
+        assert!(graph.shortest_path("note:3", "note:1").unwrap().is_none());
+    }
+}
@@ -0,0 +1,476 @@
+use crate::db::{models::*, repositories::TaskRepository, DbPool};
+use crate::formats::ics::{self, Component, IcsDateTime};
+use crate::services::access::{Access, Role};
+use crate::services::{EventBus, TaskService};
+use anyhow::{Context, Result};
+use chrono::{Duration, NaiveDate, NaiveDateTime, Utc};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+const PRODID: &str = "-//Zweites Gehirn//Tasks//EN";
+
+/// Links an exported scheduling VEVENT back to its task
+const TASK_UID_PROPERTY: &str = "X-ZG-TASK-UID";
+
+/// Export and import of tasks as iCalendar files.
+///
+/// Tasks with a scheduled or due date are exported as VTODOs, optionally with
+/// an all-day VEVENT on the scheduled date. UIDs are the task UIDs, so
+/// importing the same items again updates the tasks instead of duplicating them.
+pub struct IcsService {
+    repository: TaskRepository,
+    tasks: TaskService,
+    access: Access,
+}
+
+impl IcsService {
+    pub fn new(pool: DbPool, events: Arc<EventBus>) -> Self {
+        Self {
+            access: Access::new(pool.clone()),
+            repository: TaskRepository::new(pool.clone()),
+            tasks: TaskService::new(pool, events),
+        }
+    }
+
+    /// Build a VCALENDAR with all scheduled or due tasks
+    pub fn export_calendar(&self, include_events: bool) -> Result<Component> {
+        self.access.require(Role::Viewer)?;
+        let all_tasks = self.repository.get_all(None)?;
+        let uids: HashMap<i64, String> = all_tasks.iter().map(|t| (t.id, t.uid.clone())).collect();
+
+        let mut calendar = new_calendar();
+        calendar.add_text("X-WR-CALNAME", "Zweites Gehirn");
+
+        let now = IcsDateTime::Utc(Utc::now());
+        let dated = all_tasks
+            .iter()
+            .filter(|t| t.scheduled_date.is_some() || t.due_date.is_some());
+        for task in dated {
+            calendar.components.push(task_to_vtodo(task, &uids, &now));
+
+            if include_events {
+                if let Some(event) = task_to_vevent(task, &now) {
+                    calendar.components.push(event);
+                }
+            }
+        }
+
+        Ok(calendar)
+    }
+
+    /// Export to a file, e.g. in a synced folder that calendar apps subscribe to.
+    /// Returns the number of exported tasks.
+    pub fn export_to_file(&self, path: &Path, include_events: bool) -> Result<usize> {
+        let calendar = self.export_calendar(include_events)?;
+        let count = calendar.find_all("VTODO").len();
+
+        // Write atomically so subscribers never read a half-written file
+        let tmp = path.with_extension("ics.zg-tmp");
+        std::fs::write(&tmp, ics::serialize(&calendar))?;
+        std::fs::rename(&tmp, path)?;
+
+        tracing::info!("Exported {} tasks to {}", count, path.display());
+        Ok(count)
+    }
+
+    pub fn import_file(&self, path: &Path) -> Result<IcsImportReport> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        self.import_calendar(&content)
+    }
+
+    /// Import all VTODOs and VEVENTs of an iCalendar stream as tasks
+    pub fn import_calendar(&self, content: &str) -> Result<IcsImportReport> {
+        self.access.require(Role::Editor)?;
+        let roots = ics::parse(content)?;
+        let mut report = IcsImportReport::default();
+        let mut parents: Vec<(i64, String)> = Vec::new();
+
+        for root in &roots {
+            for todo in root.find_all("VTODO") {
+                let Some(task) = self.import_item(todo, &mut report)? else {
+                    continue;
+                };
+                if let Some(parent_uid) = parent_uid(todo) {
+                    parents.push((task.id, parent_uid));
+                }
+            }
+
+            for event in root.find_all("VEVENT") {
+                // Scheduling events of our own export only carry the scheduled date
+                if let Some(task_uid) = event.get(TASK_UID_PROPERTY).map(|p| p.text()) {
+                    if let Some(task) = self.repository.find_by_uid(&task_uid)? {
+                        let start = event.get("DTSTART").and_then(|p| IcsDateTime::parse(&p.value));
+                        if let Some(scheduled_date) = reschedule(&task, start) {
+                            let mut updated = task.clone();
+                            updated.scheduled_date = Some(scheduled_date);
+                            self.tasks.overwrite_task(task.id, &updated)?;
+                            report.updated += 1;
+                        }
+                        continue;
+                    }
+                }
+                self.import_item(event, &mut report)?;
+            }
+        }
+
+        // Resolve parents once all items exist, so order in the file does not matter
+        for (task_id, parent_uid) in parents {
+            if let Some(parent) = self.repository.find_by_uid(&parent_uid)? {
+                let mut task = self.repository.get_by_id(task_id)?;
+                if parent.id != task.id && task.parent_task_id != Some(parent.id) {
+                    task.parent_task_id = Some(parent.id);
+                    self.tasks.overwrite_task(task_id, &task)?;
+                }
+            }
+        }
+
+        tracing::info!(
+            "ICS import: {} created, {} updated, {} skipped",
+            report.created,
+            report.updated,
+            report.skipped
+        );
+        Ok(report)
+    }
+
+    /// Create or update the task for a VTODO/VEVENT, matched by UID
+    fn import_item(
+        &self,
+        component: &Component,
+        report: &mut IcsImportReport,
+    ) -> Result<Option<Task>> {
+        let status = component.get("STATUS").map(|p| p.value.to_ascii_uppercase());
+        if status.as_deref() == Some("CANCELLED") {
+            report.skipped += 1;
+            return Ok(None);
+        }
+
+        let uid = component.get("UID").map(|p| p.text());
+        let existing = match &uid {
+            Some(uid) => self.repository.find_by_uid(uid)?,
+            None => None,
+        };
+
+        let task = match existing {
+            Some(task) => {
+                report.updated += 1;
+                let mut updated = task.clone();
+                apply_component(&mut updated, component);
+                self.tasks.overwrite_task(task.id, &updated)?
+            }
+            None => {
+                report.created += 1;
+                self.tasks.import_task(uid.as_deref(), |task| {
+                    apply_component(task, component);
+                    Ok(())
+                })?
+            }
+        };
+        Ok(Some(task))
+    }
+}
+
+/// Empty VCALENDAR with the required properties
+pub fn new_calendar() -> Component {
+    let mut calendar = Component::new("VCALENDAR");
+    calendar
+        .add("VERSION", "2.0")
+        .add("PRODID", PRODID)
+        .add("CALSCALE", "GREGORIAN");
+    calendar
+}
+
+/// VTODO representation of a task. `uids` maps task IDs to UIDs for RELATED-TO.
+pub fn task_to_vtodo(task: &Task, uids: &HashMap<i64, String>, now: &IcsDateTime) -> Component {
+    let mut todo = Component::new("VTODO");
+    todo.add_text("UID", &task.uid).add_date("DTSTAMP", now);
+    if let Some(created) = parse_timestamp(&task.created_at) {
+        todo.add_date("CREATED", &created);
+    }
+    if let Some(modified) = parse_timestamp(&task.updated_at) {
+        todo.add_date("LAST-MODIFIED", &modified);
+    }
+
+    todo.add_text("SUMMARY", &task.title);
+    if let Some(description) = &task.description {
+        todo.add_text("DESCRIPTION", description);
+    }
+    if let Some(start) = task.scheduled_date.as_deref().and_then(parse_app_date) {
+        todo.add_date("DTSTART", &start);
+    }
+    if let Some(due) = task.due_date.as_deref().and_then(parse_app_date) {
+        todo.add_date("DUE", &due);
+    }
+
+    let status = match task.status.as_str() {
+        "completed" => "COMPLETED",
+        "in_progress" => "IN-PROCESS",
+        _ => "NEEDS-ACTION",
+    };
+    todo.add("STATUS", status);
+    if task.status == "completed" {
+        todo.add("PERCENT-COMPLETE", "100");
+        if let Some(completed) = task.completed_at.as_deref().and_then(parse_timestamp) {
+            todo.add_date("COMPLETED", &completed);
+        }
+    }
+    if let Some(priority) = to_ics_priority(task.priority) {
+        todo.add("PRIORITY", priority.to_string());
+    }
+
+    let tags = parse_tags(task);
+    if !tags.is_empty() {
+        let escaped: Vec<String> = tags.iter().map(|t| ics::escape_text(t)).collect();
+        todo.add("CATEGORIES", escaped.join(","));
+    }
+    if let Some(parent_uid) = task.parent_task_id.and_then(|id| uids.get(&id)) {
+        todo.add_with_params("RELATED-TO", &[("RELTYPE", "PARENT")], ics::escape_text(parent_uid));
+    }
+
+    todo
+}
+
+/// All-day event on the scheduled date, so the task shows up in calendar views
+fn task_to_vevent(task: &Task, now: &IcsDateTime) -> Option<Component> {
+    let date = task.scheduled_date.as_deref().and_then(parse_app_date)?.date();
+
+    let mut event = Component::new("VEVENT");
+    event
+        .add_text("UID", &format!("{}-scheduled", task.uid))
+        .add_date("DTSTAMP", now)
+        .add_text("SUMMARY", &task.title)
+        .add_date("DTSTART", &IcsDateTime::Date(date))
+        .add_date("DTEND", &IcsDateTime::Date(date + Duration::days(1)))
+        .add("TRANSP", "TRANSPARENT")
+        .add_text(TASK_UID_PROPERTY, &task.uid);
+    Some(event)
+}
+
+/// Apply the properties of a VTODO or VEVENT to a task
+pub fn apply_component(task: &mut Task, component: &Component) {
+    let is_event = component.name == "VEVENT";
+    let date = |name: &str| {
+        component
+            .get(name)
+            .and_then(|p| IcsDateTime::parse(&p.value))
+    };
+
+    task.title = component
+        .get("SUMMARY")
+        .map(|p| p.text())
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or_else(|| "Untitled".to_string());
+    task.description = component
+        .get("DESCRIPTION")
+        .map(|p| p.text())
+        .filter(|s| !s.is_empty());
+    task.scheduled_date = date("DTSTART").map(|d| to_app_date(&d));
+    if !is_event {
+        task.due_date = date("DUE").map(|d| to_app_date(&d));
+    }
+    task.priority = component
+        .get("PRIORITY")
+        .and_then(|p| p.value.trim().parse().ok())
+        .map(from_ics_priority)
+        .unwrap_or(0);
+
+    let tags: Vec<String> = component
+        .get_all("CATEGORIES")
+        .flat_map(|p| ics::split_text_list(&p.value))
+        .collect();
+    task.tags = if tags.is_empty() {
+        None
+    } else {
+        serde_json::to_string(&tags).ok()
+    };
+
+    match component.get("STATUS").map(|p| p.value.to_ascii_uppercase()).as_deref() {
+        Some("COMPLETED") => {
+            task.status = "completed".to_string();
+            task.completed_at = date("COMPLETED")
+                .map(|d| d.to_utc())
+                .or_else(|| task.completed_at.as_deref().and_then(parse_timestamp).map(|d| d.to_utc()))
+                .or_else(|| Some(Utc::now()))
+                .map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string());
+        }
+        Some("IN-PROCESS") => {
+            task.status = "in_progress".to_string();
+            task.completed_at = None;
+        }
+        _ if !is_event => {
+            task.status = "todo".to_string();
+            task.completed_at = None;
+        }
+        _ => {}
+    }
+}
+
+/// New scheduled date for a task whose scheduling event was moved to `start`.
+/// Keeps the time of day of the task; `None` if the date did not change.
+fn reschedule(task: &Task, start: Option<IcsDateTime>) -> Option<String> {
+    let new_date = start?.date();
+    match task.scheduled_date.as_deref().and_then(parse_app_date) {
+        Some(current) if current.date() == new_date => None,
+        Some(IcsDateTime::Floating(dt)) => Some(to_app_date(&IcsDateTime::Floating(new_date.and_time(dt.time())))),
+        _ => Some(to_app_date(&IcsDateTime::Date(new_date))),
+    }
+}
+
+/// UID of the parent item from RELATED-TO, if any
+pub fn parent_uid(component: &Component) -> Option<String> {
+    component
+        .get_all("RELATED-TO")
+        .find(|p| {
+            p.param("RELTYPE")
+                .is_none_or(|t| t.eq_ignore_ascii_case("PARENT"))
+        })
+        .map(|p| p.text())
+}
+
+fn parse_tags(task: &Task) -> Vec<String> {
+    task.tags
+        .as_deref()
+        .and_then(|t| serde_json::from_str(t).ok())
+        .unwrap_or_default()
+}
+
+/// Parse a task date (`2026-10-20` or a local date-time) as an iCalendar value
+fn parse_app_date(value: &str) -> Option<IcsDateTime> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Some(IcsDateTime::Date(date));
+    }
+    ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .map(IcsDateTime::Floating)
+}
+
+/// Format an iCalendar value the way task dates are stored
+fn to_app_date(value: &IcsDateTime) -> String {
+    match value {
+        IcsDateTime::Date(date) => date.format("%Y-%m-%d").to_string(),
+        other => other.to_local().format("%Y-%m-%dT%H:%M:%S").to_string(),
+    }
+}
+
+/// Parse a database timestamp (`CURRENT_TIMESTAMP`, always UTC)
+fn parse_timestamp(value: &str) -> Option<IcsDateTime> {
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+        .ok()
+        .map(|dt| IcsDateTime::Utc(dt.and_utc()))
+}
+
+/// Map task priority (0 = none, 4 = urgent) to iCalendar (1 = highest, 9 = lowest)
+fn to_ics_priority(priority: i32) -> Option<u8> {
+    match priority {
+        p if p >= 4 => Some(1),
+        3 => Some(3),
+        2 => Some(5),
+        1 => Some(9),
+        _ => None,
+    }
+}
+
+fn from_ics_priority(priority: u8) -> i32 {
+    match priority {
+        1..=2 => 4,
+        3..=4 => 3,
+        5 => 2,
+        6..=9 => 1,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_test_database;
+    use crate::services::WorkspaceService;
+
+    fn new_task(title: &str, due_date: Option<&str>, scheduled_date: Option<&str>) -> CreateTaskInput {
+        CreateTaskInput {
+            title: title.to_string(),
+            description: Some("Line one\nLine two, with comma".to_string()),
+            scheduled_date: scheduled_date.map(String::from),
+            due_date: due_date.map(String::from),
+            tags: Some(vec!["health".to_string(), "a,b".to_string()]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn export_and_reimport_updates_instead_of_duplicating() {
+        let pool = init_test_database().unwrap();
+        let service = IcsService::new(pool.clone(), Arc::new(EventBus::new()));
+        let repo = TaskRepository::new(pool);
+
+        let task = repo
+            .create(new_task("Dentist", Some("2026-10-20"), Some("2026-10-19T15:00:00")))
+            .unwrap();
+        repo.create(new_task("Undated", None, None)).unwrap();
+
+        let calendar = service.export_calendar(true).unwrap();
+        assert_eq!(calendar.find_all("VTODO").len(), 1);
+        assert_eq!(calendar.find_all("VEVENT").len(), 1);
+        let text = ics::serialize(&calendar);
+
+        let report = service.import_calendar(&text).unwrap();
+        assert_eq!(report.created, 0);
+        assert_eq!(repo.get_all(None).unwrap().len(), 2);
+
+        let reimported = repo.get_by_id(task.id).unwrap();
+        assert_eq!(reimported.title, task.title);
+        assert_eq!(reimported.description, task.description);
+        assert_eq!(reimported.due_date, task.due_date);
+        assert_eq!(reimported.scheduled_date, task.scheduled_date);
+        assert_eq!(reimported.tags, task.tags);
+    }
+
+    #[test]
+    fn imports_external_items_and_keeps_their_uids() {
+        let pool = init_test_database().unwrap();
+        let service = IcsService::new(pool.clone(), Arc::new(EventBus::new()));
+        let repo = TaskRepository::new(pool.clone());
+
+        let input = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\n\
+            BEGIN:VTODO\r\nUID:child@example.com\r\nSUMMARY:Book flight\r\n\
+            RELATED-TO:parent@example.com\r\nPRIORITY:1\r\nSTATUS:COMPLETED\r\n\
+            COMPLETED:20261018T120000Z\r\nEND:VTODO\r\n\
+            BEGIN:VTODO\r\nUID:parent@example.com\r\nSUMMARY:Plan trip\r\n\
+            DUE;VALUE=DATE:20261101\r\nEND:VTODO\r\n\
+            BEGIN:VEVENT\r\nUID:event@example.com\r\nSUMMARY:Team offsite\r\n\
+            DTSTART;VALUE=DATE:20261105\r\nEND:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+
+        let report = service.import_calendar(input).unwrap();
+        assert_eq!(report.created, 3);
+
+        let child = repo.find_by_uid("child@example.com").unwrap().unwrap();
+        let parent = repo.find_by_uid("parent@example.com").unwrap().unwrap();
+        let event = repo.find_by_uid("event@example.com").unwrap().unwrap();
+        assert_eq!(child.parent_task_id, Some(parent.id));
+        assert_eq!(child.priority, 4);
+        assert_eq!(child.status, "completed");
+        assert_eq!(child.completed_at.as_deref(), Some("2026-10-18 12:00:00"));
+        assert_eq!(parent.due_date.as_deref(), Some("2026-11-01"));
+        assert_eq!(event.scheduled_date.as_deref(), Some("2026-11-05"));
+
+        let report = service.import_calendar(input).unwrap();
+        assert_eq!((report.created, report.updated), (0, 3));
+        assert_eq!(repo.get_all(None).unwrap().len(), 3);
+
+        // UIDs are unique per workspace, so another one gets its own copies
+        let workspaces = WorkspaceService::new(pool, Arc::new(EventBus::new()));
+        let work = workspaces
+            .create_workspace(CreateWorkspaceInput {
+                name: "Work".to_string(),
+                workspace_type: None,
+            })
+            .unwrap();
+        workspaces.switch_workspace(work.id).unwrap();
+        assert_eq!(service.import_calendar(input).unwrap().created, 3);
+        assert_eq!(repo.get_all(None).unwrap().len(), 3);
+    }
+}
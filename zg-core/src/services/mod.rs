@@ -0,0 +1,34 @@
+pub mod access;
+pub mod ai;
+pub mod caldav;
+pub mod device_sync;
+pub mod event_bus;
+pub mod graph_service;
+pub mod ics_service;
+pub mod note_service;
+pub mod obsidian_sync_service;
+pub mod planner_service;
+pub mod preferences_service;
+pub mod recommendation_service;
+pub mod reminder_service;
+pub mod task_service;
+pub mod todotxt_sync_service;
+pub mod user_service;
+pub mod workspace_service;
+
+pub use ai::{queue::AiQueueService, BreakdownService};
+pub use caldav::CaldavSyncService;
+pub use device_sync::DeviceSyncService;
+pub use event_bus::EventBus;
+pub use graph_service::GraphService;
+pub use ics_service::IcsService;
+pub use note_service::NoteService;
+pub use obsidian_sync_service::ObsidianSyncService;
+pub use planner_service::PlannerService;
+pub use preferences_service::PreferencesService;
+pub use recommendation_service::RecommendationService;
+pub use reminder_service::ReminderService;
+pub use task_service::TaskService;
+pub use todotxt_sync_service::TodoTxtSyncService;
+pub use user_service::UserService;
+pub use workspace_service::WorkspaceService;
@@ -0,0 +1,308 @@
+use crate::db::{
+    models::*,
+    repositories::{
+        link_repository::{NOTE, TASK},
+        LinkRepository, NoteRepository, WorkspaceRepository,
+    },
+    DbPool,
+};
+use crate::formats::wikilinks;
+use crate::services::access::{Access, Role};
+use anyhow::{bail, Result};
+
+/// Markdown notes with `[[wiki-links]]` to other notes and tasks.
+///
+/// Links are re-indexed whenever a note body changes, and renaming a note
+/// rewrites the links in every note pointing to it. Notes are those of the
+/// active workspace; the `_in_workspace` methods are for syncs that stay in
+/// the workspace they were started in.
+pub struct NoteService {
+    notes: NoteRepository,
+    links: LinkRepository,
+    workspaces: WorkspaceRepository,
+    access: Access,
+}
+
+impl NoteService {
+    pub fn new(pool: DbPool) -> Self {
+        Self {
+            notes: NoteRepository::new(pool.clone()),
+            links: LinkRepository::new(pool.clone()),
+            workspaces: WorkspaceRepository::new(pool.clone()),
+            access: Access::new(pool),
+        }
+    }
+
+    pub fn create_note(&self, input: CreateNoteInput) -> Result<Note> {
+        self.create_note_in_workspace(self.workspaces.active_id()?, input)
+    }
+
+    pub fn create_note_in_workspace(&self, workspace_id: i64, input: CreateNoteInput) -> Result<Note> {
+        self.access.require_in(workspace_id, Role::Editor)?;
+        let title = self.check_title(workspace_id, &input.title, None)?;
+        let note = self.notes.create(
+            workspace_id,
+            &CreateNoteInput {
+                title,
+                tags: input.tags.filter(|tags| !tags.is_empty()),
+                ..input
+            },
+        )?;
+
+        self.index_links(workspace_id, &note)?;
+        self.links.resolve_note_title(workspace_id, &note.title, note.id)?;
+        Ok(note)
+    }
+
+    pub fn get_note(&self, id: i64) -> Result<Note> {
+        let workspace_id = self.workspaces.active_id()?;
+        self.access.require_in(workspace_id, Role::Viewer)?;
+        self.notes.get_by_id(workspace_id, id)
+    }
+
+    pub fn get_all_notes(&self) -> Result<Vec<Note>> {
+        self.get_all_notes_in_workspace(self.workspaces.active_id()?)
+    }
+
+    pub fn get_all_notes_in_workspace(&self, workspace_id: i64) -> Result<Vec<Note>> {
+        self.access.require_in(workspace_id, Role::Viewer)?;
+        self.notes.get_all(workspace_id)
+    }
+
+    pub fn search_notes(&self, query: &str) -> Result<Vec<Note>> {
+        let workspace_id = self.workspaces.active_id()?;
+        self.access.require_in(workspace_id, Role::Viewer)?;
+        self.notes.search(workspace_id, query)
+    }
+
+    pub fn update_note(&self, id: i64, input: UpdateNoteInput) -> Result<Note> {
+        let workspace_id = self.workspaces.active_id()?;
+        let mut note = self.notes.get_by_id(workspace_id, id)?;
+        if let Some(title) = input.title {
+            note.title = title;
+        }
+        if let Some(body) = input.body {
+            note.body = body;
+        }
+        if let Some(project_id) = input.project_id {
+            note.project_id = Some(project_id);
+        }
+        if let Some(tags) = input.tags {
+            note.tags = tags_json(&tags)?;
+        }
+
+        self.save_note_in_workspace(workspace_id, note)
+    }
+
+    /// Write all editable fields of a note. Renaming a note rewrites the
+    /// links in the notes pointing to it.
+    pub fn save_note(&self, note: Note) -> Result<Note> {
+        self.save_note_in_workspace(self.workspaces.active_id()?, note)
+    }
+
+    pub fn save_note_in_workspace(&self, workspace_id: i64, mut note: Note) -> Result<Note> {
+        self.access.require_in(workspace_id, Role::Editor)?;
+        let existing = self.notes.get_by_id(workspace_id, note.id)?;
+        note.title = self.check_title(workspace_id, &note.title, Some(note.id))?;
+
+        if note.title == existing.title {
+            let note = self.notes.update(workspace_id, &note)?;
+            self.index_links(workspace_id, &note)?;
+            return Ok(note);
+        }
+
+        // Rename: rewrite links in the notes pointing here, this one included
+        note.body = wikilinks::rename_note_links(&note.body, &existing.title, &note.title);
+        let note = self.notes.update(workspace_id, &note)?;
+        self.index_links(workspace_id, &note)?;
+
+        for backlink in self.links.backlinks(workspace_id, NOTE, note.id)? {
+            if backlink.source_id == note.id {
+                continue;
+            }
+            let mut source = self.notes.get_by_id(workspace_id, backlink.source_id)?;
+            let rewritten = wikilinks::rename_note_links(&source.body, &existing.title, &note.title);
+            if rewritten != source.body {
+                source.body = rewritten;
+                let source = self.notes.update(workspace_id, &source)?;
+                self.index_links(workspace_id, &source)?;
+            }
+        }
+
+        self.links.resolve_note_title(workspace_id, &note.title, note.id)?;
+        self.notes.get_by_id(workspace_id, note.id)
+    }
+
+    /// Delete a note; links pointing to it become unresolved
+    pub fn delete_note(&self, id: i64) -> Result<()> {
+        self.delete_note_in_workspace(self.workspaces.active_id()?, id)
+    }
+
+    pub fn delete_note_in_workspace(&self, workspace_id: i64, id: i64) -> Result<()> {
+        self.access.require_in(workspace_id, Role::Editor)?;
+        self.notes.delete(workspace_id, id)
+    }
+
+    pub fn get_note_links(&self, id: i64) -> Result<Vec<Link>> {
+        self.access.require(Role::Viewer)?;
+        self.links.outgoing(NOTE, id)
+    }
+
+    pub fn get_note_backlinks(&self, id: i64) -> Result<Vec<Backlink>> {
+        let workspace_id = self.workspaces.active_id()?;
+        self.access.require_in(workspace_id, Role::Viewer)?;
+        self.links.backlinks(workspace_id, NOTE, id)
+    }
+
+    pub fn get_task_backlinks(&self, task_id: i64) -> Result<Vec<Backlink>> {
+        let workspace_id = self.workspaces.active_id()?;
+        self.access.require_in(workspace_id, Role::Viewer)?;
+        self.links.backlinks(workspace_id, TASK, task_id)
+    }
+
+    fn index_links(&self, workspace_id: i64, note: &Note) -> Result<()> {
+        self.links
+            .replace_for_source(workspace_id, NOTE, note.id, &wikilinks::parse_links(&note.body))
+    }
+
+    fn check_title(&self, workspace_id: i64, title: &str, id: Option<i64>) -> Result<String> {
+        let title = title.trim();
+        if title.is_empty() {
+            bail!("Note title must not be empty");
+        }
+        if title.contains(['[', ']', '|', '#']) {
+            bail!("Note title must not contain '[', ']', '|' or '#'");
+        }
+        if let Some(other) = self.notes.find_by_title(workspace_id, title)? {
+            if Some(other.id) != id {
+                bail!("A note titled \"{}\" already exists", other.title);
+            }
+        }
+        Ok(title.to_string())
+    }
+}
+
+/// Tags are stored as a JSON array, or NULL when there are none
+pub fn tags_json(tags: &[String]) -> Result<Option<String>> {
+    if tags.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(serde_json::to_string(tags)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{init_test_database, repositories::TaskRepository};
+
+    fn create(service: &NoteService, title: &str, body: &str) -> Note {
+        service
+            .create_note(CreateNoteInput {
+                title: title.to_string(),
+                body: Some(body.to_string()),
+                project_id: None,
+                tags: None,
+            })
+            .unwrap()
+    }
+
+    fn rename(service: &NoteService, id: i64, title: &str) -> Note {
+        service
+            .update_note(
+                id,
+                UpdateNoteInput {
+                    title: Some(title.to_string()),
+                    body: None,
+                    project_id: None,
+                    tags: None,
+                },
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn backlinks_resolve_for_notes_created_later_and_tasks() {
+        let pool = init_test_database().unwrap();
+        let service = NoteService::new(pool.clone());
+        let task = TaskRepository::new(pool)
+            .create(CreateTaskInput {
+                title: "Write report".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let source = create(
+            &service,
+            "Journal",
+            &format!("Met about [[Quarterly Review|the review]], see [[task:{}]].", task.id),
+        );
+        let target = create(&service, "quarterly review", "Numbers");
+
+        let backlinks = service.get_note_backlinks(target.id).unwrap();
+        assert_eq!(backlinks.len(), 1);
+        assert_eq!(backlinks[0].source_id, source.id);
+        assert_eq!(backlinks[0].alias.as_deref(), Some("the review"));
+
+        let task_backlinks = service.get_task_backlinks(task.id).unwrap();
+        assert_eq!(task_backlinks.len(), 1);
+        assert_eq!(task_backlinks[0].source_title, "Journal");
+
+        assert_eq!(service.search_notes("numb").unwrap()[0].id, target.id);
+        assert!(create_fails(&service, "QUARTERLY REVIEW"));
+    }
+
+    fn create_fails(service: &NoteService, title: &str) -> bool {
+        service
+            .create_note(CreateNoteInput {
+                title: title.to_string(),
+                body: None,
+                project_id: None,
+                tags: None,
+            })
+            .is_err()
+    }
+
+    #[test]
+    fn rename_rewrites_links_in_other_notes() {
+        let pool = init_test_database().unwrap();
+        let service = NoteService::new(pool);
+
+        let target = create(&service, "Ideas", "Self link: [[Ideas]]");
+        let source = create(&service, "Inbox", "Check [[ideas#Later|later]] and [[Ideas]].");
+
+        rename(&service, target.id, "Project Ideas");
+
+        assert_eq!(
+            service.get_note(source.id).unwrap().body,
+            "Check [[Project Ideas#Later|later]] and [[Project Ideas]]."
+        );
+        assert_eq!(
+            service.get_note(target.id).unwrap().body,
+            "Self link: [[Project Ideas]]"
+        );
+        assert_eq!(service.get_note_backlinks(target.id).unwrap().len(), 3);
+        assert!(service
+            .get_note_links(source.id)
+            .unwrap()
+            .iter()
+            .all(|l| l.target_ref == "Project Ideas" && l.target_id == Some(target.id)));
+    }
+
+    #[test]
+    fn deleting_a_note_leaves_unresolved_links() {
+        let pool = init_test_database().unwrap();
+        let service = NoteService::new(pool);
+
+        let target = create(&service, "Draft", "");
+        let source = create(&service, "Index", "[[Draft]]");
+        service.delete_note(target.id).unwrap();
+
+        let links = service.get_note_links(source.id).unwrap();
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].target_id, None);
+
+        let recreated = create(&service, "Draft", "");
+        assert_eq!(service.get_note_backlinks(recreated.id).unwrap().len(), 1);
+    }
+}
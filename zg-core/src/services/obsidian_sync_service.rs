@@ -0,0 +1,741 @@
+use crate::db::{
+    models::*,
+    repositories::{ObsidianStateRepository, ProjectRepository, TaskRepository, WorkspaceRepository},
+    DbPool,
+};
+use crate::formats::obsidian::{self, Frontmatter, TaskLine};
+use crate::services::{note_service::tags_json, EventBus, NoteService, TaskService};
+use crate::services::access::{Access, Role};
+use anyhow::{Context, Result};
+use chrono::Utc;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+
+/// Quiet period after a file event before syncing
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(500);
+
+const CONFLICT_SUFFIX: &str = ".conflict.md";
+
+/// Two-way sync between notes and the Markdown files of an Obsidian vault.
+///
+/// Every note is mirrored as `<title>.md` with its UID, tags, project and
+/// dates in the frontmatter. The written content and a fingerprint of the
+/// note are remembered after each sync, so the next sync can tell which side
+/// changed. When both did, the app version wins and the vault version is kept
+/// next to it as `<title>.conflict.md`.
+///
+/// Task lines in the Tasks plugin format become tasks and get the task UID
+/// appended as `🆔 <uid>`. Edits to such a line update the task; changes made
+/// in the app are written back into the line.
+///
+/// A sync stays in the workspace that was active when it started, and a
+/// watched vault keeps syncing with the workspace it was watched from.
+pub struct ObsidianSyncService {
+    notes: NoteService,
+    tasks: TaskRepository,
+    task_service: TaskService,
+    projects: ProjectRepository,
+    state: ObsidianStateRepository,
+    workspaces: WorkspaceRepository,
+    watcher: Mutex<Option<RecommendedWatcher>>,
+    access: Access,
+}
+
+/// A Markdown file in the vault
+struct VaultFile {
+    /// Path relative to the vault, with `/` separators
+    path: String,
+    content: String,
+    frontmatter: Frontmatter,
+    body: String,
+}
+
+impl ObsidianSyncService {
+    pub fn new(pool: DbPool, events: Arc<EventBus>) -> Self {
+        Self {
+            access: Access::new(pool.clone()),
+            notes: NoteService::new(pool.clone()),
+            tasks: TaskRepository::new(pool.clone()),
+            task_service: TaskService::new(pool.clone(), events),
+            projects: ProjectRepository::new(pool.clone()),
+            workspaces: WorkspaceRepository::new(pool.clone()),
+            state: ObsidianStateRepository::new(pool),
+            watcher: Mutex::new(None),
+        }
+    }
+
+    /// Reconcile the vault with the active workspace and write the merged
+    /// result back
+    pub fn sync_vault(&self, vault: &Path) -> Result<ObsidianSyncReport> {
+        self.sync_vault_in_workspace(self.workspaces.active_id()?, vault)
+    }
+
+    fn sync_vault_in_workspace(&self, workspace_id: i64, vault: &Path) -> Result<ObsidianSyncReport> {
+        self.access.require_in(workspace_id, Role::Editor)?;
+        std::fs::create_dir_all(vault)
+            .with_context(|| format!("Failed to create vault {}", vault.display()))?;
+        let key = vault.to_string_lossy().to_string();
+        let mut report = ObsidianSyncReport::default();
+
+        let previous = self.state.load(workspace_id, &key)?;
+        let files = scan_vault(vault)?;
+        let mut project_names: HashMap<i64, String> = self
+            .projects
+            .get_all_in_workspace(workspace_id)?
+            .into_iter()
+            .map(|p| (p.id, p.name))
+            .collect();
+        let notes: HashMap<i64, Note> = self
+            .notes
+            .get_all_notes_in_workspace(workspace_id)?
+            .into_iter()
+            .map(|n| (n.id, n))
+            .collect();
+
+        // Vault to app: pair every file with a note
+        let mut file_of: HashMap<i64, String> = HashMap::new();
+        let mut imported: HashSet<i64> = HashSet::new();
+
+        for file in files.values() {
+            let note = file
+                .frontmatter
+                .id
+                .as_deref()
+                .and_then(|uid| notes.values().find(|n| n.uid == uid))
+                .filter(|n| !file_of.contains_key(&n.id));
+
+            if let Some(note) = note {
+                file_of.insert(note.id, file.path.clone());
+                let Some(synced) = previous.get(&note.id) else {
+                    if self.differs(note, file, &project_names) {
+                        self.keep_conflict(vault, file, &mut report)?;
+                    }
+                    continue;
+                };
+
+                let file_changed = file.content != synced.content || file.path != synced.file_path;
+                let app_changed = fingerprint(note) != synced.fingerprint;
+                if file_changed && app_changed {
+                    self.keep_conflict(vault, file, &mut report)?;
+                } else if file_changed {
+                    self.import(workspace_id, note.clone(), file, &mut project_names)?;
+                    imported.insert(note.id);
+                    report.notes_updated += 1;
+                }
+                continue;
+            }
+
+            // Deleted in the app since the last sync, unchanged in the vault
+            let deleted = previous.iter().any(|(id, synced)| {
+                synced.file_path == file.path
+                    && synced.content == file.content
+                    && !notes.contains_key(id)
+            });
+            if deleted {
+                std::fs::remove_file(vault.join(&file.path))?;
+                report.files_deleted += 1;
+                continue;
+            }
+
+            // Same title on both sides, never synced: keep the app version
+            let title = title_from_path(&file.path);
+            let twin = notes.values().find(|n| {
+                n.title.eq_ignore_ascii_case(&title)
+                    && !file_of.contains_key(&n.id)
+                    && !previous.contains_key(&n.id)
+            });
+            if let Some(note) = twin {
+                file_of.insert(note.id, file.path.clone());
+                if self.differs(note, file, &project_names) {
+                    self.keep_conflict(vault, file, &mut report)?;
+                }
+                continue;
+            }
+
+            let note = self.create_from_file(workspace_id, file, &mut project_names)?;
+            file_of.insert(note.id, file.path.clone());
+            imported.insert(note.id);
+            report.notes_created += 1;
+        }
+
+        // Files removed from the vault
+        for (id, synced) in &previous {
+            let Some(note) = notes.get(id) else {
+                continue;
+            };
+            if !file_of.contains_key(id) && fingerprint(note) == synced.fingerprint {
+                self.notes.delete_note_in_workspace(workspace_id, note.id)?;
+                report.notes_deleted += 1;
+            }
+        }
+
+        // Task lines, in notes changed on either side
+        let mut tasks_by_uid: HashMap<String, Task> = self
+            .tasks
+            .get_all_in_workspace(workspace_id)?
+            .into_iter()
+            .map(|t| (t.uid.clone(), t))
+            .collect();
+        let mut seen_tasks = HashSet::new();
+        for note in self.notes.get_all_notes_in_workspace(workspace_id)? {
+            let from_vault = imported.contains(&note.id);
+            let body = self.sync_task_lines(
+                workspace_id,
+                &note.body,
+                from_vault,
+                &mut tasks_by_uid,
+                &mut seen_tasks,
+                &mut report,
+            )?;
+            if body != note.body {
+                self.notes.save_note_in_workspace(workspace_id, Note { body, ..note })?;
+            }
+        }
+
+        // App to vault
+        let mut next_state = HashMap::new();
+        let mut taken: HashSet<String> = files.keys().map(|p| p.to_lowercase()).collect();
+        for note in self.notes.get_all_notes_in_workspace(workspace_id)? {
+            let stem = file_stem(&note.title);
+            let current = file_of.get(&note.id).and_then(|p| files.get(p));
+
+            let path = match current {
+                Some(file) if title_from_path(&file.path) == stem => file.path.clone(),
+                Some(file) => {
+                    let dir = file.path.rsplit_once('/').map(|(dir, _)| format!("{dir}/"));
+                    free_path(&format!("{}{stem}", dir.unwrap_or_default()), &mut taken)
+                }
+                None => free_path(&stem, &mut taken),
+            };
+
+            let mut frontmatter = Frontmatter {
+                id: Some(note.uid.clone()),
+                tags: note_tags(&note),
+                project: note.project_id.and_then(|id| project_names.get(&id).cloned()),
+                created: Some(note.created_at.clone()),
+                updated: Some(note.updated_at.clone()),
+                extra: Vec::new(),
+            };
+            if let Some(file) = current {
+                frontmatter.extra = file.frontmatter.extra.clone();
+            }
+            let content = obsidian::render_document(&frontmatter, &note.body);
+
+            if current.map(|f| &f.content) != Some(&content) || current.map(|f| &f.path) != Some(&path) {
+                write_atomically(&vault.join(&path), &content)?;
+                report.files_written += 1;
+                if let Some(old) = current.filter(|f| f.path != path) {
+                    std::fs::remove_file(vault.join(&old.path))?;
+                }
+            }
+
+            next_state.insert(
+                note.id,
+                ObsidianSyncState {
+                    file_path: path,
+                    content,
+                    fingerprint: fingerprint(&note),
+                },
+            );
+        }
+
+        self.state.replace(workspace_id, &key, &next_state)?;
+
+        tracing::info!(
+            "Obsidian sync of {}: {} created, {} updated, {} deleted, {} files written, {} conflicts",
+            vault.display(),
+            report.notes_created,
+            report.notes_updated,
+            report.notes_deleted,
+            report.files_written,
+            report.conflicts
+        );
+        Ok(report)
+    }
+
+    /// Sync once, then keep syncing whenever a Markdown file in the vault
+    /// changes. Replaces any previously watched vault.
+    pub fn watch(self: &Arc<Self>, vault: PathBuf) -> Result<()> {
+        let workspace_id = self.workspaces.active_id()?;
+        self.sync_vault_in_workspace(workspace_id, &vault)?;
+
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            if let Ok(event) = event {
+                if event.paths.iter().any(|p| is_note_file(p)) {
+                    let _ = tx.send(());
+                }
+            }
+        })?;
+        watcher.watch(&vault, RecursiveMode::Recursive)?;
+
+        // The thread ends when the watcher (and with it the sender) is dropped
+        let service = Arc::clone(self);
+        std::thread::spawn(move || {
+            while rx.recv().is_ok() {
+                while rx.recv_timeout(WATCH_DEBOUNCE).is_ok() {}
+                if let Err(e) = service.sync_vault_in_workspace(workspace_id, &vault) {
+                    tracing::error!("Obsidian sync of {} failed: {e:#}", vault.display());
+                }
+            }
+        });
+
+        *self.watcher.lock().unwrap() = Some(watcher);
+        Ok(())
+    }
+
+    /// Stop watching the vault, if any
+    pub fn unwatch(&self) {
+        self.watcher.lock().unwrap().take();
+    }
+
+    /// Whether the file holds something else than the note
+    fn differs(&self, note: &Note, file: &VaultFile, project_names: &HashMap<i64, String>) -> bool {
+        let project = note.project_id.and_then(|id| project_names.get(&id));
+        note.body != file.body
+            || note_tags(note) != file.frontmatter.tags
+            || project.map(|p| p.to_lowercase())
+                != file.frontmatter.project.as_ref().map(|p| p.to_lowercase())
+    }
+
+    /// Keep the vault version of a note that also changed in the app
+    fn keep_conflict(&self, vault: &Path, file: &VaultFile, report: &mut ObsidianSyncReport) -> Result<()> {
+        let stem = file.path.strip_suffix(".md").unwrap_or(&file.path);
+        let mut path = vault.join(format!("{stem}{CONFLICT_SUFFIX}"));
+        let mut n = 2;
+        while path.exists() {
+            path = vault.join(format!("{stem} {n}{CONFLICT_SUFFIX}"));
+            n += 1;
+        }
+
+        write_atomically(&path, &file.content)?;
+        tracing::warn!("Obsidian conflict in {}, vault version kept in {}", file.path, path.display());
+        report.conflicts += 1;
+        Ok(())
+    }
+
+    fn import(
+        &self,
+        workspace_id: i64,
+        mut note: Note,
+        file: &VaultFile,
+        project_names: &mut HashMap<i64, String>,
+    ) -> Result<Note> {
+        // A renamed file renames the note
+        let title = title_from_path(&file.path);
+        if file_stem(&note.title) != title {
+            note.title = title;
+        }
+        note.body = file.body.clone();
+        note.tags = tags_json(&file.frontmatter.tags)?;
+        note.project_id = self.project_id(workspace_id, file, project_names)?;
+        self.notes.save_note_in_workspace(workspace_id, note)
+    }
+
+    fn create_from_file(
+        &self,
+        workspace_id: i64,
+        file: &VaultFile,
+        project_names: &mut HashMap<i64, String>,
+    ) -> Result<Note> {
+        let existing = self.notes.get_all_notes_in_workspace(workspace_id)?;
+        let base = title_from_path(&file.path);
+        let mut title = base.clone();
+        let mut n = 2;
+        while existing.iter().any(|note| note.title.eq_ignore_ascii_case(&title)) {
+            title = format!("{base} {n}");
+            n += 1;
+        }
+
+        self.notes.create_note_in_workspace(
+            workspace_id,
+            CreateNoteInput {
+                title,
+                body: Some(file.body.clone()),
+                project_id: self.project_id(workspace_id, file, project_names)?,
+                tags: Some(file.frontmatter.tags.clone()),
+            },
+        )
+    }
+
+    fn project_id(
+        &self,
+        workspace_id: i64,
+        file: &VaultFile,
+        project_names: &mut HashMap<i64, String>,
+    ) -> Result<Option<i64>> {
+        let Some(name) = &file.frontmatter.project else {
+            return Ok(None);
+        };
+        let project = self.projects.find_or_create_in_workspace(workspace_id, name)?;
+        project_names.insert(project.id, project.name);
+        Ok(Some(project.id))
+    }
+
+    /// Create tasks for new task lines and reconcile known ones. With
+    /// `from_vault`, lines win over tasks; otherwise tasks are written into lines.
+    fn sync_task_lines(
+        &self,
+        workspace_id: i64,
+        body: &str,
+        from_vault: bool,
+        tasks_by_uid: &mut HashMap<String, Task>,
+        seen: &mut HashSet<String>,
+        report: &mut ObsidianSyncReport,
+    ) -> Result<String> {
+        let mut lines = Vec::new();
+
+        for line in body.split_inclusive('\n') {
+            let (text, ending) = match line.strip_suffix('\n') {
+                Some(text) => (text, "\n"),
+                None => (line, ""),
+            };
+            let Some(mut item) = obsidian::parse_task_line(text).filter(|i| !i.is_cancelled()) else {
+                lines.push(line.to_string());
+                continue;
+            };
+
+            // A copied line must not take over the original task
+            if item.id.as_ref().is_some_and(|id| !seen.insert(id.clone())) {
+                item.id = None;
+            }
+
+            let task = match item.id.as_ref().map(|id| tasks_by_uid.get(id)) {
+                // Unknown or deleted task: leave the line alone
+                Some(None) => {
+                    lines.push(line.to_string());
+                    continue;
+                }
+                Some(Some(task)) => {
+                    let differs = obsidian::serialize_task_line(&task_to_line(task, &item))
+                        != obsidian::serialize_task_line(&item);
+                    if from_vault && differs {
+                        report.tasks_updated += 1;
+                        let mut updated = task.clone();
+                        apply_line(&mut updated, &item)?;
+                        self.task_service
+                            .overwrite_task_in_workspace(workspace_id, task.id, &updated)?
+                    } else {
+                        task.clone()
+                    }
+                }
+                None if item.title.is_empty() => {
+                    lines.push(line.to_string());
+                    continue;
+                }
+                None => {
+                    report.tasks_created += 1;
+                    let task = self
+                        .task_service
+                        .import_task_in_workspace(workspace_id, None, |task| apply_line(task, &item))?;
+                    seen.insert(task.uid.clone());
+                    task
+                }
+            };
+
+            lines.push(format!(
+                "{}{ending}",
+                obsidian::serialize_task_line(&task_to_line(&task, &item))
+            ));
+            tasks_by_uid.insert(task.uid.clone(), task);
+        }
+
+        Ok(lines.concat())
+    }
+}
+
+/// Write the fields represented in a task line onto a task
+fn apply_line(task: &mut Task, item: &TaskLine) -> Result<()> {
+    task.title = item.title.clone();
+    task.priority = item.priority;
+    task.tags = tags_json(&item.tags)?;
+    task.scheduled_date = keep_time(&task.scheduled_date, &item.scheduled);
+    task.due_date = keep_time(&task.due_date, &item.due);
+
+    if item.is_completed() {
+        if task.status != "completed" {
+            task.status = "completed".to_string();
+            task.completed_at = Some(match &item.done {
+                Some(date) => format!("{date} 00:00:00"),
+                None => Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            });
+        }
+    } else {
+        task.status = if item.status == '/' { "in_progress" } else { "todo" }.to_string();
+        task.completed_at = None;
+    }
+    Ok(())
+}
+
+/// Build the task line of a task, keeping layout and unknown fields of `line`
+fn task_to_line(task: &Task, line: &TaskLine) -> TaskLine {
+    TaskLine {
+        prefix: line.prefix.clone(),
+        status: match task.status.as_str() {
+            "completed" => 'x',
+            "in_progress" => '/',
+            _ => ' ',
+        },
+        title: task.title.clone(),
+        tags: task
+            .tags
+            .as_deref()
+            .and_then(|t| serde_json::from_str(t).ok())
+            .unwrap_or_default(),
+        priority: task.priority,
+        scheduled: task.scheduled_date.as_deref().map(date_part),
+        due: task.due_date.as_deref().map(date_part),
+        done: task.completed_at.as_deref().map(date_part),
+        id: Some(task.uid.clone()),
+        extras: line.extras.clone(),
+    }
+}
+
+/// Take a date from a task line, keeping the time of the current value if
+/// the date did not change
+fn keep_time(current: &Option<String>, date: &Option<String>) -> Option<String> {
+    match (current, date) {
+        (Some(current), Some(date)) if date_part(current) == *date => Some(current.clone()),
+        _ => date.clone(),
+    }
+}
+
+fn date_part(value: &str) -> String {
+    value.chars().take(10).collect()
+}
+
+fn note_tags(note: &Note) -> Vec<String> {
+    note.tags
+        .as_deref()
+        .and_then(|t| serde_json::from_str(t).ok())
+        .unwrap_or_default()
+}
+
+/// The synced fields of a note, to detect changes in the app
+fn fingerprint(note: &Note) -> String {
+    serde_json::json!([note.title, note.body, note.project_id, note.tags]).to_string()
+}
+
+/// File name for a note title, without characters file systems reject
+fn file_stem(title: &str) -> String {
+    title
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '-',
+            c => c,
+        })
+        .collect()
+}
+
+fn title_from_path(path: &str) -> String {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    name.strip_suffix(".md").unwrap_or(name).to_string()
+}
+
+/// `<stem>.md`, or `<stem> 2.md` etc. if taken; marks the result as taken
+fn free_path(stem: &str, taken: &mut HashSet<String>) -> String {
+    let mut path = format!("{stem}.md");
+    let mut n = 2;
+    while taken.contains(&path.to_lowercase()) {
+        path = format!("{stem} {n}.md");
+        n += 1;
+    }
+    taken.insert(path.to_lowercase());
+    path
+}
+
+fn is_note_file(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    name.ends_with(".md")
+        && !name.ends_with(CONFLICT_SUFFIX)
+        && !path
+            .components()
+            .any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+}
+
+/// All Markdown files of the vault, skipping hidden folders like `.obsidian`
+fn scan_vault(vault: &Path) -> Result<BTreeMap<String, VaultFile>> {
+    let mut files = BTreeMap::new();
+    let mut dirs = vec![PathBuf::new()];
+
+    while let Some(dir) = dirs.pop() {
+        let entries = std::fs::read_dir(vault.join(&dir))
+            .with_context(|| format!("Failed to read {}", vault.join(&dir).display()))?;
+        for entry in entries {
+            let entry = entry?;
+            let relative = dir.join(entry.file_name());
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            if entry.file_type()?.is_dir() {
+                dirs.push(relative);
+            } else if is_note_file(&relative) {
+                let content = std::fs::read_to_string(entry.path())?;
+                let (frontmatter, body) = obsidian::parse_document(&content);
+                let path = relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                files.insert(
+                    path.clone(),
+                    VaultFile {
+                        path,
+                        content,
+                        frontmatter,
+                        body,
+                    },
+                );
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+/// Write via a temporary file, so Obsidian never sees a half-written note
+fn write_atomically(path: &Path, content: &str) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("md.zg-tmp");
+    std::fs::write(&tmp, content)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_test_database;
+
+    fn temp_vault(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("zg-obsidian-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn read(vault: &Path, name: &str) -> String {
+        std::fs::read_to_string(vault.join(name)).unwrap()
+    }
+
+    #[test]
+    fn imports_notes_and_task_lines() {
+        let pool = init_test_database().unwrap();
+        let service = ObsidianSyncService::new(pool.clone(), Arc::new(EventBus::new()));
+        let vault = temp_vault("import");
+        std::fs::create_dir_all(vault.join("Areas")).unwrap();
+        std::fs::write(
+            vault.join("Areas/Health.md"),
+            "---\ntags: [body]\nproject: Self\naliases: [Gesundheit]\n---\n- [ ] Call dentist #phone ⏫ 📅 2026-10-20\n",
+        )
+        .unwrap();
+
+        let report = service.sync_vault(&vault).unwrap();
+        assert_eq!(report.notes_created, 1);
+        assert_eq!(report.tasks_created, 1);
+
+        let note = service.notes.get_all_notes().unwrap().remove(0);
+        let task = TaskRepository::new(pool).get_all(None).unwrap().remove(0);
+        assert_eq!(note.title, "Health");
+        assert_eq!(note_tags(&note), vec!["body"]);
+        assert!(note.project_id.is_some());
+        assert_eq!(task.title, "Call dentist");
+        assert_eq!(task.priority, 3);
+        assert_eq!(task.due_date.as_deref(), Some("2026-10-20"));
+
+        let content = read(&vault, "Areas/Health.md");
+        assert!(content.contains(&format!("id: {}", note.uid)));
+        assert!(content.contains("aliases: [Gesundheit]"));
+        assert!(content.contains(&format!("- [ ] Call dentist #phone ⏫ 📅 2026-10-20 🆔 {}\n", task.uid)));
+
+        // Nothing changed: nothing to do
+        let report = service.sync_vault(&vault).unwrap();
+        assert_eq!(report, ObsidianSyncReport::default());
+    }
+
+    #[test]
+    fn changes_flow_both_ways() {
+        let pool = init_test_database().unwrap();
+        let service = ObsidianSyncService::new(pool.clone(), Arc::new(EventBus::new()));
+        let tasks = TaskRepository::new(pool);
+        let vault = temp_vault("both-ways");
+
+        let note = service
+            .notes
+            .create_note(CreateNoteInput {
+                title: "Plan".to_string(),
+                body: Some("- [ ] Book flights\n".to_string()),
+                project_id: None,
+                tags: None,
+            })
+            .unwrap();
+        service.sync_vault(&vault).unwrap();
+        let task = tasks.get_all(None).unwrap().remove(0);
+
+        // Completing the task in the app ticks the line
+        tasks.complete(task.id).unwrap();
+        service.sync_vault(&vault).unwrap();
+        assert!(read(&vault, "Plan.md").contains("- [x] Book flights ✅ "));
+
+        // Editing and renaming the file updates note and task
+        let content = read(&vault, "Plan.md").replace("[x] Book flights", "[ ] Book trains");
+        std::fs::remove_file(vault.join("Plan.md")).unwrap();
+        std::fs::write(vault.join("Trip.md"), content).unwrap();
+        let report = service.sync_vault(&vault).unwrap();
+
+        assert_eq!(report.notes_updated, 1);
+        assert_eq!(report.tasks_updated, 1);
+        assert_eq!(service.notes.get_note(note.id).unwrap().title, "Trip");
+        let task = tasks.get_by_id(task.id).unwrap();
+        assert_eq!((task.title.as_str(), task.status.as_str()), ("Book trains", "todo"));
+
+        // Deleting the file deletes the note
+        std::fs::remove_file(vault.join("Trip.md")).unwrap();
+        assert_eq!(service.sync_vault(&vault).unwrap().notes_deleted, 1);
+        assert!(service.notes.get_all_notes().unwrap().is_empty());
+    }
+
+    #[test]
+    fn conflicting_edits_keep_both_versions() {
+        let pool = init_test_database().unwrap();
+        let service = ObsidianSyncService::new(pool, Arc::new(EventBus::new()));
+        let vault = temp_vault("conflict");
+
+        let note = service
+            .notes
+            .create_note(CreateNoteInput {
+                title: "Ideas".to_string(),
+                body: Some("one\n".to_string()),
+                project_id: None,
+                tags: None,
+            })
+            .unwrap();
+        service.sync_vault(&vault).unwrap();
+
+        let edited = read(&vault, "Ideas.md").replace("one", "vault edit");
+        std::fs::write(vault.join("Ideas.md"), &edited).unwrap();
+        service
+            .notes
+            .update_note(
+                note.id,
+                UpdateNoteInput {
+                    title: None,
+                    body: Some("app edit\n".to_string()),
+                    project_id: None,
+                    tags: None,
+                },
+            )
+            .unwrap();
+
+        let report = service.sync_vault(&vault).unwrap();
+        assert_eq!(report.conflicts, 1);
+        assert!(read(&vault, "Ideas.md").ends_with("app edit\n"));
+        assert_eq!(read(&vault, "Ideas.conflict.md"), edited);
+        assert_eq!(service.notes.get_note(note.id).unwrap().body, "app edit\n");
+    }
+}
@@ -0,0 +1,488 @@
+use crate::db::{
+    models::*,
+    repositories::{
+        plan_repository::{NewBlock, BREAK, FIXED, TASK},
+        PlanRepository, PreferencesRepository, TaskRepository,
+    },
+    DbPool,
+};
+use crate::services::access::{Access, Role};
+use anyhow::{bail, Context, Result};
+use chrono::{Local, NaiveDate, Timelike};
+use std::collections::HashSet;
+
+/// Days ahead in which a due date makes a task urgent
+const SOON_DAYS: i64 = 3;
+/// Planning for today starts at the next multiple of this many minutes
+const START_ROUNDING: u32 = 5;
+
+/// Plans days as time blocks: tasks go into the free time between fixed
+/// appointments within working hours, with breaks, and high-energy work into
+/// the peak hours where possible.
+///
+/// Candidates are open tasks that are scheduled for the day (or earlier), due
+/// within a few days or have a priority; the rest of the backlog stays out of
+/// the plan.
+pub struct PlannerService {
+    plans: PlanRepository,
+    tasks: TaskRepository,
+    preferences: PreferencesRepository,
+    access: Access,
+}
+
+impl PlannerService {
+    pub fn new(pool: DbPool) -> Self {
+        Self {
+            access: Access::new(pool.clone()),
+            plans: PlanRepository::new(pool.clone()),
+            tasks: TaskRepository::new(pool.clone()),
+            preferences: PreferencesRepository::new(pool),
+        }
+    }
+
+    /// Plan a day from scratch, replacing an earlier plan. For today,
+    /// planning starts now. Without settings, the working hours of the
+    /// preferences apply.
+    pub fn plan_day(&self, date: &str, settings: Option<PlanSettings>) -> Result<DayPlan> {
+        self.access.require(Role::Editor)?;
+        let settings = self.settings_or_preferred(settings)?;
+        self.plan_at(date, &settings, start_for(date)?)
+    }
+
+    /// Re-plan the rest of a day from `from_time` (`HH:MM`, now by default),
+    /// e.g. after something ran late. Completed blocks stay; unfinished and
+    /// overflowing tasks of the plan are placed again.
+    pub fn reflow_day(&self, date: &str, from_time: Option<&str>, settings: Option<PlanSettings>) -> Result<DayPlan> {
+        self.access.require(Role::Editor)?;
+        let from = match from_time {
+            Some(time) => parse_time(time)?,
+            None => start_for(date)?.unwrap_or(0),
+        };
+        let settings = self.settings_or_preferred(settings)?;
+        self.reflow_at(date, &settings, from)
+    }
+
+    pub fn get_day_plan(&self, date: &str) -> Result<DayPlan> {
+        self.access.require(Role::Viewer)?;
+        let mut overflow = Vec::new();
+        for id in self.plans.overflow(date)? {
+            // Deleted or finished in the meantime
+            if let Ok(task) = self.tasks.get_by_id(id) {
+                if task.status != "completed" {
+                    overflow.push(task);
+                }
+            }
+        }
+
+        Ok(DayPlan {
+            date: date.to_string(),
+            blocks: self.plans.blocks(date)?,
+            overflow,
+        })
+    }
+
+    /// Add a fixed appointment the plan has to work around
+    pub fn add_fixed_block(&self, date: &str, start_time: &str, end_time: &str, title: &str) -> Result<TimeBlock> {
+        self.access.require(Role::Editor)?;
+        parse_date(date)?;
+        let (start, end) = (parse_time(start_time)?, parse_time(end_time)?);
+        if start >= end {
+            bail!("A block must end after it starts");
+        }
+
+        self.plans.add_block(
+            date,
+            &NewBlock {
+                start_time: format_time(start),
+                end_time: format_time(end),
+                kind: FIXED,
+                task_id: None,
+                title: title.trim().to_string(),
+            },
+        )
+    }
+
+    pub fn delete_time_block(&self, id: i64) -> Result<()> {
+        self.access.require(Role::Editor)?;
+        self.plans.delete_block(id)
+    }
+
+    fn settings_or_preferred(&self, settings: Option<PlanSettings>) -> Result<PlanSettings> {
+        match settings {
+            Some(settings) => Ok(settings),
+            None => Ok(self.preferences.get()?.working_hours),
+        }
+    }
+
+    fn plan_at(&self, date: &str, settings: &PlanSettings, from: Option<u32>) -> Result<DayPlan> {
+        let day = parse_date(date)?;
+        let tasks = self.tasks.get_all(None)?;
+        let open_parents: HashSet<i64> = tasks
+            .iter()
+            .filter(|t| t.status != "completed")
+            .filter_map(|t| t.parent_task_id)
+            .collect();
+
+        let candidates: Vec<Task> = tasks
+            .into_iter()
+            .filter(|t| t.status != "completed" && !open_parents.contains(&t.id))
+            .filter(|t| date_of(t.scheduled_date.as_deref()).is_none_or(|d| d <= day))
+            .filter(|t| t.priority > 0 || t.scheduled_date.is_some() || urgency(t, day) > 0)
+            .collect();
+
+        let busy = self.blocks_of_kind(date, &[FIXED])?;
+        self.schedule_and_save(date, day, settings, from, candidates, &busy, &[])
+    }
+
+    fn reflow_at(&self, date: &str, settings: &PlanSettings, from: u32) -> Result<DayPlan> {
+        let day = parse_date(date)?;
+        let blocks = self.plans.blocks(date)?;
+
+        let mut keep = Vec::new();
+        let mut busy = Vec::new();
+        let mut task_ids = Vec::new();
+        for block in &blocks {
+            let (start, end) = (parse_time(&block.start_time)?, parse_time(&block.end_time)?);
+            let task = block.task_id.and_then(|id| self.tasks.get_by_id(id).ok());
+            let done = task.as_ref().is_some_and(|t| t.status == "completed");
+
+            if block.kind == FIXED {
+                busy.push((start, end));
+            } else if end <= from && (block.kind == BREAK || done) {
+                keep.push(block.id);
+                busy.push((start, end));
+            } else if let Some(task) = task.filter(|_| !done) {
+                task_ids.push(task.id);
+            }
+        }
+        task_ids.extend(self.plans.overflow(date)?);
+
+        let mut seen = HashSet::new();
+        let candidates: Vec<Task> = task_ids
+            .into_iter()
+            .filter(|id| seen.insert(*id))
+            .filter_map(|id| self.tasks.get_by_id(id).ok())
+            .filter(|t| t.status != "completed")
+            .collect();
+
+        self.schedule_and_save(date, day, settings, Some(from), candidates, &busy, &keep)
+    }
+
+    fn blocks_of_kind(&self, date: &str, kinds: &[&str]) -> Result<Vec<(u32, u32)>> {
+        self.plans
+            .blocks(date)?
+            .iter()
+            .filter(|b| kinds.contains(&b.kind.as_str()))
+            .map(|b| Ok((parse_time(&b.start_time)?, parse_time(&b.end_time)?)))
+            .collect()
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn schedule_and_save(
+        &self,
+        date: &str,
+        day: NaiveDate,
+        settings: &PlanSettings,
+        from: Option<u32>,
+        candidates: Vec<Task>,
+        busy: &[(u32, u32)],
+        keep: &[i64],
+    ) -> Result<DayPlan> {
+        let rules = Rules::from_settings(settings)?;
+        let start = from.map_or(rules.work.0, |from| from.max(rules.work.0));
+        let free = free_slots((start, rules.work.1), busy);
+
+        let (blocks, overflow) = schedule(candidates, &free, &rules, day);
+        self.plans.replace_plan(date, keep, &blocks, &overflow)?;
+        tracing::info!(
+            "Planned {date}: {} blocks, {} tasks overflowing",
+            blocks.len(),
+            overflow.len()
+        );
+
+        self.get_day_plan(date)
+    }
+}
+
+/// Fail if the settings cannot be planned with
+pub fn check_settings(settings: &PlanSettings) -> Result<()> {
+    Rules::from_settings(settings).map(|_| ())
+}
+
+/// Settings with times in minutes since midnight
+struct Rules {
+    work: (u32, u32),
+    peak: (u32, u32),
+    break_after: u32,
+    break_minutes: u32,
+    default_task_minutes: u32,
+}
+
+impl Rules {
+    fn from_settings(settings: &PlanSettings) -> Result<Self> {
+        let rules = Self {
+            work: (parse_time(&settings.work_start)?, parse_time(&settings.work_end)?),
+            peak: (parse_time(&settings.peak_start)?, parse_time(&settings.peak_end)?),
+            break_after: settings.break_after_minutes,
+            break_minutes: settings.break_minutes,
+            default_task_minutes: settings.default_task_minutes,
+        };
+        if rules.work.0 >= rules.work.1 {
+            bail!("Working hours must end after they start");
+        }
+        if rules.peak.0 > rules.peak.1 {
+            bail!("Peak hours must end after they start");
+        }
+        if rules.break_after == 0 || rules.default_task_minutes == 0 {
+            bail!("Break interval and default task length must be positive");
+        }
+        Ok(rules)
+    }
+}
+
+/// Fill the free slots from the start, picking at each point the most urgent
+/// task that fits, preferring tasks whose energy suits the time of day.
+/// Returns the blocks and the IDs of the tasks that did not fit.
+fn schedule(mut candidates: Vec<Task>, free: &[(u32, u32)], rules: &Rules, day: NaiveDate) -> (Vec<NewBlock>, Vec<i64>) {
+    candidates.sort_by(|a, b| {
+        urgency(b, day)
+            .cmp(&urgency(a, day))
+            .then_with(|| b.priority.cmp(&a.priority))
+            .then_with(|| a.due_date.is_none().cmp(&b.due_date.is_none()))
+            .then_with(|| a.due_date.cmp(&b.due_date))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    let minutes = |task: &Task| {
+        task.estimated_minutes
+            .filter(|m| *m > 0)
+            .map_or(rules.default_task_minutes, |m| m as u32)
+    };
+
+    let mut blocks = Vec::new();
+    for &(start, end) in free {
+        let mut time = start;
+        // Minutes of work since the last break or appointment
+        let mut streak = 0;
+
+        loop {
+            let in_peak = rules.peak.0 <= time && time < rules.peak.1;
+            // Minutes a task needs here, including a break before it if due
+            let needed = |duration: u32| {
+                if streak > 0 && streak + duration > rules.break_after {
+                    duration + rules.break_minutes
+                } else {
+                    duration
+                }
+            };
+
+            let mut best: Option<(usize, (u8, u8))> = None;
+            for (i, task) in candidates.iter().enumerate() {
+                if needed(minutes(task)) > end - time {
+                    continue;
+                }
+                let rank = (urgency(task, day), energy_fit(task, in_peak));
+                if best.is_none_or(|(_, best_rank)| rank > best_rank) {
+                    best = Some((i, rank));
+                }
+            }
+            let Some((i, _)) = best else {
+                break;
+            };
+
+            let duration = minutes(&candidates[i]);
+            if needed(duration) > duration {
+                blocks.push(NewBlock {
+                    start_time: format_time(time),
+                    end_time: format_time(time + rules.break_minutes),
+                    kind: BREAK,
+                    task_id: None,
+                    title: "Break".to_string(),
+                });
+                time += rules.break_minutes;
+                streak = 0;
+            }
+
+            let task = candidates.remove(i);
+            blocks.push(NewBlock {
+                start_time: format_time(time),
+                end_time: format_time(time + duration),
+                kind: TASK,
+                task_id: Some(task.id),
+                title: task.title,
+            });
+            time += duration;
+            streak += duration;
+        }
+    }
+
+    (blocks, candidates.iter().map(|t| t.id).collect())
+}
+
+/// 2 for tasks overdue, due or scheduled on the day, 1 for tasks due soon
+fn urgency(task: &Task, day: NaiveDate) -> u8 {
+    let due = date_of(task.due_date.as_deref());
+    if due.is_some_and(|d| d <= day) || date_of(task.scheduled_date.as_deref()) == Some(day) {
+        2
+    } else if due.is_some_and(|d| (d - day).num_days() <= SOON_DAYS) {
+        1
+    } else {
+        0
+    }
+}
+
+fn energy_fit(task: &Task, in_peak: bool) -> u8 {
+    match (task.energy_level.as_deref(), in_peak) {
+        (Some("high"), true) | (Some("low"), false) => 2,
+        (Some("high"), false) | (Some("low"), true) => 0,
+        _ => 1,
+    }
+}
+
+/// The parts of `window` not covered by any busy interval
+fn free_slots(window: (u32, u32), busy: &[(u32, u32)]) -> Vec<(u32, u32)> {
+    let mut busy = busy.to_vec();
+    busy.sort();
+
+    let mut slots = Vec::new();
+    let mut start = window.0;
+    for (busy_start, busy_end) in busy {
+        if busy_start > start {
+            slots.push((start, busy_start.min(window.1)));
+        }
+        start = start.max(busy_end);
+    }
+    if start < window.1 {
+        slots.push((start, window.1));
+    }
+    slots.retain(|(start, end)| start < end);
+    slots
+}
+
+/// Minutes since midnight at which planning starts: now for today, nothing
+/// for other days
+fn start_for(date: &str) -> Result<Option<u32>> {
+    let now = Local::now();
+    if parse_date(date)? != now.date_naive() {
+        return Ok(None);
+    }
+    let minutes = now.hour() * 60 + now.minute();
+    Ok(Some(minutes.div_ceil(START_ROUNDING) * START_ROUNDING))
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").with_context(|| format!("Invalid date: {date}"))
+}
+
+fn parse_time(time: &str) -> Result<u32> {
+    let parsed = time
+        .split_once(':')
+        .and_then(|(h, m)| Some((h.parse::<u32>().ok()?, m.parse::<u32>().ok()?)))
+        .filter(|(h, m)| (*h < 24 && *m < 60) || (*h == 24 && *m == 0));
+    match parsed {
+        Some((hours, minutes)) => Ok(hours * 60 + minutes),
+        None => bail!("Invalid time: {time}"),
+    }
+}
+
+fn format_time(minutes: u32) -> String {
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// The date part of a date or timestamp column
+fn date_of(value: Option<&str>) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value?.get(..10)?, "%Y-%m-%d").ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_test_database;
+
+    const DAY: &str = "2026-03-02";
+
+    fn task(tasks: &TaskRepository, title: &str, priority: i32, minutes: i32, energy: Option<&str>, due: Option<&str>) -> Task {
+        tasks
+            .create(CreateTaskInput {
+                title: title.to_string(),
+                priority: Some(priority),
+                estimated_minutes: Some(minutes),
+                energy_level: energy.map(String::from),
+                due_date: due.map(String::from),
+                ..Default::default()
+            })
+            .unwrap()
+    }
+
+    fn layout(plan: &DayPlan) -> Vec<String> {
+        plan.blocks
+            .iter()
+            .map(|b| format!("{}-{} {}", b.start_time, b.end_time, b.title))
+            .collect()
+    }
+
+    #[test]
+    fn plans_around_appointments_and_reflows() {
+        let pool = init_test_database().unwrap();
+        let tasks = TaskRepository::new(pool.clone());
+        let service = PlannerService::new(pool);
+
+        let mail = task(&tasks, "Answer mail", 2, 30, Some("low"), Some(DAY));
+        task(&tasks, "Write proposal", 2, 90, Some("high"), None);
+        task(&tasks, "Fix bug", 3, 45, Some("medium"), Some("2026-03-04"));
+        task(&tasks, "Migrate server", 1, 300, Some("high"), None);
+        task(&tasks, "Someday idea", 0, 30, None, None);
+        task(&tasks, "File receipts", 1, 30, Some("low"), None);
+        service.add_fixed_block(DAY, "12:00", "13:00", "Lunch").unwrap();
+
+        // Urgent work first, a break before focus would exceed 90 minutes,
+        // high-energy work in the peak hours and low-energy work after lunch
+        let plan = service.plan_at(DAY, &PlanSettings::default(), None).unwrap();
+        assert_eq!(
+            layout(&plan),
+            vec![
+                "09:00-09:30 Answer mail",
+                "09:30-10:15 Fix bug",
+                "10:15-10:30 Break",
+                "10:30-12:00 Write proposal",
+                "12:00-13:00 Lunch",
+                "13:00-13:30 File receipts",
+            ]
+        );
+        let overflow: Vec<&str> = plan.overflow.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(overflow, vec!["Migrate server"]);
+
+        // The bug fix runs late: everything not done moves behind 10:00
+        tasks.complete(mail.id).unwrap();
+        let plan = service.reflow_at(DAY, &PlanSettings::default(), 10 * 60).unwrap();
+        assert_eq!(
+            layout(&plan),
+            vec![
+                "09:00-09:30 Answer mail",
+                "10:00-10:45 Fix bug",
+                "10:45-11:15 File receipts",
+                "12:00-13:00 Lunch",
+                "13:00-14:30 Write proposal",
+            ]
+        );
+        assert_eq!(plan.overflow.len(), 1);
+        assert_eq!(service.get_day_plan(DAY).unwrap().blocks, plan.blocks);
+    }
+
+    #[test]
+    fn computes_free_slots_and_validates_input() {
+        assert_eq!(
+            free_slots((540, 1020), &[(600, 660), (630, 700), (1000, 1100), (400, 500)]),
+            vec![(540, 600), (700, 1000)]
+        );
+        assert_eq!(parse_time("24:00").unwrap(), 1440);
+        assert!(parse_time("9.30").is_err());
+
+        let service = PlannerService::new(init_test_database().unwrap());
+        assert!(service.add_fixed_block(DAY, "14:00", "13:00", "Backwards").is_err());
+        let settings = PlanSettings {
+            work_end: "08:00".to_string(),
+            ..PlanSettings::default()
+        };
+        assert!(service.plan_day(DAY, Some(settings)).is_err());
+    }
+}
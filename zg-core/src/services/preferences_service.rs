@@ -0,0 +1,336 @@
+use crate::db::{models::*, repositories::PreferencesRepository, DbPool};
+use crate::services::ai::provider::{self, ANTHROPIC, OPENAI_COMPATIBLE, PROVIDERS};
+use crate::services::event_bus::{DomainEvent, EventBus};
+use crate::services::{planner_service, reminder_service};
+use anyhow::{bail, Context, Result};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+const DEFAULT_AI_TIMEOUT_SECS: u32 = 60;
+const MAX_AI_TIMEOUT_SECS: u32 = 600;
+const THEMES: [&str; 3] = ["system", "light", "dark"];
+const VIEWS: [&str; 2] = ["list", "kanban"];
+
+/// The device-wide preferences. Other services read them through the
+/// repository; changes go through here to be validated and published as
+/// `DomainEvent::PreferencesChanged`.
+pub struct PreferencesService {
+    pool: DbPool,
+    repository: PreferencesRepository,
+    events: Arc<EventBus>,
+}
+
+impl PreferencesService {
+    pub fn new(pool: DbPool, events: Arc<EventBus>) -> Self {
+        Self {
+            repository: PreferencesRepository::new(pool.clone()),
+            pool,
+            events,
+        }
+    }
+
+    pub fn get_preferences(&self) -> Result<Preferences> {
+        self.repository.get()
+    }
+
+    pub fn update_preferences(&self, preferences: Preferences) -> Result<Preferences> {
+        let preferences = check_preferences(preferences)?;
+        self.events.transaction(&self.pool, |tx, events| {
+            PreferencesRepository::save_in(tx, &preferences)?;
+            events.push(DomainEvent::PreferencesChanged(preferences.clone()));
+            Ok(())
+        })?;
+        Ok(preferences)
+    }
+
+    pub fn get_ai_settings(&self) -> Result<AiSettings> {
+        load_ai_settings(&self.repository)
+    }
+
+    pub fn update_ai_settings(&self, input: AiSettingsInput) -> Result<AiSettings> {
+        let mut preferences = self.repository.get()?;
+        preferences.ai = check_ai(AiPreferences {
+            provider: Some(input.provider),
+            model: input.model,
+            base_url: input.base_url,
+            timeout_secs: input.timeout_secs,
+        })?;
+        let api_key = match input.api_key {
+            None => self.repository.get_ai_api_key()?,
+            Some(key) if key.trim().is_empty() => None,
+            Some(key) => Some(key.trim().to_string()),
+        };
+
+        self.events.transaction(&self.pool, |tx, events| {
+            PreferencesRepository::save_in(tx, &preferences)?;
+            PreferencesRepository::save_ai_api_key_in(tx, api_key.as_deref())?;
+            events.push(DomainEvent::PreferencesChanged(preferences.clone()));
+            Ok(())
+        })?;
+        tracing::info!("AI provider set to {}", preferences.ai.provider.as_deref().unwrap_or(ANTHROPIC));
+
+        self.get_ai_settings()
+    }
+
+    /// Replace the API server token with a new one. Only its hash is kept,
+    /// so the token is shown this once.
+    pub fn generate_api_token(&self) -> Result<String> {
+        let token = self.repository.random_token()?;
+        self.repository.save_api_token_hash(&sha256_hex(&token))?;
+        Ok(token)
+    }
+
+    pub fn verify_api_token(&self, token: &str) -> Result<bool> {
+        Ok(self.repository.get_api_token_hash()? == Some(sha256_hex(token)))
+    }
+
+    pub fn get_quiet_hours(&self) -> Result<Option<QuietHours>> {
+        Ok(self.repository.get()?.quiet_hours)
+    }
+
+    /// Set or (with `None`) clear the quiet hours
+    pub fn set_quiet_hours(&self, hours: Option<QuietHours>) -> Result<Option<QuietHours>> {
+        let preferences = Preferences {
+            quiet_hours: hours,
+            ..self.repository.get()?
+        };
+        Ok(self.update_preferences(preferences)?.quiet_hours)
+    }
+}
+
+/// AI settings with defaults filled in. Without a stored key, the key is
+/// taken from `ANTHROPIC_API_KEY` or `OPENAI_API_KEY`.
+pub fn load_ai_settings(repository: &PreferencesRepository) -> Result<AiSettings> {
+    let ai = repository.get()?.ai;
+    let provider = ai.provider.unwrap_or_else(|| ANTHROPIC.to_string());
+    let env_key = match provider.as_str() {
+        OPENAI_COMPATIBLE => "OPENAI_API_KEY",
+        _ => "ANTHROPIC_API_KEY",
+    };
+
+    Ok(AiSettings {
+        model: ai
+            .model
+            .unwrap_or_else(|| provider::default_model(&provider).to_string()),
+        base_url: ai
+            .base_url
+            .unwrap_or_else(|| provider::default_base_url(&provider).to_string()),
+        api_key: repository
+            .get_ai_api_key()?
+            .or_else(|| std::env::var(env_key).ok())
+            .filter(|k| !k.is_empty()),
+        timeout_secs: ai.timeout_secs.unwrap_or(DEFAULT_AI_TIMEOUT_SECS),
+        provider,
+    })
+}
+
+/// The day `now` belongs to: before the rollover time it is still the day before
+pub fn current_day(preferences: &Preferences, now: NaiveDateTime) -> NaiveDate {
+    let rollover = parse_time(&preferences.rollover_time).unwrap_or(NaiveTime::MIN);
+    (now - rollover.signed_duration_since(NaiveTime::MIN)).date()
+}
+
+fn check_preferences(mut preferences: Preferences) -> Result<Preferences> {
+    if !THEMES.contains(&preferences.theme.as_str()) {
+        bail!("Unknown theme: {}", preferences.theme);
+    }
+    if !VIEWS.contains(&preferences.default_view.as_str()) {
+        bail!("Unknown view: {}", preferences.default_view);
+    }
+    parse_time(&preferences.rollover_time)?;
+    planner_service::check_settings(&preferences.working_hours)?;
+    if let Some(hours) = &preferences.quiet_hours {
+        reminder_service::check_quiet_hours(hours)?;
+    }
+
+    let pomodoro = &preferences.pomodoro;
+    for (minutes, max, what) in [
+        (pomodoro.focus_minutes, 180, "Focus sessions"),
+        (pomodoro.short_break_minutes, 60, "Short breaks"),
+        (pomodoro.long_break_minutes, 120, "Long breaks"),
+    ] {
+        if !(1..=max).contains(&minutes) {
+            bail!("{what} must last 1 to {max} minutes");
+        }
+    }
+    if !(1..=12).contains(&pomodoro.sessions_before_long_break) {
+        bail!("A long break must come after 1 to 12 sessions");
+    }
+
+    if preferences.api_server.port < 1024 {
+        bail!("The API server needs a port from 1024 up");
+    }
+
+    preferences.ai = check_ai(preferences.ai)?;
+    Ok(preferences)
+}
+
+/// Trimmed AI preferences, with empty values meaning the default
+fn check_ai(ai: AiPreferences) -> Result<AiPreferences> {
+    let provider = ai.provider.map(|p| p.trim().to_string()).filter(|p| !p.is_empty());
+    if let Some(provider) = &provider {
+        if !PROVIDERS.contains(&provider.as_str()) {
+            bail!("Unknown AI provider: {provider}");
+        }
+    }
+    let model = ai.model.map(|m| m.trim().to_string()).filter(|m| !m.is_empty());
+    let base_url = ai
+        .base_url
+        .map(|u| u.trim().to_string())
+        .filter(|u| !u.is_empty());
+    if let Some(base_url) = &base_url {
+        let url = reqwest::Url::parse(base_url).context("Invalid base URL")?;
+        if !matches!(url.scheme(), "http" | "https") {
+            bail!("The base URL must start with http:// or https://");
+        }
+    }
+    if let Some(timeout) = ai.timeout_secs {
+        if !(1..=MAX_AI_TIMEOUT_SECS).contains(&timeout) {
+            bail!("The timeout must be between 1 and {MAX_AI_TIMEOUT_SECS} seconds");
+        }
+    }
+
+    Ok(AiPreferences {
+        provider,
+        model,
+        base_url,
+        timeout_secs: ai.timeout_secs,
+    })
+}
+
+fn sha256_hex(value: &str) -> String {
+    Sha256::digest(value.as_bytes()).iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn parse_time(time: &str) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(time, "%H:%M").with_context(|| format!("Invalid time: {time}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_test_database;
+    use std::sync::Mutex;
+
+    fn input(provider: &str) -> AiSettingsInput {
+        AiSettingsInput {
+            provider: provider.to_string(),
+            model: None,
+            base_url: None,
+            api_key: None,
+            timeout_secs: None,
+        }
+    }
+
+    #[test]
+    fn fills_defaults_and_keeps_the_stored_key() {
+        let service = PreferencesService::new(init_test_database().unwrap(), Arc::new(EventBus::new()));
+
+        let settings = service
+            .update_ai_settings(AiSettingsInput {
+                api_key: Some(" sk-local ".to_string()),
+                timeout_secs: Some(120),
+                ..input(OPENAI_COMPATIBLE)
+            })
+            .unwrap();
+        assert_eq!(settings.model, "llama3.1");
+        assert_eq!(settings.base_url, "http://localhost:11434/v1");
+        assert_eq!(settings.api_key.as_deref(), Some("sk-local"));
+        assert_eq!(settings.timeout_secs, 120);
+
+        let settings = service
+            .update_ai_settings(AiSettingsInput {
+                model: Some("qwen2.5".to_string()),
+                base_url: Some("http://192.168.1.5:1234/v1".to_string()),
+                ..input(OPENAI_COMPATIBLE)
+            })
+            .unwrap();
+        assert_eq!(settings.model, "qwen2.5");
+        assert_eq!(settings.base_url, "http://192.168.1.5:1234/v1");
+        assert_eq!(settings.api_key.as_deref(), Some("sk-local"));
+        assert_eq!(settings.timeout_secs, DEFAULT_AI_TIMEOUT_SECS);
+
+        // The key is never sent to the frontend
+        assert!(!serde_json::to_string(&settings).unwrap().contains("sk-local"));
+    }
+
+    #[test]
+    fn rejects_invalid_settings() {
+        let service = PreferencesService::new(init_test_database().unwrap(), Arc::new(EventBus::new()));
+
+        assert!(service.update_ai_settings(input("gemini")).is_err());
+        assert!(service
+            .update_ai_settings(AiSettingsInput {
+                base_url: Some("ftp://example.com".to_string()),
+                ..input(ANTHROPIC)
+            })
+            .is_err());
+        assert!(service
+            .update_ai_settings(AiSettingsInput {
+                timeout_secs: Some(0),
+                ..input(ANTHROPIC)
+            })
+            .is_err());
+        assert_eq!(service.get_ai_settings().unwrap().provider, ANTHROPIC);
+    }
+
+    #[test]
+    fn validates_and_publishes_preference_changes() {
+        let events = Arc::new(EventBus::new());
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let recorder = Arc::clone(&seen);
+        events.subscribe("recorder", move |_, event| {
+            if let DomainEvent::PreferencesChanged(preferences) = event {
+                recorder.lock().unwrap().push(preferences.theme.clone());
+            }
+            Ok(())
+        });
+        let service = PreferencesService::new(init_test_database().unwrap(), events);
+        assert_eq!(service.get_preferences().unwrap(), Preferences::default());
+
+        let mut preferences = Preferences {
+            theme: "dark".to_string(),
+            rollover_time: "04:00".to_string(),
+            ..Preferences::default()
+        };
+        preferences.pomodoro.focus_minutes = 50;
+        preferences.ai.model = Some("  ".to_string());
+        let saved = service.update_preferences(preferences).unwrap();
+        assert_eq!(saved.ai.model, None);
+        assert_eq!(service.get_preferences().unwrap(), saved);
+
+        for invalid in [
+            Preferences {
+                theme: "neon".to_string(),
+                ..saved.clone()
+            },
+            Preferences {
+                rollover_time: "25:00".to_string(),
+                ..saved.clone()
+            },
+            Preferences {
+                working_hours: PlanSettings {
+                    work_start: "18:00".to_string(),
+                    ..PlanSettings::default()
+                },
+                ..saved.clone()
+            },
+            Preferences {
+                pomodoro: PomodoroSettings {
+                    short_break_minutes: 0,
+                    ..PomodoroSettings::default()
+                },
+                ..saved.clone()
+            },
+        ] {
+            assert!(service.update_preferences(invalid).is_err());
+        }
+        assert_eq!(*seen.lock().unwrap(), vec!["dark"]);
+
+        // Working past midnight still counts as the day before
+        let late = NaiveDate::from_ymd_opt(2026, 3, 3).unwrap().and_hms_opt(1, 30, 0).unwrap();
+        assert_eq!(current_day(&saved, late), NaiveDate::from_ymd_opt(2026, 3, 2).unwrap());
+        assert_eq!(current_day(&Preferences::default(), late), late.date());
+    }
+}
@@ -0,0 +1,346 @@
+use crate::db::{
+    models::*,
+    repositories::{PreferencesRepository, TaskRepository},
+    DbPool,
+};
+use crate::services::access::{Access, Role};
+use crate::services::preferences_service::current_day;
+use anyhow::{bail, Result};
+use chrono::{Local, NaiveDate};
+use std::collections::HashSet;
+
+const DEFAULT_LIMIT: usize = 3;
+
+/// Answers "what should I do now?" by scoring actionable tasks against the
+/// current energy and the time available.
+///
+/// The score is a weighted sum of deadline pressure, priority, energy match,
+/// fit into the available time and age, so every suggestion can be explained.
+pub struct RecommendationService {
+    tasks: TaskRepository,
+    preferences: PreferencesRepository,
+    access: Access,
+}
+
+impl RecommendationService {
+    pub fn new(pool: DbPool) -> Self {
+        Self {
+            access: Access::new(pool.clone()),
+            tasks: TaskRepository::new(pool.clone()),
+            preferences: PreferencesRepository::new(pool),
+        }
+    }
+
+    /// The best `limit` tasks (3 by default) for the given energy (`low`,
+    /// `medium` or `high`) and minutes
+    pub fn suggest_next_tasks(
+        &self,
+        current_energy: &str,
+        available_minutes: u32,
+        limit: Option<usize>,
+        weights: Option<ScoringWeights>,
+    ) -> Result<Vec<TaskSuggestion>> {
+        self.access.require(Role::Viewer)?;
+        self.suggest_at(
+            current_energy,
+            available_minutes,
+            limit,
+            weights,
+            current_day(&self.preferences.get()?, Local::now().naive_local()),
+        )
+    }
+
+    fn suggest_at(
+        &self,
+        current_energy: &str,
+        available_minutes: u32,
+        limit: Option<usize>,
+        weights: Option<ScoringWeights>,
+        today: NaiveDate,
+    ) -> Result<Vec<TaskSuggestion>> {
+        let Some(energy) = energy_rank(current_energy) else {
+            bail!("Unknown energy level: {current_energy}");
+        };
+        let weights = weights.unwrap_or_default();
+        let all_weights = [weights.deadline, weights.priority, weights.energy, weights.time_fit, weights.age];
+        if all_weights.iter().any(|w| !w.is_finite() || *w < 0.0) {
+            bail!("Scoring weights must be zero or positive");
+        }
+
+        let tasks = self.tasks.get_all(None)?;
+        // Parents with open subtasks are worked on through their subtasks
+        let open_parents: HashSet<i64> = tasks
+            .iter()
+            .filter(|t| t.status != "completed")
+            .filter_map(|t| t.parent_task_id)
+            .collect();
+
+        let mut suggestions: Vec<TaskSuggestion> = tasks
+            .into_iter()
+            .filter(|t| t.status != "completed" && !open_parents.contains(&t.id))
+            .filter(|t| date_of(t.scheduled_date.as_deref()).is_none_or(|d| d <= today))
+            .map(|task| {
+                let factors = score_factors(&task, energy, available_minutes, today);
+                let score = weights.deadline * factors.deadline
+                    + weights.priority * factors.priority
+                    + weights.energy * factors.energy
+                    + weights.time_fit * factors.time_fit
+                    + weights.age * factors.age;
+                let reason = reason(&task, &factors, &weights, current_energy, available_minutes, today);
+                TaskSuggestion {
+                    task,
+                    score,
+                    factors,
+                    reason,
+                }
+            })
+            .collect();
+
+        suggestions.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.task.due_date.is_none().cmp(&b.task.due_date.is_none()))
+                .then_with(|| a.task.due_date.cmp(&b.task.due_date))
+                .then_with(|| a.task.id.cmp(&b.task.id))
+        });
+        suggestions.truncate(limit.unwrap_or(DEFAULT_LIMIT));
+        Ok(suggestions)
+    }
+}
+
+fn score_factors(task: &Task, energy: i32, available_minutes: u32, today: NaiveDate) -> ScoreFactors {
+    let deadline = match date_of(task.due_date.as_deref()) {
+        Some(due) => {
+            let days_left = (due - today).num_days();
+            if days_left <= 0 {
+                1.0
+            } else {
+                1.0 / (1.0 + days_left as f64 / 3.0)
+            }
+        }
+        None => 0.0,
+    };
+
+    // A task that needs more energy than there is scores low; one that needs
+    // less is fine, just not ideal
+    let energy = match task.energy_level.as_deref().and_then(energy_rank) {
+        Some(needed) if needed == energy => 1.0,
+        Some(needed) if needed < energy => 0.75,
+        Some(needed) if needed == energy + 1 => 0.25,
+        Some(_) => 0.0,
+        None => 0.5,
+    };
+
+    // Tasks that don't fit still count a little, as they can be started
+    let time_fit = match task.estimated_minutes {
+        Some(minutes) if minutes <= available_minutes as i32 => 1.0,
+        Some(minutes) => 0.5 * available_minutes as f64 / minutes as f64,
+        None => 0.5,
+    };
+
+    let age = date_of(Some(&task.created_at))
+        .map(|created| ((today - created).num_days() as f64 / 30.0).clamp(0.0, 1.0))
+        .unwrap_or(0.0);
+
+    ScoreFactors {
+        deadline,
+        priority: (task.priority.clamp(0, 4) as f64) / 4.0,
+        energy,
+        time_fit,
+        age,
+    }
+}
+
+/// Explain a score through its biggest contributions
+fn reason(
+    task: &Task,
+    factors: &ScoreFactors,
+    weights: &ScoringWeights,
+    current_energy: &str,
+    available_minutes: u32,
+    today: NaiveDate,
+) -> String {
+    let mut parts: Vec<(f64, String)> = Vec::new();
+
+    if let Some(due) = date_of(task.due_date.as_deref()) {
+        let days = (due - today).num_days();
+        let text = match days {
+            ..=-2 => format!("overdue by {} days", -days),
+            -1 => "overdue since yesterday".to_string(),
+            0 => "due today".to_string(),
+            1 => "due tomorrow".to_string(),
+            _ => format!("due in {days} days"),
+        };
+        parts.push((weights.deadline * factors.deadline, text));
+    }
+    let priority = match task.priority {
+        4 => Some("urgent"),
+        3 => Some("high priority"),
+        2 => Some("medium priority"),
+        _ => None,
+    };
+    if let Some(priority) = priority {
+        parts.push((weights.priority * factors.priority, priority.to_string()));
+    }
+    if factors.energy >= 1.0 {
+        parts.push((weights.energy * factors.energy, format!("matches your {current_energy} energy")));
+    } else if factors.energy >= 0.75 {
+        parts.push((weights.energy * factors.energy * 0.5, "easy on your energy".to_string()));
+    }
+    if let Some(minutes) = task.estimated_minutes.filter(|_| factors.time_fit >= 1.0) {
+        parts.push((
+            weights.time_fit * factors.time_fit,
+            format!("takes about {minutes} of your {available_minutes} minutes"),
+        ));
+    }
+    if factors.age >= 0.5 {
+        let days = date_of(Some(&task.created_at)).map(|c| (today - c).num_days()).unwrap_or(0);
+        parts.push((weights.age * factors.age, format!("waiting for {days} days")));
+    }
+
+    parts.sort_by(|a, b| b.0.total_cmp(&a.0));
+    let mut reason: Vec<String> = parts
+        .into_iter()
+        .filter(|(contribution, _)| *contribution > 0.0)
+        .take(3)
+        .map(|(_, text)| text)
+        .collect();
+
+    if let Some(minutes) = task.estimated_minutes.filter(|m| *m > available_minutes as i32) {
+        reason.push(format!("needs about {minutes} minutes, so you can make a start"));
+    }
+    if factors.energy < 0.5 && task.energy_level.is_some() {
+        reason.push(format!("needs more energy than {current_energy}"));
+    }
+
+    let reason = if reason.is_empty() {
+        "nothing more pressing is waiting".to_string()
+    } else {
+        reason.join(", ")
+    };
+    let mut chars = reason.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => reason,
+    }
+}
+
+fn energy_rank(level: &str) -> Option<i32> {
+    match level.trim().to_lowercase().as_str() {
+        "low" => Some(1),
+        "medium" => Some(2),
+        "high" => Some(3),
+        _ => None,
+    }
+}
+
+/// The date part of a date or timestamp column
+fn date_of(value: Option<&str>) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value?.get(..10)?, "%Y-%m-%d").ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_test_database;
+    use chrono::Duration;
+
+    struct Fixture {
+        service: RecommendationService,
+        tasks: TaskRepository,
+        today: NaiveDate,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let pool = init_test_database().unwrap();
+            Self {
+                service: RecommendationService::new(pool.clone()),
+                tasks: TaskRepository::new(pool),
+                today: Local::now().date_naive(),
+            }
+        }
+
+        fn task(&self, title: &str, priority: i32, minutes: i32, energy: &str, due_in: Option<i64>) -> Task {
+            self.tasks
+                .create(CreateTaskInput {
+                    title: title.to_string(),
+                    priority: Some(priority),
+                    estimated_minutes: Some(minutes),
+                    energy_level: Some(energy.to_string()),
+                    due_date: due_in.map(|d| (self.today + Duration::days(d)).to_string()),
+                    ..Default::default()
+                })
+                .unwrap()
+        }
+
+        fn titles(&self, energy: &str, minutes: u32) -> Vec<String> {
+            self.service
+                .suggest_at(energy, minutes, Some(10), None, self.today)
+                .unwrap()
+                .into_iter()
+                .map(|s| s.task.title)
+                .collect()
+        }
+    }
+
+    #[test]
+    fn ranks_by_deadline_energy_and_time() {
+        let f = Fixture::new();
+        f.task("Pay rent", 2, 10, "low", Some(0));
+        f.task("Write report", 3, 120, "high", Some(7));
+        f.task("Water plants", 0, 5, "low", None);
+
+        // Low on energy and time, the plants beat the report
+        assert_eq!(f.titles("low", 15), vec!["Pay rent", "Water plants", "Write report"]);
+
+        // With energy and time, the report moves ahead of the chores
+        f.task("Sort photos", 0, 30, "low", None);
+        let best = f.service.suggest_at("high", 180, Some(1), None, f.today).unwrap();
+        assert_eq!(best[0].task.title, "Pay rent");
+        let titles = f.titles("high", 180);
+        assert_eq!(titles[1], "Write report");
+
+        let suggestion = &f.service.suggest_at("low", 15, Some(1), None, f.today).unwrap()[0];
+        assert_eq!(suggestion.reason, "Due today, matches your low energy, takes about 10 of your 15 minutes");
+        assert_eq!(suggestion.factors.deadline, 1.0);
+    }
+
+    #[test]
+    fn skips_tasks_that_are_not_actionable() {
+        let f = Fixture::new();
+        let parent = f.task("Move house", 4, 600, "high", Some(1));
+        f.tasks
+            .create(CreateTaskInput {
+                title: "Book movers".to_string(),
+                estimated_minutes: Some(15),
+                scheduled_date: Some((f.today + Duration::days(2)).to_string()),
+                parent_task_id: Some(parent.id),
+                ..Default::default()
+            })
+            .unwrap();
+        let done = f.task("Done already", 4, 5, "low", Some(0));
+        f.tasks.complete(done.id).unwrap();
+        f.task("Call bank", 1, 10, "medium", None);
+
+        // The parent has an open subtask, which is scheduled for later
+        assert_eq!(f.titles("medium", 30), vec!["Call bank"]);
+        assert!(f.service.suggest_at("sleepy", 30, None, None, f.today).is_err());
+    }
+
+    #[test]
+    fn weights_are_configurable() {
+        let f = Fixture::new();
+        f.task("Due soon", 0, 60, "medium", Some(1));
+        f.task("Important", 4, 60, "medium", None);
+
+        assert_eq!(f.titles("medium", 60), vec!["Due soon", "Important"]);
+        let weights = ScoringWeights {
+            deadline: 0.5,
+            ..ScoringWeights::default()
+        };
+        let suggestions = f.service.suggest_at("medium", 60, None, Some(weights), f.today).unwrap();
+        assert_eq!(suggestions[0].task.title, "Important");
+        assert_eq!(suggestions[0].reason, "Urgent, matches your medium energy, takes about 60 of your 60 minutes");
+    }
+}
@@ -0,0 +1,397 @@
+use crate::db::{
+    models::*,
+    repositories::{reminder_repository::PendingReminder, PreferencesRepository, ReminderRepository, TaskRepository},
+    DbPool,
+};
+use crate::services::event_bus::{DomainEvent, EventBus};
+use crate::services::access::{Access, Role};
+use anyhow::{bail, Result};
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use std::sync::Arc;
+
+const DATETIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+/// Due dates without a time count as due at this time, so that reminders
+/// relative to them fall into the day
+const DEFAULT_DUE_TIME: (u32, u32) = (9, 0);
+const SCHEDULER_TICK: std::time::Duration = std::time::Duration::from_secs(30);
+const MAX_SNOOZE_MINUTES: u32 = 7 * 24 * 60;
+
+/// Where fired reminders go: app events and native notifications
+pub trait ReminderSink: Send + Sync {
+    fn fire(&self, notification: &ReminderNotification);
+}
+
+/// Reminders at fixed times or relative to due dates, with snoozing and
+/// quiet hours. Reminders that came due while the app was closed fire on the
+/// first run of the scheduler.
+pub struct ReminderService {
+    reminders: ReminderRepository,
+    tasks: TaskRepository,
+    preferences: PreferencesRepository,
+    access: Access,
+}
+
+impl ReminderService {
+    pub fn new(pool: DbPool) -> Self {
+        Self {
+            access: Access::new(pool.clone()),
+            reminders: ReminderRepository::new(pool.clone()),
+            tasks: TaskRepository::new(pool.clone()),
+            preferences: PreferencesRepository::new(pool),
+        }
+    }
+
+    /// Dismiss the open reminders of a task when it is completed, so that
+    /// reopening it later does not fire stale ones
+    pub fn subscribe(bus: &EventBus) {
+        bus.subscribe("reminders", |conn, event| {
+            if let DomainEvent::TaskCompleted(task) = event {
+                let now = format_datetime(Local::now().naive_local());
+                ReminderRepository::dismiss_for_task_in(conn, task.id, &now)?;
+            }
+            Ok(())
+        });
+    }
+
+    /// Add a reminder at `remind_at` or `minutes_before_due`, exactly one
+    pub fn add_reminder(&self, task_id: i64, input: CreateReminderInput) -> Result<Reminder> {
+        self.access.require(Role::Editor)?;
+        let task = self.tasks.get_by_id(task_id)?;
+
+        let input = match (&input.remind_at, input.minutes_before_due) {
+            (Some(remind_at), None) => CreateReminderInput {
+                remind_at: Some(format_datetime(parse_datetime(remind_at)?)),
+                minutes_before_due: None,
+            },
+            (None, Some(minutes)) => {
+                if minutes < 0 {
+                    bail!("Minutes before due must not be negative");
+                }
+                if task.due_date.is_none() {
+                    bail!("The task has no due date to remind relative to");
+                }
+                input
+            }
+            _ => bail!("A reminder needs either a time or minutes before due"),
+        };
+
+        let reminder = self.reminders.create(task_id, &input)?;
+        self.with_next_fire_at(reminder, task.due_date.as_deref())
+    }
+
+    pub fn get_reminders(&self, task_id: i64) -> Result<Vec<Reminder>> {
+        self.access.require(Role::Viewer)?;
+        let task = self.tasks.get_by_id(task_id)?;
+        self.reminders
+            .for_task(task_id)?
+            .into_iter()
+            .map(|r| self.with_next_fire_at(r, task.due_date.as_deref()))
+            .collect()
+    }
+
+    pub fn delete_reminder(&self, id: i64) -> Result<()> {
+        self.access.require(Role::Editor)?;
+        self.reminders.delete(id)
+    }
+
+    /// Fire the reminder again in `minutes`
+    pub fn snooze_reminder(&self, id: i64, minutes: u32) -> Result<Reminder> {
+        self.access.require(Role::Editor)?;
+        if !(1..=MAX_SNOOZE_MINUTES).contains(&minutes) {
+            bail!("Snooze for 1 to {MAX_SNOOZE_MINUTES} minutes");
+        }
+        let until = Local::now().naive_local() + Duration::minutes(minutes.into());
+        let reminder = self.reminders.snooze(id, &format_datetime(until))?;
+        let task = self.tasks.get_by_id(reminder.task_id)?;
+        self.with_next_fire_at(reminder, task.due_date.as_deref())
+    }
+
+    /// Fire due reminders every few seconds, starting with those missed
+    /// while the app was closed
+    pub async fn run_scheduler(self: Arc<Self>, sink: Arc<dyn ReminderSink>) {
+        let mut first_run = true;
+        loop {
+            match self.take_due_at(Local::now().naive_local()) {
+                Ok(due) => {
+                    if first_run && !due.is_empty() {
+                        tracing::info!("Catching up on {} missed reminders", due.len());
+                    }
+                    for notification in &due {
+                        sink.fire(notification);
+                    }
+                }
+                Err(e) => tracing::error!("Failed to check reminders: {e:#}"),
+            }
+            first_run = false;
+            tokio::time::sleep(SCHEDULER_TICK).await;
+        }
+    }
+
+    /// Reminders due at `now`, marked as fired. Nothing fires during quiet
+    /// hours; what came due meanwhile fires when they end.
+    fn take_due_at(&self, now: NaiveDateTime) -> Result<Vec<ReminderNotification>> {
+        let quiet = self.quiet_minutes()?;
+        if quiet.is_some_and(|quiet| in_quiet_hours(now, quiet)) {
+            return Ok(Vec::new());
+        }
+
+        let mut due = Vec::new();
+        for PendingReminder { reminder, title, due_date } in self.reminders.pending()? {
+            let Some(fire_at) = base_fire_time(&reminder, due_date.as_deref()) else {
+                continue;
+            };
+            if fire_at > now {
+                continue;
+            }
+
+            self.reminders.mark_fired(reminder.id, &format_datetime(now))?;
+            due.push(ReminderNotification {
+                reminder_id: reminder.id,
+                task_id: reminder.task_id,
+                title,
+                due_date,
+                fire_at: format_datetime(fire_at),
+            });
+        }
+        Ok(due)
+    }
+
+    fn with_next_fire_at(&self, mut reminder: Reminder, due_date: Option<&str>) -> Result<Reminder> {
+        let quiet = self.quiet_minutes()?;
+        reminder.next_fire_at = base_fire_time(&reminder, due_date)
+            .filter(|_| reminder.fired_at.is_none())
+            .map(|at| match quiet {
+                Some(quiet) if in_quiet_hours(at, quiet) => end_of_quiet_hours(at, quiet),
+                _ => at,
+            })
+            .map(format_datetime);
+        Ok(reminder)
+    }
+
+    fn quiet_minutes(&self) -> Result<Option<(u32, u32)>> {
+        match self.preferences.get()?.quiet_hours {
+            Some(hours) => Ok(Some((parse_time(&hours.start)?, parse_time(&hours.end)?))),
+            None => Ok(None),
+        }
+    }
+}
+
+pub fn check_quiet_hours(hours: &QuietHours) -> Result<()> {
+    if parse_time(&hours.start)? == parse_time(&hours.end)? {
+        bail!("Quiet hours must not start and end at the same time");
+    }
+    Ok(())
+}
+
+/// When a reminder is due, before quiet hours are applied
+fn base_fire_time(reminder: &Reminder, due_date: Option<&str>) -> Option<NaiveDateTime> {
+    if let Some(until) = &reminder.snoozed_until {
+        return parse_datetime(until).ok();
+    }
+    if let Some(at) = &reminder.remind_at {
+        return parse_datetime(at).ok();
+    }
+    let due = parse_datetime(due_date?).ok()?;
+    Some(due - Duration::minutes(reminder.minutes_before_due?.into()))
+}
+
+fn in_quiet_hours(time: NaiveDateTime, (start, end): (u32, u32)) -> bool {
+    let minute = time.hour() * 60 + time.minute();
+    if start <= end {
+        start <= minute && minute < end
+    } else {
+        minute >= start || minute < end
+    }
+}
+
+fn end_of_quiet_hours(time: NaiveDateTime, (_, end): (u32, u32)) -> NaiveDateTime {
+    let end_time = NaiveTime::from_hms_opt(end / 60, end % 60, 0).unwrap_or_default();
+    let same_day = time.date().and_time(end_time);
+    if same_day > time {
+        same_day
+    } else {
+        same_day + Duration::days(1)
+    }
+}
+
+/// Accepts dates (at the default due time) and times with or without seconds
+fn parse_datetime(value: &str) -> Result<NaiveDateTime> {
+    let value = value.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let (hour, minute) = DEFAULT_DUE_TIME;
+        return Ok(date.and_hms_opt(hour, minute, 0).unwrap_or_default());
+    }
+    NaiveDateTime::parse_from_str(value, DATETIME_FORMAT)
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M"))
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S"))
+        .map_err(|_| anyhow::anyhow!("Invalid date and time: {value}"))
+}
+
+fn format_datetime(time: NaiveDateTime) -> String {
+    time.format(DATETIME_FORMAT).to_string()
+}
+
+fn parse_time(time: &str) -> Result<u32> {
+    match NaiveTime::parse_from_str(time, "%H:%M") {
+        Ok(parsed) => Ok(parsed.hour() * 60 + parsed.minute()),
+        Err(_) => bail!("Invalid time: {time}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_test_database;
+
+    fn setup() -> (ReminderService, TaskRepository) {
+        let pool = init_test_database().unwrap();
+        (ReminderService::new(pool.clone()), TaskRepository::new(pool))
+    }
+
+    fn task(tasks: &TaskRepository, title: &str, due_date: Option<&str>) -> Task {
+        tasks
+            .create(CreateTaskInput {
+                title: title.to_string(),
+                due_date: due_date.map(String::from),
+                ..Default::default()
+            })
+            .unwrap()
+    }
+
+    fn at(time: &str) -> NaiveDateTime {
+        parse_datetime(time).unwrap()
+    }
+
+    fn fired(service: &ReminderService, now: &str) -> Vec<String> {
+        service
+            .take_due_at(at(now))
+            .unwrap()
+            .into_iter()
+            .map(|n| format!("{} {}", n.title, n.fire_at))
+            .collect()
+    }
+
+    #[test]
+    fn fires_absolute_and_relative_reminders_once() {
+        let (service, tasks) = setup();
+        let dentist = task(&tasks, "Dentist", Some("2026-03-02T15:00:00"));
+        let taxes = task(&tasks, "Taxes", Some("2026-03-05"));
+        let done = task(&tasks, "Done", Some("2026-03-02"));
+
+        let relative = service
+            .add_reminder(
+                dentist.id,
+                CreateReminderInput {
+                    remind_at: None,
+                    minutes_before_due: Some(30),
+                },
+            )
+            .unwrap();
+        assert_eq!(relative.next_fire_at.as_deref(), Some("2026-03-02T14:30:00"));
+        service
+            .add_reminder(
+                taxes.id,
+                CreateReminderInput {
+                    remind_at: Some("2026-03-02T10:00".to_string()),
+                    minutes_before_due: None,
+                },
+            )
+            .unwrap();
+        service
+            .add_reminder(
+                done.id,
+                CreateReminderInput {
+                    remind_at: Some("2026-03-02T08:00".to_string()),
+                    minutes_before_due: None,
+                },
+            )
+            .unwrap();
+        tasks.complete(done.id).unwrap();
+
+        assert!(fired(&service, "2026-03-02T09:59:00").is_empty());
+        // Both came due while the app was closed; they are caught up on
+        assert_eq!(
+            fired(&service, "2026-03-02T16:00:00"),
+            vec!["Dentist 2026-03-02T14:30:00", "Taxes 2026-03-02T10:00:00"]
+        );
+        assert!(fired(&service, "2026-03-02T16:00:30").is_empty());
+        assert_eq!(service.get_reminders(dentist.id).unwrap()[0].next_fire_at, None);
+
+        let snoozed = service.snooze_reminder(relative.id, 10).unwrap();
+        assert!(snoozed.fired_at.is_none() && snoozed.next_fire_at.is_some());
+
+        let no_due = task(&tasks, "Someday", None);
+        let relative_without_due = CreateReminderInput {
+            remind_at: None,
+            minutes_before_due: Some(10),
+        };
+        assert!(service.add_reminder(no_due.id, relative_without_due).is_err());
+        let both = CreateReminderInput {
+            remind_at: Some("2026-03-02T10:00".to_string()),
+            minutes_before_due: Some(10),
+        };
+        assert!(service.add_reminder(dentist.id, both).is_err());
+    }
+
+    #[test]
+    fn holds_reminders_back_during_quiet_hours() {
+        let pool = init_test_database().unwrap();
+        let service = ReminderService::new(pool.clone());
+        let preferences = crate::services::PreferencesService::new(pool.clone(), Arc::new(EventBus::new()));
+        let flight = task(&TaskRepository::new(pool), "Flight", Some("2026-03-03T06:00:00"));
+        preferences
+            .set_quiet_hours(Some(QuietHours {
+                start: "22:00".to_string(),
+                end: "07:00".to_string(),
+            }))
+            .unwrap();
+
+        let reminder = service
+            .add_reminder(
+                flight.id,
+                CreateReminderInput {
+                    remind_at: None,
+                    minutes_before_due: Some(8 * 60),
+                },
+            )
+            .unwrap();
+        assert_eq!(reminder.next_fire_at.as_deref(), Some("2026-03-03T07:00:00"));
+
+        assert!(fired(&service, "2026-03-02T23:00:00").is_empty());
+        assert!(fired(&service, "2026-03-03T06:59:00").is_empty());
+        assert_eq!(fired(&service, "2026-03-03T07:00:00"), vec!["Flight 2026-03-02T22:00:00"]);
+
+        assert!(preferences
+            .set_quiet_hours(Some(QuietHours {
+                start: "22:00".to_string(),
+                end: "22:00".to_string(),
+            }))
+            .is_err());
+        preferences.set_quiet_hours(None).unwrap();
+        assert_eq!(preferences.get_quiet_hours().unwrap(), None);
+    }
+
+    #[test]
+    fn completing_a_task_dismisses_its_reminders() {
+        let pool = init_test_database().unwrap();
+        let bus = Arc::new(EventBus::new());
+        ReminderService::subscribe(&bus);
+        let service = ReminderService::new(pool.clone());
+        let tasks = crate::services::TaskService::new(pool.clone(), bus);
+
+        let report = task(&TaskRepository::new(pool), "Report", None);
+        service
+            .add_reminder(
+                report.id,
+                CreateReminderInput {
+                    remind_at: Some("2026-03-02T10:00".to_string()),
+                    minutes_before_due: None,
+                },
+            )
+            .unwrap();
+        tasks.complete_task(report.id).unwrap();
+        tasks.move_task(report.id, "todo", 0, None).unwrap();
+
+        assert!(fired(&service, "2026-03-02T16:00:00").is_empty());
+    }
+}
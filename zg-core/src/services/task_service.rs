@@ -0,0 +1,527 @@
+use crate::db::{
+    models::*,
+    repositories::{PreferencesRepository, ProjectRepository, TaskRepository, WorkspaceRepository},
+    DbPool,
+};
+use crate::formats::quick_add;
+use crate::services::access::{Access, Role};
+use crate::services::event_bus::{status_events, DomainEvent, EventBus};
+use crate::services::preferences_service::current_day;
+use anyhow::{bail, Context, Result};
+use chrono::{Duration, Local, NaiveDate};
+use std::sync::Arc;
+
+/// Task operations. Every change is published on the event bus from inside
+/// its transaction. Reading needs the viewer role, changing the editor role.
+pub struct TaskService {
+    pool: DbPool,
+    repository: TaskRepository,
+    projects: ProjectRepository,
+    workspaces: WorkspaceRepository,
+    preferences: PreferencesRepository,
+    access: Access,
+    events: Arc<EventBus>,
+}
+
+impl TaskService {
+    pub fn new(pool: DbPool, events: Arc<EventBus>) -> Self {
+        Self {
+            repository: TaskRepository::new(pool.clone()),
+            projects: ProjectRepository::new(pool.clone()),
+            workspaces: WorkspaceRepository::new(pool.clone()),
+            preferences: PreferencesRepository::new(pool.clone()),
+            access: Access::new(pool.clone()),
+            pool,
+            events,
+        }
+    }
+
+    pub fn create_task(&self, input: CreateTaskInput) -> Result<Task> {
+        self.access.require(Role::Editor)?;
+        self.events.transaction(&self.pool, |tx, events| {
+            let task = TaskRepository::create_in(tx, input)?;
+            events.push(DomainEvent::TaskCreated(task.clone()));
+            Ok(task)
+        })
+    }
+
+    /// Create several tasks in one transaction, so either all or none exist
+    pub fn create_tasks(&self, inputs: Vec<CreateTaskInput>) -> Result<Vec<Task>> {
+        self.access.require(Role::Editor)?;
+        self.events.transaction(&self.pool, |tx, events| {
+            inputs
+                .into_iter()
+                .map(|input| {
+                    let task = TaskRepository::create_in(tx, input)?;
+                    events.push(DomainEvent::TaskCreated(task.clone()));
+                    Ok(task)
+                })
+                .collect()
+        })
+    }
+
+    pub fn get_task(&self, id: i64) -> Result<Task> {
+        self.access.require(Role::Viewer)?;
+        self.repository.get_by_id(id)
+    }
+
+    pub fn get_all_tasks(&self, status: Option<String>) -> Result<Vec<Task>> {
+        self.access.require(Role::Viewer)?;
+        self.repository.get_all(status)
+    }
+
+    /// Tasks matching all filters of the query
+    pub fn query_tasks(&self, query: &TaskQuery) -> Result<Vec<Task>> {
+        self.access.require(Role::Viewer)?;
+        let today = current_day(&self.preferences.get()?, Local::now().naive_local());
+        let due_by = match query.due.as_deref() {
+            None => None,
+            Some("today") => Some(today),
+            Some("overdue") => Some(today - Duration::days(1)),
+            Some(date) => Some(
+                NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                    .with_context(|| format!("Invalid due filter: {date}"))?,
+            ),
+        };
+        let words: Vec<String> = query
+            .search
+            .iter()
+            .flat_map(|search| search.split_whitespace())
+            .map(str::to_lowercase)
+            .collect();
+
+        let tasks = self.repository.get_all(query.status.clone())?;
+        Ok(tasks
+            .into_iter()
+            .filter(|task| query.project_id.is_none() || task.project_id == query.project_id)
+            .filter(|task| match due_by {
+                Some(due_by) => due_date_of(task).is_some_and(|due| due <= due_by),
+                None => true,
+            })
+            .filter(|task| {
+                let text = format!("{} {}", task.title, task.description.as_deref().unwrap_or_default()).to_lowercase();
+                words.iter().all(|word| text.contains(word.as_str()))
+            })
+            .collect())
+    }
+
+    /// Tasks assigned to the signed-in user
+    pub fn get_my_tasks(&self, status: Option<&str>) -> Result<Vec<Task>> {
+        self.access.require(Role::Viewer)?;
+        self.repository.get_assigned_to_current(status)
+    }
+
+    /// Apply an edit. With `expected_version` set, fails with
+    /// `TaskError::Conflict` if the task changed since.
+    pub fn update_task(&self, id: i64, input: UpdateTaskInput) -> Result<Task> {
+        self.access.require(Role::Editor)?;
+        self.events.transaction(&self.pool, |tx, events| {
+            let previous = TaskRepository::get_by_id_in(tx, id)?;
+            let task = TaskRepository::update_in(tx, id, input)?;
+            events.push(DomainEvent::TaskUpdated(task.clone()));
+            events.extend(status_events(&previous, &task));
+            Ok(task)
+        })
+    }
+
+    /// Create a task from an item of another app, e.g. a synced one, keeping
+    /// its UID if it has one. `fill` sets the fields on the blank task and
+    /// must not write to the database itself.
+    pub fn import_task(&self, uid: Option<&str>, fill: impl FnOnce(&mut Task) -> Result<()>) -> Result<Task> {
+        self.import_task_in_workspace(self.workspaces.active_id()?, uid, fill)
+    }
+
+    /// `import_task` for a sync that stays in the workspace it was started in
+    pub fn import_task_in_workspace(
+        &self,
+        workspace_id: i64,
+        uid: Option<&str>,
+        fill: impl FnOnce(&mut Task) -> Result<()>,
+    ) -> Result<Task> {
+        self.access.require_in(workspace_id, Role::Editor)?;
+        self.events.transaction(&self.pool, |tx, events| {
+            let task = TaskRepository::create_in_workspace(tx, workspace_id, CreateTaskInput::default())?;
+            let mut task = match uid {
+                Some(uid) => TaskRepository::set_uid_in(tx, workspace_id, task.id, uid)?,
+                None => task,
+            };
+            fill(&mut task)?;
+            let task = TaskRepository::overwrite_in(tx, workspace_id, task.id, &task)?;
+            events.push(DomainEvent::TaskCreated(task.clone()));
+            Ok(task)
+        })
+    }
+
+    /// Replace all user-editable fields of a task, e.g. with its synced version
+    pub fn overwrite_task(&self, id: i64, task: &Task) -> Result<Task> {
+        self.overwrite_task_in_workspace(self.workspaces.active_id()?, id, task)
+    }
+
+    pub fn overwrite_task_in_workspace(&self, workspace_id: i64, id: i64, task: &Task) -> Result<Task> {
+        self.access.require_in(workspace_id, Role::Editor)?;
+        self.events.transaction(&self.pool, |tx, events| {
+            let previous = TaskRepository::get_by_id_in_workspace(tx, workspace_id, id)?;
+            let task = TaskRepository::overwrite_in(tx, workspace_id, id, task)?;
+            events.push(DomainEvent::TaskUpdated(task.clone()));
+            events.extend(status_events(&previous, &task));
+            Ok(task)
+        })
+    }
+
+    pub fn delete_task(&self, id: i64) -> Result<()> {
+        self.delete_task_in_workspace(self.workspaces.active_id()?, id)
+    }
+
+    pub fn delete_task_in_workspace(&self, workspace_id: i64, id: i64) -> Result<()> {
+        self.access.require_in(workspace_id, Role::Editor)?;
+        self.events.transaction(&self.pool, |tx, events| {
+            let task = TaskRepository::get_by_id_in_workspace(tx, workspace_id, id)?;
+            TaskRepository::delete_in_workspace(tx, workspace_id, id)?;
+            events.push(DomainEvent::TaskDeleted(task));
+            Ok(())
+        })
+    }
+
+    pub fn complete_task(&self, id: i64) -> Result<Task> {
+        self.access.require(Role::Editor)?;
+        self.events.transaction(&self.pool, |tx, events| {
+            let previous = TaskRepository::get_by_id_in(tx, id)?;
+            let task = TaskRepository::complete_in(tx, id)?;
+            events.push(DomainEvent::TaskUpdated(task.clone()));
+            events.extend(status_events(&previous, &task));
+            Ok(task)
+        })
+    }
+
+    /// Move a task to a Kanban column (status) and position. With an
+    /// `expected_version`, fails with `TaskError::Conflict` if the task
+    /// changed since.
+    pub fn move_task(&self, id: i64, status: &str, position: i32, expected_version: Option<i64>) -> Result<Task> {
+        self.access.require(Role::Editor)?;
+        self.events.transaction(&self.pool, |tx, events| {
+            let previous = TaskRepository::get_by_id_in(tx, id)?;
+            let task = TaskRepository::move_to_column_in(tx, id, status, position, expected_version)?;
+            events.push(DomainEvent::TaskMoved(task.clone()));
+            events.extend(status_events(&previous, &task));
+            Ok(task)
+        })
+    }
+
+    /// Assign a task to a member of its workspace, or unassign it with `None`
+    pub fn assign_task(&self, id: i64, assignee_id: Option<i64>) -> Result<Task> {
+        self.access.require(Role::Editor)?;
+        if let Some(user_id) = assignee_id {
+            if self.workspaces.role_of(self.workspaces.active_id()?, user_id)?.is_none() {
+                bail!("User {user_id} is not a member of this workspace");
+            }
+        }
+
+        self.events.transaction(&self.pool, |tx, events| {
+            let task = TaskRepository::assign_in(tx, id, assignee_id)?;
+            events.push(DomainEvent::TaskUpdated(task.clone()));
+            Ok(task)
+        })
+    }
+
+    // Subtask methods
+    pub fn get_task_with_subtasks(&self, id: i64) -> Result<TaskWithSubtasks> {
+        self.access.require(Role::Viewer)?;
+        self.repository.get_with_subtasks(id)
+    }
+
+    pub fn get_subtasks(&self, parent_id: i64) -> Result<Vec<Task>> {
+        self.access.require(Role::Viewer)?;
+        self.repository.get_subtasks(parent_id)
+    }
+
+    /// Parse quick-add text like "call dentist tomorrow 3pm #health !2" into
+    /// task input, without saving anything
+    pub fn parse_quick_add(&self, text: &str) -> Result<QuickAddPreview> {
+        self.access.require(Role::Viewer)?;
+        let parsed = quick_add::parse(text, Local::now().naive_local());
+        if parsed.title.is_empty() {
+            bail!("Quick add text has no title");
+        }
+
+        let project_id = match &parsed.project {
+            Some(name) => self.projects.find_by_name(name)?.map(|p| p.id),
+            None => None,
+        };
+
+        Ok(QuickAddPreview {
+            input: CreateTaskInput {
+                title: parsed.title,
+                project_id,
+                priority: parsed.priority,
+                estimated_minutes: parsed.estimated_minutes,
+                energy_level: parsed.energy_level,
+                scheduled_date: parsed.scheduled_date,
+                due_date: parsed.due_date,
+                tags: (!parsed.tags.is_empty()).then_some(parsed.tags),
+                ..Default::default()
+            },
+            project_name: parsed.project,
+        })
+    }
+}
+
+/// The day a task is due, ignoring the time
+fn due_date_of(task: &Task) -> Option<NaiveDate> {
+    let due = task.due_date.as_deref()?;
+    NaiveDate::parse_from_str(due.get(..10)?, "%Y-%m-%d").ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{init_test_database, repositories::TaskError};
+    use std::sync::Mutex;
+
+    #[test]
+    fn publishes_every_change() {
+        let events = Arc::new(EventBus::new());
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let recorder = Arc::clone(&seen);
+        events.subscribe("recorder", move |_, event| {
+            let name = match event {
+                DomainEvent::TaskCreated(_) => "created",
+                DomainEvent::TaskUpdated(_) => "updated",
+                DomainEvent::TaskMoved(_) => "moved",
+                DomainEvent::TaskStatusChanged { .. } => "status_changed",
+                DomainEvent::TaskCompleted(_) => "completed",
+                DomainEvent::TaskDeleted(_) => "deleted",
+                DomainEvent::PreferencesChanged(_) => return Ok(()),
+            };
+            recorder
+                .lock()
+                .unwrap()
+                .push(format!("{name} {}", event.task().unwrap().status));
+            Ok(())
+        });
+        let service = TaskService::new(init_test_database().unwrap(), events);
+
+        let task = service
+            .create_task(CreateTaskInput {
+                title: "Water plants".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        service.move_task(task.id, "in_progress", 0, None).unwrap();
+        service.move_task(task.id, "in_progress", 1, None).unwrap();
+        service.complete_task(task.id).unwrap();
+        service.delete_task(task.id).unwrap();
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                "created todo",
+                "moved in_progress",
+                "status_changed in_progress",
+                "moved in_progress",
+                "updated completed",
+                "status_changed completed",
+                "completed completed",
+                "deleted completed",
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_edits_of_a_stale_version() {
+        let events = Arc::new(EventBus::new());
+        let seen = Arc::new(Mutex::new(0));
+        let counter = Arc::clone(&seen);
+        events.subscribe("counter", move |_, _| {
+            *counter.lock().unwrap() += 1;
+            Ok(())
+        });
+        let service = TaskService::new(init_test_database().unwrap(), events);
+        let task = service.create_task(serde_json::from_str(r#"{"title": "Draft"}"#).unwrap()).unwrap();
+        assert_eq!(task.version, 1);
+
+        let edit = |title: &str, expected_version: Option<i64>| -> UpdateTaskInput {
+            serde_json::from_value(serde_json::json!({ "title": title, "expected_version": expected_version }))
+                .unwrap()
+        };
+        let renamed = service.update_task(task.id, edit("Final", Some(task.version))).unwrap();
+        assert_eq!(renamed.version, 2);
+        let published = *seen.lock().unwrap();
+
+        // Another window still holds version 1
+        let stale = service.update_task(task.id, edit("Draft 2", Some(task.version))).unwrap_err();
+        match stale.downcast_ref::<TaskError>() {
+            Some(TaskError::Conflict(current)) => assert_eq!((current.title.as_str(), current.version), ("Final", 2)),
+            None => panic!("expected a conflict, got {stale:#}"),
+        }
+        let stale = service.move_task(task.id, "in_progress", 0, Some(task.version)).unwrap_err();
+        assert!(stale.downcast_ref::<TaskError>().is_some());
+        assert_eq!(*seen.lock().unwrap(), published);
+        assert_eq!(service.get_task(task.id).unwrap().title, "Final");
+
+        // Any write counts, and without a version edits always apply
+        let completed = service.complete_task(task.id).unwrap();
+        assert_eq!(completed.version, 3);
+        let moved = service.move_task(task.id, "todo", 0, Some(completed.version)).unwrap();
+        assert_eq!(moved.version, 4);
+        assert_eq!(service.update_task(task.id, edit("Again", None)).unwrap().version, 5);
+    }
+
+    /// A task with every optional field set, and another one to be its parent
+    fn full_task(service: &TaskService) -> (Task, Task) {
+        let project = ProjectRepository::new(service.pool.clone())
+            .create(CreateProjectInput {
+                name: "Home".into(),
+                color: "#fff".into(),
+                icon: None,
+            })
+            .unwrap();
+        let parent = service.create_task(serde_json::from_str(r#"{"title": "Move"}"#).unwrap()).unwrap();
+        let input = serde_json::json!({
+            "title": "Pack books",
+            "description": "Into the small boxes",
+            "project_id": project.id,
+            "priority": 2,
+            "estimated_minutes": 45,
+            "difficulty_level": 3,
+            "energy_level": "low",
+            "scheduled_date": "2030-01-01",
+            "due_date": "2030-01-02",
+            "parent_task_id": parent.id,
+            "tags": ["home"],
+        });
+        (service.create_task(serde_json::from_value(input).unwrap()).unwrap(), parent)
+    }
+
+    fn patch(service: &TaskService, id: i64, patch: serde_json::Value) -> Result<Task> {
+        service.update_task(id, serde_json::from_value(patch).unwrap())
+    }
+
+    #[test]
+    fn patches_set_and_clear_every_field() {
+        let service = TaskService::new(init_test_database().unwrap(), Arc::new(EventBus::new()));
+        let (task, parent) = full_task(&service);
+        let other_project = ProjectRepository::new(service.pool.clone())
+            .create(CreateProjectInput {
+                name: "Work".into(),
+                color: "#000".into(),
+                icon: None,
+            })
+            .unwrap();
+
+        let set = patch(
+            &service,
+            task.id,
+            serde_json::json!({
+                "title": "Pack all books",
+                "description": "Heavy ones first",
+                "project_id": other_project.id,
+                "status": "in_progress",
+                "priority": 4,
+                "estimated_minutes": 60,
+                "difficulty_level": 5,
+                "energy_level": "high",
+                "scheduled_date": "2030-02-01",
+                "due_date": "2030-02-02T17:00",
+                "parent_task_id": null,
+                "order_index": 7,
+                "tags": ["home", "boxes"],
+            }),
+        )
+        .unwrap();
+        assert_eq!(set.title, "Pack all books");
+        assert_eq!(set.description.as_deref(), Some("Heavy ones first"));
+        assert_eq!(set.project_id, Some(other_project.id));
+        assert_eq!(set.status, "in_progress");
+        assert_eq!(set.priority, 4);
+        assert_eq!(set.estimated_minutes, Some(60));
+        assert_eq!(set.difficulty_level, Some(5));
+        assert_eq!(set.energy_level.as_deref(), Some("high"));
+        assert_eq!(set.scheduled_date.as_deref(), Some("2030-02-01"));
+        assert_eq!(set.due_date.as_deref(), Some("2030-02-02T17:00"));
+        assert_eq!(set.parent_task_id, None);
+        assert_eq!(set.order_index, 7);
+        assert_eq!(set.tags.as_deref(), Some(r#"["home","boxes"]"#));
+        assert_eq!(set.version, task.version + 1);
+
+        let cleared = patch(
+            &service,
+            task.id,
+            serde_json::json!({
+                "description": null,
+                "project_id": null,
+                "estimated_minutes": null,
+                "difficulty_level": null,
+                "energy_level": null,
+                "scheduled_date": null,
+                "due_date": null,
+                "tags": null,
+            }),
+        )
+        .unwrap();
+        assert_eq!(
+            (&cleared.description, cleared.project_id, cleared.estimated_minutes, cleared.difficulty_level),
+            (&None, None, None, None)
+        );
+        assert_eq!((&cleared.energy_level, &cleared.scheduled_date, &cleared.due_date), (&None, &None, &None));
+        assert_eq!(cleared.tags, None);
+        assert_eq!(cleared.title, "Pack all books");
+
+        // Left out fields stay as they are
+        let reparented = patch(&service, task.id, serde_json::json!({ "parent_task_id": parent.id })).unwrap();
+        assert_eq!(reparented.parent_task_id, Some(parent.id));
+        assert_eq!((reparented.title, reparented.status, reparented.priority, reparented.order_index), (set.title, set.status, 4, 7));
+    }
+
+    #[test]
+    fn rejects_invalid_patches_without_changing_anything() {
+        let service = TaskService::new(init_test_database().unwrap(), Arc::new(EventBus::new()));
+        let (task, parent) = full_task(&service);
+
+        for invalid in [
+            serde_json::json!({ "title": null }),
+            serde_json::json!({ "title": "  " }),
+            serde_json::json!({ "status": null }),
+            serde_json::json!({ "status": "garbage" }),
+            serde_json::json!({ "priority": null }),
+            serde_json::json!({ "priority": 9 }),
+            serde_json::json!({ "order_index": null }),
+            serde_json::json!({ "estimated_minutes": 0 }),
+            serde_json::json!({ "difficulty_level": 6 }),
+            serde_json::json!({ "energy_level": "lots" }),
+            serde_json::json!({ "scheduled_date": "tomorrow" }),
+            serde_json::json!({ "due_date": "2030-13-01" }),
+            serde_json::json!({ "project_id": 999 }),
+            serde_json::json!({ "parent_task_id": 999 }),
+            serde_json::json!({ "parent_task_id": task.id }),
+            // Valid changes are not applied either when one is invalid
+            serde_json::json!({ "description": "Changed", "tags": ["moved"], "priority": -1 }),
+        ] {
+            assert!(patch(&service, task.id, invalid.clone()).is_err(), "{invalid} was accepted");
+        }
+        // The parent would become a subtask of its own subtask
+        assert!(patch(&service, parent.id, serde_json::json!({ "parent_task_id": task.id })).is_err());
+        assert!(service.move_task(task.id, "done", 0, None).is_err());
+
+        let unchanged = service.get_task(task.id).unwrap();
+        assert_eq!(unchanged.version, task.version);
+        assert_eq!(unchanged.description, task.description);
+        assert_eq!(unchanged.tags, task.tags);
+    }
+
+    #[test]
+    fn keeps_the_completion_time_in_step_with_the_status() {
+        let service = TaskService::new(init_test_database().unwrap(), Arc::new(EventBus::new()));
+        let (task, _) = full_task(&service);
+
+        let completed = patch(&service, task.id, serde_json::json!({ "status": "completed" })).unwrap();
+        assert!(completed.completed_at.is_some());
+        let reordered = service.move_task(task.id, "completed", 3, None).unwrap();
+        assert_eq!(reordered.completed_at, completed.completed_at);
+
+        let reopened = service.move_task(task.id, "todo", 0, None).unwrap();
+        assert_eq!(reopened.completed_at, None);
+        assert!(service.move_task(task.id, "completed", 0, None).unwrap().completed_at.is_some());
+        let started = patch(&service, task.id, serde_json::json!({ "status": "in_progress" })).unwrap();
+        assert_eq!(started.completed_at, None);
+    }
+}
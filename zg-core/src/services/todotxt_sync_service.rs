@@ -0,0 +1,607 @@
+use crate::db::{
+    models::*,
+    repositories::{ProjectRepository, TaskRepository, TodoTxtStateRepository, WorkspaceRepository},
+    DbPool,
+};
+use crate::formats::todotxt::{self, TodoItem};
+use crate::services::access::{Access, Role};
+use crate::services::{EventBus, TaskService};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+
+/// Quiet period after a file event before syncing, so editors that write in
+/// several steps only trigger one sync
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Two-way sync between tasks and a todo.txt file.
+///
+/// Every synced line carries the task ID as `zg:<id>`. The canonical line of
+/// each task is remembered after a sync, so the next sync can tell whether the
+/// file, the app or both changed a task. When both changed, the newer side
+/// wins (file modification time vs. the task's last update).
+///
+/// A sync stays in the workspace that was active when it started, and a
+/// watched file keeps syncing with the workspace it was watched from.
+pub struct TodoTxtSyncService {
+    tasks: TaskRepository,
+    task_service: TaskService,
+    projects: ProjectRepository,
+    state: TodoTxtStateRepository,
+    workspaces: WorkspaceRepository,
+    watcher: Mutex<Option<RecommendedWatcher>>,
+    access: Access,
+}
+
+impl TodoTxtSyncService {
+    pub fn new(pool: DbPool, events: Arc<EventBus>) -> Self {
+        Self {
+            access: Access::new(pool.clone()),
+            tasks: TaskRepository::new(pool.clone()),
+            task_service: TaskService::new(pool.clone(), events),
+            projects: ProjectRepository::new(pool.clone()),
+            workspaces: WorkspaceRepository::new(pool.clone()),
+            state: TodoTxtStateRepository::new(pool),
+            watcher: Mutex::new(None),
+        }
+    }
+
+    /// Reconcile the file with the active workspace and write the merged
+    /// result back
+    pub fn sync_file(&self, path: &Path) -> Result<TodoTxtSyncReport> {
+        self.sync_file_in_workspace(self.workspaces.active_id()?, path)
+    }
+
+    fn sync_file_in_workspace(&self, workspace_id: i64, path: &Path) -> Result<TodoTxtSyncReport> {
+        self.access.require_in(workspace_id, Role::Editor)?;
+        let key = path.to_string_lossy().to_string();
+        let mut report = TodoTxtSyncReport::default();
+
+        let previous = self.state.load(workspace_id, &key)?;
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                // A synced file that went missing may be on an unmounted drive
+                // or in the middle of an editor's save; reading it as empty
+                // would delete every task in it
+                if !previous.is_empty() {
+                    bail!("{} was synced before but is missing", path.display());
+                }
+                String::new()
+            }
+            Err(e) => return Err(e).context(format!("Failed to read {}", path.display())),
+        };
+        let file_modified = std::fs::metadata(path)
+            .and_then(|m| m.modified())
+            .map(DateTime::<Utc>::from)
+            .unwrap_or(DateTime::<Utc>::UNIX_EPOCH);
+
+        let mut project_names: HashMap<i64, String> = self
+            .projects
+            .get_all_in_workspace(workspace_id)?
+            .into_iter()
+            .map(|p| (p.id, p.name))
+            .collect();
+        let mut tasks: HashMap<i64, Task> = self
+            .tasks
+            .get_all_in_workspace(workspace_id)?
+            .into_iter()
+            .map(|t| (t.id, t))
+            .collect();
+
+        let mut lines = Vec::new();
+        let mut next_state = HashMap::new();
+        let mut seen = HashSet::new();
+
+        for raw in content.lines() {
+            let Some(mut item) = todotxt::parse_line(raw) else {
+                continue;
+            };
+
+            // A copied line must not take over the original task
+            if let Some(id) = item.id {
+                if !seen.insert(id) {
+                    item.id = None;
+                }
+            }
+
+            let task = match item.id {
+                Some(id) => match (tasks.remove(&id), previous.get(&id)) {
+                    (Some(task), Some(synced)) => {
+                        let file_line = todotxt::serialize_line(&item);
+                        let task_line = app_line(&task, &project_names, todotxt::parse_line(synced).as_ref());
+                        let file_changed = &file_line != synced;
+                        let app_changed = &task_line != synced;
+
+                        if file_changed && app_changed {
+                            report.conflicts += 1;
+                        }
+                        if file_changed && (!app_changed || file_modified >= task_modified_at(&task)) {
+                            report.updated_in_app += 1;
+                            self.update_from_item(workspace_id, task, &item, &mut project_names)?
+                        } else {
+                            task
+                        }
+                    }
+                    (Some(task), None) => {
+                        // Never synced with this file before: newer side wins
+                        let differs =
+                            todotxt::serialize_line(&item) != app_line(&task, &project_names, Some(&item));
+                        if differs && file_modified >= task_modified_at(&task) {
+                            report.updated_in_app += 1;
+                            self.update_from_item(workspace_id, task, &item, &mut project_names)?
+                        } else {
+                            task
+                        }
+                    }
+                    (None, Some(_)) => {
+                        // Deleted in the app since the last sync
+                        continue;
+                    }
+                    (None, None) => {
+                        report.created_in_app += 1;
+                        self.create_from_item(workspace_id, &item, &mut project_names)?
+                    }
+                },
+                None => {
+                    report.created_in_app += 1;
+                    self.create_from_item(workspace_id, &item, &mut project_names)?
+                }
+            };
+
+            let line = app_line(&task, &project_names, Some(&item));
+            next_state.insert(task.id, line.clone());
+            lines.push(line);
+        }
+
+        // Tasks that are not (or no longer) in the file
+        let mut remaining: Vec<Task> = tasks.into_values().collect();
+        remaining.sort_by_key(|t| t.id);
+        for task in remaining {
+            let synced = previous.get(&task.id).and_then(|line| todotxt::parse_line(line));
+            let line = app_line(&task, &project_names, synced.as_ref());
+            if previous.get(&task.id) == Some(&line) {
+                // Removed from the file and unchanged in the app
+                self.task_service.delete_task_in_workspace(workspace_id, task.id)?;
+                report.deleted_in_app += 1;
+                continue;
+            }
+            next_state.insert(task.id, line.clone());
+            lines.push(line);
+        }
+
+        let mut output = lines.join("\n");
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        if output != content {
+            write_atomically(path, &output)?;
+            report.file_written = true;
+        }
+
+        self.state.replace(workspace_id, &key, &next_state)?;
+        report.lines = lines.len();
+
+        tracing::info!(
+            "todo.txt sync of {}: {} created, {} updated, {} deleted, {} conflicts",
+            path.display(),
+            report.created_in_app,
+            report.updated_in_app,
+            report.deleted_in_app,
+            report.conflicts
+        );
+        Ok(report)
+    }
+
+    /// Sync once, then keep syncing whenever the file changes on disk.
+    /// Replaces any previously watched file.
+    pub fn watch(self: &Arc<Self>, path: PathBuf) -> Result<()> {
+        let workspace_id = self.workspaces.active_id()?;
+        self.sync_file_in_workspace(workspace_id, &path)?;
+
+        // Watch the directory: editors often replace the file instead of writing to it
+        let dir = path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."))
+            .to_path_buf();
+        let file_name = path.file_name().map(|n| n.to_os_string());
+
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            if let Ok(event) = event {
+                if event.paths.iter().any(|p| p.file_name() == file_name.as_deref()) {
+                    let _ = tx.send(());
+                }
+            }
+        })?;
+        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+
+        // The thread ends when the watcher (and with it the sender) is dropped
+        let service = Arc::clone(self);
+        std::thread::spawn(move || {
+            while rx.recv().is_ok() {
+                while rx.recv_timeout(WATCH_DEBOUNCE).is_ok() {}
+                if let Err(e) = service.sync_file_in_workspace(workspace_id, &path) {
+                    tracing::error!("todo.txt sync of {} failed: {e:#}", path.display());
+                }
+            }
+        });
+
+        *self.watcher.lock().unwrap() = Some(watcher);
+        Ok(())
+    }
+
+    /// Stop watching the todo.txt file, if any
+    pub fn unwatch(&self) {
+        self.watcher.lock().unwrap().take();
+    }
+
+    fn create_from_item(
+        &self,
+        workspace_id: i64,
+        item: &TodoItem,
+        project_names: &mut HashMap<i64, String>,
+    ) -> Result<Task> {
+        let project_id = self.item_project(workspace_id, item, project_names)?;
+        self.task_service
+            .import_task_in_workspace(workspace_id, None, |task| apply_item(task, item, project_id))
+    }
+
+    fn update_from_item(
+        &self,
+        workspace_id: i64,
+        task: Task,
+        item: &TodoItem,
+        project_names: &mut HashMap<i64, String>,
+    ) -> Result<Task> {
+        let project_id = self.item_project(workspace_id, item, project_names)?;
+        let mut updated = task.clone();
+        apply_item(&mut updated, item, project_id)?;
+        self.task_service.overwrite_task_in_workspace(workspace_id, task.id, &updated)
+    }
+
+    /// The project of the first `+project` token of an item, if any
+    fn item_project(
+        &self,
+        workspace_id: i64,
+        item: &TodoItem,
+        project_names: &mut HashMap<i64, String>,
+    ) -> Result<Option<i64>> {
+        match item.projects.first() {
+            Some(token) => Ok(Some(self.find_project(workspace_id, token, project_names)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Resolve a `+project` token, matching names with spaces written as dashes.
+    /// Unknown projects are created and added to `project_names`.
+    fn find_project(&self, workspace_id: i64, token: &str, project_names: &mut HashMap<i64, String>) -> Result<i64> {
+        let existing = project_names
+            .iter()
+            .filter(|(_, name)| todotxt::to_token(name).eq_ignore_ascii_case(token))
+            .map(|(id, _)| *id)
+            .min();
+        if let Some(id) = existing {
+            return Ok(id);
+        }
+
+        let project = self.projects.find_or_create_in_workspace(workspace_id, token)?;
+        project_names.insert(project.id, project.name);
+        Ok(project.id)
+    }
+}
+
+/// The line of a task, keeping what only the file knows from `line`, the
+/// task's line in the file or as last synced: its creation date and any
+/// other `key:value` pairs such as `rec:1w`
+fn app_line(task: &Task, project_names: &HashMap<i64, String>, line: Option<&TodoItem>) -> String {
+    let mut item = task_to_item(task, project_names);
+    if let Some(line) = line {
+        item.creation_date = line.creation_date.clone();
+        item.extras = line.extras.clone();
+    }
+    todotxt::serialize_line(&item)
+}
+
+/// Write the fields represented in todo.txt from an item onto a task
+fn apply_item(task: &mut Task, item: &TodoItem, project_id: Option<i64>) -> Result<()> {
+    // Tags with spaces are written as dashed contexts; keep the original tag
+    let tags = task_tags(task);
+    let contexts: Vec<&String> = item
+        .contexts
+        .iter()
+        .map(|context| tags.iter().find(|tag| todotxt::to_token(tag) == *context).unwrap_or(context))
+        .collect();
+    task.tags = if contexts.is_empty() {
+        None
+    } else {
+        Some(serde_json::to_string(&contexts)?)
+    };
+    task.title = item.text.clone();
+    task.priority = item.priority.map(letter_to_priority).unwrap_or(0);
+    task.due_date = item.due.clone();
+    task.project_id = project_id;
+
+    if item.completed {
+        if task.status != "completed" {
+            task.status = "completed".to_string();
+            task.completed_at = Some(match &item.completion_date {
+                Some(date) => format!("{date} 00:00:00"),
+                None => Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            });
+        }
+    } else if task.status == "completed" {
+        task.status = "todo".to_string();
+        task.completed_at = None;
+    }
+    Ok(())
+}
+
+fn task_tags(task: &Task) -> Vec<String> {
+    task.tags
+        .as_deref()
+        .and_then(|t| serde_json::from_str(t).ok())
+        .unwrap_or_default()
+}
+
+/// Build the todo.txt representation of a task
+pub fn task_to_item(task: &Task, project_names: &HashMap<i64, String>) -> TodoItem {
+    let tags = task_tags(task);
+
+    TodoItem {
+        completed: task.status == "completed",
+        priority: priority_to_letter(task.priority),
+        completion_date: task.completed_at.as_deref().map(date_part),
+        creation_date: Some(date_part(&task.created_at)),
+        text: task.title.clone(),
+        projects: task
+            .project_id
+            .and_then(|id| project_names.get(&id))
+            .map(|name| vec![todotxt::to_token(name)])
+            .unwrap_or_default(),
+        contexts: tags.iter().map(|t| todotxt::to_token(t)).collect(),
+        due: task.due_date.as_deref().map(date_part),
+        id: Some(task.id),
+        extras: Vec::new(),
+    }
+}
+
+/// Map the numeric task priority (0 = none, 4 = urgent) to a todo.txt letter
+pub fn priority_to_letter(priority: i32) -> Option<char> {
+    match priority {
+        p if p >= 4 => Some('A'),
+        3 => Some('B'),
+        2 => Some('C'),
+        1 => Some('D'),
+        _ => None,
+    }
+}
+
+/// Map a todo.txt priority letter to the numeric task priority
+pub fn letter_to_priority(letter: char) -> i32 {
+    match letter {
+        'A' => 4,
+        'B' => 3,
+        'C' => 2,
+        _ => 1,
+    }
+}
+
+fn date_part(value: &str) -> String {
+    value.chars().take(10).collect()
+}
+
+/// Last time the task was changed in the app
+fn task_modified_at(task: &Task) -> DateTime<Utc> {
+    [Some(&task.updated_at), task.completed_at.as_ref()]
+        .into_iter()
+        .flatten()
+        .filter_map(|t| NaiveDateTime::parse_from_str(t, "%Y-%m-%d %H:%M:%S").ok())
+        .map(|t| t.and_utc())
+        .max()
+        .unwrap_or(DateTime::<Utc>::UNIX_EPOCH)
+}
+
+fn write_atomically(path: &Path, content: &str) -> Result<()> {
+    let tmp = path.with_extension("txt.zg-tmp");
+    std::fs::write(&tmp, content)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_test_database;
+    use crate::services::WorkspaceService;
+
+    fn temp_file(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("zg-todotxt-{}-{name}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("todo.txt");
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn imports_new_lines_and_tags_them_with_ids() {
+        let pool = init_test_database().unwrap();
+        let service = TodoTxtSyncService::new(pool.clone(), Arc::new(EventBus::new()));
+        let path = temp_file("import");
+        std::fs::write(&path, "(A) Call dentist +health @phone due:2026-10-20\n").unwrap();
+
+        let report = service.sync_file(&path).unwrap();
+        assert_eq!(report.created_in_app, 1);
+
+        let task = &TaskRepository::new(pool).get_all(None).unwrap()[0];
+        assert_eq!(task.title, "Call dentist");
+        assert_eq!(task.priority, 4);
+        assert_eq!(task.due_date.as_deref(), Some("2026-10-20"));
+        assert_eq!(task.tags.as_deref(), Some(r#"["phone"]"#));
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains(&format!("zg:{}", task.id)));
+        assert!(content.contains("+health"));
+
+        // A second sync is a no-op
+        let report = service.sync_file(&path).unwrap();
+        assert_eq!(report, TodoTxtSyncReport { lines: 1, ..Default::default() });
+    }
+
+    #[test]
+    fn changes_flow_both_ways() {
+        let pool = init_test_database().unwrap();
+        let events = Arc::new(EventBus::new());
+        let published = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&published);
+        events.subscribe("log", move |_, event| {
+            log.lock().unwrap().push(event.to_string());
+            Ok(())
+        });
+        let service = TodoTxtSyncService::new(pool.clone(), events);
+        let repo = TaskRepository::new(pool);
+        let path = temp_file("both-ways");
+
+        let task = repo
+            .create(CreateTaskInput {
+                title: "Water plants".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        service.sync_file(&path).unwrap();
+
+        // App -> file
+        repo.complete(task.id).unwrap();
+        service.sync_file(&path).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("x "));
+
+        // File -> app
+        std::fs::write(&path, format!("Water all plants zg:{}\n", task.id)).unwrap();
+        service.sync_file(&path).unwrap();
+        let task = repo.get_by_id(task.id).unwrap();
+        assert_eq!(task.title, "Water all plants");
+        assert_eq!(task.status, "todo");
+
+        // Removing the line deletes the task
+        std::fs::write(&path, "").unwrap();
+        let report = service.sync_file(&path).unwrap();
+        assert_eq!(report.deleted_in_app, 1);
+        assert!(repo.get_by_id(task.id).is_err());
+
+        let id = task.id;
+        assert_eq!(
+            *published.lock().unwrap(),
+            [
+                format!("task {id} updated"),
+                format!("task {id} status completed -> todo"),
+                format!("task {id} deleted"),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_missing_file_deletes_nothing() {
+        let pool = init_test_database().unwrap();
+        let service = TodoTxtSyncService::new(pool.clone(), Arc::new(EventBus::new()));
+        let repo = TaskRepository::new(pool);
+        let path = temp_file("missing");
+        std::fs::write(&path, "Call dentist\nBuy milk\n").unwrap();
+        service.sync_file(&path).unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(service.sync_file(&path).is_err());
+        assert_eq!(repo.get_all(None).unwrap().len(), 2);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn a_watched_file_stays_in_its_workspace() {
+        let pool = init_test_database().unwrap();
+        let events = Arc::new(EventBus::new());
+        let service = Arc::new(TodoTxtSyncService::new(pool.clone(), Arc::clone(&events)));
+        let workspaces = WorkspaceService::new(pool.clone(), events);
+        let repo = TaskRepository::new(pool);
+        let path = temp_file("workspaces");
+        std::fs::write(&path, "Call dentist\nBuy milk\n").unwrap();
+        service.watch(path.clone()).unwrap();
+        let personal = workspaces.active_workspace().unwrap().id;
+
+        let work = workspaces
+            .create_workspace(CreateWorkspaceInput {
+                name: "Work".to_string(),
+                workspace_type: None,
+            })
+            .unwrap();
+        workspaces.switch_workspace(work.id).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, content.replace("Buy milk", "Buy oat milk")).unwrap();
+
+        let titles = || {
+            let mut titles: Vec<String> = read(|| repo.get_all_in_workspace(personal))
+                .into_iter()
+                .map(|t| t.title)
+                .collect();
+            titles.sort();
+            titles
+        };
+        let deadline = std::time::Instant::now() + Duration::from_secs(10);
+        while titles() != ["Buy oat milk", "Call dentist"] && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        service.unwatch();
+
+        assert_eq!(titles(), ["Buy oat milk", "Call dentist"]);
+        assert!(read(|| repo.get_all(None)).is_empty());
+    }
+
+    /// Read while the watcher may be syncing: the shared in-memory test
+    /// database fails reads during a write instead of waiting
+    fn read<T>(query: impl Fn() -> Result<T>) -> T {
+        for _ in 0..50 {
+            if let Ok(value) = query() {
+                return value;
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        query().unwrap()
+    }
+
+    #[test]
+    fn keeps_what_only_the_file_knows() {
+        let pool = init_test_database().unwrap();
+        let service = TodoTxtSyncService::new(pool.clone(), Arc::new(EventBus::new()));
+        let repo = TaskRepository::new(pool.clone());
+        let path = temp_file("extras");
+        let line = "(B) 2026-10-01 Plan trip @home rec:1w t:2026-10-18";
+        std::fs::write(&path, format!("{line}\nBuy milk\n")).unwrap();
+
+        service.sync_file(&path).unwrap();
+        service.sync_file(&path).unwrap();
+        let tasks = repo.get_all(None).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content,
+            format!("{line} zg:{}\nBuy milk zg:{}\n", tasks[0].id, tasks[1].id)
+        );
+
+        // A tag with a space is written as a dashed context and comes back unchanged
+        let tagged = tasks[1].id;
+        pool.get()
+            .unwrap()
+            .execute("UPDATE tasks SET tags = '[\"deep work\"]' WHERE id = ?1", [tagged])
+            .unwrap();
+        service.sync_file(&path).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, content.replace("Buy milk", "Buy oat milk")).unwrap();
+        service.sync_file(&path).unwrap();
+        let retitled = repo.get_by_id(tagged).unwrap();
+        assert_eq!(retitled.title, "Buy oat milk");
+        assert_eq!(retitled.tags.as_deref(), Some(r#"["deep work"]"#));
+    }
+}
@@ -0,0 +1,305 @@
+use crate::db::{
+    models::*,
+    repositories::{UserRepository, WorkspaceRepository},
+    DbPool,
+};
+use crate::services::access::Access;
+use anyhow::{bail, Result};
+use sha2::{Digest, Sha256};
+
+/// Wrong PINs in a row before each further attempt has to wait
+const FREE_PIN_ATTEMPTS: u32 = 3;
+/// The first wait, doubled with every wrong PIN up to `MAX_PIN_BACKOFF_SECS`
+const PIN_BACKOFF_SECS: u64 = 30;
+const MAX_PIN_BACKOFF_SECS: u64 = 3600;
+
+/// Local user profiles sharing the database, each with an optional PIN.
+///
+/// The PIN keeps housemates out of each other's profile; it is not meant to
+/// withstand an attacker with access to the database file. After a few wrong
+/// PINs in a row, each further attempt has to wait longer.
+pub struct UserService {
+    users: UserRepository,
+    workspaces: WorkspaceRepository,
+    access: Access,
+}
+
+impl UserService {
+    pub fn new(pool: DbPool) -> Self {
+        Self {
+            users: UserRepository::new(pool.clone()),
+            workspaces: WorkspaceRepository::new(pool.clone()),
+            access: Access::new(pool),
+        }
+    }
+
+    pub fn get_users(&self) -> Result<Vec<User>> {
+        self.users.get_all()
+    }
+
+    /// The signed-in user, `None` while signed out
+    pub fn current_user(&self) -> Result<Option<User>> {
+        self.users
+            .current_id()?
+            .map(|id| self.users.get_by_id(id))
+            .transpose()
+    }
+
+    /// Create a profile with a personal workspace of its own
+    pub fn create_user(&self, input: CreateUserInput) -> Result<User> {
+        let display_name = check_display_name(&input.display_name)?;
+        let email = input.email.as_deref().map(str::trim).filter(|e| !e.is_empty());
+        let pin_hash = input.pin.as_deref().map(|pin| self.hash_new_pin(pin)).transpose()?;
+
+        let user = self.users.create(display_name, email, pin_hash.as_deref())?;
+        self.workspaces.create("Personal", "personal", user.id)?;
+        Ok(user)
+    }
+
+    /// Change the signed-in user's own profile
+    pub fn update_user(&self, id: i64, input: UpdateUserInput) -> Result<User> {
+        self.require_self(id)?;
+        let user = self.users.get_by_id(id)?;
+
+        let display_name = match &input.display_name {
+            Some(name) => check_display_name(name)?,
+            None => &user.display_name,
+        };
+        let email = match &input.email {
+            Some(email) => Some(email.trim()).filter(|e| !e.is_empty()),
+            None => user.email.as_deref(),
+        };
+        self.users.update(id, display_name, email)
+    }
+
+    /// Set, change or (with `None`) remove the signed-in user's PIN. An
+    /// existing PIN must be given to change it.
+    pub fn set_pin(&self, id: i64, current_pin: Option<&str>, new_pin: Option<&str>) -> Result<User> {
+        self.require_self(id)?;
+        self.verify_pin(id, current_pin)?;
+
+        let pin_hash = new_pin.map(|pin| self.hash_new_pin(pin)).transpose()?;
+        self.users.set_pin_hash(id, pin_hash.as_deref())?;
+        self.users.get_by_id(id)
+    }
+
+    /// Sign in to a profile. If the active workspace is not one of theirs,
+    /// their first workspace becomes active.
+    pub fn sign_in(&self, id: i64, pin: Option<&str>) -> Result<User> {
+        let user = self.users.get_by_id(id)?;
+        self.verify_pin(id, pin)?;
+        self.users.set_current(Some(id))?;
+
+        let active = self.workspaces.active_id()?;
+        if self.workspaces.role_of(active, id)?.is_none() {
+            if let Some(workspace) = self.workspaces.get_for_user(id)?.first() {
+                self.workspaces.set_active(workspace.id)?;
+            }
+        }
+        Ok(user)
+    }
+
+    pub fn sign_out(&self) -> Result<()> {
+        self.users.set_current(None)
+    }
+
+    /// Sign out on startup if the last user has a PIN, so that it has to be
+    /// entered again
+    pub fn lock_if_protected(&self) -> Result<()> {
+        if let Some(user) = self.current_user()? {
+            if user.has_pin {
+                self.sign_out()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn require_self(&self, id: i64) -> Result<()> {
+        if self.access.current_user()? != id {
+            bail!("Only the user themselves can change their profile");
+        }
+        Ok(())
+    }
+
+    fn verify_pin(&self, id: i64, pin: Option<&str>) -> Result<()> {
+        let Some(stored) = self.users.pin_hash(id)? else {
+            return Ok(());
+        };
+        let Some((salt, _)) = stored.split_once('$') else {
+            bail!("Stored PIN of user {id} is malformed");
+        };
+        let Some(pin) = pin else {
+            bail!("PIN required");
+        };
+        if let Some(seconds) = self.users.pin_locked_for(id)? {
+            bail!("Too many wrong PINs, try again in {seconds} seconds");
+        }
+
+        if hash_pin(salt, pin) == stored {
+            return self.users.reset_failed_pins(id);
+        }
+        let attempts = self.users.record_failed_pin(id)?;
+        if let Some(delay) = pin_backoff_secs(attempts) {
+            self.users.lock_pin(id, delay)?;
+        }
+        bail!("Wrong PIN")
+    }
+
+    fn hash_new_pin(&self, pin: &str) -> Result<String> {
+        if !(4..=8).contains(&pin.len()) || !pin.bytes().all(|b| b.is_ascii_digit()) {
+            bail!("A PIN must have 4 to 8 digits");
+        }
+        Ok(hash_pin(&self.users.random_salt()?, pin))
+    }
+}
+
+/// How long to refuse a PIN after `attempts` wrong ones in a row
+fn pin_backoff_secs(attempts: u32) -> Option<u64> {
+    let doublings = attempts.checked_sub(FREE_PIN_ATTEMPTS)?;
+    Some(PIN_BACKOFF_SECS.saturating_mul(1 << doublings.min(16)).min(MAX_PIN_BACKOFF_SECS))
+}
+
+fn check_display_name(name: &str) -> Result<&str> {
+    let name = name.trim();
+    if name.is_empty() {
+        bail!("Display name must not be empty");
+    }
+    Ok(name)
+}
+
+/// "salt$hash" with the hex SHA-256 of salt and PIN
+fn hash_pin(salt: &str, pin: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(b"$");
+    hasher.update(pin.as_bytes());
+    let hash: String = hasher.finalize().iter().map(|b| format!("{b:02x}")).collect();
+    format!("{salt}${hash}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_test_database;
+    use crate::services::access::{AccessError, Role};
+    use crate::services::{EventBus, TaskService, WorkspaceService};
+    use std::sync::Arc;
+
+    fn new_user(name: &str, pin: Option<&str>) -> CreateUserInput {
+        CreateUserInput {
+            display_name: name.to_string(),
+            email: None,
+            pin: pin.map(String::from),
+        }
+    }
+
+    fn new_task(title: &str) -> CreateTaskInput {
+        CreateTaskInput {
+            title: title.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn signs_in_with_pin() {
+        let service = UserService::new(init_test_database().unwrap());
+        let sam = service.create_user(new_user("Sam", Some("1234"))).unwrap();
+        assert!(sam.has_pin);
+        assert!(service.create_user(new_user("Kim", Some("12ab"))).is_err());
+
+        assert!(service.sign_in(sam.id, None).is_err());
+        assert!(service.sign_in(sam.id, Some("4321")).is_err());
+        service.sign_in(sam.id, Some("1234")).unwrap();
+        assert_eq!(service.current_user().unwrap().unwrap().id, sam.id);
+
+        // Only the signed-in user can change their PIN, and only with the old one
+        assert!(service.set_pin(1, None, Some("0000")).is_err());
+        assert!(service.set_pin(sam.id, Some("0000"), Some("5678")).is_err());
+        service.set_pin(sam.id, Some("1234"), Some("5678")).unwrap();
+
+        service.lock_if_protected().unwrap();
+        assert_eq!(service.current_user().unwrap(), None);
+        service.sign_in(sam.id, Some("5678")).unwrap();
+    }
+
+    #[test]
+    fn backs_off_after_wrong_pins() {
+        let pool = init_test_database().unwrap();
+        let service = UserService::new(pool.clone());
+        let sam = service.create_user(new_user("Sam", Some("1234"))).unwrap();
+
+        for _ in 0..FREE_PIN_ATTEMPTS {
+            assert_eq!(service.sign_in(sam.id, Some("0000")).unwrap_err().to_string(), "Wrong PIN");
+        }
+        // Even the right PIN has to wait now
+        let locked = service.sign_in(sam.id, Some("1234")).unwrap_err();
+        assert!(locked.to_string().starts_with("Too many wrong PINs"), "{locked}");
+        assert_eq!(pin_backoff_secs(FREE_PIN_ATTEMPTS - 1), None);
+        assert_eq!(pin_backoff_secs(FREE_PIN_ATTEMPTS + 1), Some(2 * PIN_BACKOFF_SECS));
+        assert_eq!(pin_backoff_secs(100), Some(MAX_PIN_BACKOFF_SECS));
+
+        // Once the wait is over, the right PIN starts the count afresh
+        let expire = "UPDATE users SET pin_locked_until = datetime('now', '-1 second')";
+        pool.get().unwrap().execute(expire, []).unwrap();
+        service.sign_in(sam.id, Some("1234")).unwrap();
+        assert!(service.sign_in(sam.id, Some("0000")).is_err());
+        service.sign_in(sam.id, Some("1234")).unwrap();
+    }
+
+    #[test]
+    fn enforces_workspace_roles() {
+        let pool = init_test_database().unwrap();
+        let users = UserService::new(pool.clone());
+        let workspaces = WorkspaceService::new(pool.clone(), Arc::new(EventBus::new()));
+        let tasks = TaskService::new(pool.clone(), Arc::new(EventBus::new()));
+        let kim = users.create_user(new_user("Kim", None)).unwrap();
+
+        tasks.create_task(new_task("Plan offsite")).unwrap();
+        workspaces.set_member_role(1, kim.id, "viewer").unwrap();
+        // The only owner cannot give up ownership
+        assert!(workspaces.set_member_role(1, 1, "editor").is_err());
+
+        users.sign_in(kim.id, None).unwrap();
+        // Kim is a member, so the active workspace stays
+        assert_eq!(workspaces.active_workspace().unwrap().id, 1);
+        assert_eq!(tasks.get_all_tasks(None).unwrap().len(), 1);
+        let denied = tasks.create_task(new_task("Book venue")).unwrap_err();
+        assert_eq!(
+            denied.downcast_ref::<AccessError>(),
+            Some(&AccessError::Forbidden(Role::Editor))
+        );
+        assert!(workspaces.rename_workspace(1, "Ours").is_err());
+
+        users.sign_out().unwrap();
+        let denied = tasks.get_all_tasks(None).unwrap_err();
+        assert_eq!(denied.downcast_ref::<AccessError>(), Some(&AccessError::SignedOut));
+    }
+
+    #[test]
+    fn lists_tasks_assigned_to_me() {
+        let pool = init_test_database().unwrap();
+        let users = UserService::new(pool.clone());
+        let workspaces = WorkspaceService::new(pool.clone(), Arc::new(EventBus::new()));
+        let tasks = TaskService::new(pool.clone(), Arc::new(EventBus::new()));
+        let kim = users.create_user(new_user("Kim", None)).unwrap();
+
+        let offsite = tasks.create_task(new_task("Plan offsite")).unwrap();
+        let venue = tasks.create_task(new_task("Book venue")).unwrap();
+        tasks.create_task(new_task("Order snacks")).unwrap();
+        assert!(tasks.assign_task(venue.id, Some(kim.id)).is_err());
+
+        workspaces.set_member_role(1, kim.id, "editor").unwrap();
+        tasks.assign_task(offsite.id, Some(1)).unwrap();
+        assert_eq!(tasks.assign_task(venue.id, Some(kim.id)).unwrap().assignee_id, Some(kim.id));
+        let mine: Vec<String> = tasks.get_my_tasks(None).unwrap().into_iter().map(|t| t.title).collect();
+        assert_eq!(mine, vec!["Plan offsite"]);
+
+        users.sign_in(kim.id, None).unwrap();
+        assert_eq!(tasks.get_my_tasks(Some("todo")).unwrap()[0].id, venue.id);
+
+        // Removing a member unassigns their tasks
+        users.sign_in(1, None).unwrap();
+        workspaces.remove_member(1, kim.id).unwrap();
+        assert_eq!(tasks.get_task(venue.id).unwrap().assignee_id, None);
+    }
+}
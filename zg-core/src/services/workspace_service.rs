@@ -0,0 +1,364 @@
+use crate::db::{
+    models::*,
+    repositories::{ProjectRepository, TaskRepository, UserRepository, WorkspaceRepository},
+    DbPool,
+};
+use crate::services::access::{Access, Role};
+use crate::services::event_bus::{DomainEvent, EventBus};
+use anyhow::{bail, Result};
+use rusqlite::Connection;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+const WORKSPACE_TYPES: [&str; 2] = ["personal", "team"];
+
+/// Workspaces keep tasks, notes, projects and plans apart. The active one is
+/// stored with the preferences, so that it survives restarts, and scopes every
+/// repository query. Users only see the workspaces they are members of.
+pub struct WorkspaceService {
+    pool: DbPool,
+    repository: WorkspaceRepository,
+    users: UserRepository,
+    access: Access,
+    events: Arc<EventBus>,
+}
+
+impl WorkspaceService {
+    pub fn new(pool: DbPool, events: Arc<EventBus>) -> Self {
+        Self {
+            repository: WorkspaceRepository::new(pool.clone()),
+            users: UserRepository::new(pool.clone()),
+            access: Access::new(pool.clone()),
+            pool,
+            events,
+        }
+    }
+
+    /// Workspaces of the signed-in user
+    pub fn get_workspaces(&self) -> Result<Vec<Workspace>> {
+        self.repository.get_for_user(self.access.current_user()?)
+    }
+
+    pub fn create_workspace(&self, input: CreateWorkspaceInput) -> Result<Workspace> {
+        let name = check_name(&input.name)?;
+        let workspace_type = input.workspace_type.as_deref().unwrap_or("personal");
+        if !WORKSPACE_TYPES.contains(&workspace_type) {
+            bail!("Unknown workspace type: {workspace_type}");
+        }
+
+        self.repository.create(name, workspace_type, self.access.current_user()?)
+    }
+
+    pub fn rename_workspace(&self, id: i64, name: &str) -> Result<Workspace> {
+        let name = check_name(name)?;
+        self.repository.get_by_id(id)?;
+        self.access.require_in(id, Role::Owner)?;
+        self.repository.rename(id, name)
+    }
+
+    /// Delete a workspace and everything in it. If it is the active one,
+    /// another workspace of the user becomes active.
+    pub fn delete_workspace(&self, id: i64) -> Result<()> {
+        self.repository.get_by_id(id)?;
+        self.access.require_in(id, Role::Owner)?;
+        let workspaces = self.get_workspaces()?;
+        let Some(other) = workspaces.iter().find(|w| w.id != id) else {
+            bail!("The last workspace cannot be deleted");
+        };
+
+        if self.repository.active_id()? == id {
+            self.repository.set_active(other.id)?;
+        }
+        self.repository.delete(id)
+    }
+
+    pub fn active_workspace(&self) -> Result<Workspace> {
+        self.repository.get_by_id(self.repository.active_id()?)
+    }
+
+    pub fn switch_workspace(&self, id: i64) -> Result<Workspace> {
+        let workspace = self.repository.get_by_id(id)?;
+        self.access.require_in(id, Role::Viewer)?;
+        self.repository.set_active(id)?;
+        Ok(workspace)
+    }
+
+    pub fn get_members(&self, workspace_id: i64) -> Result<Vec<WorkspaceMember>> {
+        self.access.require_in(workspace_id, Role::Viewer)?;
+        self.repository.members(workspace_id)
+    }
+
+    /// Add a user to a workspace or change their role
+    pub fn set_member_role(&self, workspace_id: i64, user_id: i64, role: &str) -> Result<WorkspaceMember> {
+        let role = Role::parse(role)?;
+        self.access.require_in(workspace_id, Role::Owner)?;
+        self.users.get_by_id(user_id)?;
+        if role != Role::Owner {
+            self.keep_an_owner(workspace_id, user_id)?;
+        }
+
+        self.repository.set_member(workspace_id, user_id, role.as_str())?;
+        self.member(workspace_id, user_id)
+    }
+
+    /// Remove a user from a workspace; their tasks there are unassigned
+    pub fn remove_member(&self, workspace_id: i64, user_id: i64) -> Result<()> {
+        self.access.require_in(workspace_id, Role::Owner)?;
+        self.member(workspace_id, user_id)?;
+        self.keep_an_owner(workspace_id, user_id)?;
+        self.repository.remove_member(workspace_id, user_id)
+    }
+
+    /// Move or copy tasks of the active workspace, with their subtasks, into
+    /// another workspace. Projects are matched by name and created there if
+    /// missing. Returns the number of tasks moved or copied.
+    ///
+    /// Moved tasks are published as deleted, since they leave the active
+    /// workspace. Copies leave it unchanged and publish nothing.
+    pub fn transfer_tasks(&self, task_ids: &[i64], workspace_id: i64, copy: bool) -> Result<usize> {
+        let target = self.repository.get_by_id(workspace_id)?;
+        if target.id == self.repository.active_id()? {
+            bail!("The tasks are already in {}", target.name);
+        }
+        self.access.require(Role::Editor)?;
+        self.access.require_in(workspace_id, Role::Editor)?;
+
+        self.events.transaction(&self.pool, |tx, events| {
+            let selected: HashSet<i64> = task_ids.iter().copied().collect();
+            let mut transferred = HashSet::new();
+
+            for &id in task_ids {
+                let task = TaskRepository::get_by_id_in(tx, id)?;
+                // Subtasks go along with their selected ancestor
+                if transferred.contains(&id) || has_selected_ancestor(tx, &task, &selected)? {
+                    continue;
+                }
+                transfer(tx, &task, workspace_id, None, copy, &mut transferred, events)?;
+            }
+            Ok(transferred.len())
+        })
+    }
+
+    fn member(&self, workspace_id: i64, user_id: i64) -> Result<WorkspaceMember> {
+        match self.repository.members(workspace_id)?.into_iter().find(|m| m.user_id == user_id) {
+            Some(member) => Ok(member),
+            None => bail!("User {user_id} is not a member of workspace {workspace_id}"),
+        }
+    }
+
+    /// Fail if `user_id` is the only owner of the workspace
+    fn keep_an_owner(&self, workspace_id: i64, user_id: i64) -> Result<()> {
+        let owners: Vec<i64> = self
+            .repository
+            .members(workspace_id)?
+            .into_iter()
+            .filter(|m| m.role == Role::Owner.as_str())
+            .map(|m| m.user_id)
+            .collect();
+        if owners == [user_id] {
+            bail!("A workspace needs at least one owner");
+        }
+        Ok(())
+    }
+}
+
+fn check_name(name: &str) -> Result<&str> {
+    let name = name.trim();
+    if name.is_empty() {
+        bail!("Workspace name must not be empty");
+    }
+    Ok(name)
+}
+
+fn has_selected_ancestor(conn: &Connection, task: &Task, selected: &HashSet<i64>) -> Result<bool> {
+    let mut parent_id = task.parent_task_id;
+    while let Some(id) = parent_id {
+        if selected.contains(&id) {
+            return Ok(true);
+        }
+        parent_id = TaskRepository::get_by_id_in(conn, id)?.parent_task_id;
+    }
+    Ok(false)
+}
+
+/// Transfer a task and its subtasks below `parent_id` in the target workspace
+fn transfer(
+    conn: &Connection,
+    task: &Task,
+    workspace_id: i64,
+    parent_id: Option<i64>,
+    copy: bool,
+    transferred: &mut HashSet<i64>,
+    events: &mut Vec<DomainEvent>,
+) -> Result<()> {
+    let project_id = match task.project_id {
+        Some(project_id) => ProjectRepository::counterpart_in(conn, project_id, workspace_id)?,
+        None => None,
+    };
+    let subtasks = TaskRepository::get_subtasks_in(conn, task.id)?;
+
+    let id = if copy {
+        TaskRepository::copy_to_workspace_in(conn, task.id, workspace_id, project_id, parent_id)?
+    } else {
+        TaskRepository::move_to_workspace_in(conn, task.id, workspace_id, project_id, parent_id)?;
+        events.push(DomainEvent::TaskDeleted(task.clone()));
+        task.id
+    };
+    transferred.insert(task.id);
+
+    for subtask in &subtasks {
+        transfer(conn, subtask, workspace_id, Some(id), copy, transferred, events)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_test_database;
+    use crate::services::{DeviceSyncService, EventBus, NoteService};
+    use std::sync::Arc;
+
+    fn new_task(title: &str, project_id: Option<i64>, parent_task_id: Option<i64>) -> CreateTaskInput {
+        CreateTaskInput {
+            title: title.to_string(),
+            project_id,
+            parent_task_id,
+            ..Default::default()
+        }
+    }
+
+    fn titles(tasks: &TaskRepository) -> Vec<String> {
+        let mut titles: Vec<String> = tasks.get_all(None).unwrap().into_iter().map(|t| t.title).collect();
+        titles.sort();
+        titles
+    }
+
+    fn new_note(title: &str) -> CreateNoteInput {
+        CreateNoteInput {
+            title: title.to_string(),
+            body: None,
+            project_id: None,
+            tags: None,
+        }
+    }
+
+    #[test]
+    fn scopes_data_to_the_active_workspace() {
+        let pool = init_test_database().unwrap();
+        let service = WorkspaceService::new(pool.clone(), Arc::new(EventBus::new()));
+        let tasks = TaskRepository::new(pool.clone());
+        let notes = NoteService::new(pool.clone());
+
+        let groceries = tasks.create(new_task("Groceries", None, None)).unwrap();
+        notes.create_note(new_note("Ideas")).unwrap();
+
+        let work = service
+            .create_workspace(CreateWorkspaceInput {
+                name: " Work ".to_string(),
+                workspace_type: Some("team".to_string()),
+            })
+            .unwrap();
+        assert_eq!(work.name, "Work");
+        service.switch_workspace(work.id).unwrap();
+
+        assert!(tasks.get_all(None).unwrap().is_empty());
+        assert!(tasks.get_by_id(groceries.id).is_err());
+        // Note titles only need to be unique within a workspace
+        notes.create_note(new_note("Ideas")).unwrap();
+        tasks.create(new_task("Quarterly report", None, None)).unwrap();
+        assert_eq!(titles(&tasks), vec!["Quarterly report"]);
+
+        service.switch_workspace(1).unwrap();
+        assert_eq!(titles(&tasks), vec!["Groceries"]);
+        assert_eq!(notes.get_all_notes().unwrap().len(), 1);
+        assert_eq!(service.active_workspace().unwrap().name, "Personal");
+    }
+
+    #[test]
+    fn moves_and_copies_task_trees_between_workspaces() {
+        let pool = init_test_database().unwrap();
+        let events = Arc::new(EventBus::new());
+        let deleted = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = Arc::clone(&deleted);
+        events.subscribe("log", move |_, event| {
+            if let DomainEvent::TaskDeleted(task) = event {
+                log.lock().unwrap().push(task.title.clone());
+            }
+            Ok(())
+        });
+        let service = WorkspaceService::new(pool.clone(), events);
+        let tasks = TaskRepository::new(pool.clone());
+        let projects = ProjectRepository::new(pool.clone());
+        let work = service
+            .create_workspace(CreateWorkspaceInput {
+                name: "Work".to_string(),
+                workspace_type: None,
+            })
+            .unwrap();
+
+        let office = projects.find_or_create("Office").unwrap();
+        let move_desk = tasks.create(new_task("Move desk", Some(office.id), None)).unwrap();
+        let cables = tasks.create(new_task("Label cables", None, Some(move_desk.id))).unwrap();
+        let plants = tasks.create(new_task("Water plants", None, None)).unwrap();
+
+        // Selecting a subtask along with its parent transfers it once
+        assert_eq!(service.transfer_tasks(&[cables.id, move_desk.id], work.id, false).unwrap(), 2);
+        assert_eq!(service.transfer_tasks(&[plants.id], work.id, true).unwrap(), 1);
+        assert_eq!(titles(&tasks), vec!["Water plants"]);
+        // Moved tasks leave the active workspace, copies do not
+        assert_eq!(*deleted.lock().unwrap(), ["Move desk", "Label cables"]);
+
+        service.switch_workspace(work.id).unwrap();
+        assert_eq!(titles(&tasks), vec!["Label cables", "Move desk", "Water plants"]);
+        let moved = tasks.get_with_subtasks(move_desk.id).unwrap();
+        assert_eq!(moved.subtasks.len(), 1);
+        let office_at_work = projects.find_by_name("Office").unwrap().unwrap();
+        assert_ne!(office_at_work.id, office.id);
+        assert_eq!(moved.task.project_id, Some(office_at_work.id));
+
+        assert!(service.transfer_tasks(&[move_desk.id], work.id, false).is_err());
+    }
+
+    #[test]
+    fn deletes_workspaces_with_their_data() {
+        let pool = init_test_database().unwrap();
+        let service = WorkspaceService::new(pool.clone(), Arc::new(EventBus::new()));
+        let tasks = crate::services::TaskService::new(pool.clone(), Arc::new(EventBus::new()));
+
+        assert!(service.delete_workspace(1).is_err());
+
+        let side = service
+            .create_workspace(CreateWorkspaceInput {
+                name: "Side project".to_string(),
+                workspace_type: None,
+            })
+            .unwrap();
+        service.switch_workspace(side.id).unwrap();
+        tasks.create_task(new_task("Launch", None, None)).unwrap();
+        NoteService::new(pool.clone()).create_note(new_note("Launch plan")).unwrap();
+        let folder = std::env::temp_dir().join(format!("zg-workspace-delete-{}", std::process::id()));
+        let device_sync = DeviceSyncService::new(pool.clone(), Arc::new(EventBus::new()));
+        device_sync.configure(&folder).unwrap();
+        device_sync.sync().unwrap();
+
+        service.delete_workspace(side.id).unwrap();
+        assert_eq!(service.active_workspace().unwrap().id, 1);
+        assert_eq!(service.get_workspaces().unwrap().len(), 1);
+        for table in [
+            "tasks",
+            "notes",
+            "workspace_members WHERE workspace_id != 1",
+            "device_sync_config",
+            "device_sync_fields",
+        ] {
+            let remaining: i64 = pool
+                .get()
+                .unwrap()
+                .query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| row.get(0))
+                .unwrap();
+            assert_eq!(remaining, 0, "{table}");
+        }
+        let _ = std::fs::remove_dir_all(folder);
+    }
+}
@@ -0,0 +1,160 @@
+//! The local REST API, started through `ApiServer` as the app does and
+//! called over loopback
+
+mod common;
+
+use common::TempDatabase;
+use reqwest::{Client, StatusCode};
+use serde_json::{json, Value};
+use std::net::{Ipv4Addr, TcpListener};
+use std::sync::Arc;
+use std::time::Duration;
+use zg_core::db::models::ApiServerPreferences;
+use zg_core::server::ApiServer;
+use zg_core::services::{EventBus, PreferencesService, TaskService};
+
+/// Enable the server on a free loopback port and wait until it answers.
+/// Returns the database, the base URL and the token.
+async fn start(name: &str) -> (TempDatabase, String, String) {
+    let db = TempDatabase::new(name);
+    let pool = db.pool();
+    let bus = Arc::new(EventBus::new());
+    let tasks = Arc::new(TaskService::new(pool.clone(), Arc::clone(&bus)));
+    let preferences = Arc::new(PreferencesService::new(pool, Arc::clone(&bus)));
+    let server = Arc::new(ApiServer::new(tasks, Arc::clone(&preferences), &bus));
+    tokio::spawn(server.run());
+
+    let token = preferences.generate_api_token().unwrap();
+    let port = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap().local_addr().unwrap().port();
+    let mut settings = preferences.get_preferences().unwrap();
+    settings.api_server = ApiServerPreferences { enabled: true, port };
+    preferences.update_preferences(settings).unwrap();
+
+    let url = format!("http://127.0.0.1:{port}/api");
+    for _ in 0..100 {
+        if Client::new().get(format!("{url}/tasks")).send().await.is_ok() {
+            return (db, url, token);
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("the API server did not start on port {port}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn serves_task_crud_over_loopback() {
+    let (_db, url, token) = start("api-crud").await;
+    let client = Client::new();
+
+    let created: Value = client
+        .post(format!("{url}/tasks"))
+        .bearer_auth(&token)
+        .json(&json!({ "title": "Write report", "due_date": "2020-03-02" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let id = created["id"].as_i64().unwrap();
+
+    let quick = client
+        .post(format!("{url}/tasks/quick-add"))
+        .bearer_auth(&token)
+        .json(&json!({ "text": "call dentist #health !3" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(quick.status(), StatusCode::CREATED);
+    let quick: Value = quick.json().await.unwrap();
+    assert_eq!(quick["title"], "call dentist");
+    assert_eq!(quick["priority"], 3);
+
+    let updated: Value = client
+        .patch(format!("{url}/tasks/{id}"))
+        .bearer_auth(&token)
+        .json(&json!({ "title": "Write quarterly report" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(updated["title"], "Write quarterly report");
+
+    let stale = client
+        .patch(format!("{url}/tasks/{id}"))
+        .bearer_auth(&token)
+        .json(&json!({ "title": "Write report v2", "expected_version": created["version"] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(stale.status(), StatusCode::CONFLICT);
+    let stale: Value = stale.json().await.unwrap();
+    assert_eq!(stale["current"]["title"], "Write quarterly report");
+
+    let completed: Value = client
+        .post(format!("{url}/tasks/{id}/complete"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(completed["status"], "completed");
+
+    let open: Vec<Value> = client
+        .get(format!("{url}/tasks?status=todo&search=DENTIST"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(open.len(), 1);
+    let overdue: Vec<Value> = client
+        .get(format!("{url}/tasks?due=overdue"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(overdue.len(), 1);
+    assert_eq!(overdue[0]["id"].as_i64(), Some(id));
+
+    let deleted = client
+        .delete(format!("{url}/tasks/{id}"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(deleted.status(), StatusCode::NO_CONTENT);
+    let missing = client
+        .get(format!("{url}/tasks/{id}"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn rejects_requests_without_a_valid_token() {
+    let (_db, url, _) = start("api-token").await;
+    let client = Client::new();
+
+    let anonymous = client.get(format!("{url}/tasks")).send().await.unwrap();
+    assert_eq!(anonymous.status(), StatusCode::UNAUTHORIZED);
+    let wrong = client
+        .get(format!("{url}/tasks"))
+        .bearer_auth("not-the-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(wrong.status(), StatusCode::UNAUTHORIZED);
+    let body: Value = wrong.json().await.unwrap();
+    assert_eq!(body["error"], "Invalid token");
+}
@@ -0,0 +1,176 @@
+//! CalDAV sync against the in-memory server in `mock_caldav`, and against a
+//! real server such as Radicale when `ZG_TEST_CALDAV_URL` is set (with
+//! `ZG_TEST_CALDAV_USER` and `ZG_TEST_CALDAV_PASSWORD`)
+
+mod common;
+mod mock_caldav;
+
+use common::TempDatabase;
+use mock_caldav::MockCaldavServer;
+use zg_core::db::{models::*, repositories::TaskRepository};
+use std::sync::Arc;
+use zg_core::services::{CaldavSyncService, EventBus};
+
+type Setup = (TempDatabase, CaldavSyncService, TaskRepository);
+
+fn setup(server: &MockCaldavServer, name: &str) -> Setup {
+    connect(name, server.collection_url(), "user".to_string(), "secret".to_string())
+}
+
+fn connect(
+    name: &str,
+    collection_url: String,
+    username: String,
+    password: String,
+) -> Setup {
+    let db = TempDatabase::new(name);
+    let pool = db.pool();
+    let service = CaldavSyncService::new(pool.clone(), Arc::new(EventBus::new()));
+    let config = service
+        .configure(CaldavConfigInput {
+            collection_url,
+            username,
+            password: password.clone(),
+        })
+        .unwrap();
+    assert!(!format!("{config:?}").contains(&password));
+    (db, service, TaskRepository::new(pool))
+}
+
+fn new_task(title: &str) -> CreateTaskInput {
+    CreateTaskInput {
+        title: title.to_string(),
+        ..Default::default()
+    }
+}
+
+fn remote_todo(uid: &str, summary: &str) -> String {
+    format!(
+        "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//Test//EN\r\nBEGIN:VTODO\r\n\
+         UID:{uid}\r\nSUMMARY:{summary}\r\nSTATUS:NEEDS-ACTION\r\nEND:VTODO\r\nEND:VCALENDAR\r\n"
+    )
+}
+
+#[tokio::test]
+async fn pushes_local_tasks_and_pulls_remote_ones() {
+    let server = MockCaldavServer::start().await;
+    let (_db, service, repo) = setup(&server, "caldav-push-pull");
+
+    let local = repo.create(new_task("Local task")).unwrap();
+    server.put_remote("remote.ics", &remote_todo("remote-1", "Remote task"));
+
+    let report = service.sync().await.unwrap();
+    assert_eq!(report.pushed_created, 1);
+    assert_eq!(report.pulled_created, 1);
+    assert_eq!(server.resource_count(), 2);
+    assert!(server.body(&format!("{}.ics", local.uid)).unwrap().contains("SUMMARY:Local task"));
+    assert_eq!(repo.find_by_uid("remote-1").unwrap().unwrap().title, "Remote task");
+
+    // Nothing changed: nothing to transfer
+    let report = service.sync().await.unwrap();
+    assert_eq!(report, CaldavSyncReport::default());
+}
+
+#[tokio::test]
+async fn applies_remote_edits_and_deletions() {
+    let server = MockCaldavServer::start().await;
+    let (_db, service, repo) = setup(&server, "caldav-remote-edits");
+
+    let task = repo.create(new_task("Original")).unwrap();
+    service.sync().await.unwrap();
+
+    let href = format!("{}.ics", task.uid);
+    server.put_remote(&href, &remote_todo(&task.uid, "Edited remotely"));
+    let report = service.sync().await.unwrap();
+    assert_eq!(report.pulled_updated, 1);
+    assert_eq!(report.pushed_updated, 0);
+    assert_eq!(repo.get_by_id(task.id).unwrap().title, "Edited remotely");
+
+    server.delete_remote(&href);
+    let report = service.sync().await.unwrap();
+    assert_eq!(report.pulled_deleted, 1);
+    assert!(repo.get_by_id(task.id).is_err());
+}
+
+#[tokio::test]
+async fn local_edits_and_deletions_are_pushed() {
+    let server = MockCaldavServer::start().await;
+    let (_db, service, repo) = setup(&server, "caldav-local-edits");
+
+    let task = repo.create(new_task("Original")).unwrap();
+    service.sync().await.unwrap();
+    let href = format!("{}.ics", task.uid);
+
+    let mut edited = task.clone();
+    edited.title = "Edited locally".to_string();
+    repo.overwrite(task.id, &edited).unwrap();
+    let report = service.sync().await.unwrap();
+    assert_eq!(report.pushed_updated, 1);
+    assert!(server.body(&href).unwrap().contains("SUMMARY:Edited locally"));
+
+    repo.delete(task.id).unwrap();
+    let report = service.sync().await.unwrap();
+    assert_eq!(report.pushed_deleted, 1);
+    assert_eq!(server.resource_count(), 0);
+}
+
+#[tokio::test]
+async fn diverged_versions_are_both_kept() {
+    let server = MockCaldavServer::start().await;
+    let (_db, service, repo) = setup(&server, "caldav-diverged");
+
+    let task = repo.create(new_task("Original")).unwrap();
+    service.sync().await.unwrap();
+
+    let mut edited = task.clone();
+    edited.title = "Local version".to_string();
+    repo.overwrite(task.id, &edited).unwrap();
+    server.put_remote(&format!("{}.ics", task.uid), &remote_todo(&task.uid, "Remote version"));
+
+    let report = service.sync().await.unwrap();
+    assert_eq!(report.conflicts, 1);
+
+    let mut titles: Vec<String> = repo.get_all(None).unwrap().into_iter().map(|t| t.title).collect();
+    titles.sort();
+    assert_eq!(titles, vec!["Local version (conflict)", "Remote version"]);
+    assert_eq!(server.resource_count(), 2);
+}
+
+#[tokio::test]
+async fn falls_back_to_listing_without_sync_support() {
+    let server = MockCaldavServer::start().await;
+    server.disable_sync_collection();
+    let (_db, service, repo) = setup(&server, "caldav-listing");
+
+    server.put_remote("a.ics", &remote_todo("a", "From listing"));
+    service.sync().await.unwrap();
+    assert!(repo.find_by_uid("a").unwrap().is_some());
+
+    server.delete_remote("a.ics");
+    let report = service.sync().await.unwrap();
+    assert_eq!(report.pulled_deleted, 1);
+}
+
+#[tokio::test]
+async fn syncs_with_a_real_server() {
+    let Ok(url) = std::env::var("ZG_TEST_CALDAV_URL") else {
+        eprintln!("Skipped: ZG_TEST_CALDAV_URL is not set");
+        return;
+    };
+    let (_db, service, repo) = connect(
+        "caldav-real",
+        url,
+        std::env::var("ZG_TEST_CALDAV_USER").unwrap_or_default(),
+        std::env::var("ZG_TEST_CALDAV_PASSWORD").unwrap_or_default(),
+    );
+
+    let task = repo.create(new_task("zg integration test")).unwrap();
+    let report = service.sync().await.unwrap();
+    assert_eq!(report.pushed_created, 1);
+    assert!(repo.find_by_uid(&task.uid).unwrap().is_some());
+
+    // Leave the collection as it was
+    repo.delete(task.id).unwrap();
+    let report = service.sync().await.unwrap();
+    assert_eq!(report.pushed_deleted, 1);
+}
@@ -0,0 +1,25 @@
+//! Helpers shared by the integration tests
+
+use std::path::PathBuf;
+use zg_core::db::{self, DbPool};
+
+/// A fresh database file, removed when dropped
+pub struct TempDatabase(PathBuf);
+
+impl TempDatabase {
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("zg-core-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        Self(dir)
+    }
+
+    pub fn pool(&self) -> DbPool {
+        db::init_database_at(&self.0.join("zweites_gehirn.db")).unwrap()
+    }
+}
+
+impl Drop for TempDatabase {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,107 @@
+//! Services working together on a database file, the way the app, the API
+//! server and `zg` use them, without any UI
+
+mod common;
+
+use common::TempDatabase;
+use std::sync::{Arc, Mutex};
+use zg_core::db::models::*;
+use zg_core::services::{
+    event_bus::DomainEvent, EventBus, NoteService, PreferencesService, ReminderService, TaskService,
+    WorkspaceService,
+};
+
+fn quick_add(tasks: &TaskService, text: &str) -> Task {
+    let preview = tasks.parse_quick_add(text).unwrap();
+    tasks.create_task(preview.input).unwrap()
+}
+
+#[test]
+fn completing_a_task_dismisses_its_reminders() {
+    let db = TempDatabase::new("reminders");
+    let pool = db.pool();
+    let events = Arc::new(EventBus::new());
+    ReminderService::subscribe(&events);
+    let tasks = TaskService::new(pool.clone(), Arc::clone(&events));
+    let reminders = ReminderService::new(pool);
+
+    let task = quick_add(&tasks, "renew passport !3");
+    reminders
+        .add_reminder(
+            task.id,
+            CreateReminderInput {
+                remind_at: Some("2099-01-01T09:00:00".into()),
+                minutes_before_due: None,
+            },
+        )
+        .unwrap();
+
+    tasks.complete_task(task.id).unwrap();
+    let reminder = &reminders.get_reminders(task.id).unwrap()[0];
+    assert!(reminder.fired_at.is_some());
+    assert_eq!(reminder.next_fire_at, None);
+}
+
+#[test]
+fn workspaces_scope_tasks_and_notes() {
+    let db = TempDatabase::new("workspaces");
+    let pool = db.pool();
+    let tasks = TaskService::new(pool.clone(), Arc::new(EventBus::new()));
+    let notes = NoteService::new(pool.clone());
+    let workspaces = WorkspaceService::new(pool, Arc::new(EventBus::new()));
+
+    quick_add(&tasks, "water the plants");
+    let personal = workspaces.active_workspace().unwrap();
+    let team = workspaces
+        .create_workspace(CreateWorkspaceInput {
+            name: "Team".into(),
+            workspace_type: Some("team".into()),
+        })
+        .unwrap();
+    workspaces.switch_workspace(team.id).unwrap();
+    quick_add(&tasks, "plan the offsite");
+    notes
+        .create_note(CreateNoteInput {
+            title: "Offsite agenda".into(),
+            body: Some("Venue, plants for the room".into()),
+            project_id: None,
+            tags: None,
+        })
+        .unwrap();
+
+    let search = TaskQuery {
+        search: Some("plants".into()),
+        ..Default::default()
+    };
+    assert!(tasks.query_tasks(&search).unwrap().is_empty());
+    assert_eq!(notes.search_notes("plants").unwrap().len(), 1);
+
+    workspaces.switch_workspace(personal.id).unwrap();
+    assert_eq!(tasks.query_tasks(&search).unwrap()[0].title, "water the plants");
+    assert!(notes.search_notes("plants").unwrap().is_empty());
+}
+
+#[test]
+fn preference_changes_are_published() {
+    let db = TempDatabase::new("preferences");
+    let pool = db.pool();
+    let events = Arc::new(EventBus::new());
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&seen);
+    events.subscribe("test", move |_, event| {
+        if let DomainEvent::PreferencesChanged(preferences) = event {
+            sink.lock().unwrap().push(preferences.theme.clone());
+        }
+        Ok(())
+    });
+    let preferences = PreferencesService::new(pool.clone(), events);
+
+    let mut changed = preferences.get_preferences().unwrap();
+    changed.theme = "dark".into();
+    preferences.update_preferences(changed).unwrap();
+
+    assert_eq!(*seen.lock().unwrap(), ["dark"]);
+    // Another pool on the same file, as the `zg` client would open it
+    let reopened = PreferencesService::new(db.pool(), Arc::new(EventBus::new()));
+    assert_eq!(reopened.get_preferences().unwrap().theme, "dark");
+}
@@ -0,0 +1,288 @@
+//! In-memory CalDAV server for the sync tests.
+//!
+//! Speaks just enough HTTP/1.1 and WebDAV (PROPFIND, sync-collection REPORT,
+//! GET, PUT and DELETE with ETag preconditions) for the sync client.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+const COLLECTION: &str = "/dav/tasks/";
+
+#[derive(Default)]
+struct State {
+    resources: BTreeMap<String, (String, String)>,
+    /// (revision, href) of every change, deletions included
+    changes: Vec<(u64, String)>,
+    revision: u64,
+    sync_collection_disabled: bool,
+}
+
+impl State {
+    fn store(&mut self, href: &str, body: &str) -> String {
+        self.revision += 1;
+        let etag = format!("\"{}\"", self.revision);
+        self.resources
+            .insert(href.to_string(), (body.to_string(), etag.clone()));
+        self.changes.push((self.revision, href.to_string()));
+        etag
+    }
+
+    fn remove(&mut self, href: &str) {
+        self.revision += 1;
+        self.resources.remove(href);
+        self.changes.push((self.revision, href.to_string()));
+    }
+}
+
+#[derive(Clone)]
+pub struct MockCaldavServer {
+    url: String,
+    state: Arc<Mutex<State>>,
+}
+
+struct Request {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+impl MockCaldavServer {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = Self {
+            url: format!("http://{}", listener.local_addr().unwrap()),
+            state: Arc::new(Mutex::new(State::default())),
+        };
+
+        let state = Arc::clone(&server.state);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let state = Arc::clone(&state);
+                tokio::spawn(async move {
+                    let _ = handle_connection(stream, state).await;
+                });
+            }
+        });
+
+        server
+    }
+
+    pub fn collection_url(&self) -> String {
+        format!("{}{}", self.url, COLLECTION)
+    }
+
+    /// Simulate a change made by another client
+    pub fn put_remote(&self, name: &str, body: &str) {
+        self.state
+            .lock()
+            .unwrap()
+            .store(&format!("{COLLECTION}{name}"), body);
+    }
+
+    pub fn delete_remote(&self, name: &str) {
+        self.state
+            .lock()
+            .unwrap()
+            .remove(&format!("{COLLECTION}{name}"));
+    }
+
+    pub fn body(&self, name: &str) -> Option<String> {
+        self.state
+            .lock()
+            .unwrap()
+            .resources
+            .get(&format!("{COLLECTION}{name}"))
+            .map(|(body, _)| body.clone())
+    }
+
+    pub fn resource_count(&self) -> usize {
+        self.state.lock().unwrap().resources.len()
+    }
+
+    /// Behave like a server without RFC 6578 support
+    pub fn disable_sync_collection(&self) {
+        self.state.lock().unwrap().sync_collection_disabled = true;
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream, state: Arc<Mutex<State>>) -> std::io::Result<()> {
+    let request = read_request(&mut stream).await?;
+    let (status, headers, body) = respond(&request, &mut state.lock().unwrap());
+
+    let mut response = format!(
+        "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        body.len()
+    );
+    for (name, value) in headers {
+        response.push_str(&format!("{name}: {value}\r\n"));
+    }
+    response.push_str("\r\n");
+    response.push_str(&body);
+
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+async fn read_request(stream: &mut TcpStream) -> std::io::Result<Request> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let header_end = loop {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default().to_string();
+    let path = request_line.next().unwrap_or_default().to_string();
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(n, v)| (n.trim().to_string(), v.trim().to_string()))
+        .collect();
+
+    let length: usize = headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, v)| v.parse().ok())
+        .unwrap_or(0);
+    while buffer.len() < header_end + length {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+    }
+
+    Ok(Request {
+        method,
+        path,
+        headers,
+        body: String::from_utf8_lossy(&buffer[header_end..]).to_string(),
+    })
+}
+
+fn respond(request: &Request, state: &mut State) -> (&'static str, Vec<(String, String)>, String) {
+    let current_etag = state.resources.get(&request.path).map(|(_, etag)| etag.clone());
+
+    match request.method.as_str() {
+        "PROPFIND" => {
+            let entries: String = state
+                .resources
+                .iter()
+                .map(|(href, (_, etag))| propstat_response(href, etag))
+                .collect();
+            ("207 Multi-Status", xml_headers(), multistatus(&entries, None))
+        }
+        "REPORT" if state.sync_collection_disabled => ("501 Not Implemented", vec![], String::new()),
+        "REPORT" => {
+            let since: u64 = request
+                .body
+                .split("<d:sync-token>")
+                .nth(1)
+                .and_then(|rest| rest.split("</d:sync-token>").next())
+                .and_then(|token| token.trim().strip_prefix("token-"))
+                .and_then(|rev| rev.parse().ok())
+                .unwrap_or(0);
+
+            let mut changed: Vec<&String> = state
+                .changes
+                .iter()
+                .filter(|(rev, _)| *rev > since)
+                .map(|(_, href)| href)
+                .collect();
+            changed.sort();
+            changed.dedup();
+
+            let entries: String = changed
+                .into_iter()
+                .map(|href| match state.resources.get(href) {
+                    Some((_, etag)) => propstat_response(href, etag),
+                    None => format!(
+                        "<d:response><d:href>{href}</d:href>\
+                         <d:status>HTTP/1.1 404 Not Found</d:status></d:response>"
+                    ),
+                })
+                .collect();
+            let token = format!("token-{}", state.revision);
+            ("207 Multi-Status", xml_headers(), multistatus(&entries, Some(&token)))
+        }
+        "GET" => match state.resources.get(&request.path) {
+            Some((body, etag)) => ("200 OK", vec![("ETag".to_string(), etag.clone())], body.clone()),
+            None => ("404 Not Found", vec![], String::new()),
+        },
+        "PUT" => {
+            let precondition_ok = match (request.header("If-Match"), request.header("If-None-Match")) {
+                (Some("*"), _) => current_etag.is_some(),
+                (Some(expected), _) => current_etag.as_deref() == Some(expected),
+                (None, Some("*")) => current_etag.is_none(),
+                _ => true,
+            };
+            if !precondition_ok {
+                return ("412 Precondition Failed", vec![], String::new());
+            }
+            let status = if current_etag.is_some() { "204 No Content" } else { "201 Created" };
+            let etag = state.store(&request.path, &request.body);
+            (status, vec![("ETag".to_string(), etag)], String::new())
+        }
+        "DELETE" => {
+            if let Some(expected) = request.header("If-Match") {
+                if current_etag.as_deref() != Some(expected) {
+                    return ("412 Precondition Failed", vec![], String::new());
+                }
+            }
+            if current_etag.is_none() {
+                return ("404 Not Found", vec![], String::new());
+            }
+            state.remove(&request.path);
+            ("204 No Content", vec![], String::new())
+        }
+        _ => ("405 Method Not Allowed", vec![], String::new()),
+    }
+}
+
+fn propstat_response(href: &str, etag: &str) -> String {
+    format!(
+        "<d:response><d:href>{href}</d:href><d:propstat><d:prop>\
+         <d:getetag>{}</d:getetag></d:prop><d:status>HTTP/1.1 200 OK</d:status>\
+         </d:propstat></d:response>",
+        etag.replace('"', "&quot;")
+    )
+}
+
+fn multistatus(entries: &str, sync_token: Option<&str>) -> String {
+    let collection = format!(
+        "<d:response><d:href>{COLLECTION}</d:href><d:propstat><d:prop><d:resourcetype>\
+         <d:collection/></d:resourcetype></d:prop><d:status>HTTP/1.1 200 OK</d:status>\
+         </d:propstat></d:response>"
+    );
+    let token = sync_token
+        .map(|t| format!("<d:sync-token>{t}</d:sync-token>"))
+        .unwrap_or_default();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?><d:multistatus xmlns:d=\"DAV:\">\
+         {collection}{entries}{token}</d:multistatus>"
+    )
+}
+
+fn xml_headers() -> Vec<(String, String)> {
+    vec![("Content-Type".to_string(), "application/xml; charset=utf-8".to_string())]
+}